@@ -0,0 +1,70 @@
+//! Measures read throughput of `get_config_by_key` as reader threads are added.
+//!
+//! Run with `cargo bench --bench concurrent_reads`, and with `--features arc-swap` to
+//! compare lock-free reads. With a lock that readers share, the total throughput grows
+//! with the number of threads instead of staying flat. The last run repeats the readers
+//! while another thread keeps writing.
+
+use std::hint::black_box;
+use std::sync::Barrier;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Instant;
+
+use serde_json::json;
+use zirv_config::config;
+
+const READS_PER_THREAD: u32 = 200_000;
+
+fn bench(threads: u32, with_writer: bool) {
+    let barrier = Barrier::new(threads as usize + 1);
+    let done = AtomicBool::new(false);
+    let elapsed = thread::scope(|scope| {
+        if with_writer {
+            scope.spawn(|| {
+                let mut port = 3000;
+                while !done.load(Ordering::Relaxed) {
+                    port += 1;
+                    config::set_config_by_key("server.port", port).unwrap();
+                }
+            });
+        }
+        let readers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    barrier.wait();
+                    for _ in 0..READS_PER_THREAD {
+                        black_box(config::get_config_by_key(black_box("server.port")));
+                    }
+                })
+            })
+            .collect();
+        barrier.wait();
+        let start = Instant::now();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        let elapsed = start.elapsed();
+        done.store(true, Ordering::Relaxed);
+        elapsed
+    });
+    let reads = f64::from(threads * READS_PER_THREAD);
+    let label = if with_writer { " + writer" } else { "" };
+    println!(
+        "{:<28} {:>10.2} M reads/s",
+        format!("{threads} reader thread(s){label}"),
+        reads / elapsed.as_secs_f64() / 1e6
+    );
+}
+
+fn main() {
+    config::register_config(
+        "server",
+        json!({ "host": "0.0.0.0", "port": 3000, "workers": 8 }),
+    );
+
+    for threads in [1, 2, 4, 8] {
+        bench(threads, false);
+    }
+    bench(4, true);
+}
@@ -0,0 +1,95 @@
+//! Compares the heap taken by a namespace full of repeated strings when it is held as a
+//! `serde_json::Value` with the same namespace registered as an interned one.
+//!
+//! Run with `cargo bench --bench interning --features intern`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde_json::{Value, json};
+use zirv_config::config;
+
+/// Counts the bytes currently allocated, to measure what a value keeps alive.
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            LIVE.fetch_add(layout.size(), Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new.is_null() {
+            LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+            LIVE.fetch_add(new_size, Ordering::Relaxed);
+        }
+        new
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// The bytes that `f` leaves allocated once it returns.
+fn retained<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = LIVE.load(Ordering::Relaxed);
+    let value = f();
+    (value, LIVE.load(Ordering::Relaxed).saturating_sub(before))
+}
+
+/// A generated routing table of `routes` entries that share a handful of methods,
+/// upstreams, regions and tags, as such tables do.
+fn fixture(routes: usize) -> Value {
+    const METHODS: [&str; 4] = ["GET", "POST", "PUT", "DELETE"];
+    let routes: Vec<Value> = (0..routes)
+        .map(|index| {
+            json!({
+                "path": format!("/api/v1/resource/{index}"),
+                "method": METHODS[index % METHODS.len()],
+                "upstream": format!("svc-{}.internal:8080", index % 16),
+                "region": "eu-west-1",
+                "tags": ["public", "v1"],
+                "timeout_ms": 1_000 + index % 5 * 250,
+            })
+        })
+        .collect();
+    json!({ "routes": routes })
+}
+
+fn report(name: &str, bytes: usize) {
+    println!("{name:<32} {:>10.1} KB", bytes as f64 / 1e3);
+}
+
+fn main() {
+    let (value, parsed) = retained(|| fixture(20_000));
+    println!(
+        "routing table: {:.1} MB of JSON",
+        serde_json::to_vec(&value).unwrap().len() as f64 / 1e6
+    );
+    report("serde_json::Value", parsed);
+
+    let ((), interned) = retained(|| {
+        config::register_interned_config("interned_routes", fixture(20_000)).unwrap();
+    });
+    report("register_interned_config", interned);
+    println!(
+        "{:<32} {:>10.1} %",
+        "saved",
+        100.0 * (1.0 - interned as f64 / parsed as f64)
+    );
+
+    // The interned namespace reads back as the same JSON.
+    let routes: Value = config::get_namespace("interned_routes").unwrap();
+    assert_eq!(routes, value);
+}
@@ -0,0 +1,81 @@
+//! Compares lookups by string key against lookups by pre-parsed `ConfigKey`, and against
+//! copying the whole configuration to read one value out of it.
+//!
+//! Run with `cargo bench --bench key_lookup`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use serde_json::{Map, Value, json};
+use zirv_config::config;
+use zirv_config::key::ConfigKey;
+
+const ITERATIONS: u32 = 200_000;
+
+/// Copying the whole configuration is orders of magnitude slower, so it runs fewer times.
+const FULL_COPY_ITERATIONS: u32 = 200;
+
+fn bench(name: &str, f: impl FnMut()) {
+    bench_n(name, ITERATIONS, f);
+}
+
+fn bench_n(name: &str, iterations: u32, mut f: impl FnMut()) {
+    for _ in 0..iterations / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{name:<32} {:>12.1} ns/iter",
+        elapsed.as_nanos() as f64 / f64::from(iterations)
+    );
+}
+
+fn main() {
+    config::register_config(
+        "server",
+        json!({ "http": { "listener": { "port": 3000 } }, "host": "0.0.0.0" }),
+    );
+
+    // A few thousand unrelated keys, which a lookup should not pay for.
+    let services: Map<String, Value> = (0..5_000)
+        .map(|index| {
+            (
+                format!("svc{index}"),
+                json!({ "host": "10.0.0.1", "port": index }),
+            )
+        })
+        .collect();
+    config::register_config("services", services);
+
+    let raw = r#"server.http."listener".port"#;
+    let key = ConfigKey::parse(raw).unwrap();
+
+    bench("get_config_by_key(&str)", || {
+        black_box(config::get_config_by_key(black_box(raw)));
+    });
+    bench("get_config_by_key(&ConfigKey)", || {
+        black_box(config::get_config_by_key(black_box(&key)));
+    });
+    // The same 20 keys over and over, as a request handler would read them.
+    let hot: Vec<String> = (0..20)
+        .map(|index| format!("services.svc{index}.port"))
+        .collect();
+    let mut next = 0;
+    bench("get_config_by_key(20 hot keys)", || {
+        black_box(config::get_config_by_key(black_box(hot[next].as_str())));
+        next = (next + 1) % hot.len();
+    });
+    bench_n("get_config() then index", FULL_COPY_ITERATIONS, || {
+        black_box(config::get_config()["server"]["http"]["listener"]["port"].clone());
+    });
+    bench("get_config_as::<u16>(&str)", || {
+        black_box(config::get_config_as::<u16>(black_box(raw)).unwrap());
+    });
+    bench("get_config_as::<u16>(&ConfigKey)", || {
+        black_box(config::get_config_as::<u16>(black_box(&key)).unwrap());
+    });
+}
@@ -0,0 +1,70 @@
+//! Compares a large pass-through namespace stored as parsed JSON with the same namespace
+//! stored as a raw blob, from registration to handing it on.
+//!
+//! Run with `cargo bench --bench raw_value`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use serde_json::value::RawValue;
+use serde_json::{Value, json};
+use zirv_config::config;
+
+const ITERATIONS: u32 = 50;
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    for _ in 0..ITERATIONS / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{name:<40} {:>12.1} ns/iter",
+        elapsed.as_nanos() as f64 / f64::from(ITERATIONS)
+    );
+}
+
+/// A generated routing table of `routes` entries, as JSON text.
+fn routing_table(routes: usize) -> String {
+    let routes: Vec<Value> = (0..routes)
+        .map(|index| {
+            json!({
+                "path": format!("/api/v1/resource/{index}"),
+                "upstream": format!("svc-{index}.internal:8080"),
+                "timeout_ms": 1_000 + index,
+            })
+        })
+        .collect();
+    json!({ "routes": routes }).to_string()
+}
+
+fn main() {
+    let table = routing_table(20_000);
+    println!("routing table: {:.1} MB", table.len() as f64 / 1e6);
+
+    bench("register_config (parsed)", || {
+        let value: Value = serde_json::from_str(&table).unwrap();
+        config::register_config("parsed_routes", value);
+    });
+    bench("register_raw_config", || {
+        let raw = RawValue::from_string(table.clone()).unwrap();
+        config::unregister_raw_config("raw_routes");
+        config::register_raw_config("raw_routes", raw).unwrap();
+    });
+
+    bench("get_config_by_key (parsed)", || {
+        black_box(config::get_config_by_key("parsed_routes"));
+    });
+    bench("get_raw_config", || {
+        black_box(config::get_raw_config("raw_routes"));
+    });
+    bench("serialize get_config_by_key (parsed)", || {
+        black_box(serde_json::to_vec(&config::get_config_by_key("parsed_routes")).unwrap());
+    });
+    bench("serialize get_raw_config", || {
+        black_box(serde_json::to_vec(&config::get_raw_config("raw_routes").as_deref()).unwrap());
+    });
+}
@@ -0,0 +1,56 @@
+//! Compares copying the whole configuration with sharing its snapshot, on a fixture of
+//! roughly 2 MB of JSON.
+//!
+//! Run with `cargo bench --bench snapshot`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use serde_json::{Value, json};
+use zirv_config::config;
+
+const ITERATIONS: u32 = 200;
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    for _ in 0..ITERATIONS / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{name:<32} {:>12.1} ns/iter",
+        elapsed.as_nanos() as f64 / f64::from(ITERATIONS)
+    );
+}
+
+/// Builds a namespace of `routes` entries, each about 150 bytes of JSON.
+fn fixture(routes: usize) -> Value {
+    let routes: Vec<Value> = (0..routes)
+        .map(|index| {
+            json!({
+                "path": format!("/api/v1/resource/{index}"),
+                "methods": ["GET", "POST"],
+                "timeout_ms": 1_000 + index,
+                "upstream": { "host": format!("svc-{index}.internal"), "port": 8080 },
+                "tags": ["public", "v1"],
+            })
+        })
+        .collect();
+    json!({ "routes": routes })
+}
+
+fn main() {
+    config::register_config("gateway", fixture(13_000));
+    let size = serde_json::to_vec(&config::get_config()).unwrap().len();
+    println!("fixture size: {:.1} MB", size as f64 / 1e6);
+
+    bench("get_config", || {
+        black_box(config::get_config());
+    });
+    bench("get_config_arc", || {
+        black_box(config::get_config_arc());
+    });
+}
@@ -0,0 +1,55 @@
+//! Compares typed namespace reads through serde against the `Arc` cache.
+//!
+//! Run with `cargo bench --bench typed_cache`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use serde::Deserialize;
+use serde_json::json;
+use zirv_config::config;
+
+const ITERATIONS: u32 = 200_000;
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct ServerConfig {
+    host: String,
+    port: u16,
+    workers: u32,
+    allowed_origins: Vec<String>,
+}
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    for _ in 0..ITERATIONS / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{name:<32} {:>10.1} ns/iter",
+        elapsed.as_nanos() as f64 / f64::from(ITERATIONS)
+    );
+}
+
+fn main() {
+    config::register_config(
+        "server",
+        json!({
+            "host": "0.0.0.0",
+            "port": 3000,
+            "workers": 8,
+            "allowed_origins": ["https://a.example", "https://b.example"]
+        }),
+    );
+
+    bench("get_namespace::<ServerConfig>", || {
+        black_box(config::get_namespace::<ServerConfig>(black_box("server")).unwrap());
+    });
+    bench("get_cached::<ServerConfig>", || {
+        black_box(config::get_cached::<ServerConfig>(black_box("server")).unwrap());
+    });
+}
@@ -0,0 +1,246 @@
+//! Composing the configuration at startup from files, the environment and overrides.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::config::{
+    ConfigError, ConfigSource, EnvSource, Layer, application_config, merge_map, set_in,
+};
+use crate::source::{ReloadOptions, install_sources};
+
+/// Assembles the configuration from files, environment variables and overrides, and
+/// installs it in one step.
+///
+/// Whatever order the steps are given in, they apply with a fixed precedence, each
+/// deep-merged over the ones before it:
+///
+/// 1. the blocks [registered](crate::config::register_config) by the application, such
+///    as library defaults,
+/// 2. the [files](ConfigBuilder::file), in the order given,
+/// 3. the [environment variables](ConfigBuilder::env_prefix),
+/// 4. the [overrides](ConfigBuilder::overrides), such as those given on the command line.
+///
+/// [`build`](ConfigBuilder::build) adds the steps as [sources](ConfigSource) at
+/// [`FILE_PRIORITY`](ConfigBuilder::FILE_PRIORITY),
+/// [`ENV_PRIORITY`](ConfigBuilder::ENV_PRIORITY) and
+/// [`OVERRIDE_PRIORITY`](ConfigBuilder::OVERRIDE_PRIORITY), so that
+/// [`reload_config`](crate::config::reload_config) later loads them again in the same way,
+/// along with any sources [added](crate::config::add_source) by hand.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use zirv_config::config::{self, ConfigBuilder};
+///
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let dir = dir.path();
+/// std::fs::write(dir.join("default.json"), r#"{ "http": { "port": 8080, "workers": 4 } }"#).unwrap();
+///
+/// let report = ConfigBuilder::new()
+///     .file(dir.join("default.json"))
+///     .file_optional(dir.join("local.json"))
+///     .overrides([("http.workers", 16)])
+///     .build()
+///     .unwrap();
+/// assert_eq!(report.missing_files, [dir.join("local.json")]);
+/// assert_eq!(config::get_config_by_key("http"), Some(json!({ "port": 8080, "workers": 16 })));
+/// ```
+#[derive(Debug, Default)]
+pub struct ConfigBuilder {
+    files: Vec<Layer>,
+    env: Option<EnvSource>,
+    overrides: Map<String, Value>,
+    error: Option<ConfigError>,
+}
+
+/// What [`ConfigBuilder::build`] loaded, and what it warned about.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildReport {
+    /// The names of the sources that provided values, from the lowest precedence to the
+    /// highest, such as the paths of the files.
+    pub sources: Vec<String>,
+    /// The optional files that did not exist.
+    pub missing_files: Vec<PathBuf>,
+    /// The environment variables with the prefix that set a key no file or registered
+    /// block defines, which often means a typo in the name.
+    pub unknown_env_vars: Vec<String>,
+}
+
+impl BuildReport {
+    /// Describes each missing file and unknown environment variable, as `build` also logs.
+    pub fn warnings(&self) -> Vec<String> {
+        let missing = self
+            .missing_files
+            .iter()
+            .map(|path| format!("optional file {} does not exist", path.display()));
+        let unknown = self.unknown_env_vars.iter().map(|name| {
+            format!("environment variable {name} sets a key that nothing else defines")
+        });
+        missing.chain(unknown).collect()
+    }
+}
+
+/// The overrides of a [`ConfigBuilder`] as a source.
+struct Overrides(Value);
+
+impl ConfigSource for Overrides {
+    fn name(&self) -> &str {
+        "overrides"
+    }
+
+    fn load(&self) -> Result<Value, ConfigError> {
+        Ok(self.0.clone())
+    }
+}
+
+impl ConfigBuilder {
+    /// The priority of the files of a builder, above the registered blocks at
+    /// [`APPLICATION_PRIORITY`](crate::config::APPLICATION_PRIORITY).
+    pub const FILE_PRIORITY: i32 = 100;
+    /// The priority of the environment variables of a builder, above its files.
+    pub const ENV_PRIORITY: i32 = 200;
+    /// The priority of the overrides of a builder, above its environment variables.
+    pub const OVERRIDE_PRIORITY: i32 = 300;
+
+    /// A builder with no steps, which installs just the registered blocks.
+    pub fn new() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    /// Adds a file that must exist, in the format given by its extension, over the files
+    /// added before it.
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.files.push(Layer::required(path));
+        self
+    }
+
+    /// Adds a file that is skipped if it does not exist, such as a local override, and
+    /// listed in [`BuildReport::missing_files`].
+    pub fn file_optional(mut self, path: impl Into<PathBuf>) -> Self {
+        self.files.push(Layer::optional(path));
+        self
+    }
+
+    /// Reads the environment variables named `PREFIX_...` over the files, as an
+    /// [`EnvSource`] with that prefix does, so `APP_SERVER__PORT` sets `server.port` for
+    /// the prefix `APP`. Replaces any prefix given before.
+    pub fn env_prefix(mut self, prefix: &str) -> Self {
+        self.env = Some(EnvSource::new(prefix));
+        self
+    }
+
+    /// Sets each dot-separated key to its value over everything else, later pairs
+    /// overriding earlier ones. Values are stored as they serialize, so a string stays a
+    /// string.
+    ///
+    /// A key that is malformed or runs through a value that is not an object, or a value
+    /// that cannot be serialized, fails [`build`](ConfigBuilder::build).
+    pub fn overrides<I, K, V>(mut self, pairs: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Serialize,
+    {
+        for (key, value) in pairs {
+            if self.error.is_some() {
+                break;
+            }
+            let key = key.as_ref();
+            let set = serde_json::to_value(value)
+                .map_err(|source| ConfigError::Serialization {
+                    key: key.to_string(),
+                    source,
+                })
+                .and_then(|value| set_in(&mut self.overrides, key, value));
+            if let Err(error) = set {
+                self.error = Some(error);
+            }
+        }
+        self
+    }
+
+    /// Loads every step and installs the result as the configuration, in a single swap,
+    /// whether or not [`init_config`](crate::config::init_config) was called.
+    ///
+    /// Sources added before with [`add_source`](crate::config::add_source) are loaded
+    /// along with the steps, and values not provided by any of them, by the registered
+    /// blocks or by runtime writes are dropped, as
+    /// [`reload_config`](crate::config::reload_config) does. Meant
+    /// to be called once, at startup: building again adds the steps again.
+    ///
+    /// Fails with the error of an override given to the builder, and otherwise as
+    /// [`reload_config`](crate::config::reload_config) does, in which case the
+    /// configuration is left as it was and no source is added.
+    pub fn build(self) -> Result<BuildReport, ConfigError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let missing_files: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|layer| !layer.is_required() && !layer.path().exists())
+            .map(|layer| layer.path().to_path_buf())
+            .collect();
+
+        let mut added: Vec<(Box<dyn ConfigSource>, i32)> = Vec::new();
+        for layer in self.files {
+            added.push((Box::new(layer), ConfigBuilder::FILE_PRIORITY));
+        }
+        if let Some(env) = &self.env {
+            added.push((Box::new(env.clone()), ConfigBuilder::ENV_PRIORITY));
+        }
+        if !self.overrides.is_empty() {
+            added.push((
+                Box::new(Overrides(Value::Object(self.overrides))),
+                ConfigBuilder::OVERRIDE_PRIORITY,
+            ));
+        }
+        let (loaded, _) = install_sources(added, &ReloadOptions::default())?;
+
+        let mut unknown_env_vars = Vec::new();
+        if let Some(env) = &self.env {
+            let mut below = application_config();
+            for source in &loaded {
+                if source.priority < ConfigBuilder::ENV_PRIORITY {
+                    merge_map(&mut below, source.document.clone());
+                }
+            }
+            for (name, segments, _) in env.variables() {
+                if !defines(&below, &segments) {
+                    unknown_env_vars.push(name);
+                }
+            }
+        }
+        let report = BuildReport {
+            sources: loaded
+                .into_iter()
+                .filter(|source| !source.document.is_empty())
+                .map(|source| source.name)
+                .collect(),
+            missing_files,
+            unknown_env_vars,
+        };
+        for warning in report.warnings() {
+            log::warn!("{warning}");
+        }
+        Ok(report)
+    }
+}
+
+/// Tells whether `root` holds a value at the path of `segments`.
+fn defines(root: &Map<String, Value>, segments: &[String]) -> bool {
+    let Some((last, parents)) = segments.split_last() else {
+        return false;
+    };
+    let mut map = root;
+    for segment in parents {
+        match map.get(segment) {
+            Some(Value::Object(child)) => map = child,
+            _ => return false,
+        }
+    }
+    map.contains_key(last)
+}
@@ -1,24 +1,426 @@
 //! Module for managing configuration.
-//! 
+//!
 //! The global configuration is stored as a JSON object. Each subsystem (e.g. server, logging)
 //! can register its configuration under a specific namespace (e.g. "server", "logging").
 //! The library provides functions to read the full configuration or fetch a value using a dot-separated key.
 
-use std::sync::OnceLock;
 use serde::Serialize;
-use serde_json::{Value, Map};
+use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+use serde_json::{Map, Value};
+use std::any::{Any, TypeId};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+#[cfg(not(feature = "arc-swap"))]
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, PoisonError};
 
-/// Global configuration store, as a JSON object wrapped in a Mutex for mutable access.
-static GLOBAL_CONFIG: OnceLock<std::sync::Mutex<Map<String, Value>>> = OnceLock::new();
+#[cfg(feature = "intern")]
+use crate::intern::Interned;
+use crate::key::{AsConfigKey, ParsedKey, Segment, escape_segment, parse_key};
+use crate::suggest;
+
+pub use crate::builder::{BuildReport, ConfigBuilder};
+pub use crate::error::ConfigError;
+pub use crate::limits::Limits;
+pub use crate::loader::EnvSource;
+pub use crate::loader::load_dir;
+#[cfg(feature = "hcl")]
+pub use crate::loader::load_hcl_file;
+#[cfg(feature = "properties")]
+pub use crate::loader::load_properties_file;
+#[cfg(feature = "toml")]
+pub use crate::loader::load_standard_layout;
+pub use crate::loader::{DotenvOptions, load_dotenv, load_dotenv_with};
+#[cfg(feature = "remote")]
+pub use crate::loader::{FetchPolicy, RemoteOptions, RemoteSource, Validators, load_url};
+pub use crate::loader::{Format, load_file, load_file_as};
+pub use crate::loader::{INCLUDE_KEY, IncludeOptions, load_file_with_includes};
+pub use crate::loader::{Layer, LayerReport, load_layered};
+#[cfg(feature = "toml")]
+pub use crate::loader::{PROFILE_KEY, ProfileOptions, load_profile};
+#[cfg(feature = "ini")]
+pub use crate::loader::{load_ini_file, load_ini_file_lenient};
+pub use crate::loader::{load_json_file, load_json_file_into};
+pub use crate::loader::{load_reader, load_stdin};
+#[cfg(feature = "toml")]
+pub use crate::loader::{load_toml_file, load_toml_str};
+#[cfg(feature = "yaml")]
+pub use crate::loader::{load_yaml_file, load_yaml_profiles, load_yaml_str};
+#[cfg(feature = "query")]
+pub use crate::query::query;
+pub use crate::refresh::{Refresh, RefreshHandle, RefreshStatus, start_refresh};
+pub use crate::section::ConfigSection;
+pub use crate::source::{
+    APPLICATION_PRIORITY, ConfigSource, ReloadOptions, ReloadReport, SourceStatus, add_source,
+    reload_config, reload_config_with, reload_namespace, remove_source,
+};
+pub use crate::stats::{ConfigStats, NamespaceStats, config_stats};
+#[cfg(feature = "chrono")]
+pub use crate::typed::get_datetime;
+#[cfg(feature = "time")]
+pub use crate::typed::get_offset_datetime;
+pub use crate::typed::{
+    get_bool_lenient, get_bytes, get_bytes_exact, get_duration, get_existing_path, get_ip,
+    get_path, get_size, get_socket_addr, get_string_list, get_string_list_with,
+};
+#[cfg(feature = "uuid")]
+pub use crate::typed::{get_uuid, get_uuid_list};
+#[cfg(feature = "watch")]
+pub use crate::watch::{WatchHandle, WatchOptions, watch_file};
+
+/// Holds the current snapshot of the store: a `std` read-write lock by default, or a
+/// non-poisoning `parking_lot::RwLock` with the `parking_lot` feature. Readers share the
+/// lock, and it is only held long enough to clone or replace the `Arc`. With the
+/// `arc-swap` feature, which takes precedence, the `Arc` is swapped atomically and readers
+/// take no lock at all.
+#[cfg(not(any(feature = "arc-swap", feature = "parking_lot")))]
+type StoreLock = std::sync::RwLock<Arc<Snapshot>>;
+#[cfg(all(feature = "parking_lot", not(feature = "arc-swap")))]
+type StoreLock = parking_lot::RwLock<Arc<Snapshot>>;
+#[cfg(feature = "arc-swap")]
+type StoreLock = arc_swap::ArcSwap<Snapshot>;
+
+/// Global configuration store, as an immutable snapshot that writers replace wholesale.
+static GLOBAL_CONFIG: OnceLock<StoreLock> = OnceLock::new();
+
+/// One version of the store, mapping each namespace to its block.
+///
+/// Every block sits behind its own `Arc`, which the versions before and after share until
+/// a write changes that namespace, so a write only copies the namespaces it touches.
+#[derive(Default)]
+struct Snapshot {
+    namespaces: BTreeMap<String, Block>,
+    /// The blocks gathered into one map, built for the first reader that borrows the
+    /// whole configuration at once.
+    whole: OnceLock<Arc<Map<String, Value>>>,
+}
+
+impl Snapshot {
+    fn get(&self, namespace: &str) -> Option<&Value> {
+        self.namespaces.get(namespace).map(Block::value)
+    }
+
+    /// Returns the raw namespace registered under `namespace`, if any.
+    fn raw(&self, namespace: &str) -> Option<&RawNamespace> {
+        self.namespaces.get(namespace)?.raw()
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &String> {
+        self.namespaces.keys()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.namespaces
+            .iter()
+            .map(|(name, block)| (name, block.value()))
+    }
+
+    /// Returns the whole configuration as one map, copying the blocks into it the first
+    /// time it is asked for.
+    fn whole(&self) -> &Arc<Map<String, Value>> {
+        self.whole.get_or_init(|| {
+            let map = self
+                .iter()
+                .map(|(name, block)| (name.clone(), block.clone()));
+            Arc::new(map.collect())
+        })
+    }
+}
+
+/// A namespace's block in a [`Snapshot`].
+#[derive(Clone)]
+enum Block {
+    Value(Arc<Value>),
+    /// A [raw namespace](register_raw_config), which reads see through its parsed copy.
+    Raw(Arc<RawBlock>),
+}
+
+impl Block {
+    fn value(&self) -> &Value {
+        match self {
+            Block::Value(value) => value,
+            Block::Raw(raw) => raw.parsed.get_or_init(|| raw.namespace.to_value()),
+        }
+    }
+
+    fn raw(&self) -> Option<&RawNamespace> {
+        match self {
+            Block::Value(_) => None,
+            Block::Raw(raw) => Some(&raw.namespace),
+        }
+    }
+}
+
+impl From<RawNamespace> for Block {
+    fn from(namespace: RawNamespace) -> Self {
+        Block::Raw(Arc::new(RawBlock {
+            namespace,
+            parsed: OnceLock::new(),
+        }))
+    }
+}
+
+/// Raw namespaces are written out as their text, and interned ones without a copy.
+impl Serialize for Block {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Block::Value(value) => value.serialize(serializer),
+            Block::Raw(raw) => raw.namespace.serialize(serializer),
+        }
+    }
+}
+
+/// A raw namespace, together with the copy of it parsed by the first read that looks
+/// into it, which later reads share.
+struct RawBlock {
+    namespace: RawNamespace,
+    parsed: OnceLock<Value>,
+}
+
+/// The namespaces a write may change, which are the only ones it copies.
+enum Scope {
+    /// The namespaces with these names, whether they exist yet or not.
+    Namespaces(Vec<String>),
+    /// Every namespace.
+    Whole,
+}
+
+impl Scope {
+    /// The namespace that the parsed key `segments` falls under.
+    fn key(segments: &[Segment]) -> Self {
+        Scope::Namespaces(
+            segments
+                .iter()
+                .take(1)
+                .map(|s| s.name.to_string())
+                .collect(),
+        )
+    }
+
+    /// The namespaces that `keys` fall under, skipping any key that does not parse, which
+    /// fails the write anyway.
+    fn keys<'a>(keys: impl IntoIterator<Item = &'a str>) -> Self {
+        let names = keys
+            .into_iter()
+            .filter_map(|key| Some(parse_key(key).ok()?.first()?.name.to_string()));
+        Scope::Namespaces(names.collect())
+    }
+}
+
+/// Serializes writers, each of which builds the next snapshot from the current one while
+/// readers keep using the current one.
+static WRITER: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+thread_local! {
+    /// Whether this thread is inside a write callback, where another write would deadlock.
+    static IN_WRITE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The final snapshot and its generation, set by [`freeze_config`]. Once set, readers use
+/// it without locking and every write fails.
+static FROZEN: OnceLock<(u64, Arc<Snapshot>)> = OnceLock::new();
+
+/// Incremented after every write to the store, so cached derived values can tell whether
+/// they are stale.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Typed values deserialized by [`get_cached`], keyed by type and then namespace, each
+/// tagged with the store generation it was read at.
+type TypedCache = HashMap<TypeId, HashMap<String, (u64, Arc<dyn Any + Send + Sync>)>>;
+
+static TYPED_CACHE: OnceLock<std::sync::Mutex<TypedCache>> = OnceLock::new();
+
+/// A namespace kept as it was registered rather than as a JSON value, as a [raw
+/// namespace](register_raw_config).
+#[derive(Clone)]
+enum RawNamespace {
+    /// Unparsed JSON text, from [`register_raw_config`].
+    Text(Arc<RawValue>),
+    /// A value with its strings shared, from `register_interned_config`.
+    #[cfg(feature = "intern")]
+    Interned(Arc<Interned>),
+}
+
+impl RawNamespace {
+    /// Parses or copies the namespace into an owned JSON value.
+    fn to_value(&self) -> Value {
+        match self {
+            RawNamespace::Text(raw) => parse_raw(raw),
+            #[cfg(feature = "intern")]
+            RawNamespace::Interned(interned) => interned.to_value(),
+        }
+    }
+}
+
+impl Serialize for RawNamespace {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            RawNamespace::Text(raw) => raw.serialize(serializer),
+            #[cfg(feature = "intern")]
+            RawNamespace::Interned(interned) => interned.serialize(serializer),
+        }
+    }
+}
+
+/// The [`RegistrationPolicy`] set by [`set_registration_policy`], as its discriminant.
+static REGISTRATION_POLICY: AtomicU8 = AtomicU8::new(RegistrationPolicy::Overwrite as u8);
+
+/// The blocks registered through [`register_config`] and its siblings, as they would be
+/// with no other writes. [`reload_config`] layers them at [`APPLICATION_PRIORITY`] between
+/// the configuration sources. Written only under the writer lock, like the snapshot.
+static REGISTERED: OnceLock<std::sync::Mutex<Map<String, Value>>> = OnceLock::new();
+
+/// The values written at runtime, through [`set_config_by_key`] and its siblings or by
+/// the loader functions, as they would be over the registered blocks with no other
+/// writes. [`reload_config`] layers them over [`REGISTERED`], at the same priority.
+/// Written only under the writer lock, like the snapshot.
+static RUNTIME: OnceLock<std::sync::Mutex<Map<String, Value>>> = OnceLock::new();
+
+/// Set by [`enable_strict_init`].
+static STRICT_INIT: AtomicBool = AtomicBool::new(false);
+
+/// Set once a read before initialization has been logged, so the warning is not repeated.
+static WARNED_UNINITIALIZED: AtomicBool = AtomicBool::new(false);
 
 /// Initializes the global configuration as an empty JSON object.
 /// This should be called once early in the application startup.
 pub fn init_config() {
-    GLOBAL_CONFIG.get_or_init(|| std::sync::Mutex::new(Map::new()));
+    GLOBAL_CONFIG.get_or_init(Default::default);
+}
+
+/// Makes reads before initialization an error.
+///
+/// The store counts as initialized once [`init_config`] has been called or anything has
+/// been written to it. By default, reading an uninitialized store behaves as if it were
+/// empty and logs a warning the first time. In strict mode, [`try_get_config`],
+/// [`try_get_config_by_key`] and [`get_required`] return [`ConfigError::NotInitialized`]
+/// instead, which surfaces subsystems that read their configuration before `main` set it
+/// up. The non-`try` readers keep returning empty results.
+///
+/// Strict mode cannot be turned off again, so call this first thing in `main`.
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::enable_strict_init();
+/// assert!(matches!(
+///     config::try_get_config_by_key("server.port"),
+///     Err(config::ConfigError::NotInitialized)
+/// ));
+///
+/// config::init_config();
+/// assert_eq!(config::try_get_config_by_key("server.port").unwrap(), None);
+/// ```
+pub fn enable_strict_init() {
+    STRICT_INIT.store(true, Ordering::Relaxed);
+}
+
+/// Makes the configuration read-only for the rest of the process.
+///
+/// Call this once bootstrapping is done, so that no code path can change the configuration
+/// at runtime. Afterwards, mutators that return a `Result`, such as [`try_register_config`],
+/// [`set_config_by_key`] and [`merge_config`], fail with [`ConfigError::Frozen`], and the
+/// others, such as [`register_config`] and [`update_config`], panic. Reads are unaffected,
+/// and no longer take the store lock. Freezing cannot be undone, and freezing twice does
+/// nothing.
+///
+/// # Panics
+///
+/// Panics if called from inside a write callback.
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config::{self, ConfigError};
+/// use serde_json::json;
+///
+/// config::register_config("server", json!({ "port": 8080 }));
+/// config::freeze_config();
+///
+/// let err = config::set_config_by_key("server.port", 9090).unwrap_err();
+/// assert!(matches!(err, ConfigError::Frozen));
+/// assert_eq!(config::get_config_by_key("server.port"), Some(json!(8080)));
+/// ```
+pub fn freeze_config() {
+    assert!(
+        !IN_WRITE.get(),
+        "the configuration cannot be frozen from inside a write callback"
+    );
+    let global = GLOBAL_CONFIG.get_or_init(Default::default);
+    // Held so that no write is in flight while the final snapshot is taken.
+    let _writer = lock_writer();
+    FROZEN.get_or_init(|| load_snapshot(global));
+}
+
+/// Returns whether [`freeze_config`] has been called.
+pub fn is_frozen() -> bool {
+    FROZEN.get().is_some()
+}
+
+/// What [`register_config`] does when something is already registered under the namespace.
+///
+/// Set process-wide with [`set_registration_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum RegistrationPolicy {
+    /// Replace the existing block. This is the default.
+    #[default]
+    Overwrite,
+    /// Replace the existing block and log a warning naming the namespace.
+    Warn,
+    /// Keep the existing block and fail with [`ConfigError::AlreadyRegistered`].
+    Error,
+    /// Deep-merge the new block into the existing one, as [`merge_config_at`] does.
+    DeepMerge,
+}
+
+impl RegistrationPolicy {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => RegistrationPolicy::Warn,
+            2 => RegistrationPolicy::Error,
+            3 => RegistrationPolicy::DeepMerge,
+            _ => RegistrationPolicy::Overwrite,
+        }
+    }
+}
+
+/// Sets what registering a namespace that is already registered does, for the whole
+/// process.
+///
+/// The policy applies to [`register_config`], [`try_register_config`], the
+/// [`register_config!`](crate::register_config) macro and [`register_batch`]. Set it once
+/// at startup, before dependencies register their configuration.
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config::{self, ConfigError, RegistrationPolicy};
+/// use serde_json::json;
+///
+/// config::set_registration_policy(RegistrationPolicy::Error);
+/// config::register_config("metrics", json!({ "port": 9100 }));
+///
+/// let err = config::try_register_config("metrics", json!({ "port": 9200 })).unwrap_err();
+/// assert!(matches!(err, ConfigError::AlreadyRegistered { .. }));
+/// assert_eq!(config::get_config_by_key("metrics.port"), Some(json!(9100)));
+/// ```
+pub fn set_registration_policy(policy: RegistrationPolicy) {
+    REGISTRATION_POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+/// Returns the policy set by [`set_registration_policy`].
+pub fn registration_policy() -> RegistrationPolicy {
+    RegistrationPolicy::from_u8(REGISTRATION_POLICY.load(Ordering::Relaxed))
 }
 
 /// Registers a configuration block under a given namespace.
-/// 
+///
 /// # Arguments
 ///
 /// * `namespace` - The key under which to register the configuration (for example, "server").
@@ -33,103 +435,4737 @@ pub fn init_config() {
 ///     port: u16,
 ///     host: String,
 /// }
-/// 
+///
 /// let server_config = ServerConfig { port: 3000, host: "0.0.0.0".to_string() };
-/// register_config!("server", server_config);
+/// register_config!("server", server_config).unwrap();
 /// ```
-pub fn register_config<T: Serialize>(namespace: &str, config: T) {
-    let global = GLOBAL_CONFIG.get_or_init(|| std::sync::Mutex::new(Map::new()));
-    let value = serde_json::to_value(config).expect("Serialization failed");
-    let mut guard = global.lock().expect("Mutex poisoned");
-    guard.insert(namespace.to_string(), value);
-}
-
-pub fn get_config() -> Value {
-    if let Some(global) = GLOBAL_CONFIG.get() {
-        let guard = global.lock().expect("Mutex poisoned");
-        Value::Object(guard.clone())
-    } else {
-        Value::Object(Map::new())
+///
+/// A namespace containing dots is a path, so registering under `"server.tls"` stores the
+/// block under the `tls` key of the `server` object, creating it if needed, where
+/// `get_config_by_key("server.tls.cert")` finds it. Segments can be quoted as in any other
+/// key (see the [`key`](crate::key) module) to register under a top-level name that really
+/// contains a dot. Empty and whitespace-only namespaces, or segments, are invalid.
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("server", json!({ "port": 443 }));
+/// config::register_config("server.tls", json!({ "cert": "/etc/tls/cert.pem" }));
+/// assert_eq!(config::get_config_by_key("server.tls.cert"), Some(json!("/etc/tls/cert.pem")));
+/// assert_eq!(config::get_config_by_key("server.port"), Some(json!(443)));
+/// ```
+///
+/// Returns the block previously registered under `namespace`, if any, so that callers can
+/// detect and report when they replaced someone else's configuration. What happens to it
+/// is decided by the [`RegistrationPolicy`]; by default, it is replaced. The
+/// `register_config!` macro discards it, and returns registration errors instead of
+/// panicking.
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// assert_eq!(config::register_config("cache", json!({ "ttl": 60 })), None);
+/// let previous = config::register_config("cache", json!({ "ttl": 300 }));
+/// assert_eq!(previous, Some(json!({ "ttl": 60 })));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `config` cannot be serialized, if `namespace` is invalid, if a parent
+/// segment of a dotted namespace holds a value that is not an object, or if the
+/// configuration is [frozen](freeze_config). Use [`try_register_config`] to handle those
+/// cases instead.
+pub fn register_config<T: Serialize>(namespace: &str, config: T) -> Option<Value> {
+    match try_register_config(namespace, config) {
+        Ok(previous) => previous,
+        Err(err) => panic!("{err}"),
     }
 }
 
-/// Retrieves a configuration value given a dot-separated key path (e.g., "server.port").
-/// 
-/// If the key is not found, returns `None`.
+/// Registers a configuration block under a given namespace, like [`register_config`], but
+/// returns an error instead of panicking.
 ///
-/// # Examples
+/// Fails with [`ConfigError::Serialization`] if `config` cannot be serialized,
+/// [`ConfigError::InvalidPath`] if `namespace` is empty or has an empty or whitespace-only
+/// segment, and [`ConfigError::NotAnObject`] if a parent segment of a dotted namespace
+/// holds a value that is not an object, as well as [`ConfigError::AlreadyRegistered`] under
+/// [`RegistrationPolicy::Error`] or under a [raw namespace](register_raw_config), and
+/// [`ConfigError::Frozen`] once the configuration is
+/// [frozen](freeze_config). The store is left untouched on failure.
+///
+/// # Example
 ///
 /// ```rust
-/// # use zirv_config::read_config;
-/// // Get full config
-/// let full_config = read_config!();
-/// println!("Config: {:?}", full_config);
+/// # use zirv_config::config;
+/// use std::collections::HashMap;
+///
+/// // JSON object keys must be strings, so this map cannot be serialized.
+/// let bad: HashMap<(u8, u8), bool> = HashMap::from([((0, 0), true)]);
+/// let err = config::try_register_config("grid", bad).unwrap_err();
+/// assert!(matches!(err, config::ConfigError::Serialization { .. }));
+///
+/// assert_eq!(config::try_register_config("grid", [1, 2]).unwrap(), None);
+///
+/// let err = config::try_register_config(" ", [1, 2]).unwrap_err();
+/// assert!(matches!(err, config::ConfigError::InvalidPath { .. }));
 /// ```
-pub fn get_config_by_key(key: &str) -> Option<Value> {
-    let mut current = get_config();
-    for part in key.split('.') {
-        match current {
-            Value::Object(map) => {
-                if let Some(v) = map.get(part) {
-                    current = v.clone();
-                } else {
-                    return None;
-                }
-            }
-            _ => return None,
+pub fn try_register_config<T: Serialize>(
+    namespace: &str,
+    config: T,
+) -> Result<Option<Value>, ConfigError> {
+    let segments = parse_namespace(namespace)?;
+    let value = serde_json::to_value(config).map_err(|source| ConfigError::Serialization {
+        key: namespace.to_string(),
+        source,
+    })?;
+    let policy = registration_policy();
+    try_write_store(Scope::key(&segments), |map| {
+        let previous = register_at(map, namespace, &segments, value.clone(), policy)?;
+        record_registration(namespace, &segments, value, policy);
+        Ok(previous)
+    })
+}
+
+/// Registers `value` at the parsed `namespace` in `map`, applying `policy` if something is
+/// already there, and returns the previous block.
+fn register_at(
+    map: &mut Map<String, Value>,
+    namespace: &str,
+    segments: &[Segment],
+    value: Value,
+    policy: RegistrationPolicy,
+) -> Result<Option<Value>, ConfigError> {
+    reject_raw(segments)?;
+    let (parent, leaf) = parent_mut(map, namespace, segments)?;
+    let Some(existing) = parent.get_mut(leaf) else {
+        parent.insert(leaf.to_string(), value);
+        return Ok(None);
+    };
+    match policy {
+        RegistrationPolicy::Overwrite => {}
+        RegistrationPolicy::Warn => {
+            log::warn!("replacing the configuration registered under \"{namespace}\"");
+        }
+        RegistrationPolicy::Error => {
+            return Err(ConfigError::AlreadyRegistered {
+                namespace: namespace.to_string(),
+            });
+        }
+        RegistrationPolicy::DeepMerge => {
+            let previous = existing.clone();
+            merge_value(existing, value);
+            return Ok(Some(previous));
         }
     }
-    Some(current)
+    Ok(Some(std::mem::replace(existing, value)))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde_json::json;
+/// Fails with [`ConfigError::AlreadyRegistered`] if the first of `segments` names a
+/// [raw namespace](register_raw_config). Registrations check this before touching
+/// [`REGISTERED`], which the check of the store on every write comes too late for.
+fn reject_raw(segments: &[Segment]) -> Result<(), ConfigError> {
+    match raw_namespace(&segments[0].name) {
+        Some(_) => Err(ConfigError::AlreadyRegistered {
+            namespace: segments[0].name.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
 
-    #[derive(serde::Serialize)]
-    struct DummyConfig {
-        port: u16,
-        host: String,
+/// Applies a registration that succeeded in the store to [`REGISTERED`], which must be
+/// done under the writer lock.
+fn record_registration(
+    namespace: &str,
+    segments: &[Segment],
+    value: Value,
+    policy: RegistrationPolicy,
+) {
+    // The store has already applied the policy, so only whether to merge matters here.
+    let policy = match policy {
+        RegistrationPolicy::DeepMerge => RegistrationPolicy::DeepMerge,
+        _ => RegistrationPolicy::Overwrite,
+    };
+    // The block replaces what was written under it at runtime too, or is merged over it.
+    let mut runtime = runtime();
+    match block_at(&runtime, segments) {
+        Some(mut written) if policy == RegistrationPolicy::DeepMerge => {
+            merge_value(&mut written, value.clone());
+            if let Ok(slot) = entry_mut(&mut runtime, namespace, segments) {
+                *slot = written;
+            }
+        }
+        _ => {
+            remove_entry(&mut runtime, segments);
+        }
     }
+    drop(runtime);
+    let mut registered = registered();
+    if let Err(err) = register_at(&mut registered, namespace, segments, value, policy) {
+        log::debug!("not recording the block registered under \"{namespace}\": {err}");
+    }
+}
 
-    #[test]
-    fn test_register_and_get_full_config() {
-        // Initialize global config.
-        init_config();
+/// Locks [`REGISTERED`], recovering it if a thread panicked while holding it.
+fn registered() -> std::sync::MutexGuard<'static, Map<String, Value>> {
+    REGISTERED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
 
-        // Register two configuration blocks.
-        register_config("server", DummyConfig { port: 3000, host: "0.0.0.0".into() });
-        register_config("logging", json!({
-            "level": "info",
-            "file": "app.log"
-        }));
+/// Locks [`RUNTIME`], recovering it if a thread panicked while holding it.
+fn runtime() -> std::sync::MutexGuard<'static, Map<String, Value>> {
+    RUNTIME
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
 
-        let config = get_config();
-        if let Value::Object(map) = config {
-            assert!(map.contains_key("server"));
-            assert!(map.contains_key("logging"));
-        } else {
-            panic!("Global config is not an object");
+/// Returns a copy of the blocks [registered](register_config) by the application, with
+/// the values written at runtime over them: what [`reload_config`] layers at
+/// [`APPLICATION_PRIORITY`].
+pub(crate) fn application_config() -> Map<String, Value> {
+    let mut layer = registered().clone();
+    merge_map(&mut layer, runtime().clone());
+    layer
+}
+
+/// Replaces the store with `below`, the blocks [registered](register_config) and the
+/// values written at runtime over it, and `above` over those, as [`reload_config`] does,
+/// and returns the number of keys changed.
+pub(crate) fn rebuild_config(
+    below: Map<String, Value>,
+    above: Map<String, Value>,
+) -> Result<usize, ConfigError> {
+    try_write_store(Scope::Whole, |map| {
+        let mut next = below;
+        merge_map(&mut next, application_config());
+        merge_map(&mut next, above);
+        let changed = count_changed(map, &next);
+        *map = next;
+        Ok(changed)
+    })
+}
+
+/// Replaces the block at `namespace` in the store with that of `below`, the blocks
+/// [registered](register_config) and the values written at runtime over it, and `above`
+/// over those, as [`reload_namespace`] does, removing it if none of them has one.
+pub(crate) fn rebuild_namespace(
+    namespace: &str,
+    below: &Map<String, Value>,
+    above: &Map<String, Value>,
+) -> Result<(), ConfigError> {
+    let segments = parse_namespace(namespace)?;
+    try_write_store(Scope::key(&segments), |map| {
+        let layers = [
+            block_at(below, &segments),
+            block_at(&registered(), &segments),
+            block_at(&runtime(), &segments),
+            block_at(above, &segments),
+        ];
+        let mut block: Option<Value> = None;
+        for layer in layers.into_iter().flatten() {
+            match &mut block {
+                Some(block) => merge_value(block, layer),
+                None => block = Some(layer),
+            }
+        }
+        match block {
+            Some(block) => *entry_mut(map, namespace, &segments)? = block,
+            None => {
+                remove_entry(map, &segments);
+            }
         }
+        Ok(())
+    })
+}
+
+/// Returns a copy of the value at `segments` in `map`, if every parent along the way is an
+/// object.
+fn block_at(map: &Map<String, Value>, segments: &[Segment]) -> Option<Value> {
+    let (leaf, parents) = segments.split_last()?;
+    let mut current = map;
+    for segment in parents {
+        current = match current.get(segment.name.as_ref()) {
+            Some(Value::Object(child)) => child,
+            _ => return None,
+        };
     }
+    current.get(leaf.name.as_ref()).cloned()
+}
 
-    #[test]
-    fn test_get_config_by_key() {
-        // Clear and reinitialize the global config.
-        // (For testing, we can simulate a new initialization by using a new OnceLock instance.)
-        // Note: In real usage, GLOBAL_CONFIG is static and persistent across calls.
-        init_config();
-        register_config("server", DummyConfig { port: 3000, host: "0.0.0.0".into() });
+/// Counts the flattened keys, as [`flatten_config`] produces them, that `old` and `new`
+/// do not share with the same value.
+fn count_changed(old: &Map<String, Value>, new: &Map<String, Value>) -> usize {
+    let (old, new) = (flatten_map(old), flatten_map(new));
+    let removed = old.keys().filter(|key| !new.contains_key(*key)).count();
+    let changed = new
+        .iter()
+        .filter(|(key, value)| old.get(*key) != Some(value))
+        .count();
+    removed + changed
+}
 
-        let port = get_config_by_key("server.port");
-        assert_eq!(port, Some(json!(3000)));
+/// Registers a configuration block only if nothing is registered under `namespace` yet.
+///
+/// Intended for libraries that ship defaults: whichever of the application and the library
+/// registers first, the application's configuration is kept. Returns `true` if `config`
+/// was inserted and `false` if an existing block, or a [raw](register_raw_config) one,
+/// was left in place. Only the namespace
+/// itself is checked; keys are not merged into an existing block. Dotted namespaces are
+/// paths, as in [`register_config`].
+///
+/// # Panics
+///
+/// Panics if `config` cannot be serialized, if `namespace` is invalid, if a parent
+/// segment of a dotted namespace holds a value that is not an object, or if the
+/// configuration is [frozen](freeze_config).
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("pool", json!({ "size": 32 }));
+/// assert!(!config::register_config_once("pool", json!({ "size": 8 })));
+/// assert_eq!(config::get_config_by_key("pool.size"), Some(json!(32)));
+/// ```
+pub fn register_config_once<T: Serialize>(namespace: &str, config: T) -> bool {
+    let value = serde_json::to_value(config).unwrap_or_else(|source| {
+        panic!(
+            "{}",
+            ConfigError::Serialization {
+                key: namespace.to_string(),
+                source
+            }
+        )
+    });
+    let inserted = parse_namespace(namespace).and_then(|segments| {
+        try_write_store(Scope::key(&segments), |map| {
+            // A raw namespace is a registered block too.
+            if segments.len() == 1 && raw_namespace(&segments[0].name).is_some() {
+                return Ok(false);
+            }
+            reject_raw(&segments)?;
+            let (parent, leaf) = parent_mut(map, namespace, &segments)?;
+            Ok(match parent.entry(leaf) {
+                serde_json::map::Entry::Vacant(entry) => {
+                    entry.insert(value.clone());
+                    record_registration(namespace, &segments, value, RegistrationPolicy::Overwrite);
+                    true
+                }
+                serde_json::map::Entry::Occupied(_) => false,
+            })
+        })
+    });
+    inserted.unwrap_or_else(|err| panic!("{err}"))
+}
 
-        let host = get_config_by_key("server.host");
-        assert_eq!(host, Some(json!("0.0.0.0")));
+/// Returns the block registered under `namespace`, registering the result of `init` first
+/// if there is none.
+///
+/// `init` is only invoked when the namespace is absent, and runs while other writers are
+/// held off, so concurrent callers never compute the block twice. Like the closure passed
+/// to [`update_config`], it may read the configuration but must not write to it. Dotted
+/// namespaces are paths, as in [`register_config`]. A [raw namespace](register_raw_config)
+/// is returned parsed.
+///
+/// # Panics
+///
+/// Panics if `init` returns a value that cannot be serialized, if `namespace` is invalid,
+/// or if a parent segment of a dotted namespace holds a value that is not an object. Once
+/// the configuration is [frozen](freeze_config), a registered block is still returned,
+/// but registering a missing one panics.
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// let hw = config::get_or_register("hardware", || json!({ "cores": 8 }));
+/// assert_eq!(hw, json!({ "cores": 8 }));
+///
+/// // Already registered: the closure is not run again.
+/// let hw = config::get_or_register("hardware", || -> serde_json::Value { unreachable!() });
+/// assert_eq!(hw, json!({ "cores": 8 }));
+/// ```
+pub fn get_or_register<T: Serialize, F: FnOnce() -> T>(namespace: &str, init: F) -> Value {
+    if is_frozen() {
+        let existing = read_store(|map| namespace_value(map, namespace));
+        return match existing {
+            Ok(value) => value,
+            Err(ConfigError::KeyNotFound { .. }) => panic!("{}", ConfigError::Frozen),
+            Err(err) => panic!("{err}"),
+        };
+    }
+    // Errors are raised after the write is abandoned, so the panic does not unwind through
+    // the writer lock.
+    let value = parse_namespace(namespace).and_then(|segments| {
+        try_write_store(Scope::key(&segments), |map| {
+            if segments.len() == 1
+                && let Some(raw) = raw_namespace(&segments[0].name)
+            {
+                return Ok(raw.to_value());
+            }
+            reject_raw(&segments)?;
+            let (parent, leaf) = parent_mut(map, namespace, &segments)?;
+            match parent.entry(leaf) {
+                serde_json::map::Entry::Occupied(entry) => Ok(entry.get().clone()),
+                serde_json::map::Entry::Vacant(entry) => {
+                    let value = serde_json::to_value(init()).map_err(|source| {
+                        ConfigError::Serialization {
+                            key: namespace.to_string(),
+                            source,
+                        }
+                    })?;
+                    entry.insert(value.clone());
+                    record_registration(
+                        namespace,
+                        &segments,
+                        value.clone(),
+                        RegistrationPolicy::Overwrite,
+                    );
+                    Ok(value)
+                }
+            }
+        })
+    });
+    value.unwrap_or_else(|err| panic!("{err}"))
+}
 
-        // Non-existent key returns None.
-        let missing = get_config_by_key("server.nonexistent");
-        assert!(missing.is_none());
+/// Removes a whole configuration block registered under `namespace`.
+///
+/// Returns the removed block, or `None` if nothing was registered under that namespace.
+/// Dotted namespaces are paths, as in [`register_config`].
+///
+/// # Panics
+///
+/// Panics if the configuration is [frozen](freeze_config).
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::{register_config, unregister_config};
+/// register_config!("plugin", serde_json::json!({ "enabled": true })).unwrap();
+/// assert!(unregister_config!("plugin").is_some());
+/// assert!(unregister_config!("plugin").is_none());
+/// ```
+pub fn unregister_config(namespace: &str) -> Option<Value> {
+    let segments = parse_namespace(namespace).ok()?;
+    let removed = try_write_store(Scope::key(&segments), |map| {
+        let removed = remove_entry(map, &segments).ok_or(None)?;
+        remove_entry(&mut registered(), &segments);
+        remove_entry(&mut runtime(), &segments);
+        Ok(removed)
+    });
+    match removed {
+        Ok(removed) => Some(removed),
+        Err(None) => None,
+        Err(Some(err)) => panic!("{err}"),
+    }
+}
+
+/// Registers a namespace as a blob of JSON text that is kept as is rather than parsed.
+///
+/// Suits large blocks, such as generated routing tables, that the application mostly
+/// passes through verbatim: the text is stored once and shared by [`get_raw_config`]
+/// without being parsed or copied, and [`serialize_config_to_writer`] writes it out as
+/// is. Every other read sees it like a registered block: the first one that looks into
+/// it, such as [`get_config_by_key`], [`get_namespace`] or [`get_config_arc`], parses
+/// the text, and later reads share the parsed copy until the namespace is registered
+/// again or removed.
+///
+/// Nothing else may be written under a raw namespace: [`set_config_by_key`], the merges
+/// and every other mutator fail with [`ConfigError::AlreadyRegistered`] for a key whose
+/// first segment names a raw namespace, or panic with it if they panic on errors, like
+/// [`update_config`]. A raw namespace must be a single segment. Returns the blob previously registered under it,
+/// if any.
+///
+/// Fails with [`ConfigError::InvalidPath`] if `namespace` is invalid or has more than one
+/// segment, [`ConfigError::AlreadyRegistered`] if a regular block, or one interned with
+/// `register_interned_config`, is registered under it (registering a regular block under
+/// a raw namespace fails likewise), and
+/// [`ConfigError::Frozen`] once the configuration is [frozen](freeze_config).
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::value::RawValue;
+///
+/// let table = RawValue::from_string(r#"{"routes": [{"path": "/", "to": "web"}]}"#.into()).unwrap();
+/// config::register_raw_config("routing", table).unwrap();
+///
+/// assert_eq!(config::get_raw_config("routing").unwrap().get(), r#"{"routes": [{"path": "/", "to": "web"}]}"#);
+/// assert_eq!(config::get_config_by_key("routing.routes.0.to"), Some("web".into()));
+/// ```
+pub fn register_raw_config(
+    namespace: &str,
+    raw: Box<RawValue>,
+) -> Result<Option<Arc<RawValue>>, ConfigError> {
+    let name = raw_namespace_name(namespace)?;
+    try_write_snapshot(|namespaces| {
+        let previous = match namespaces.get(&name).map(Block::raw) {
+            Some(Some(RawNamespace::Text(previous))) => Some(Arc::clone(previous)),
+            None => None,
+            _ => return Err(ConfigError::AlreadyRegistered { namespace: name }),
+        };
+        namespaces.insert(name, RawNamespace::Text(Arc::from(raw)).into());
+        Ok(previous)
+    })
+}
+
+/// Returns the blob registered under `namespace` with [`register_raw_config`], without
+/// parsing or copying it.
+pub fn get_raw_config(namespace: &str) -> Option<Arc<RawValue>> {
+    match raw_namespace(&raw_namespace_name(namespace).ok()?)? {
+        RawNamespace::Text(raw) => Some(raw),
+        #[cfg(feature = "intern")]
+        RawNamespace::Interned(_) => None,
+    }
+}
+
+/// Removes the blob registered under `namespace` with [`register_raw_config`] and
+/// returns it, or `None` if there is none.
+///
+/// # Panics
+///
+/// Panics if the configuration is [frozen](freeze_config).
+pub fn unregister_raw_config(namespace: &str) -> Option<Arc<RawValue>> {
+    let name = raw_namespace_name(namespace).ok()?;
+    let removed = try_write_snapshot(|namespaces| {
+        let removed = match namespaces.get(&name).and_then(Block::raw) {
+            Some(RawNamespace::Text(raw)) => Arc::clone(raw),
+            _ => return Err(None),
+        };
+        namespaces.remove(&name);
+        Ok(removed)
+    });
+    match removed {
+        Ok(removed) => Some(removed),
+        Err(None) => None,
+        Err(Some(err)) => panic!("{err}"),
+    }
+}
+
+/// Registers a namespace whose repeated strings, values and object keys alike, are stored
+/// once.
+///
+/// Suits large blocks that repeat the same strings over and over, such as generated tables
+/// whose entries all name the same few hosts, methods and keys: `config` is serialized,
+/// and each distinct string in it is kept once and shared by all its occurrences. Reads
+/// see the same JSON as if the block had been registered with [`register_config`], and
+/// [`serialize_config_to_writer`] writes it without copying it. The first read that looks
+/// into it, such as [`get_config_by_key`] or [`get_namespace`], makes a plain copy that
+/// later reads share, so the saving lasts until something reads into it.
+///
+/// An interned namespace is a [raw namespace](register_raw_config) kept interned rather
+/// than as text, under the same rules: it must be a single segment, and nothing else may
+/// be written under it. It has no blob for [`get_raw_config`] to return. Returns the block previously interned
+/// under it, if any.
+///
+/// Fails with [`ConfigError::Serialization`] if `config` cannot be serialized,
+/// [`ConfigError::InvalidPath`] if `namespace` is invalid or has more than one segment,
+/// [`ConfigError::AlreadyRegistered`] if a regular block or a raw blob is registered under
+/// it, and [`ConfigError::Frozen`] once the configuration is [frozen](freeze_config).
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// let routes: Vec<_> = (0..3)
+///     .map(|index| json!({ "path": format!("/v1/{index}"), "upstream": "billing.internal" }))
+///     .collect();
+/// config::register_interned_config("routing", json!({ "routes": routes })).unwrap();
+///
+/// let routing: serde_json::Value = config::get_namespace("routing").unwrap();
+/// assert_eq!(routing["routes"][2]["upstream"], "billing.internal");
+/// assert_eq!(config::get_config()["routing"]["routes"][0]["path"], "/v1/0");
+/// assert_eq!(
+///     zirv_config::read_config!("routing.routes.1.path" as String).unwrap(),
+///     "/v1/1"
+/// );
+/// ```
+#[cfg(feature = "intern")]
+pub fn register_interned_config<T: Serialize>(
+    namespace: &str,
+    config: T,
+) -> Result<Option<Value>, ConfigError> {
+    let name = raw_namespace_name(namespace)?;
+    let value = serde_json::to_value(config).map_err(|source| ConfigError::Serialization {
+        key: namespace.to_string(),
+        source,
+    })?;
+    // Interned before other writers are held off, since a large block takes a while.
+    let interned = Arc::new(Interned::new(value));
+    try_write_snapshot(|namespaces| {
+        let previous = match namespaces.get(&name).map(Block::raw) {
+            Some(Some(RawNamespace::Interned(previous))) => Some(previous.to_value()),
+            None => None,
+            _ => return Err(ConfigError::AlreadyRegistered { namespace: name }),
+        };
+        namespaces.insert(name, RawNamespace::Interned(interned).into());
+        Ok(previous)
+    })
+}
+
+/// Removes the block registered under `namespace` with [`register_interned_config`] and
+/// returns it, or `None` if there is none.
+///
+/// # Panics
+///
+/// Panics if the configuration is [frozen](freeze_config).
+#[cfg(feature = "intern")]
+pub fn unregister_interned_config(namespace: &str) -> Option<Value> {
+    let name = raw_namespace_name(namespace).ok()?;
+    let removed = try_write_snapshot(|namespaces| {
+        let removed = match namespaces.get(&name).and_then(Block::raw) {
+            Some(RawNamespace::Interned(interned)) => interned.to_value(),
+            _ => return Err(None),
+        };
+        namespaces.remove(&name);
+        Ok(removed)
+    });
+    match removed {
+        Ok(removed) => Some(removed),
+        Err(None) => None,
+        Err(Some(err)) => panic!("{err}"),
+    }
+}
+
+/// Parses a raw namespace, which must be a single segment, into its name.
+fn raw_namespace_name(namespace: &str) -> Result<String, ConfigError> {
+    match parse_namespace(namespace)?.as_slice() {
+        [segment] => Ok(segment.name.to_string()),
+        _ => Err(ConfigError::InvalidPath {
+            key: namespace.to_string(),
+            message: "raw namespaces must be a single segment".to_string(),
+        }),
+    }
+}
+
+/// Returns the raw namespace registered under the top-level `name`, if any.
+fn raw_namespace(name: &str) -> Option<RawNamespace> {
+    read_store(|snapshot| snapshot.raw(name).cloned())
+}
+
+/// Parses a raw blob, which was validated as JSON when it was registered.
+fn parse_raw(raw: &RawValue) -> Value {
+    serde_json::from_str(raw.get()).expect("raw configuration is validated on registration")
+}
+
+/// Copies `snapshot` into a JSON object.
+fn whole_value(snapshot: &Snapshot) -> Value {
+    Value::Object(Map::clone(snapshot.whole()))
+}
+
+/// Returns the names of all registered namespaces, sorted alphabetically.
+///
+/// Only top-level names are listed, so a block registered under `"server.tls"` appears as
+/// `server`. [Raw namespaces](register_raw_config) are listed too.
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("server", serde_json::json!({ "port": 3000 }));
+/// config::register_config("logging", serde_json::json!({ "level": "info" }));
+/// assert_eq!(config::config_namespaces(), vec!["logging", "server"]);
+/// ```
+pub fn config_namespaces() -> Vec<String> {
+    read_store(|snapshot| snapshot.keys().cloned().collect())
+}
+
+/// Returns each registered namespace together with its number of direct child keys,
+/// sorted alphabetically by namespace.
+///
+/// Namespaces holding a non-object value report zero children.
+pub fn config_namespace_sizes() -> Vec<(String, usize)> {
+    read_store(|map| {
+        let mut sizes: Vec<(String, usize)> = map
+            .iter()
+            .map(|(name, value)| {
+                let children = value.as_object().map_or(0, Map::len);
+                (name.clone(), children)
+            })
+            .collect();
+        sizes.sort();
+        sizes
+    })
+}
+
+/// Returns a copy of the whole configuration.
+///
+/// This deep-clones every stored value on each call, and parses any [raw
+/// namespaces](register_raw_config), which is measurable for a large configuration.
+/// Prefer [`get_config_arc`], which shares the current snapshot instead, or
+/// [`get_config_by_key`] to read a single value.
+pub fn get_config() -> Value {
+    read_store(whole_value)
+}
+
+/// Returns the current snapshot of the whole configuration, without copying it.
+///
+/// Writers never modify a snapshot in place: each write installs a new one. The returned
+/// map therefore stays valid and unchanged for as long as it is held, however the
+/// configuration changes in the meantime. The first call after a write gathers the
+/// namespaces into the map, and later calls share it, so it is cheap to take on every
+/// request.
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("server", json!({ "port": 3000 }));
+/// let snapshot = config::get_config_arc();
+///
+/// config::set_config_by_key("server.port", 8080).unwrap();
+/// assert_eq!(snapshot["server"]["port"], json!(3000));
+/// assert_eq!(config::get_config_arc()["server"]["port"], json!(8080));
+/// ```
+pub fn get_config_arc() -> Arc<Map<String, Value>> {
+    read_store(|snapshot| Arc::clone(snapshot.whole()))
+}
+
+/// Runs `f` against a borrowed view of the whole configuration and returns its result.
+///
+/// Nothing is cloned beyond what [`get_config_arc`] copies once per write, which suits
+/// checks such as comparing a string or counting elements. The view is the snapshot
+/// current when `f` is called: writes made while it runs,
+/// including any it makes itself, are not visible through it. Keep the closure short, as
+/// the snapshot it reads stays alive until it returns, and do not call the mutating
+/// functions of this module from it.
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("cors", json!({ "origins": ["https://a.example"] }));
+/// let namespaces = config::with_config(|map| map.len());
+/// assert_eq!(namespaces, 1);
+/// ```
+pub fn with_config<R>(f: impl FnOnce(&Map<String, Value>) -> R) -> R {
+    read_store(|snapshot| f(snapshot.whole()))
+}
+
+/// Runs `f` against a borrowed view of the value at a dot-separated key path, or `None`
+/// if nothing is stored there, and returns its result.
+///
+/// Keys are resolved as in [`get_config_by_key`], and the view behaves as described for
+/// [`with_config`].
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("cors", json!({ "origins": ["https://a.example", "https://b.example"] }));
+///
+/// let count = config::with_config_at("cors.origins", |origins| {
+///     origins.and_then(|origins| origins.as_array()).map_or(0, Vec::len)
+/// });
+/// assert_eq!(count, 2);
+/// assert!(config::with_config_at("cors.origins.0", |origin| origin == Some(&json!("https://a.example"))));
+/// ```
+pub fn with_config_at<R>(key: impl AsConfigKey, f: impl FnOnce(Option<&Value>) -> R) -> R {
+    let Ok(key) = key.parsed_key() else {
+        return f(None);
+    };
+    read_store(|map| f(walk(map, &key.names()).ok()))
+}
+
+/// Serializes the whole configuration as JSON straight into `writer`, without copying it
+/// first.
+///
+/// The output is the same as serializing [`get_config`], but is written from the current
+/// snapshot, and [raw namespaces](register_raw_config) are written verbatim rather than
+/// parsed. With `pretty`, the output is indented as by `serde_json::to_writer_pretty`.
+/// Wrap unbuffered writers such as files in an `io::BufWriter`.
+///
+/// Fails with [`ConfigError::Serialization`] if writing fails.
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("server", serde_json::json!({ "port": 3000 }));
+///
+/// let mut out = Vec::new();
+/// config::serialize_config_to_writer(&mut out, false).unwrap();
+/// assert_eq!(out, br#"{"server":{"port":3000}}"#);
+/// ```
+pub fn serialize_config_to_writer<W: io::Write>(
+    writer: W,
+    pretty: bool,
+) -> Result<(), ConfigError> {
+    let snapshot = current_snapshot().1;
+    write_json(writer, "", &snapshot.namespaces, pretty)
+}
+
+/// Serializes the value at a dot-separated key path as JSON straight into `writer`, as
+/// [`serialize_config_to_writer`] does for the whole configuration.
+///
+/// A key naming a [raw namespace](register_raw_config) writes its text verbatim. Fails
+/// with [`ConfigError::KeyNotFound`] or the other lookup errors of [`get_required`] if
+/// nothing is stored at `key`, and with [`ConfigError::Serialization`] if writing fails.
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("server", serde_json::json!({ "tls": { "enabled": true } }));
+///
+/// let mut out = Vec::new();
+/// config::serialize_config_at_to_writer("server.tls", &mut out, false).unwrap();
+/// assert_eq!(out, br#"{"enabled":true}"#);
+/// ```
+pub fn serialize_config_at_to_writer<W: io::Write>(
+    key: impl AsConfigKey,
+    writer: W,
+    pretty: bool,
+) -> Result<(), ConfigError> {
+    let key = key.parsed_key()?;
+    let snapshot = current_snapshot().1;
+    if let [segment] = &key.segments[..]
+        && let Some(raw) = snapshot.raw(&segment.name)
+    {
+        return write_json(writer, key.raw, raw, pretty);
+    }
+    write_json(writer, key.raw, lookup(&snapshot, &key)?, pretty)
+}
+
+fn write_json<W: io::Write, T: Serialize + ?Sized>(
+    writer: W,
+    key: &str,
+    value: &T,
+    pretty: bool,
+) -> Result<(), ConfigError> {
+    let result = if pretty {
+        serde_json::to_writer_pretty(writer, value)
+    } else {
+        serde_json::to_writer(writer, value)
+    };
+    result.map_err(|source| ConfigError::Serialization {
+        key: key.to_string(),
+        source,
+    })
+}
+
+/// Returns the version of the configuration, a counter that every successful write
+/// increments.
+///
+/// Comparing versions tells cheaply whether the configuration has changed since it was
+/// last looked at, for caching values derived from it or reporting reloads. Failed
+/// writes, which leave the store untouched, do not count, and neither do reads. The
+/// counter starts at zero in every process and is not persisted anywhere.
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config;
+/// let before = config::get_config_version();
+/// config::set_config_by_key("server.port", 8080).unwrap();
+/// assert_eq!(config::get_config_version(), before + 1);
+///
+/// config::get_config_by_key("server.port");
+/// assert_eq!(config::get_config_version(), before + 1);
+/// ```
+pub fn get_config_version() -> u64 {
+    GENERATION.load(Ordering::Acquire)
+}
+
+/// Returns a copy of the whole configuration together with its
+/// [version](get_config_version), read from the same snapshot.
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("server", serde_json::json!({ "port": 3000 }));
+///
+/// let (config, version) = config::get_config_versioned();
+/// assert_eq!(config["server"]["port"], 3000);
+/// assert_eq!(version, config::get_config_version());
+/// ```
+pub fn get_config_versioned() -> (Value, u64) {
+    let (version, config) = read_versioned(whole_value);
+    (config, version)
+}
+
+/// Returns a copy of the whole configuration, like [`get_config`].
+///
+/// Fails only with [`ConfigError::NotInitialized`], when [strict
+/// initialization](enable_strict_init) is enabled and the store has not been initialized.
+///
+/// # Example
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("app", serde_json::json!({ "name": "demo" }));
+/// let config = config::try_get_config().unwrap();
+/// assert_eq!(config["app"]["name"], "demo");
+/// ```
+pub fn try_get_config() -> Result<Value, ConfigError> {
+    try_read_store(whole_value)
+}
+
+/// Retrieves a configuration value given a dot-separated key path (e.g., "server.port").
+///
+/// Numeric segments index into arrays, so `"upstreams.0.host"` reads the `host` of the
+/// first upstream, and negative segments count from the end (`"upstreams.-1"` is the last
+/// upstream). Object keys that happen to be numeric are still looked up as keys.
+///
+/// Segments containing dots can be quoted (`hosts."api.example.com".timeout`) or escaped
+/// with a backslash (`hosts.api\.example\.com.timeout`); see the [`key`](crate::key) module
+/// for the full syntax.
+///
+/// The key may be a string or a pre-parsed [`ConfigKey`](crate::key::ConfigKey). If the key
+/// is not found, returns `None`; use [`get_required`] for an error that lists the nearest
+/// keys that do exist, including "did you mean" suggestions for likely typos.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::read_config;
+/// // Get full config
+/// let full_config = read_config!();
+/// println!("Config: {:?}", full_config);
+/// ```
+pub fn get_config_by_key(key: impl AsConfigKey) -> Option<Value> {
+    get_config_by_path(&key.parsed_key().ok()?.names())
+}
+
+/// Retrieves a configuration value given a dot-separated key path, like
+/// [`get_config_by_key`], but reports problems instead of hiding them.
+///
+/// Returns `Ok(None)` if nothing is stored at `key`, [`ConfigError::InvalidPath`] if the
+/// key cannot be parsed, and [`ConfigError::NotInitialized`] if the store is read before
+/// initialization in [strict mode](enable_strict_init). Use [`get_required`] to treat a
+/// missing key as an error too.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("server", json!({ "port": 3000 }));
+/// assert_eq!(config::try_get_config_by_key("server.port").unwrap(), Some(json!(3000)));
+/// assert_eq!(config::try_get_config_by_key("server.host").unwrap(), None);
+///
+/// let err = config::try_get_config_by_key(r#"server."port"#).unwrap_err();
+/// assert!(matches!(err, config::ConfigError::InvalidPath { .. }));
+/// ```
+pub fn try_get_config_by_key(key: impl AsConfigKey) -> Result<Option<Value>, ConfigError> {
+    let key = key.parsed_key()?;
+    try_read_store(|map| walk(map, &key.names()).ok().cloned())
+}
+
+/// Whether a key is stored, explicitly set to `null`, or set to a value.
+///
+/// Returned by [`get_config_entry`], for settings where `null` means something different
+/// from the key being absent, such as `logging.file: null` turning file logging off while
+/// a missing `logging.file` means the default.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Entry {
+    /// Nothing is stored at the key.
+    Missing,
+    /// The key is present and holds `null`.
+    Null,
+    /// The key is present and holds this value, which is never `null`.
+    Value(Value),
+}
+
+impl Entry {
+    /// Returns `true` if nothing is stored at the key.
+    pub fn is_missing(&self) -> bool {
+        matches!(self, Entry::Missing)
+    }
+
+    /// Returns `true` if the key is present and holds `null`.
+    pub fn is_null(&self) -> bool {
+        matches!(self, Entry::Null)
+    }
+
+    /// Returns the stored value, or `None` if the key is missing or `null`.
+    pub fn into_value(self) -> Option<Value> {
+        match self {
+            Entry::Value(value) => Some(value),
+            Entry::Missing | Entry::Null => None,
+        }
+    }
+}
+
+/// Retrieves the entry at a dot-separated key path, telling an explicit `null` apart from
+/// a missing key.
+///
+/// A key that cannot be parsed, or whose path cannot be resolved, is [`Entry::Missing`],
+/// as it is `None` for [`get_config_by_key`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config::{self, Entry};
+/// use serde_json::json;
+///
+/// config::register_config("logging", json!({ "level": "info", "file": null }));
+///
+/// assert_eq!(config::get_config_entry("logging.level"), Entry::Value(json!("info")));
+/// assert_eq!(config::get_config_entry("logging.file"), Entry::Null);
+/// assert_eq!(config::get_config_entry("logging.format"), Entry::Missing);
+/// ```
+pub fn get_config_entry(key: impl AsConfigKey) -> Entry {
+    match get_config_by_key(key) {
+        None => Entry::Missing,
+        Some(Value::Null) => Entry::Null,
+        Some(value) => Entry::Value(value),
+    }
+}
+
+/// Retrieves a configuration value given a path of already-separated segments.
+///
+/// Each segment is used verbatim as an object key (or array index), so segments may
+/// contain dots or quotes without any escaping. If the path is not found, returns `None`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("hosts", json!({ "api.example.com": { "timeout": 30 } }));
+/// assert_eq!(
+///     config::get_config_by_path(&["hosts", "api.example.com", "timeout"]),
+///     Some(json!(30))
+/// );
+/// ```
+pub fn get_config_by_path<S: AsRef<str>>(path: &[S]) -> Option<Value> {
+    read_store(|map| walk(map, path).ok().cloned())
+}
+
+/// Retrieves a configuration value given an RFC 6901 JSON Pointer (e.g., "/server/port").
+///
+/// Unlike dot-separated keys, pointers can address keys containing dots: each `/`-separated
+/// token is unescaped (`~1` becomes `/` and `~0` becomes `~`) and used as an object key, or
+/// as an array index. Per the spec, `""` refers to the whole configuration and `"/"` to the
+/// key named by the empty string. Returns `None` if the pointer is malformed or does not
+/// resolve.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("hosts", json!({ "api.example.com": { "timeout": 30 } }));
+/// assert_eq!(config::get_config_by_pointer("/hosts/api.example.com/timeout"), Some(json!(30)));
+/// ```
+pub fn get_config_by_pointer(pointer: &str) -> Option<Value> {
+    if pointer.is_empty() {
+        return Some(get_config());
+    }
+    let mut tokens = pointer
+        .strip_prefix('/')?
+        .split('/')
+        .map(unescape_pointer_token);
+    read_store(|map| {
+        let mut current = map.get(&tokens.next()?)?;
+        for token in tokens {
+            current = match current {
+                Value::Object(map) => map.get(&token)?,
+                Value::Array(items) => items.get(parse_pointer_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(current.clone())
+    })
+}
+
+/// Retrieves a configuration value that must exist, given a dot-separated key path.
+///
+/// Unlike [`get_config_by_key`], a missing key is reported as [`ConfigError::KeyNotFound`],
+/// which names the requested key, the longest prefix that does exist, the first segment
+/// that does not and the keys available in its place. This makes it suitable for fail-fast validation at startup. A key
+/// that passes through a scalar, or through an array by a segment that is not an index, is
+/// reported as [`ConfigError::NotAnObject`] instead, naming the prefix that resolved to it
+/// and its type, where [`get_config_by_key`] returns `None`. In [strict
+/// mode](enable_strict_init), reading before initialization returns
+/// [`ConfigError::NotInitialized`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("db", serde_json::json!({ "host": "localhost" }));
+///
+/// let err = config::get_required("db.url").unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     r#"key not found: "db.url" (resolved up to "db", "url" not present; available: ["host"])"#
+/// );
+/// ```
+pub fn get_required(key: impl AsConfigKey) -> Result<Value, ConfigError> {
+    let key = key.parsed_key()?;
+    try_read_store(|map| lookup(map, &key).cloned())?
+}
+
+/// Sets a single configuration value at a dot-separated key path.
+///
+/// Intermediate objects are created as needed and the leaf is replaced if it already
+/// exists. The whole operation happens under the store lock, so concurrent readers never
+/// observe a partially built path.
+///
+/// Returns [`ConfigError::NotAnObject`] (leaving the store untouched) if an intermediate
+/// segment exists but is not an object, [`ConfigError::InvalidPath`] if the key is
+/// malformed, [`ConfigError::Serialization`] if `value` cannot be serialized,
+/// [`ConfigError::AlreadyRegistered`] if the key is under a
+/// [raw namespace](register_raw_config), and [`ConfigError::Frozen`] once the
+/// configuration is [frozen](freeze_config).
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::set_config_by_key("features.beta", true).unwrap();
+/// assert_eq!(config::get_config_by_key("features.beta"), Some(serde_json::json!(true)));
+/// ```
+pub fn set_config_by_key<T: Serialize>(key: &str, value: T) -> Result<(), ConfigError> {
+    let value = serde_json::to_value(value).map_err(|source| ConfigError::Serialization {
+        key: key.to_string(),
+        source,
+    })?;
+    let segments = parse_key(key)?;
+    try_write_runtime(
+        Scope::key(&segments),
+        |map| {
+            *entry_mut(map, key, &segments)? = value;
+            Ok(())
+        },
+        |runtime, written| record_at(runtime, written, key, &segments),
+    )
+}
+
+/// Stores `value` at a dot-separated key path in `map`, as [`set_config_by_key`] does in
+/// the store.
+pub(crate) fn set_in(
+    map: &mut Map<String, Value>,
+    key: &str,
+    value: Value,
+) -> Result<(), ConfigError> {
+    let segments = parse_key(key)?;
+    *entry_mut(map, key, &segments)? = value;
+    Ok(())
+}
+
+/// Appends a value to the array at a dot-separated key path, returning the new length.
+///
+/// If nothing exists at `key`, an array holding just `value` is created there, along with
+/// any missing intermediate objects. The read and the append happen under the store lock,
+/// so concurrent pushes are never lost.
+///
+/// Returns [`ConfigError::NotAnArray`] if `key` holds a value that is not an array, plus
+/// the errors documented on [`set_config_by_key`]. The store is left untouched on error.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// assert_eq!(config::push_config("cluster.peers", "10.0.0.1").unwrap(), 1);
+/// assert_eq!(config::push_config("cluster.peers", "10.0.0.2").unwrap(), 2);
+/// assert_eq!(
+///     config::get_config_by_key("cluster.peers"),
+///     Some(json!(["10.0.0.1", "10.0.0.2"]))
+/// );
+/// ```
+pub fn push_config<T: Serialize>(key: &str, value: T) -> Result<usize, ConfigError> {
+    let value = serde_json::to_value(value).map_err(|source| ConfigError::Serialization {
+        key: key.to_string(),
+        source,
+    })?;
+    let segments = parse_key(key)?;
+    try_write_runtime(
+        Scope::key(&segments),
+        |map| {
+            let entry = entry_mut(map, key, &segments)?;
+            if entry.is_null() {
+                *entry = Value::Array(Vec::new());
+            }
+            match entry {
+                Value::Array(items) => {
+                    items.push(value);
+                    Ok(items.len())
+                }
+                other => Err(ConfigError::NotAnArray {
+                    key: key.to_string(),
+                    found_type: value_type_name(other),
+                }),
+            }
+        },
+        |runtime, written| record_at(runtime, written, key, &segments),
+    )
+}
+
+/// Atomically reads and modifies the configuration with `f`, returning its result.
+///
+/// The closure edits a copy of the configuration while other writers are held off, and the
+/// copy is installed once it returns, so no other write can happen in between and readers
+/// see either none of its changes or all of them. If the closure panics, the store is left
+/// unchanged.
+///
+/// The closure may read the configuration, through [`get_config_by_key`] and the other
+/// readers, and sees it as it was before the update; use the map it is given to see its
+/// own changes. Writing to the configuration from inside the closure panics.
+///
+/// As the closure may change any namespace, the copy is of the whole configuration. The
+/// writers that name a key, such as [`update_config_at`] and [`set_config_by_key`], copy
+/// only the namespace they write.
+///
+/// # Panics
+///
+/// Panics if the configuration is [frozen](freeze_config), or if the closure writes under
+/// a [raw namespace](register_raw_config).
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::update_config(|map| {
+///     map.insert("features".to_string(), json!({ "beta": true }));
+/// });
+/// assert_eq!(config::get_config_by_key("features.beta"), Some(json!(true)));
+/// ```
+pub fn update_config<R>(f: impl FnOnce(&mut Map<String, Value>) -> R) -> R {
+    write_runtime(Scope::Whole, f, record_namespaces)
+}
+
+/// Atomically reads and modifies the value at a dot-separated key path with `f`.
+///
+/// If nothing is stored at `key`, intermediate objects are created and `f` receives
+/// `Value::Null`, which stays in place unless `f` replaces it. Paths are resolved as in
+/// [`set_config_by_key`], and the closure may read, but not write, the configuration as
+/// described for [`update_config`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// let increment = |value: &mut serde_json::Value| *value = json!(value.as_u64().unwrap_or(0) + 1);
+/// config::update_config_at("stats.requests", increment).unwrap();
+/// config::update_config_at("stats.requests", increment).unwrap();
+/// assert_eq!(config::get_config_by_key("stats.requests"), Some(json!(2)));
+/// ```
+pub fn update_config_at<R>(key: &str, f: impl FnOnce(&mut Value) -> R) -> Result<R, ConfigError> {
+    let segments = parse_key(key)?;
+    try_write_runtime(
+        Scope::key(&segments),
+        |map| Ok(f(entry_mut(map, key, &segments)?)),
+        |runtime, written| record_at(runtime, written, key, &segments),
+    )
+}
+
+/// A set of staged configuration changes, applied together by [`transaction`].
+#[derive(Debug, Default)]
+pub struct Transaction {
+    operations: Vec<Operation>,
+    error: Option<ConfigError>,
+}
+
+#[derive(Debug)]
+enum Operation {
+    Set(String, Value),
+    Remove(String),
+}
+
+impl Transaction {
+    /// Stages setting the value at a dot-separated key path, as [`set_config_by_key`] does.
+    pub fn set<T: Serialize>(&mut self, key: &str, value: T) -> &mut Self {
+        if self.error.is_none() {
+            match serde_json::to_value(value) {
+                Ok(value) => self.operations.push(Operation::Set(key.to_string(), value)),
+                Err(source) => {
+                    self.error = Some(ConfigError::Serialization {
+                        key: key.to_string(),
+                        source,
+                    })
+                }
+            }
+        }
+        self
+    }
+
+    /// Stages removing the value at a dot-separated key path, as [`remove_config_by_key`]
+    /// does. Removing a missing key is not an error.
+    pub fn remove(&mut self, key: &str) -> &mut Self {
+        self.operations.push(Operation::Remove(key.to_string()));
+        self
+    }
+
+    /// Applies the staged operations to `map` in order, stopping at the first failure.
+    fn apply(&self, map: &mut Map<String, Value>) -> Result<(), ConfigError> {
+        for operation in &self.operations {
+            match operation {
+                Operation::Set(key, value) => {
+                    let segments = parse_key(key)?;
+                    *entry_mut(map, key, &segments)? = value.clone();
+                }
+                Operation::Remove(key) => {
+                    let segments = parse_key(key)?;
+                    remove_entry(map, &segments);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Applies several changes to the configuration as a single atomic update.
+///
+/// `f` stages changes on a [`Transaction`]; they are then applied together under the store
+/// lock, so readers observe either none or all of them. If any staged change fails (for
+/// example because a path runs through a scalar), the error is returned and none of the
+/// changes are applied.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("cache", json!({ "legacy": true }));
+/// config::transaction(|tx| {
+///     tx.set("db.host", "db.internal")
+///         .set("db.port", 5432)
+///         .remove("cache.legacy");
+/// })
+/// .unwrap();
+///
+/// assert_eq!(config::get_config_by_key("db.port"), Some(json!(5432)));
+/// assert!(config::get_config_by_key("cache.legacy").is_none());
+/// ```
+pub fn transaction(f: impl FnOnce(&mut Transaction)) -> Result<(), ConfigError> {
+    let mut transaction = Transaction::default();
+    f(&mut transaction);
+    if let Some(error) = transaction.error {
+        return Err(error);
+    }
+    let keys = transaction
+        .operations
+        .iter()
+        .map(|operation| match operation {
+            Operation::Set(key, _) | Operation::Remove(key) => key.as_str(),
+        });
+    try_write_runtime(
+        Scope::keys(keys),
+        |map| transaction.apply(map),
+        |runtime, _| transaction.apply(runtime),
+    )
+}
+
+/// A set of configuration blocks, registered together by [`register_batch`].
+#[derive(Debug, Default)]
+pub struct Batch {
+    blocks: Vec<(String, Value)>,
+    error: Option<ConfigError>,
+}
+
+impl Batch {
+    /// Stages registering `config` under `namespace`, as [`register_config`] does.
+    ///
+    /// A block that fails to serialize, or an invalid namespace, fails the whole batch,
+    /// which is then not registered.
+    pub fn register<T: Serialize>(&mut self, namespace: &str, config: T) -> &mut Self {
+        if self.error.is_none() {
+            let staged = parse_namespace(namespace).and_then(|_| {
+                serde_json::to_value(config).map_err(|source| ConfigError::Serialization {
+                    key: namespace.to_string(),
+                    source,
+                })
+            });
+            match staged {
+                Ok(value) => self.blocks.push((namespace.to_string(), value)),
+                Err(error) => self.error = Some(error),
+            }
+        }
+        self
+    }
+}
+
+/// Registers several configuration blocks as a single atomic update.
+///
+/// `f` stages blocks on a [`Batch`]; they are then registered together, so readers observe
+/// either none or all of them. This avoids a thread starting up concurrently seeing
+/// `server` registered but not yet `db`. Blocks replace whatever was registered under
+/// their namespace as the [`RegistrationPolicy`] decides, and a namespace staged twice is
+/// treated as registered twice. If any block fails to serialize or cannot be placed, or
+/// the policy rejects it, the error is returned and none are registered.
+///
+/// The [`register_configs!`](crate::register_configs) macro wraps this function.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_batch(|batch| {
+///     batch
+///         .register("server", json!({ "port": 3000 }))
+///         .register("db", json!({ "url": "postgres://localhost/app" }));
+/// })
+/// .unwrap();
+///
+/// assert_eq!(config::get_config_by_key("server.port"), Some(json!(3000)));
+/// assert!(config::get_config_by_key("db.url").is_some());
+/// ```
+pub fn register_batch(f: impl FnOnce(&mut Batch)) -> Result<(), ConfigError> {
+    let mut batch = Batch::default();
+    f(&mut batch);
+    if let Some(error) = batch.error {
+        return Err(error);
+    }
+    let policy = registration_policy();
+    let namespaces = batch.blocks.iter().map(|(namespace, _)| namespace.as_str());
+    try_write_store(Scope::keys(namespaces), |map| {
+        for (namespace, value) in &batch.blocks {
+            let segments = parse_namespace(namespace)?;
+            register_at(map, namespace, &segments, value.clone(), policy)?;
+        }
+        for (namespace, value) in batch.blocks {
+            let segments = parse_namespace(&namespace)?;
+            record_registration(&namespace, &segments, value, policy);
+        }
+        Ok(())
+    })
+}
+
+/// Deep-merges `overrides` into the root of the configuration.
+///
+/// Objects are merged key by key, recursively, so namespaces and keys that `overrides`
+/// does not mention are left intact. Everywhere else the override wins: scalars replace
+/// scalars, arrays replace arrays wholesale (they are not concatenated or merged by index),
+/// and a value of a different type replaces the existing one, whether that is an object
+/// replacing a scalar or the other way round.
+///
+/// Returns [`ConfigError::NotAnObject`] if `overrides` is not an object,
+/// [`ConfigError::AlreadyRegistered`] if it has a key that names a
+/// [raw namespace](register_raw_config), and [`ConfigError::LimitExceeded`] if it exceeds
+/// the default [`Limits`]; use [`merge_config_with`] to choose the limits.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("server", json!({ "host": "localhost", "port": 3000 }));
+/// config::merge_config(json!({ "server": { "port": 8080 } })).unwrap();
+///
+/// assert_eq!(
+///     config::get_config_by_key("server"),
+///     Some(json!({ "host": "localhost", "port": 8080 }))
+/// );
+/// ```
+pub fn merge_config(overrides: Value) -> Result<(), ConfigError> {
+    merge_config_with(overrides, &Limits::default())
+}
+
+/// Deep-merges `overrides` into the root of the configuration, as [`merge_config`] does,
+/// after checking it against `limits`.
+///
+/// Use this to merge configuration from untrusted sources with tighter limits than the
+/// defaults. A value that exceeds them is rejected with [`ConfigError::LimitExceeded`]
+/// and nothing is merged.
+pub fn merge_config_with(overrides: Value, limits: &Limits) -> Result<(), ConfigError> {
+    limits.check("", &overrides)?;
+    let Value::Object(overrides) = overrides else {
+        return Err(ConfigError::NotAnObject {
+            resolved_path: String::new(),
+            found_type: value_type_name(&overrides),
+        });
+    };
+    let scope = Scope::Namespaces(overrides.keys().cloned().collect());
+    let recorded = overrides.clone();
+    try_write_runtime(
+        scope,
+        |map| {
+            merge_map(map, overrides);
+            Ok(())
+        },
+        |runtime, _| {
+            merge_map(runtime, recorded);
+            Ok(())
+        },
+    )
+}
+
+/// Deep-merges `overrides` into the value at a dot-separated key path.
+///
+/// Follows the same rules as [`merge_config`]. If nothing exists at `key` yet, `overrides`
+/// is stored there as is, creating intermediate objects as [`set_config_by_key`] does.
+///
+/// Returns [`ConfigError::NotAnObject`] (leaving the store untouched) if an intermediate
+/// segment exists but is not an object, [`ConfigError::InvalidPath`] if the key is
+/// malformed, or [`ConfigError::LimitExceeded`] if `overrides` exceeds the default
+/// [`Limits`]; use [`merge_config_at_with`] to choose the limits.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("db", json!({ "pool": { "min": 1, "max": 10 } }));
+/// config::merge_config_at("db.pool", json!({ "max": 50 })).unwrap();
+///
+/// assert_eq!(config::get_config_by_key("db.pool"), Some(json!({ "min": 1, "max": 50 })));
+/// ```
+pub fn merge_config_at(key: &str, overrides: Value) -> Result<(), ConfigError> {
+    merge_config_at_with(key, overrides, &Limits::default())
+}
+
+/// Deep-merges `overrides` into the value at a dot-separated key path, as
+/// [`merge_config_at`] does, after checking it against `limits`.
+pub fn merge_config_at_with(
+    key: &str,
+    overrides: Value,
+    limits: &Limits,
+) -> Result<(), ConfigError> {
+    let segments = parse_key(key)?;
+    limits.check(key, &overrides)?;
+    let recorded = overrides.clone();
+    try_write_runtime(
+        Scope::key(&segments),
+        |map| {
+            merge_value(entry_mut(map, key, &segments)?, overrides);
+            Ok(())
+        },
+        |runtime, _| {
+            merge_value(entry_mut(runtime, key, &segments)?, recorded);
+            Ok(())
+        },
+    )
+}
+
+/// Replaces the entire configuration with `new`, returning the previous contents.
+///
+/// The swap happens in a single operation under the store lock, so readers observe either
+/// the old configuration or the new one, never a mix of the two. This suits reload flows
+/// that build a complete configuration off to the side before installing it.
+///
+/// # Panics
+///
+/// Panics if the configuration is [frozen](freeze_config), or if `new` has a key that
+/// names a [raw namespace](register_raw_config).
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::{Map, json};
+///
+/// config::register_config("old", json!({ "a": 1 }));
+///
+/// let mut new = Map::new();
+/// new.insert("server".to_string(), json!({ "port": 8080 }));
+/// let previous = config::replace_config(new);
+///
+/// assert_eq!(previous.get("old"), Some(&json!({ "a": 1 })));
+/// assert!(config::get_config_by_key("old").is_none());
+/// assert_eq!(config::get_config_by_key("server.port"), Some(json!(8080)));
+/// ```
+pub fn replace_config(new: Map<String, Value>) -> Map<String, Value> {
+    write_runtime(
+        Scope::Whole,
+        |map| std::mem::replace(map, new),
+        record_namespaces,
+    )
+}
+
+/// Removes the value at a dot-separated key path and returns it.
+///
+/// The removed value may be a leaf or a whole subtree. Parent objects are kept even if
+/// the removal leaves them empty. Returns `None`, without modifying anything, if nothing
+/// is stored at `key`.
+///
+/// # Panics
+///
+/// Panics if the configuration is [frozen](freeze_config).
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("cache", json!({ "ttl": 60, "legacy": true }));
+/// assert_eq!(config::remove_config_by_key("cache.legacy"), Some(json!(true)));
+/// assert_eq!(config::get_config_by_key("cache"), Some(json!({ "ttl": 60 })));
+/// ```
+pub fn remove_config_by_key(key: &str) -> Option<Value> {
+    let segments = parse_key(key).ok()?;
+    write_runtime(
+        Scope::key(&segments),
+        |map| remove_entry(map, &segments),
+        |runtime, _| {
+            remove_entry(runtime, &segments);
+            Ok(())
+        },
+    )
+}
+
+/// Flattens the entire configuration into a map from dot-separated keys to leaf values.
+///
+/// Objects are descended key by key and arrays by index (e.g. `servers.0.host`). Empty
+/// objects and empty arrays have no leaves to descend into, so they are emitted as-is
+/// under their own key. Segments that contain dots, quotes or backslashes are quoted, as
+/// in `hosts."api.example.com".timeout`, so every flattened key can be read back with
+/// [`get_config_by_key`] and no two leaves share one.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("server", json!({ "port": 3000, "tags": ["a"] }));
+/// let flat = config::flatten_config();
+/// assert_eq!(flat["server.port"], json!(3000));
+/// assert_eq!(flat["server.tags.0"], json!("a"));
+///
+/// config::register_config("hosts", json!({ "api.example.com": { "timeout": 5 } }));
+/// let key = r#"hosts."api.example.com".timeout"#;
+/// assert_eq!(config::flatten_config()[key], json!(5));
+/// assert_eq!(config::get_config_by_key(key), Some(json!(5)));
+/// ```
+pub fn flatten_config() -> BTreeMap<String, Value> {
+    read_store(|snapshot| flatten_map(snapshot.iter()))
+}
+
+/// Flattens the `entries` of an object as [`flatten_config`] does.
+fn flatten_map<'a>(
+    entries: impl IntoIterator<Item = (&'a String, &'a Value)>,
+) -> BTreeMap<String, Value> {
+    let mut flat = BTreeMap::new();
+    for (key, value) in entries {
+        flatten_into(escape_segment(key).into_owned(), value, &mut flat);
+    }
+    flat
+}
+
+/// Recursively inserts the leaves of `value` into `flat`, keyed under `prefix`.
+fn flatten_into(prefix: String, value: &Value, flat: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                flatten_into(format!("{prefix}.{}", escape_segment(key)), child, flat);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (index, child) in items.iter().enumerate() {
+                flatten_into(format!("{prefix}.{index}"), child, flat);
+            }
+        }
+        leaf => {
+            flat.insert(prefix, leaf.clone());
+        }
+    }
+}
+
+/// Finds every configuration value whose dot-separated path matches a wildcard pattern.
+///
+/// A `*` segment matches exactly one path segment and a `**` segment matches any number of
+/// segments, including none. Wildcards match object keys as well as array indices. Literal
+/// segments behave as in [`get_config_by_key`], so a segment that contains dots is quoted,
+/// as in `hosts."api.example.com".*`, and a quoted `"*"` matches a key named `*`. Every
+/// matching node is returned together with its concrete path, quoted the same way, sorted
+/// by path, and each path appears at most once even if the pattern can match it in
+/// several ways. A pattern that cannot be parsed, such as one with an unterminated quote,
+/// matches nothing.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("services", json!({
+///     "auth": { "port": 8001 },
+///     "billing": { "port": 8002 }
+/// }));
+///
+/// let ports = config::query_config("services.*.port");
+/// assert_eq!(ports, vec![
+///     ("services.auth.port".to_string(), json!(8001)),
+///     ("services.billing.port".to_string(), json!(8002)),
+/// ]);
+/// ```
+pub fn query_config(pattern: &str) -> Vec<(String, Value)> {
+    let Ok(segments) = parse_pattern(pattern) else {
+        return Vec::new();
+    };
+    read_store(|map| {
+        let mut matches = BTreeMap::new();
+        let children = map.iter().map(|(key, child)| (key.clone(), child));
+        query_node("", None, children.collect(), &segments, &mut matches);
+        matches.into_iter().collect()
+    })
+}
+
+/// Runs `f` against the current snapshot of the store.
+///
+/// An uninitialized store is read as empty, with a warning logged the first time.
+fn read_store<R>(f: impl FnOnce(&Snapshot) -> R) -> R {
+    read_versioned(f).1
+}
+
+/// Like [`read_store`], but also returns the generation of the snapshot `f` read.
+fn read_versioned<R>(f: impl FnOnce(&Snapshot) -> R) -> (u64, R) {
+    // A frozen snapshot is borrowed in place, without touching its reference count.
+    if let Some((generation, map)) = FROZEN.get() {
+        return (*generation, f(map));
+    }
+    let (generation, map) = current_snapshot();
+    (generation, f(&map))
+}
+
+/// Returns the current snapshot together with its generation.
+///
+/// An uninitialized store is returned as empty, with a warning logged the first time.
+fn current_snapshot() -> (u64, Arc<Snapshot>) {
+    if let Some((generation, map)) = FROZEN.get() {
+        return (*generation, Arc::clone(map));
+    }
+    match GLOBAL_CONFIG.get() {
+        Some(global) => load_snapshot(global),
+        None => {
+            if !WARNED_UNINITIALIZED.swap(true, Ordering::Relaxed) {
+                log::warn!("configuration read before init_config() was called");
+            }
+            (GENERATION.load(Ordering::Acquire), Arc::default())
+        }
+    }
+}
+
+/// Like [`read_store`], but fails with [`ConfigError::NotInitialized`] in strict mode.
+fn try_read_store<R>(f: impl FnOnce(&Snapshot) -> R) -> Result<R, ConfigError> {
+    if STRICT_INIT.load(Ordering::Relaxed) && GLOBAL_CONFIG.get().is_none() {
+        return Err(ConfigError::NotInitialized);
+    }
+    Ok(read_store(f))
+}
+
+/// Runs `f` against a map holding copies of the namespaces in `scope`, then installs a new
+/// snapshot with the blocks left in the map in place of those namespaces, and `record`s
+/// the change in [`RUNTIME`], given the namespaces as `f` left them, so that
+/// [`reload_config`] keeps it.
+///
+/// The map is all `f` sees of the store: it may add namespaces outside `scope`, but only
+/// remove those inside it, and the namespaces it does not hold are shared with the
+/// previous snapshot rather than copied. Readers, including `f` itself, keep seeing the
+/// previous snapshot until `f` returns. If `f` panics the copy is discarded and the store
+/// is unchanged.
+///
+/// # Panics
+///
+/// Panics if called from inside another write's callback, which would otherwise deadlock,
+/// or once the configuration is frozen.
+fn write_runtime<R>(
+    scope: Scope,
+    f: impl FnOnce(&mut Map<String, Value>) -> R,
+    record: impl FnOnce(&mut Map<String, Value>, &Map<String, Value>) -> Result<(), ConfigError>,
+) -> R {
+    try_write_runtime(scope, |map| Ok::<_, ConfigError>(f(map)), record)
+        .unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Like [`write_runtime`], but discards the copy instead of installing it when `f` fails,
+/// so a failed write leaves the store untouched and records nothing, and fails with
+/// [`ConfigError::Frozen`] instead of panicking once the configuration is frozen.
+fn try_write_runtime<R, E: From<ConfigError>>(
+    scope: Scope,
+    f: impl FnOnce(&mut Map<String, Value>) -> Result<R, E>,
+    record: impl FnOnce(&mut Map<String, Value>, &Map<String, Value>) -> Result<(), ConfigError>,
+) -> Result<R, E> {
+    write_namespaces(scope, f, |written| {
+        if let Err(err) = record(&mut runtime(), written) {
+            log::debug!("not recording a runtime write for reloads: {err}");
+        }
+    })
+}
+
+/// Like [`try_write_runtime`], for the writes that are not recorded in [`RUNTIME`]: the
+/// registrations, which are recorded in [`REGISTERED`] instead, and the reloads.
+fn try_write_store<R, E: From<ConfigError>>(
+    scope: Scope,
+    f: impl FnOnce(&mut Map<String, Value>) -> Result<R, E>,
+) -> Result<R, E> {
+    write_namespaces(scope, f, |_| {})
+}
+
+/// Records in `runtime` the value that a write left at `key` in `written`.
+fn record_at(
+    runtime: &mut Map<String, Value>,
+    written: &Map<String, Value>,
+    key: &str,
+    segments: &[Segment],
+) -> Result<(), ConfigError> {
+    if let Some(value) = block_at(written, segments) {
+        *entry_mut(runtime, key, segments)? = value;
+    }
+    Ok(())
+}
+
+/// Records in `runtime` every namespace that a write of the whole store, which left the
+/// namespaces as `written`, changed or removed.
+fn record_namespaces(
+    runtime: &mut Map<String, Value>,
+    written: &Map<String, Value>,
+) -> Result<(), ConfigError> {
+    // The write is not installed yet, so this is the store as it was before.
+    read_store(|previous| {
+        for (name, block) in written {
+            if previous.get(name) != Some(block) {
+                runtime.insert(name.clone(), block.clone());
+            }
+        }
+        for name in previous.keys() {
+            if !written.contains_key(name) && previous.raw(name).is_none() {
+                runtime.remove(name);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Does the work of [`try_write_runtime`], calling `installing` with the namespaces as `f`
+/// left them once the write is sure to be installed.
+fn write_namespaces<R, E: From<ConfigError>>(
+    scope: Scope,
+    f: impl FnOnce(&mut Map<String, Value>) -> Result<R, E>,
+    installing: impl FnOnce(&Map<String, Value>),
+) -> Result<R, E> {
+    try_write_snapshot(|namespaces| {
+        let copied = |(name, block): (&String, &Block)| match block {
+            Block::Value(block) => Some((name.clone(), Value::clone(block))),
+            Block::Raw(_) => None,
+        };
+        let mut written: Map<String, Value> = match &scope {
+            Scope::Namespaces(names) => names
+                .iter()
+                .filter_map(|name| namespaces.get_key_value(name).and_then(copied))
+                .collect(),
+            Scope::Whole => namespaces.iter().filter_map(copied).collect(),
+        };
+        let taken: Vec<String> = written.keys().cloned().collect();
+
+        let value = f(&mut written)?;
+        // Writing over a raw namespace would replace it, so it is rejected instead.
+        if let Some(name) = written
+            .keys()
+            .find(|name| namespaces.get(*name).and_then(Block::raw).is_some())
+        {
+            return Err(ConfigError::AlreadyRegistered {
+                namespace: name.clone(),
+            }
+            .into());
+        }
+        installing(&written);
+
+        for name in taken {
+            if !written.contains_key(&name) {
+                namespaces.remove(&name);
+            }
+        }
+        for (name, block) in written {
+            match namespaces.get(&name) {
+                // A block the write left as it was stays shared with the previous snapshot.
+                Some(Block::Value(previous)) if **previous == block => {}
+                _ => {
+                    namespaces.insert(name, Block::Value(Arc::new(block)));
+                }
+            }
+        }
+        Ok(value)
+    })
+}
+
+/// Runs `f` against the namespaces of the current snapshot under the writer lock, and
+/// installs them as the next snapshot if it succeeds.
+///
+/// The blocks are shared with the current snapshot, which readers keep using until `f`
+/// returns, so `f` replaces a block rather than changing it in place.
+///
+/// # Panics
+///
+/// Panics if called from inside another write's callback, which would otherwise deadlock.
+fn try_write_snapshot<R, E: From<ConfigError>>(
+    f: impl FnOnce(&mut BTreeMap<String, Block>) -> Result<R, E>,
+) -> Result<R, E> {
+    assert!(
+        !IN_WRITE.get(),
+        "the configuration cannot be written from inside a write callback"
+    );
+    let global = GLOBAL_CONFIG.get_or_init(Default::default);
+    let _writer = lock_writer();
+    // Checked under the writer lock, which `freeze_config` takes, so no write lands after
+    // the final snapshot.
+    if is_frozen() {
+        return Err(ConfigError::Frozen.into());
+    }
+    let mut namespaces = load_snapshot(global).1.namespaces.clone();
+
+    let result = {
+        let _in_write = InWrite::enter();
+        f(&mut namespaces)
+    };
+
+    if result.is_ok() {
+        let next = Snapshot {
+            namespaces,
+            whole: OnceLock::new(),
+        };
+        install_snapshot(global, Arc::new(next));
+    }
+    result
+}
+
+/// Marks the current thread as inside a write callback until dropped, even on panic.
+struct InWrite;
+
+impl InWrite {
+    fn enter() -> Self {
+        IN_WRITE.set(true);
+        InWrite
+    }
+}
+
+impl Drop for InWrite {
+    fn drop(&mut self) {
+        IN_WRITE.set(false);
+    }
+}
+
+/// Takes the writer lock, recovering it if a write callback panicked while holding it.
+///
+/// A panicking write never installs its copy, so the store itself is unaffected.
+fn lock_writer() -> std::sync::MutexGuard<'static, ()> {
+    WRITER.lock().unwrap_or_else(|poisoned| {
+        log::warn!("recovering the configuration store after a write callback panicked");
+        WRITER.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+/// Returns the current snapshot together with its generation.
+///
+/// The generation never runs ahead of the snapshot: a snapshot may be newer than the
+/// generation it is returned with, but never older, so values cached against it are at
+/// worst recomputed once more than needed.
+#[cfg(not(feature = "arc-swap"))]
+fn load_snapshot(global: &StoreLock) -> (u64, Arc<Snapshot>) {
+    let current = read_lock(global);
+    // Read under the lock, which writers hold while bumping it.
+    (GENERATION.load(Ordering::Acquire), Arc::clone(&current))
+}
+
+/// Replaces the current snapshot with `next` and bumps the generation.
+#[cfg(not(feature = "arc-swap"))]
+fn install_snapshot(global: &StoreLock, next: Arc<Snapshot>) {
+    let mut current = write_lock(global);
+    // Bumped under the lock, together with the swap, so a reader that sees the old
+    // generation also sees the old contents.
+    GENERATION.fetch_add(1, Ordering::Release);
+    *current = next;
+}
+
+/// Returns the current snapshot together with its generation, as above.
+#[cfg(feature = "arc-swap")]
+fn load_snapshot(global: &StoreLock) -> (u64, Arc<Snapshot>) {
+    // The generation is loaded first: writers bump it after swapping, so the snapshot
+    // loaded next is at least as new as it.
+    let generation = GENERATION.load(Ordering::Acquire);
+    (generation, global.load_full())
+}
+
+/// Replaces the current snapshot with `next` and bumps the generation, as above.
+#[cfg(feature = "arc-swap")]
+fn install_snapshot(global: &StoreLock, next: Arc<Snapshot>) {
+    global.store(next);
+    GENERATION.fetch_add(1, Ordering::Release);
+}
+
+/// Locks the store for reading.
+#[cfg(not(any(feature = "arc-swap", feature = "parking_lot")))]
+fn read_lock(global: &StoreLock) -> impl Deref<Target = Arc<Snapshot>> + '_ {
+    global.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Locks the store for writing.
+#[cfg(not(any(feature = "arc-swap", feature = "parking_lot")))]
+fn write_lock(global: &StoreLock) -> impl DerefMut<Target = Arc<Snapshot>> + '_ {
+    global.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Locks the store for reading.
+#[cfg(all(feature = "parking_lot", not(feature = "arc-swap")))]
+fn read_lock(global: &StoreLock) -> impl Deref<Target = Arc<Snapshot>> + '_ {
+    global.read()
+}
+
+/// Locks the store for writing.
+#[cfg(all(feature = "parking_lot", not(feature = "arc-swap")))]
+fn write_lock(global: &StoreLock) -> impl DerefMut<Target = Arc<Snapshot>> + '_ {
+    global.write()
+}
+
+/// Returns the value at `segments`, creating intermediate objects and a `null` leaf as needed.
+///
+/// Fails with [`ConfigError::NotAnObject`] if an intermediate segment holds a non-object.
+/// Since intermediates are only created once the existing path runs out, a failure never
+/// leaves newly created objects behind.
+fn entry_mut<'a>(
+    map: &'a mut Map<String, Value>,
+    key: &str,
+    segments: &[Segment],
+) -> Result<&'a mut Value, ConfigError> {
+    let (leaf, parents) = segments
+        .split_last()
+        .expect("keys have at least one segment");
+    let mut current = map;
+    for segment in parents {
+        let child = current
+            .entry(segment.name.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        current = match child {
+            Value::Object(child) => child,
+            other => {
+                return Err(ConfigError::NotAnObject {
+                    resolved_path: key[..segment.end].to_string(),
+                    found_type: value_type_name(other),
+                });
+            }
+        };
+    }
+    Ok(current.entry(leaf.name.to_string()).or_insert(Value::Null))
+}
+
+/// Parses a namespace, which is a key whose segments must not be empty or whitespace.
+pub(crate) fn parse_namespace(namespace: &str) -> Result<Vec<Segment<'_>>, ConfigError> {
+    let segments = parse_key(namespace)?;
+    if segments
+        .iter()
+        .any(|segment| segment.name.trim().is_empty())
+    {
+        return Err(ConfigError::InvalidPath {
+            key: namespace.to_string(),
+            message: "namespaces must not be empty or have empty or whitespace-only segments"
+                .to_string(),
+        });
+    }
+    Ok(segments)
+}
+
+/// Returns the object that holds the last of `segments` and the name of that last segment,
+/// creating intermediate objects as [`entry_mut`] does.
+fn parent_mut<'a, 'k>(
+    map: &'a mut Map<String, Value>,
+    key: &str,
+    segments: &'k [Segment],
+) -> Result<(&'a mut Map<String, Value>, &'k str), ConfigError> {
+    let (leaf, parents) = segments
+        .split_last()
+        .expect("keys have at least one segment");
+    let parent = match parents.last() {
+        None => map,
+        Some(last) => {
+            let parent = entry_mut(map, key, parents)?;
+            if parent.is_null() {
+                *parent = Value::Object(Map::new());
+            }
+            match parent {
+                Value::Object(parent) => parent,
+                other => {
+                    return Err(ConfigError::NotAnObject {
+                        resolved_path: key[..last.end].to_string(),
+                        found_type: value_type_name(other),
+                    });
+                }
+            }
+        }
+    };
+    Ok((parent, &leaf.name))
+}
+
+/// Removes and returns the value at `segments`, if every parent along the way is an object.
+fn remove_entry(map: &mut Map<String, Value>, segments: &[Segment]) -> Option<Value> {
+    let (leaf, parents) = segments.split_last()?;
+    let mut current = map;
+    for segment in parents {
+        current = match current.get_mut(segment.name.as_ref()) {
+            Some(Value::Object(child)) => child,
+            _ => return None,
+        };
+    }
+    current.remove(leaf.name.as_ref())
+}
+
+/// Merges `overrides` into `target` key by key, recursing into objects on both sides.
+pub(crate) fn merge_map(target: &mut Map<String, Value>, overrides: Map<String, Value>) {
+    for (key, value) in overrides {
+        match target.get_mut(&key) {
+            Some(existing) => merge_value(existing, value),
+            None => {
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Merges `value` into `target` if both are objects, and replaces `target` otherwise.
+fn merge_value(target: &mut Value, value: Value) {
+    match (target, value) {
+        (Value::Object(target), Value::Object(value)) => merge_map(target, value),
+        (target, value) => *target = value,
+    }
+}
+
+/// Returns a human-readable name for the JSON type of `value`.
+pub(crate) fn value_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Walks a dot-separated key path through `root`, borrowing the value it resolves to.
+fn lookup<'a>(root: &'a Snapshot, parsed: &ParsedKey) -> Result<&'a Value, ConfigError> {
+    let (key, segments) = (parsed.raw, &parsed.segments);
+    walk(root, &parsed.names()).map_err(|miss| {
+        let resolved = |depth: usize| match depth {
+            0 => String::new(),
+            depth => key[..segments[depth - 1].end].to_string(),
+        };
+        match miss {
+            Miss::Missing { depth, parent } => {
+                let parent_key = resolved(depth);
+                let available: Vec<String> = match parent {
+                    Some(Parent::Root(snapshot)) => snapshot.keys().cloned().collect(),
+                    Some(Parent::Object(map)) => map.keys().cloned().collect(),
+                    None => Vec::new(),
+                };
+                let missing = segments
+                    .get(depth)
+                    .map_or_else(String::new, |segment| segment.name.to_string());
+                ConfigError::KeyNotFound {
+                    key: key.to_string(),
+                    suggestions: suggest_keys(&parent_key, &missing, &available),
+                    parent: parent_key,
+                    missing,
+                    available,
+                }
+            }
+            Miss::NotAnObject { depth, found_type } => ConfigError::NotAnObject {
+                resolved_path: resolved(depth),
+                found_type,
+            },
+            Miss::OutOfRange { depth, index, len } => ConfigError::IndexOutOfRange {
+                key: key.to_string(),
+                resolved_path: resolved(depth),
+                index,
+                len,
+            },
+        }
+    })
+}
+
+/// Why [`walk`] could not resolve a path.
+enum Miss<'a> {
+    /// The segment at `depth` does not exist under the value resolved so far. `parent` is
+    /// that value if it is an object.
+    Missing {
+        depth: usize,
+        parent: Option<Parent<'a>>,
+    },
+    /// The segment at `depth` cannot be looked up in the value resolved so far, which is a
+    /// scalar, or an array and the segment is not an index.
+    NotAnObject {
+        depth: usize,
+        found_type: &'static str,
+    },
+    /// The segment at `depth` is an index past the end of an array of length `len`.
+    OutOfRange {
+        depth: usize,
+        index: isize,
+        len: usize,
+    },
+}
+
+/// The object a [`Miss::Missing`] segment was looked up in.
+enum Parent<'a> {
+    /// The store itself, for a missing namespace.
+    Root(&'a Snapshot),
+    Object(&'a Map<String, Value>),
+}
+
+/// Walks already-split path segments through `root`, borrowing the value they resolve to.
+///
+/// Segments are looked up as object keys, or as indices when the current value is an array.
+/// Negative indices count from the end of the array.
+fn walk<'a, S: AsRef<str>>(root: &'a Snapshot, path: &[S]) -> Result<&'a Value, Miss<'a>> {
+    let Some((first, rest)) = path.split_first() else {
+        return Err(Miss::Missing {
+            depth: 0,
+            parent: Some(Parent::Root(root)),
+        });
+    };
+    let mut current = root.get(first.as_ref()).ok_or(Miss::Missing {
+        depth: 0,
+        parent: Some(Parent::Root(root)),
+    })?;
+    for (offset, segment) in rest.iter().enumerate() {
+        let depth = offset + 1;
+        let segment = segment.as_ref();
+        current = match current {
+            Value::Object(map) => map.get(segment).ok_or(Miss::Missing {
+                depth,
+                parent: Some(Parent::Object(map)),
+            })?,
+            Value::Array(items) => match segment.parse::<isize>() {
+                Ok(index) => array_get(items, index).ok_or(Miss::OutOfRange {
+                    depth,
+                    index,
+                    len: items.len(),
+                })?,
+                Err(_) => {
+                    return Err(Miss::NotAnObject {
+                        depth,
+                        found_type: "array",
+                    });
+                }
+            },
+            scalar => {
+                return Err(Miss::NotAnObject {
+                    depth,
+                    found_type: value_type_name(scalar),
+                });
+            }
+        };
+    }
+    Ok(current)
+}
+
+/// Returns the array element at `index`, where negative indices count from the end.
+fn array_get(items: &[Value], index: isize) -> Option<&Value> {
+    let position = if index < 0 {
+        items.len().checked_sub(index.unsigned_abs())?
+    } else {
+        index.unsigned_abs()
+    };
+    items.get(position)
+}
+
+/// A segment of a [`query_config`] pattern.
+#[derive(Debug, Clone, PartialEq)]
+enum PatternSegment<'a> {
+    /// `*`, which matches a single segment.
+    One,
+    /// `**`, which matches any number of segments.
+    Any,
+    /// Any other segment, unquoted and unescaped, which matches itself.
+    Literal(Cow<'a, str>),
+}
+
+/// Parses a [`query_config`] pattern as a key, taking `*` and `**` as wildcards only when
+/// they are neither quoted nor escaped.
+fn parse_pattern(pattern: &str) -> Result<Vec<PatternSegment<'_>>, ConfigError> {
+    let mut start = 0;
+    let segments = parse_key(pattern)?.into_iter().map(|segment| {
+        let raw = &pattern[start..segment.end];
+        start = segment.end + 1;
+        match raw {
+            "*" => PatternSegment::One,
+            "**" => PatternSegment::Any,
+            _ => PatternSegment::Literal(segment.name),
+        }
+    });
+    Ok(segments.collect())
+}
+
+/// Matches `pattern` against the node at `path`, collecting matches into `out`.
+///
+/// `node` is `None` for the root of the store, which is never itself a match.
+fn query_node(
+    path: &str,
+    node: Option<&Value>,
+    children: Vec<(String, &Value)>,
+    pattern: &[PatternSegment],
+    out: &mut BTreeMap<String, Value>,
+) {
+    let Some((segment, rest)) = pattern.split_first() else {
+        if let Some(value) = node {
+            out.insert(path.to_string(), value.clone());
+        }
+        return;
+    };
+    match segment {
+        PatternSegment::Any => {
+            query_node(path, node, children.clone(), rest, out);
+            for (key, child) in children {
+                query_child(path, &key, child, pattern, out);
+            }
+        }
+        PatternSegment::One => {
+            for (key, child) in children {
+                query_child(path, &key, child, rest, out);
+            }
+        }
+        PatternSegment::Literal(literal) => {
+            let child = match node {
+                Some(Value::Array(items)) => literal.parse::<isize>().ok().and_then(|index| {
+                    let len = items.len() as isize;
+                    let position = if index < 0 { len + index } else { index };
+                    array_get(items, index).map(|child| (position.to_string(), child))
+                }),
+                _ => children
+                    .into_iter()
+                    .find(|(key, _)| key == literal.as_ref()),
+            };
+            if let Some((key, child)) = child {
+                query_child(path, &key, child, rest, out);
+            }
+        }
+    }
+}
+
+/// Matches `pattern` against `child`, found under `key` in the node at `parent`.
+fn query_child(
+    parent: &str,
+    key: &str,
+    child: &Value,
+    pattern: &[PatternSegment],
+    out: &mut BTreeMap<String, Value>,
+) {
+    let path = if parent.is_empty() {
+        escape_segment(key).into_owned()
+    } else {
+        format!("{parent}.{}", escape_segment(key))
+    };
+    query_node(&path, Some(child), child_entries(child), pattern, out);
+}
+
+/// Returns the direct children of an object or array, keyed by name or index.
+fn child_entries(value: &Value) -> Vec<(String, &Value)> {
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, child)| (key.clone(), child))
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(index, child)| (index.to_string(), child))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Unescapes a JSON Pointer reference token as described in RFC 6901, section 4.
+fn unescape_pointer_token(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Parses a JSON Pointer array index, which must be `0` or a number without leading zeros.
+fn parse_pointer_index(token: &str) -> Option<usize> {
+    let valid = token == "0"
+        || (!token.starts_with('0')
+            && !token.is_empty()
+            && token.bytes().all(|b| b.is_ascii_digit()));
+    if valid { token.parse().ok() } else { None }
+}
+
+/// Returns whether a value exists at a dot-separated key path.
+///
+/// The stored configuration is inspected in place, without cloning any values. A key whose
+/// value is explicitly `null` counts as present.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("tls", serde_json::json!({ "cert_path": null }));
+/// assert!(config::has_config("tls.cert_path"));
+/// assert!(!config::has_config("tls.key_path"));
+/// ```
+pub fn has_config(key: impl AsConfigKey) -> bool {
+    let Ok(key) = key.parsed_key() else {
+        return false;
+    };
+    read_store(|map| walk(map, &key.names()).is_ok())
+}
+
+/// Lists the immediate child keys of the object at a dot-separated key path.
+///
+/// Returns `Some(vec![])` for an empty object, and `None` if the key is missing or does not
+/// hold an object. Only the key names are cloned, not the subtree. Use
+/// [`config_namespaces`] to list the top level.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("logging", serde_json::json!({ "level": "info", "file": "app.log" }));
+/// assert_eq!(
+///     config::config_children("logging"),
+///     Some(vec!["file".to_string(), "level".to_string()])
+/// );
+/// assert_eq!(config::config_children("logging.level"), None);
+/// ```
+pub fn config_children(prefix: &str) -> Option<Vec<String>> {
+    let prefix = prefix.parsed_key().ok()?;
+    read_store(|map| match walk(map, &prefix.names()) {
+        Ok(Value::Object(children)) => Some(children.keys().cloned().collect()),
+        _ => None,
+    })
+}
+
+/// Retrieves a configuration value by dot-separated key and deserializes it into `T`.
+///
+/// The key may be a string or a pre-parsed [`ConfigKey`](crate::key::ConfigKey).
+/// Returns [`ConfigError::KeyNotFound`] if nothing is stored at `key`,
+/// [`ConfigError::NotAnObject`] if `key` passes through a value that cannot be traversed,
+/// as for [`get_required`], or [`ConfigError::TypeMismatch`] if the stored value does not
+/// have the shape of `T`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::{config, register_config};
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct ServerConfig {
+///     port: u16,
+///     host: String,
+/// }
+///
+/// register_config!("server", ServerConfig { port: 3000, host: "0.0.0.0".to_string() }).unwrap();
+///
+/// let port: u16 = config::get_config_as("server.port").unwrap();
+/// let server: ServerConfig = config::get_config_as("server").unwrap();
+/// assert_eq!(port, server.port);
+/// ```
+pub fn get_config_as<T: DeserializeOwned>(key: impl AsConfigKey) -> Result<T, ConfigError> {
+    let key = key.parsed_key()?;
+    let value = read_store(|map| lookup(map, &key).cloned())?;
+    deserialize_value(key.raw, &value)
+}
+
+/// Like [`get_config_as`], but also returns the generation of the snapshot the value was
+/// read from.
+pub(crate) fn get_config_as_versioned<T: DeserializeOwned>(
+    key: &str,
+) -> (u64, Result<T, ConfigError>) {
+    let key = match key.parsed_key() {
+        Ok(key) => key,
+        Err(err) => return (GENERATION.load(Ordering::Acquire), Err(err)),
+    };
+    let (generation, value) = read_versioned(|map| lookup(map, &key).cloned());
+    (
+        generation,
+        value.and_then(|value| deserialize_value(key.raw, &value)),
+    )
+}
+
+/// Like [`get_config_as`], but coerces scalars whose type does not match `T`.
+///
+/// Strings holding numbers are accepted where `T` expects a number, `"true"` and
+/// `"false"` where it expects a boolean, and numbers and booleans where it expects a
+/// string. This suits values loaded from environment variables or flat files, which are
+/// always strings. A string that does not parse, such as `"3000abc"` read as a `u16`,
+/// still returns [`ConfigError::TypeMismatch`] naming the offending path.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("server", json!({ "port": "3000", "tls": "true", "name": 7 }));
+///
+/// assert_eq!(config::get_config_as_lenient::<u16>("server.port").unwrap(), 3000);
+/// assert!(config::get_config_as_lenient::<bool>("server.tls").unwrap());
+/// assert_eq!(config::get_config_as_lenient::<String>("server.name").unwrap(), "7");
+/// assert!(config::get_config_as::<u16>("server.port").is_err());
+/// ```
+pub fn get_config_as_lenient<T: DeserializeOwned>(key: impl AsConfigKey) -> Result<T, ConfigError> {
+    let key = key.parsed_key()?;
+    let value = read_store(|map| lookup(map, &key).cloned())?;
+    crate::lenient::from_value_lenient(key.raw, &value)
+}
+
+/// Retrieves the block registered under `namespace` and deserializes it into `T`.
+///
+/// `namespace` is a key path, as in [`register_config`]: a dotted namespace such as
+/// `"services.billing"` reads the block nested at that path, and a name that contains dots
+/// is quoted, as in `r#""api.example.com""#`. A top-level namespace registered with
+/// [`register_raw_config`] is read as well. Returns [`ConfigError::KeyNotFound`] if nothing
+/// is registered under `namespace`, [`ConfigError::InvalidPath`] if it cannot be parsed,
+/// or [`ConfigError::TypeMismatch`] if the block does not have the shape of `T`; serde's
+/// message then names the offending field.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+/// struct ServerConfig {
+///     port: u16,
+///     host: String,
+/// }
+///
+/// let server = ServerConfig { port: 3000, host: "0.0.0.0".to_string() };
+/// config::register_config("server", &server);
+/// assert_eq!(config::get_namespace::<ServerConfig>("server").unwrap(), server);
+///
+/// // A dotted namespace is a path to a nested block.
+/// config::register_config("services.billing", &server);
+/// assert_eq!(config::get_namespace::<ServerConfig>("services.billing").unwrap(), server);
+///
+/// let err = config::get_namespace::<ServerConfig>("client").unwrap_err();
+/// assert!(matches!(err, config::ConfigError::KeyNotFound { .. }));
+/// ```
+pub fn get_namespace<T: DeserializeOwned>(namespace: &str) -> Result<T, ConfigError> {
+    let value = read_store(|map| namespace_value(map, namespace))?;
+    deserialize_value(namespace, &value)
+}
+
+/// Like [`get_namespace`], but rejects keys in the stored block that `T` does not declare.
+///
+/// This behaves as if `T` and every type nested in it were annotated with serde's
+/// `deny_unknown_fields`, which catches typos such as `max_connctions` that would
+/// otherwise be ignored in favour of a default. Returns [`ConfigError::UnknownKeys`]
+/// listing the path of every unknown key, not just the first.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// #[derive(serde::Deserialize)]
+/// struct PoolConfig {
+///     #[serde(default)]
+///     max_connections: u32,
+/// }
+///
+/// config::register_config("pool", json!({ "max_connctions": 64 }));
+///
+/// let err = config::get_namespace_strict::<PoolConfig>("pool").err().unwrap();
+/// assert_eq!(err.to_string(), r#"unknown keys in "pool": ["pool.max_connctions"]"#);
+/// assert!(config::get_namespace::<PoolConfig>("pool").is_ok());
+/// ```
+pub fn get_namespace_strict<T: DeserializeOwned>(namespace: &str) -> Result<T, ConfigError> {
+    let value = read_store(|map| namespace_value(map, namespace))?;
+    let mut unknown = Vec::new();
+    let mut track = serde_path_to_error::Track::new();
+    let deserializer = serde_path_to_error::Deserializer::new(&value, &mut track);
+    let parsed = serde_ignored::deserialize(deserializer, |path| {
+        let mut segments = vec![namespace.to_string()];
+        ignored_path_segments(&path, &mut segments);
+        unknown.push(segments.join("."));
+    })
+    .map_err(|source| type_mismatch(namespace, &track.path(), source))?;
+    if !unknown.is_empty() {
+        return Err(ConfigError::UnknownKeys {
+            namespace: namespace.to_string(),
+            keys: unknown,
+        });
+    }
+    Ok(parsed)
+}
+
+/// Like [`get_namespace`], but caches the deserialized value and returns it as an [`Arc`].
+///
+/// The first read of a namespace as a given `T` deserializes it and stores the result;
+/// later reads return clones of the same `Arc` without touching serde. Any write to the
+/// configuration invalidates every cached value, so the next read deserializes again and
+/// always reflects the current contents. Intended for blocks read on every request and
+/// written rarely.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use std::sync::Arc;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Limits {
+///     max_body: u64,
+/// }
+///
+/// config::register_config("limits", serde_json::json!({ "max_body": 1024 }));
+///
+/// let first = config::get_cached::<Limits>("limits").unwrap();
+/// let second = config::get_cached::<Limits>("limits").unwrap();
+/// assert!(Arc::ptr_eq(&first, &second));
+/// assert_eq!(second.max_body, 1024);
+/// ```
+pub fn get_cached<T>(namespace: &str) -> Result<Arc<T>, ConfigError>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+{
+    let cache = TYPED_CACHE.get_or_init(Default::default);
+    let generation = GENERATION.load(Ordering::Acquire);
+    {
+        let cache = cache.lock().unwrap_or_else(PoisonError::into_inner);
+        let hit = cache
+            .get(&TypeId::of::<T>())
+            .and_then(|by_namespace| by_namespace.get(namespace));
+        if let Some((cached_at, value)) = hit
+            && *cached_at == generation
+            && let Ok(value) = Arc::clone(value).downcast::<T>()
+        {
+            return Ok(value);
+        }
+    }
+
+    let (generation, value) = read_versioned(|map| namespace_value(map, namespace));
+    let value = value?;
+    let parsed: Arc<T> = Arc::new(deserialize_value(namespace, &value)?);
+
+    let mut cache = cache.lock().unwrap_or_else(PoisonError::into_inner);
+    let slot = cache
+        .entry(TypeId::of::<T>())
+        .or_default()
+        .entry(namespace.to_string())
+        .or_insert_with(|| (generation, parsed.clone()));
+    // Another thread may have cached a value read after ours; keep the newer one.
+    if slot.0 < generation {
+        *slot = (generation, parsed.clone());
+    }
+    Ok(parsed)
+}
+
+/// Clones the block registered under `namespace`, or reports which namespaces exist.
+fn namespace_value(snapshot: &Snapshot, namespace: &str) -> Result<Value, ConfigError> {
+    let key = namespace.parsed_key()?;
+    lookup(snapshot, &key).cloned()
+}
+
+/// Suggests the keys in `available`, found under `parent`, closest to the missing `name`.
+fn suggest_keys(parent: &str, name: &str, available: &[String]) -> Vec<String> {
+    suggest::closest(name, available.iter().map(String::as_str))
+        .into_iter()
+        .map(|candidate| match (parent, escape_segment(candidate)) {
+            ("", segment) => segment.into_owned(),
+            (parent, segment) => format!("{parent}.{segment}"),
+        })
+        .collect()
+}
+
+/// Deserializes `value`, read from `key`, into `T`.
+///
+/// On failure, the error records the absolute path of the field that failed, such as
+/// `db.pool.max_size` for a nested field of the value at `db`.
+pub(crate) fn deserialize_value<T: DeserializeOwned>(
+    key: &str,
+    value: &Value,
+) -> Result<T, ConfigError> {
+    serde_path_to_error::deserialize(value).map_err(|err| {
+        let path = err.path().clone();
+        type_mismatch(key, &path, err.into_inner())
+    })
+}
+
+pub(crate) fn type_mismatch(
+    key: &str,
+    path: &serde_path_to_error::Path,
+    source: serde_json::Error,
+) -> ConfigError {
+    let mut absolute = key.to_string();
+    for segment in path.iter() {
+        if !absolute.is_empty() {
+            absolute.push('.');
+        }
+        match segment {
+            serde_path_to_error::Segment::Seq { index } => absolute.push_str(&index.to_string()),
+            serde_path_to_error::Segment::Map { key } => absolute.push_str(key),
+            serde_path_to_error::Segment::Enum { variant } => absolute.push_str(variant),
+            serde_path_to_error::Segment::Unknown => absolute.push('?'),
+        }
+    }
+    ConfigError::TypeMismatch {
+        key: key.to_string(),
+        path: absolute,
+        source,
+    }
+}
+
+/// Appends the map keys and array indices along `path`, skipping `Option` and newtype
+/// wrappers, which have no counterpart in the JSON.
+fn ignored_path_segments(path: &serde_ignored::Path, segments: &mut Vec<String>) {
+    match path {
+        serde_ignored::Path::Root => {}
+        serde_ignored::Path::Seq { parent, index } => {
+            ignored_path_segments(parent, segments);
+            segments.push(index.to_string());
+        }
+        serde_ignored::Path::Map { parent, key } => {
+            ignored_path_segments(parent, segments);
+            segments.push(key.clone());
+        }
+        serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => {
+            ignored_path_segments(parent, segments);
+        }
+    }
+}
+
+/// Deserializes the entire configuration into `T`, typically an application-wide struct
+/// with one field per namespace.
+///
+/// Namespaces that `T` does not declare are ignored, unless `T` opts into serde's
+/// `deny_unknown_fields`. A missing namespace or a block of the wrong shape returns
+/// [`ConfigError::TypeMismatch`] with an empty `key`, whose message names the field.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde::Deserialize;
+/// use serde_json::json;
+///
+/// #[derive(Deserialize)]
+/// struct ServerConfig {
+///     port: u16,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct AppConfig {
+///     server: ServerConfig,
+/// }
+///
+/// config::register_config("server", json!({ "port": 3000 }));
+/// config::register_config("some_library", json!({ "not": "modelled" }));
+///
+/// let app: AppConfig = config::get_config_typed().unwrap();
+/// assert_eq!(app.server.port, 3000);
+/// ```
+pub fn get_config_typed<T: DeserializeOwned>() -> Result<T, ConfigError> {
+    let value = read_store(whole_value);
+    deserialize_value("", &value)
+}
+
+/// Retrieves the object stored at a dot-separated key path as a JSON map.
+///
+/// An empty key returns the root map. A key holding a non-object value returns
+/// [`ConfigError::TypeMismatch`], and a missing key [`ConfigError::KeyNotFound`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("upstreams", serde_json::json!({ "a": 1, "b": 2 }));
+///
+/// let upstreams = config::get_config_object("upstreams").unwrap();
+/// for (name, weight) in &upstreams {
+///     println!("{name}: {weight}");
+/// }
+/// assert!(config::get_config_object("upstreams.a").is_err());
+/// ```
+pub fn get_config_object(key: &str) -> Result<Map<String, Value>, ConfigError> {
+    if key.is_empty() {
+        return Ok(read_store(|snapshot| Map::clone(snapshot.whole())));
+    }
+    get_config_as(key)
+}
+
+/// Retrieves the array stored at a dot-separated key path.
+///
+/// A key holding a non-array value returns [`ConfigError::TypeMismatch`], and a missing key
+/// [`ConfigError::KeyNotFound`]. Empty arrays yield an empty vector.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("cors", serde_json::json!({ "allowed_origins": ["https://a.com"] }));
+/// let origins = config::get_config_array("cors.allowed_origins").unwrap();
+/// assert_eq!(origins.len(), 1);
+/// ```
+pub fn get_config_array(key: &str) -> Result<Vec<Value>, ConfigError> {
+    get_config_as(key)
+}
+
+/// Retrieves the array stored at a dot-separated key path, deserializing each element into `T`.
+///
+/// If an element fails to deserialize, the returned [`ConfigError::TypeMismatch`] names the
+/// element by index, e.g. `upstreams.2`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("cors", serde_json::json!({ "allowed_origins": ["https://a.com"] }));
+/// let origins: Vec<String> = config::get_config_array_as("cors.allowed_origins").unwrap();
+/// assert_eq!(origins, vec!["https://a.com"]);
+/// ```
+pub fn get_config_array_as<T: DeserializeOwned>(key: &str) -> Result<Vec<T>, ConfigError> {
+    get_config_array(key)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| deserialize_value(&format!("{key}.{index}"), &item))
+        .collect()
+}
+
+/// Retrieves a typed configuration value, falling back to `default` if it cannot be read.
+///
+/// `default` is only invoked when the value is unavailable. A value that exists but does
+/// not deserialize into `T` is treated the same as a missing one; use
+/// [`try_get_config_or_else`] if a type mismatch should be reported instead.
+///
+/// An explicit `null` counts as present: read as an `Option<T>` it is `None`, not the
+/// default, so a `null` can switch off a setting that is enabled by default.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// let workers: u64 = config::get_config_or_else("server.workers", || 4);
+/// assert_eq!(workers, 4);
+/// ```
+pub fn get_config_or_else<T: DeserializeOwned, F: FnOnce() -> T>(
+    key: impl AsConfigKey,
+    default: F,
+) -> T {
+    get_config_as(key).unwrap_or_else(|_| default())
+}
+
+/// Retrieves a typed configuration value, falling back to `default` only if the key is missing.
+///
+/// Unlike [`get_config_or_else`], a value that exists but does not deserialize into `T`
+/// returns [`ConfigError::TypeMismatch`].
+pub fn try_get_config_or_else<T: DeserializeOwned, F: FnOnce() -> T>(
+    key: impl AsConfigKey,
+    default: F,
+) -> Result<T, ConfigError> {
+    match get_config_as(key) {
+        Err(ConfigError::KeyNotFound { .. } | ConfigError::IndexOutOfRange { .. }) => Ok(default()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(serde::Serialize)]
+    struct DummyConfig {
+        port: u16,
+        host: String,
+    }
+
+    #[test]
+    fn test_register_and_get_full_config() {
+        // Initialize global config.
+        init_config();
+
+        // Register two configuration blocks.
+        register_config(
+            "server",
+            DummyConfig {
+                port: 3000,
+                host: "0.0.0.0".into(),
+            },
+        );
+        register_config(
+            "logging",
+            json!({
+                "level": "info",
+                "file": "app.log"
+            }),
+        );
+
+        let config = get_config();
+        if let Value::Object(map) = config {
+            assert!(map.contains_key("server"));
+            assert!(map.contains_key("logging"));
+        } else {
+            panic!("Global config is not an object");
+        }
+    }
+
+    #[test]
+    fn test_get_config_by_key() {
+        // Clear and reinitialize the global config.
+        // (For testing, we can simulate a new initialization by using a new OnceLock instance.)
+        // Note: In real usage, GLOBAL_CONFIG is static and persistent across calls.
+        init_config();
+        register_config(
+            "server",
+            DummyConfig {
+                port: 3000,
+                host: "0.0.0.0".into(),
+            },
+        );
+
+        let port = get_config_by_key("server.port");
+        assert_eq!(port, Some(json!(3000)));
+
+        let host = get_config_by_key("server.host");
+        assert_eq!(host, Some(json!("0.0.0.0")));
+
+        // Non-existent key returns None.
+        let missing = get_config_by_key("server.nonexistent");
+        assert!(missing.is_none());
+    }
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct TypedServer {
+        port: u16,
+        host: String,
+        tls: bool,
+        aliases: Vec<String>,
+    }
+
+    #[test]
+    fn test_get_config_as() {
+        init_config();
+        register_config(
+            "typed_server",
+            json!({
+                "port": 8080,
+                "host": "localhost",
+                "tls": true,
+                "aliases": ["a", "b"]
+            }),
+        );
+
+        let port: u16 = get_config_as("typed_server.port").unwrap();
+        assert_eq!(port, 8080);
+        let host: String = get_config_as("typed_server.host").unwrap();
+        assert_eq!(host, "localhost");
+        let tls: bool = get_config_as("typed_server.tls").unwrap();
+        assert!(tls);
+        let aliases: Vec<String> = get_config_as("typed_server.aliases").unwrap();
+        assert_eq!(aliases, vec!["a", "b"]);
+
+        let server: TypedServer = get_config_as("typed_server").unwrap();
+        assert_eq!(
+            server,
+            TypedServer {
+                port: 8080,
+                host: "localhost".into(),
+                tls: true,
+                aliases: vec!["a".into(), "b".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_config_as_errors() {
+        init_config();
+        register_config("typed_errors", json!({ "port": "not a number" }));
+
+        let err = get_config_as::<u16>("typed_errors.port").unwrap_err();
+        assert!(matches!(err, ConfigError::TypeMismatch { .. }));
+        let message = err.to_string();
+        assert!(message.contains("typed_errors.port"), "{message}");
+        assert!(message.contains("invalid type: string"), "{message}");
+
+        let err = get_config_as::<u16>("typed_errors.missing").unwrap_err();
+        assert!(
+            matches!(err, ConfigError::KeyNotFound { ref key, .. } if key == "typed_errors.missing")
+        );
+    }
+
+    #[test]
+    fn test_get_config_arc_is_a_shared_snapshot() {
+        register_config("arc_snapshot", json!({ "n": 1 }));
+        let first = get_config_arc();
+        assert_eq!(first.get("arc_snapshot"), Some(&json!({ "n": 1 })));
+
+        set_config_by_key("arc_snapshot.n", 2).unwrap();
+        assert_eq!(first["arc_snapshot"]["n"], json!(1));
+        assert_eq!(get_config_arc()["arc_snapshot"]["n"], json!(2));
+    }
+
+    #[test]
+    fn test_writes_share_the_namespaces_they_leave_alone() {
+        register_config("shared_written", json!({ "n": 1 }));
+        register_config("shared_kept", json!({ "n": 1 }));
+        let before = current_snapshot().1;
+
+        set_config_by_key("shared_written.n", 2).unwrap();
+        update_config(|map| map.insert("shared_other".to_string(), json!(1)));
+        let after = current_snapshot().1;
+        let shared = |name: &str| std::ptr::eq(before.get(name).unwrap(), after.get(name).unwrap());
+        assert!(shared("shared_kept"));
+        assert!(!shared("shared_written"));
+        assert_eq!(before.get("shared_written"), Some(&json!({ "n": 1 })));
+    }
+
+    #[test]
+    fn test_with_config_borrows_values() {
+        register_config(
+            "with_view",
+            json!({ "tls": { "mode": "strict" }, "peers": ["a", "b", "c"] }),
+        );
+
+        assert!(with_config_at("with_view.tls.mode", |mode| mode
+            .and_then(Value::as_str)
+            == Some("strict")));
+        assert_eq!(
+            with_config_at("with_view.peers", |peers| peers
+                .and_then(Value::as_array)
+                .map(Vec::len)),
+            Some(3)
+        );
+        assert_eq!(
+            with_config_at("with_view.peers.-1", |peer| peer.cloned()),
+            Some(json!("c"))
+        );
+        assert!(with_config_at("with_view.missing", |value| value.is_none()));
+        assert!(with_config_at("with_view..tls", |value| value.is_none()));
+        assert!(with_config(|map| map["with_view"]["tls"].is_object()));
+    }
+
+    #[test]
+    fn test_with_config_alongside_other_threads() {
+        register_config("with_threads", json!({ "n": 0 }));
+        std::thread::scope(|scope| {
+            let writer = scope.spawn(|| {
+                for n in 1..=100 {
+                    set_config_by_key("with_threads.n", n).unwrap();
+                }
+            });
+            let readers: Vec<_> = (0..3)
+                .map(|_| {
+                    scope.spawn(|| {
+                        for _ in 0..100 {
+                            // Nesting plain reads inside a view must not deadlock.
+                            let (viewed, read) = with_config_at("with_threads.n", |n| {
+                                (
+                                    n.and_then(Value::as_u64).unwrap(),
+                                    get_config_by_key("with_threads.n"),
+                                )
+                            });
+                            assert!(read.and_then(|n| n.as_u64()).unwrap() >= viewed);
+                        }
+                    })
+                })
+                .collect();
+            writer.join().unwrap();
+            for reader in readers {
+                reader.join().unwrap();
+            }
+        });
+        assert_eq!(
+            with_config_at("with_threads.n", |n| n.cloned()),
+            Some(json!(100))
+        );
+    }
+
+    #[test]
+    fn test_raw_namespaces_round_trip_verbatim() {
+        // Formatting, key order and number spelling are all kept.
+        let text = r#"{ "z": 1.50, "a": [1,2 ,3], "big": 12345678901234567890 }"#;
+        let raw = RawValue::from_string(text.to_string()).unwrap();
+        assert!(register_raw_config("raw_table", raw).unwrap().is_none());
+        assert_eq!(get_raw_config("raw_table").unwrap().get(), text);
+
+        // Parsed on demand, into the same JSON.
+        let expected: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(get_config()["raw_table"], expected);
+        assert_eq!(get_namespace::<Value>("raw_table").unwrap(), expected);
+        assert_eq!(
+            get_cached::<Value>("raw_table").unwrap().as_ref(),
+            &expected
+        );
+        assert!(config_namespaces().contains(&"raw_table".to_string()));
+        // Key lookups and whole views read the same parsed copy.
+        assert_eq!(get_config_by_key("raw_table.a.-1"), Some(json!(3)));
+        assert_eq!(crate::read_config!("raw_table.z" as f64).unwrap(), 1.5);
+        assert!(has_config("raw_table.big"));
+        // Parsed once, then shared.
+        let parsed = || {
+            with_config_at("raw_table", |table| {
+                table.map(|table| table as *const Value)
+            })
+        };
+        assert_eq!(parsed(), parsed());
+        assert_eq!(get_config_arc()["raw_table"], expected);
+        assert_eq!(flatten_config()["raw_table.a.0"], json!(1));
+        assert_eq!(query_config("raw_table.a.*").len(), 3);
+
+        let replaced = RawValue::from_string("[true]".to_string()).unwrap();
+        let previous = register_raw_config("raw_table", replaced).unwrap().unwrap();
+        assert_eq!(previous.get(), text);
+        assert_eq!(get_namespace::<Vec<bool>>("raw_table").unwrap(), vec![true]);
+        assert_eq!(get_config_by_key("raw_table.0"), Some(json!(true)));
+        assert_eq!(get_config_by_key("raw_table.a"), None);
+
+        assert_eq!(unregister_raw_config("raw_table").unwrap().get(), "[true]");
+        assert!(get_raw_config("raw_table").is_none());
+        assert!(unregister_raw_config("raw_table").is_none());
+    }
+
+    #[test]
+    fn test_raw_namespaces_do_not_mix_with_regular_ones() {
+        let raw = || RawValue::from_string("{}".to_string()).unwrap();
+
+        register_config("raw_clash_regular", json!({}));
+        let err = register_raw_config("raw_clash_regular", raw()).unwrap_err();
+        assert!(
+            matches!(err, ConfigError::AlreadyRegistered { ref namespace } if namespace == "raw_clash_regular")
+        );
+
+        register_raw_config("raw_clash_raw", raw()).unwrap();
+        let err = try_register_config("raw_clash_raw.inner", json!(1)).unwrap_err();
+        assert!(
+            matches!(err, ConfigError::AlreadyRegistered { ref namespace } if namespace == "raw_clash_raw")
+        );
+
+        let err = register_raw_config("raw_clash.nested", raw()).unwrap_err();
+        assert!(matches!(err, ConfigError::InvalidPath { .. }));
+        // A quoted segment is a single top-level name.
+        register_raw_config(r#""raw_clash.dotted""#, raw()).unwrap();
+        assert!(get_raw_config(r#""raw_clash.dotted""#).is_some());
+    }
+
+    #[cfg(feature = "intern")]
+    #[test]
+    fn test_interned_namespaces_read_like_registered_ones() {
+        let table = json!({
+            "routes": [
+                { "path": "/a", "method": "GET", "upstream": "billing" },
+                { "path": "/b", "method": "GET", "upstream": "billing" },
+            ],
+        });
+        assert!(
+            register_interned_config("interned_table", &table)
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(get_config()["interned_table"], table);
+        assert_eq!(get_namespace::<Value>("interned_table").unwrap(), table);
+        assert_eq!(
+            get_cached::<Value>("interned_table").unwrap().as_ref(),
+            &table
+        );
+        let mut out = Vec::new();
+        serialize_config_at_to_writer("interned_table", &mut out, false).unwrap();
+        assert_eq!(out, serde_json::to_vec(&table).unwrap());
+        assert!(config_namespaces().contains(&"interned_table".to_string()));
+        assert_eq!(
+            crate::read_config!("interned_table.routes"),
+            Some(table["routes"].clone())
+        );
+        assert_eq!(
+            get_config_as::<String>("interned_table.routes.1.path").unwrap(),
+            "/b"
+        );
+        assert!(get_raw_config("interned_table").is_none());
+
+        // Neither a regular block nor a raw blob may be registered over it.
+        let err = try_register_config("interned_table.extra", 1).unwrap_err();
+        assert!(matches!(err, ConfigError::AlreadyRegistered { .. }));
+        let raw = RawValue::from_string("{}".to_string()).unwrap();
+        let err = register_raw_config("interned_table", raw).unwrap_err();
+        assert!(matches!(err, ConfigError::AlreadyRegistered { .. }));
+        register_config("interned_clash", json!({}));
+        let err = register_interned_config("interned_clash", json!({})).unwrap_err();
+        assert!(matches!(err, ConfigError::AlreadyRegistered { .. }));
+
+        let previous = register_interned_config("interned_table", json!([1])).unwrap();
+        assert_eq!(previous, Some(table));
+        assert!(unregister_raw_config("interned_table").is_none());
+        assert_eq!(
+            unregister_interned_config("interned_table"),
+            Some(json!([1]))
+        );
+        assert!(unregister_interned_config("interned_table").is_none());
+        assert_eq!(get_config().get("interned_table"), None);
+    }
+
+    #[test]
+    fn test_mutators_reject_writes_under_raw_namespaces() {
+        let table = r#"{"a":1}"#;
+        register_raw_config(
+            "raw_guard",
+            RawValue::from_string(table.to_string()).unwrap(),
+        )
+        .unwrap();
+        let rejected = |result: Result<(), ConfigError>| {
+            assert!(
+                matches!(&result, Err(ConfigError::AlreadyRegistered { namespace }) if namespace == "raw_guard"),
+                "{result:?}"
+            );
+        };
+
+        rejected(set_config_by_key("raw_guard.b", 2));
+        rejected(set_config_by_key("raw_guard", json!({ "b": 2 })));
+        rejected(push_config("raw_guard.list", 1).map(drop));
+        rejected(merge_config(json!({ "raw_guard": { "b": 2 } })));
+        rejected(merge_config_at("raw_guard", json!({ "b": 2 })));
+        rejected(update_config_at("raw_guard.b", |value| *value = json!(2)));
+        rejected(transaction(|tx| {
+            tx.set("raw_guard.b", 2);
+        }));
+        let panicked = std::panic::catch_unwind(|| {
+            update_config(|map| map.insert("raw_guard".to_string(), json!({ "b": 2 })))
+        });
+        assert!(panicked.is_err());
+
+        // Registering once leaves the raw block in place, and fetching it parses it.
+        assert!(!register_config_once("raw_guard", json!({ "b": 2 })));
+        assert_eq!(
+            get_or_register("raw_guard", || json!({ "b": 2 })),
+            json!({ "a": 1 })
+        );
+
+        // Both views still agree.
+        assert_eq!(get_config_by_key("raw_guard.a"), Some(json!(1)));
+        assert_eq!(get_config_by_key("raw_guard.b"), None);
+        assert_eq!(get_config()["raw_guard"], json!({ "a": 1 }));
+        assert_eq!(get_raw_config("raw_guard").unwrap().get(), table);
+    }
+
+    #[test]
+    fn test_serialize_config_at_to_writer() {
+        register_config(
+            "stream_db",
+            json!({ "pool": { "min": 1, "max": 10 }, "hosts": ["a", "b"] }),
+        );
+        for pretty in [false, true] {
+            let mut out = Vec::new();
+            serialize_config_at_to_writer("stream_db", &mut out, pretty).unwrap();
+            let value = get_config_by_key("stream_db").unwrap();
+            let expected = if pretty {
+                serde_json::to_vec_pretty(&value)
+            } else {
+                serde_json::to_vec(&value)
+            };
+            assert_eq!(out, expected.unwrap());
+        }
+
+        let mut out = Vec::new();
+        serialize_config_at_to_writer("stream_db.hosts.-1", &mut out, false).unwrap();
+        assert_eq!(out, br#""b""#);
+
+        let raw = RawValue::from_string(r#"{ "kept": "as is" }"#.to_string()).unwrap();
+        register_raw_config("stream_raw", raw).unwrap();
+        let mut out = Vec::new();
+        serialize_config_at_to_writer("stream_raw", &mut out, false).unwrap();
+        assert_eq!(out, br#"{ "kept": "as is" }"#);
+
+        let err =
+            serialize_config_at_to_writer("stream_db.missing", Vec::new(), false).unwrap_err();
+        assert!(matches!(err, ConfigError::KeyNotFound { .. }));
+
+        struct Broken;
+        impl io::Write for Broken {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk full"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let err = serialize_config_at_to_writer("stream_db", Broken, false).unwrap_err();
+        assert!(matches!(err, ConfigError::Serialization { ref key, .. } if key == "stream_db"));
+    }
+
+    #[test]
+    fn test_get_config_entry() {
+        register_config(
+            "entry_logging",
+            json!({ "level": "info", "file": null, "outputs": [null] }),
+        );
+
+        assert_eq!(
+            get_config_entry("entry_logging.level"),
+            Entry::Value(json!("info"))
+        );
+        assert_eq!(get_config_entry("entry_logging.file"), Entry::Null);
+        assert_eq!(get_config_entry("entry_logging.outputs.0"), Entry::Null);
+        assert_eq!(get_config_entry("entry_logging.format"), Entry::Missing);
+        assert_eq!(get_config_entry("entry_logging.file.path"), Entry::Missing);
+        assert_eq!(get_config_entry("entry_logging..level"), Entry::Missing);
+
+        assert!(get_config_entry("entry_logging.file").is_null());
+        assert!(get_config_entry("entry_logging.format").is_missing());
+        assert_eq!(get_config_entry("entry_logging.file").into_value(), None);
+        assert_eq!(
+            get_config_entry("entry_logging.level").into_value(),
+            Some(json!("info"))
+        );
+    }
+
+    #[test]
+    fn test_get_config_or_else() {
+        init_config();
+        register_config("fallback", json!({ "workers": 8, "name": "svc" }));
+
+        assert_eq!(get_config_or_else("fallback.workers", || 4u64), 8);
+        assert_eq!(get_config_or_else("fallback.missing", || 4u64), 4);
+        // A type mismatch falls back in the lenient variant...
+        assert_eq!(get_config_or_else("fallback.name", || 4u64), 4);
+        // ...and is reported in the strict one.
+        assert!(matches!(
+            try_get_config_or_else("fallback.name", || 4u64),
+            Err(ConfigError::TypeMismatch { .. })
+        ));
+        assert_eq!(
+            try_get_config_or_else("fallback.missing", || 4u64).unwrap(),
+            4
+        );
+
+        // The default is not evaluated when the value is present.
+        let value: u64 = get_config_or_else("fallback.workers", || panic!("default evaluated"));
+        assert_eq!(value, 8);
+
+        // A stored `null` is present, so an `Option` reads it as `None` rather than the
+        // default.
+        register_config("fallback_null", json!({ "file": null }));
+        let file: Option<String> =
+            get_config_or_else("fallback_null.file", || Some("app.log".into()));
+        assert_eq!(file, None);
+        let file: Option<String> =
+            get_config_or_else("fallback_null.path", || Some("app.log".into()));
+        assert_eq!(file.as_deref(), Some("app.log"));
+        let file: Option<String> =
+            try_get_config_or_else("fallback_null.file", || Some("app.log".into())).unwrap();
+        assert_eq!(file, None);
+    }
+
+    #[test]
+    fn test_get_required() {
+        init_config();
+        register_config("required_db", json!({ "host": "localhost", "port": 5432 }));
+
+        assert_eq!(get_required("required_db.port").unwrap(), json!(5432));
+
+        let err = get_required("required_db.url").unwrap_err();
+        match &err {
+            ConfigError::KeyNotFound {
+                key,
+                parent,
+                missing,
+                available,
+                suggestions,
+            } => {
+                assert_eq!(key, "required_db.url");
+                assert_eq!(parent, "required_db");
+                assert_eq!(missing, "url");
+                assert_eq!(available, &vec!["host".to_string(), "port".to_string()]);
+                assert!(suggestions.is_empty());
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+        let message = err.to_string();
+        assert!(message.contains("\"required_db.url\""), "{message}");
+        assert!(message.contains("\"host\""), "{message}");
+
+        // A path through a scalar reports the scalar and its type.
+        let err = get_required("required_db.host.name").unwrap_err();
+        assert!(
+            matches!(err, ConfigError::NotAnObject { ref resolved_path, found_type: "string" } if resolved_path == "required_db.host")
+        );
+    }
+
+    #[test]
+    fn test_set_config_by_key() {
+        init_config();
+
+        // Deep paths are created from scratch.
+        set_config_by_key("set_deep.a.b.c", 1).unwrap();
+        assert_eq!(
+            get_config_by_key("set_deep"),
+            Some(json!({ "a": { "b": { "c": 1 } } }))
+        );
+
+        // Existing scalars are overwritten without touching siblings.
+        register_config("set_existing", json!({ "port": 3000, "host": "0.0.0.0" }));
+        set_config_by_key("set_existing.port", 8081).unwrap();
+        assert_eq!(get_config_by_key("set_existing.port"), Some(json!(8081)));
+        assert_eq!(
+            get_config_by_key("set_existing.host"),
+            Some(json!("0.0.0.0"))
+        );
+
+        // Intermediate scalars are not clobbered.
+        let err = set_config_by_key("set_existing.host.name", "x").unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigError::NotAnObject { ref resolved_path, found_type: "string" }
+                if resolved_path == "set_existing.host"
+        ));
+        assert_eq!(
+            get_config_by_key("set_existing.host"),
+            Some(json!("0.0.0.0"))
+        );
+    }
+
+    #[test]
+    fn test_remove_config_by_key() {
+        init_config();
+        register_config(
+            "remove",
+            json!({
+                "db": { "host": "localhost", "port": 5432 },
+                "flag": true
+            }),
+        );
+
+        assert_eq!(remove_config_by_key("remove.db.port"), Some(json!(5432)));
+        assert!(get_config_by_key("remove.db.port").is_none());
+        assert_eq!(
+            get_config_by_key("remove.db.host"),
+            Some(json!("localhost"))
+        );
+        assert_eq!(get_config_by_key("remove.flag"), Some(json!(true)));
+
+        // Removing the last child keeps the now-empty parent.
+        assert_eq!(
+            remove_config_by_key("remove.db.host"),
+            Some(json!("localhost"))
+        );
+        assert_eq!(get_config_by_key("remove.db"), Some(json!({})));
+
+        // Missing keys and paths through scalars leave everything untouched.
+        assert_eq!(remove_config_by_key("remove.db.missing"), None);
+        assert_eq!(remove_config_by_key("remove.flag.inner"), None);
+        assert_eq!(
+            get_config_by_key("remove"),
+            Some(json!({ "db": {}, "flag": true }))
+        );
+    }
+
+    #[test]
+    fn test_unregister_config() {
+        init_config();
+        register_config("plugin", json!({ "version": 1 }));
+        assert_eq!(unregister_config("plugin"), Some(json!({ "version": 1 })));
+        assert!(get_config_by_key("plugin").is_none());
+        assert_eq!(unregister_config("plugin"), None);
+
+        register_config("plugin", json!({ "version": 2 }));
+        assert_eq!(get_config_by_key("plugin.version"), Some(json!(2)));
+    }
+
+    #[test]
+    fn test_has_config() {
+        init_config();
+        register_config(
+            "has",
+            json!({ "present": 1, "null": null, "nested": { "leaf": false } }),
+        );
+
+        assert!(has_config("has"));
+        assert!(has_config("has.present"));
+        assert!(has_config("has.nested.leaf"));
+        assert!(has_config("has.null"));
+        assert!(!has_config("has.absent"));
+        assert!(!has_config("has.present.inner"));
+    }
+
+    #[test]
+    fn test_config_namespaces() {
+        init_config();
+        register_config("ns_b", json!({ "x": 1, "y": 2 }));
+        register_config("ns_a", json!("scalar"));
+
+        let names = config_namespaces();
+        let a = names
+            .iter()
+            .position(|n| n == "ns_a")
+            .expect("ns_a registered");
+        let b = names
+            .iter()
+            .position(|n| n == "ns_b")
+            .expect("ns_b registered");
+        assert!(a < b);
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(names, sorted);
+
+        let sizes = config_namespace_sizes();
+        assert!(sizes.contains(&("ns_a".to_string(), 0)));
+        assert!(sizes.contains(&("ns_b".to_string(), 2)));
+    }
+
+    #[test]
+    fn test_flatten_config() {
+        init_config();
+        register_config(
+            "flatten",
+            json!({
+                "server": { "port": 3000, "tls": { "enabled": false } },
+                "servers": [{ "host": "a" }, { "host": "b", "tags": ["x", "y"] }],
+                "empty_object": {},
+                "empty_array": [],
+                "null": null
+            }),
+        );
+
+        let flat: BTreeMap<String, Value> = flatten_config()
+            .into_iter()
+            .filter(|(key, _)| key.starts_with("flatten."))
+            .collect();
+        let expected: BTreeMap<String, Value> = [
+            ("flatten.server.port", json!(3000)),
+            ("flatten.server.tls.enabled", json!(false)),
+            ("flatten.servers.0.host", json!("a")),
+            ("flatten.servers.1.host", json!("b")),
+            ("flatten.servers.1.tags.0", json!("x")),
+            ("flatten.servers.1.tags.1", json!("y")),
+            ("flatten.empty_object", json!({})),
+            ("flatten.empty_array", json!([])),
+            ("flatten.null", json!(null)),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+        assert_eq!(flat, expected);
+    }
+
+    #[test]
+    fn test_config_children() {
+        init_config();
+        register_config(
+            "children",
+            json!({
+                "logging": { "level": "info", "sinks": { "file": {}, "stdout": {} } },
+                "empty": {},
+                "list": [1, 2],
+                "scalar": 1
+            }),
+        );
+
+        assert_eq!(
+            config_children("children"),
+            Some(vec![
+                "empty".into(),
+                "list".into(),
+                "logging".into(),
+                "scalar".into()
+            ])
+        );
+        assert_eq!(
+            config_children("children.logging.sinks"),
+            Some(vec!["file".into(), "stdout".into()])
+        );
+        assert_eq!(config_children("children.empty"), Some(vec![]));
+        assert_eq!(config_children("children.list"), None);
+        assert_eq!(config_children("children.scalar"), None);
+        assert_eq!(config_children("children.missing"), None);
+    }
+
+    #[test]
+    fn test_get_config_object() {
+        init_config();
+        register_config("object", json!({ "nested": { "a": 1 }, "scalar": 2 }));
+
+        let nested = get_config_object("object.nested").unwrap();
+        assert_eq!(nested.get("a"), Some(&json!(1)));
+        assert!(get_config_object("").unwrap().contains_key("object"));
+
+        assert!(matches!(
+            get_config_object("object.scalar"),
+            Err(ConfigError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            get_config_object("object.missing"),
+            Err(ConfigError::KeyNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_get_config_array() {
+        init_config();
+        register_config(
+            "array",
+            json!({
+                "origins": ["https://a.com", "https://b.com"],
+                "empty": [],
+                "mixed": [1, 2, "three"],
+                "scalar": 1
+            }),
+        );
+
+        assert_eq!(get_config_array("array.origins").unwrap().len(), 2);
+        assert!(get_config_array("array.empty").unwrap().is_empty());
+        let origins: Vec<String> = get_config_array_as("array.origins").unwrap();
+        assert_eq!(origins, vec!["https://a.com", "https://b.com"]);
+        let empty: Vec<u16> = get_config_array_as("array.empty").unwrap();
+        assert!(empty.is_empty());
+
+        assert!(matches!(
+            get_config_array("array.scalar"),
+            Err(ConfigError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            get_config_array("array.missing"),
+            Err(ConfigError::KeyNotFound { .. })
+        ));
+
+        let err = get_config_array_as::<u32>("array.mixed").unwrap_err();
+        assert!(matches!(err, ConfigError::TypeMismatch { ref key, .. } if key == "array.mixed.2"));
+        assert!(err.to_string().contains("array.mixed.2"));
+    }
+
+    #[test]
+    fn test_array_index_paths() {
+        init_config();
+        register_config(
+            "indexed",
+            json!({
+                "upstreams": [
+                    { "host": "a", "ports": [80, 443] },
+                    { "host": "b", "ports": [8080] }
+                ],
+                "by_id": { "0": "zero", "1": { "nested": [true] } }
+            }),
+        );
+
+        assert_eq!(
+            get_config_by_key("indexed.upstreams.0.host"),
+            Some(json!("a"))
+        );
+        assert_eq!(
+            get_config_by_key("indexed.upstreams.0.ports.1"),
+            Some(json!(443))
+        );
+        assert_eq!(
+            get_config_by_key("indexed.upstreams.1.ports.0"),
+            Some(json!(8080))
+        );
+        assert_eq!(get_config_by_key("indexed.by_id.0"), Some(json!("zero")));
+        assert_eq!(
+            get_config_by_key("indexed.by_id.1.nested.0"),
+            Some(json!(true))
+        );
+
+        assert_eq!(get_config_by_key("indexed.upstreams.2"), None);
+        assert_eq!(get_config_by_key("indexed.upstreams.first"), None);
+        assert!(matches!(
+            get_required("indexed.upstreams.5.host"),
+            Err(ConfigError::IndexOutOfRange { index: 5, len: 2, ref resolved_path, .. })
+                if resolved_path == "indexed.upstreams"
+        ));
+    }
+
+    #[test]
+    fn test_negative_array_index_paths() {
+        init_config();
+        register_config(
+            "negative",
+            json!({
+                "history": [{ "timestamp": 1 }, { "timestamp": 2 }],
+                "single": ["only"],
+                "empty": [],
+                "literal": { "-1": "object key" }
+            }),
+        );
+
+        assert_eq!(
+            get_config_by_key("negative.history.-1.timestamp"),
+            Some(json!(2))
+        );
+        assert_eq!(
+            get_config_by_key("negative.history.-2.timestamp"),
+            Some(json!(1))
+        );
+        assert_eq!(get_config_by_key("negative.history.-3"), None);
+        assert_eq!(get_config_by_key("negative.single.-1"), Some(json!("only")));
+        assert_eq!(get_config_by_key("negative.empty.-1"), None);
+        assert_eq!(
+            get_config_by_key("negative.literal.-1"),
+            Some(json!("object key"))
+        );
+        assert!(matches!(
+            get_required("negative.history.-3"),
+            Err(ConfigError::IndexOutOfRange {
+                index: -3,
+                len: 2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_get_config_by_pointer() {
+        init_config();
+        register_config(
+            "pointer",
+            json!({
+                "a/b": 1,
+                "m~n": 2,
+                "dotted.key": { "list": [10, 20] },
+                "": { "": "empty" }
+            }),
+        );
+
+        assert_eq!(get_config_by_pointer("/pointer/a~1b"), Some(json!(1)));
+        assert_eq!(get_config_by_pointer("/pointer/m~0n"), Some(json!(2)));
+        assert_eq!(
+            get_config_by_pointer("/pointer/dotted.key/list/1"),
+            Some(json!(20))
+        );
+        assert_eq!(get_config_by_pointer("/pointer//"), Some(json!("empty")));
+        assert!(get_config_by_pointer("").unwrap().get("pointer").is_some());
+
+        assert_eq!(get_config_by_pointer("/pointer/dotted.key/list/01"), None);
+        assert_eq!(get_config_by_pointer("/pointer/dotted.key/list/-"), None);
+        assert_eq!(get_config_by_pointer("/pointer/dotted.key/list/2"), None);
+        assert_eq!(get_config_by_pointer("pointer"), None);
+    }
+
+    #[test]
+    fn test_query_config() {
+        init_config();
+        register_config(
+            "query",
+            json!({
+                "services": {
+                    "auth": { "port": 8001, "tls": { "port": 9001 } },
+                    "billing": { "port": 8002 }
+                },
+                "upstreams": [{ "port": 80 }, { "port": 81 }]
+            }),
+        );
+        let paths = |pattern: &str| -> Vec<String> {
+            query_config(pattern)
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect()
+        };
+
+        assert_eq!(
+            query_config("query.services.*.port"),
+            vec![
+                ("query.services.auth.port".to_string(), json!(8001)),
+                ("query.services.billing.port".to_string(), json!(8002)),
+            ]
+        );
+        assert_eq!(
+            paths("query.upstreams.*.port"),
+            vec!["query.upstreams.0.port", "query.upstreams.1.port"]
+        );
+        assert_eq!(
+            paths("query.upstreams.-1.port"),
+            vec!["query.upstreams.1.port"]
+        );
+
+        // `**` in the middle matches zero or more segments.
+        assert_eq!(
+            paths("query.services.**.port"),
+            vec![
+                "query.services.auth.port",
+                "query.services.auth.tls.port",
+                "query.services.billing.port"
+            ]
+        );
+        // `**` at the start.
+        let all_ports = paths("**.port");
+        for expected in ["query.services.auth.tls.port", "query.upstreams.0.port"] {
+            assert!(
+                all_ports.contains(&expected.to_string()),
+                "{expected} missing"
+            );
+        }
+        // `**` at the end includes the node itself and all descendants.
+        assert_eq!(
+            paths("query.services.billing.**"),
+            vec!["query.services.billing", "query.services.billing.port"]
+        );
+        // Overlapping wildcards report each path once.
+        assert_eq!(
+            paths("query.**.**.tls.port"),
+            vec!["query.services.auth.tls.port"]
+        );
+        assert_eq!(
+            paths("query.*.*.tls.*"),
+            vec!["query.services.auth.tls.port"]
+        );
+        assert!(paths("query.services.*.missing").is_empty());
+    }
+
+    #[test]
+    fn test_query_config_with_quoted_segments() {
+        register_config(
+            "query_quoted",
+            json!({
+                "api.example.com": { "timeout": 5 },
+                "api": { "example": { "com": { "timeout": 6 } } },
+                "*": { "timeout": 7 }
+            }),
+        );
+        assert_eq!(
+            query_config(r#"query_quoted."api.example.com".timeout"#),
+            vec![(
+                r#"query_quoted."api.example.com".timeout"#.to_string(),
+                json!(5)
+            )]
+        );
+        assert_eq!(
+            query_config("query_quoted.api.example.com.timeout"),
+            vec![("query_quoted.api.example.com.timeout".to_string(), json!(6))]
+        );
+        // Matched paths are quoted, so they read back with `get_config_by_key`.
+        let matches = query_config("query_quoted.*.timeout");
+        assert_eq!(matches.len(), 2);
+        for (path, value) in &matches {
+            assert_eq!(get_config_by_key(path.as_str()).as_ref(), Some(value));
+        }
+        // Quoted or escaped, `*` is a literal key.
+        assert_eq!(
+            query_config(r#"query_quoted."*".timeout"#),
+            vec![("query_quoted.*.timeout".to_string(), json!(7))]
+        );
+        assert_eq!(query_config(r"query_quoted.\*.timeout").len(), 1);
+        assert!(query_config(r#"query_quoted."api.timeout"#).is_empty());
+    }
+
+    #[test]
+    fn test_flatten_config_quotes_dotted_keys() {
+        register_config(
+            "flatten_quoted",
+            json!({
+                "api.example.com": { "timeout": 5 },
+                "api": { "example": { "com": { "timeout": 6 } } },
+                "say \"hi\"": 1
+            }),
+        );
+        let flat: BTreeMap<String, Value> = flatten_config()
+            .into_iter()
+            .filter(|(key, _)| key.starts_with("flatten_quoted."))
+            .collect();
+        assert_eq!(flat.len(), 3);
+        assert_eq!(
+            flat[r#"flatten_quoted."api.example.com".timeout"#],
+            json!(5)
+        );
+        assert_eq!(flat["flatten_quoted.api.example.com.timeout"], json!(6));
+        for (key, value) in &flat {
+            assert_eq!(
+                get_config_by_key(key.as_str()).as_ref(),
+                Some(value),
+                "{key}"
+            );
+        }
+
+        // Changes to either of the two keys are counted apart.
+        let old = Map::from_iter([("hosts".to_string(), json!({ "a.b": 1, "a": { "b": 2 } }))]);
+        let new = Map::from_iter([("hosts".to_string(), json!({ "a.b": 1, "a": { "b": 3 } }))]);
+        assert_eq!(count_changed(&old, &new), 1);
+        let new = Map::from_iter([("hosts".to_string(), json!({ "a": { "b": 1 } }))]);
+        assert_eq!(count_changed(&old, &new), 2);
+    }
+
+    #[test]
+    fn test_keys_with_literal_dots() {
+        init_config();
+        register_config(
+            "escaped",
+            json!({
+                "hosts": { "api.example.com": { "timeout": 30 } },
+                "quote\"d": 1,
+                "back\\slash": 2
+            }),
+        );
+
+        assert_eq!(
+            get_config_by_key(r#"escaped.hosts."api.example.com".timeout"#),
+            Some(json!(30))
+        );
+        assert_eq!(
+            get_config_by_key(r"escaped.hosts.api\.example\.com.timeout"),
+            Some(json!(30))
+        );
+        assert_eq!(get_config_by_key(r#"escaped."quote\"d""#), Some(json!(1)));
+        assert_eq!(get_config_by_key(r#"escaped.quote\"d"#), Some(json!(1)));
+        assert_eq!(get_config_by_key(r"escaped.back\\slash"), Some(json!(2)));
+        assert_eq!(
+            get_config_by_key("escaped.hosts.api.example.com.timeout"),
+            None
+        );
+
+        set_config_by_key(r#"escaped.hosts."db.example.com".timeout"#, 5).unwrap();
+        assert_eq!(
+            get_config_by_key(r"escaped.hosts.db\.example\.com.timeout"),
+            Some(json!(5))
+        );
+
+        let err = get_required(r#"escaped.hosts."api.example.com"#).unwrap_err();
+        assert!(matches!(err, ConfigError::InvalidPath { .. }));
+        assert!(err.to_string().contains("unterminated quote"));
+        assert!(matches!(
+            set_config_by_key(r#"escaped."open"#, 1),
+            Err(ConfigError::InvalidPath { .. })
+        ));
+    }
+
+    #[test]
+    fn test_get_config_by_path() {
+        init_config();
+        register_config(
+            "by_path",
+            json!({
+                "hosts": { "api.example.com": { "timeout": 30 } },
+                "list": [1, 2, 3]
+            }),
+        );
+
+        assert_eq!(
+            get_config_by_path(&["by_path", "hosts", "api.example.com", "timeout"]),
+            Some(json!(30))
+        );
+        assert_eq!(
+            get_config_by_key("by_path.hosts.api.example.com.timeout"),
+            None
+        );
+
+        let owned: Vec<String> = vec!["by_path".into(), "list".into(), "-1".into()];
+        assert_eq!(get_config_by_path(&owned), Some(json!(3)));
+        assert_eq!(get_config_by_path(&["by_path", "missing"]), None);
+        assert_eq!(get_config_by_path::<&str>(&[]), None);
+    }
+
+    #[test]
+    fn test_update_config_is_atomic() {
+        init_config();
+        const THREADS: u64 = 8;
+        const INCREMENTS: u64 = 500;
+
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    for _ in 0..INCREMENTS {
+                        update_config_at("update.counter", |value| {
+                            *value = json!(value.as_u64().unwrap_or(0) + 1);
+                        })
+                        .unwrap();
+                        update_config(|map| {
+                            let total = map.entry("update_total".to_string()).or_insert(json!(0));
+                            *total = json!(total.as_u64().unwrap() + 1);
+                        });
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(
+            get_config_by_key("update.counter"),
+            Some(json!(THREADS * INCREMENTS))
+        );
+        assert_eq!(
+            get_config_by_key("update_total"),
+            Some(json!(THREADS * INCREMENTS))
+        );
+    }
+
+    #[test]
+    fn test_update_config_at_conflicts() {
+        init_config();
+        register_config("update_conflict", json!({ "leaf": 1 }));
+
+        let previous = update_config_at("update_conflict.leaf", |value| value.take()).unwrap();
+        assert_eq!(previous, json!(1));
+        assert_eq!(get_config_by_key("update_conflict.leaf"), Some(Value::Null));
+        assert!(matches!(
+            update_config_at("update_conflict.leaf.inner", |_| ()),
+            Err(ConfigError::NotAnObject { .. })
+        ));
+    }
+
+    #[test]
+    fn test_transaction_commits_all_changes() {
+        init_config();
+        register_config("tx_db", json!({ "host": "old", "port": 1, "legacy": true }));
+
+        transaction(|tx| {
+            tx.set("tx_db.host", "new")
+                .set("tx_db.port", 2)
+                .remove("tx_db.legacy");
+        })
+        .unwrap();
+        assert_eq!(
+            get_config_by_key("tx_db"),
+            Some(json!({ "host": "new", "port": 2 }))
+        );
+    }
+
+    #[test]
+    fn test_transaction_rolls_back_on_failure() {
+        init_config();
+        register_config(
+            "tx_rollback",
+            json!({ "host": "old", "port": 1, "scalar": 0 }),
+        );
+
+        let err = transaction(|tx| {
+            tx.set("tx_rollback.host", "new")
+                .set("tx_rollback.scalar.inner", 1)
+                .set("tx_rollback.port", 2)
+                .remove("tx_rollback.host");
+        })
+        .unwrap_err();
+        assert!(matches!(err, ConfigError::NotAnObject { .. }));
+        assert_eq!(
+            get_config_by_key("tx_rollback"),
+            Some(json!({ "host": "old", "port": 1, "scalar": 0 }))
+        );
+
+        let mut unserializable = std::collections::HashMap::new();
+        unserializable.insert(vec![1u8], 1);
+        let err = transaction(|tx| {
+            tx.set("tx_rollback.host", "new")
+                .set("tx_rollback.map", unserializable);
+        })
+        .unwrap_err();
+        assert!(matches!(err, ConfigError::Serialization { .. }));
+        assert_eq!(get_config_by_key("tx_rollback.host"), Some(json!("old")));
+    }
+
+    #[test]
+    fn test_merge_config_nested() {
+        init_config();
+        register_config(
+            "merge_server",
+            json!({ "host": "localhost", "tls": { "enabled": false, "cert": "a.pem" } }),
+        );
+        register_config("merge_sibling", json!({ "untouched": true }));
+
+        merge_config(json!({
+            "merge_server": { "port": 8080, "tls": { "enabled": true } },
+            "merge_new": { "added": 1 }
+        }))
+        .unwrap();
+        assert_eq!(
+            get_config_by_key("merge_server"),
+            Some(
+                json!({ "host": "localhost", "port": 8080, "tls": { "enabled": true, "cert": "a.pem" } })
+            )
+        );
+        assert_eq!(get_config_by_key("merge_new.added"), Some(json!(1)));
+        assert_eq!(
+            get_config_by_key("merge_sibling"),
+            Some(json!({ "untouched": true }))
+        );
+
+        assert!(matches!(
+            merge_config(json!([1])),
+            Err(ConfigError::NotAnObject {
+                found_type: "array",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_merge_config_conflicts() {
+        init_config();
+        register_config(
+            "merge_conflict",
+            json!({
+                "scalar": 1,
+                "object": { "a": 1 },
+                "list": [1, 2, 3],
+                "keep": "yes"
+            }),
+        );
+
+        merge_config_at(
+            "merge_conflict",
+            json!({
+                "scalar": { "now": "object" },
+                "object": "now scalar",
+                "list": [4]
+            }),
+        )
+        .unwrap();
+        assert_eq!(
+            get_config_by_key("merge_conflict"),
+            Some(
+                json!({ "scalar": { "now": "object" }, "object": "now scalar", "list": [4], "keep": "yes" })
+            )
+        );
+
+        merge_config_at("merge_conflict.fresh.nested", json!({ "a": 1 })).unwrap();
+        assert_eq!(
+            get_config_by_key("merge_conflict.fresh.nested.a"),
+            Some(json!(1))
+        );
+
+        let err = merge_config_at("merge_conflict.keep.inner", json!({})).unwrap_err();
+        assert!(matches!(err, ConfigError::NotAnObject { .. }));
+        assert_eq!(get_config_by_key("merge_conflict.keep"), Some(json!("yes")));
+    }
+
+    #[test]
+    fn test_register_config_returns_previous() {
+        init_config();
+        assert_eq!(
+            register_config("clobber", json!({ "owner": "first" })),
+            None
+        );
+        assert_eq!(
+            register_config("clobber", json!({ "owner": "second" })),
+            Some(json!({ "owner": "first" }))
+        );
+        assert_eq!(get_config_by_key("clobber.owner"), Some(json!("second")));
+    }
+
+    #[test]
+    fn test_register_config_once_keeps_first() {
+        init_config();
+        // The application registers before the library's defaults.
+        register_config("once_app_first", json!({ "size": 32 }));
+        assert!(!register_config_once(
+            "once_app_first",
+            json!({ "size": 8 })
+        ));
+        assert_eq!(get_config_by_key("once_app_first.size"), Some(json!(32)));
+
+        // The library's defaults land first and the application overrides them.
+        assert!(register_config_once("once_lib_first", json!({ "size": 8 })));
+        register_config("once_lib_first", json!({ "size": 32 }));
+        assert_eq!(get_config_by_key("once_lib_first.size"), Some(json!(32)));
+    }
+
+    #[test]
+    fn test_get_or_register_runs_init_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        init_config();
+        let calls = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let value = get_or_register("lazy_probe", || {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        json!({ "cores": 4 })
+                    });
+                    assert_eq!(value, json!({ "cores": 4 }));
+                });
+            }
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        register_config("lazy_existing", json!({ "provided": true }));
+        let value = get_or_register("lazy_existing", || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            json!({ "provided": false })
+        });
+        assert_eq!(value, json!({ "provided": true }));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_push_config_concurrently() {
+        init_config();
+        std::thread::scope(|scope| {
+            for thread in 0..8 {
+                scope.spawn(move || {
+                    for i in 0..50 {
+                        push_config("push_cluster.peers", thread * 100 + i).unwrap();
+                    }
+                });
+            }
+        });
+
+        let mut peers: Vec<u64> = get_config_as("push_cluster.peers").unwrap();
+        peers.sort_unstable();
+        let expected: Vec<u64> = (0..8)
+            .flat_map(|t| (0..50).map(move |i| t * 100 + i))
+            .collect();
+        assert_eq!(peers, expected);
+    }
+
+    #[test]
+    fn test_push_config_rejects_non_arrays() {
+        init_config();
+        register_config("push_scalar", json!({ "peers": "not a list" }));
+        let err = push_config("push_scalar.peers", 1).unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigError::NotAnArray {
+                found_type: "string",
+                ..
+            }
+        ));
+        assert_eq!(
+            get_config_by_key("push_scalar.peers"),
+            Some(json!("not a list"))
+        );
+    }
+
+    #[test]
+    fn test_get_namespace_round_trip() {
+        #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+        struct Tls {
+            cert: String,
+            key: Option<String>,
+        }
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+        struct Server {
+            port: u16,
+            tls: Tls,
+            workers: Option<u32>,
+        }
+
+        init_config();
+        let server = Server {
+            port: 443,
+            tls: Tls {
+                cert: "cert.pem".to_string(),
+                key: None,
+            },
+            workers: Some(4),
+        };
+        register_config("ns_server", &server);
+        assert_eq!(get_namespace::<Server>("ns_server").unwrap(), server);
+
+        let err = get_namespace::<Server>("ns_server_missing").unwrap_err();
+        assert!(matches!(err, ConfigError::KeyNotFound { .. }));
+
+        register_config("ns_server_bad", json!({ "tls": { "cert": "cert.pem" } }));
+        let err = get_namespace::<Server>("ns_server_bad").unwrap_err();
+        assert!(matches!(err, ConfigError::TypeMismatch { .. }));
+        assert!(err.to_string().contains("missing field `port`"), "{err}");
+    }
+
+    #[test]
+    fn test_get_config_typed() {
+        #[derive(serde::Deserialize)]
+        struct Logging {
+            level: String,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct App {
+            typed_app_logging: Logging,
+            typed_app_port: u16,
+        }
+
+        #[derive(serde::Deserialize)]
+        #[allow(dead_code)]
+        struct WithMissing {
+            typed_app_logging: Logging,
+            typed_app_absent: Logging,
+        }
+
+        init_config();
+        register_config("typed_app_logging", json!({ "level": "debug" }));
+        register_config("typed_app_port", 8080);
+        // Namespaces registered by other tests act as unknown extras here.
+        register_config("typed_app_extra", json!({ "unmodelled": true }));
+
+        let app: App = get_config_typed().unwrap();
+        assert_eq!(app.typed_app_logging.level, "debug");
+        assert_eq!(app.typed_app_port, 8080);
+
+        let err = get_config_typed::<WithMissing>().err().unwrap();
+        assert!(matches!(err, ConfigError::TypeMismatch { .. }));
+        assert!(
+            err.to_string().contains("missing field `typed_app_absent`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_get_namespace_strict_reports_unknown_keys() {
+        #[derive(serde::Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Upstream {
+            host: String,
+        }
+
+        #[derive(serde::Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Pool {
+            #[serde(default)]
+            max_connections: u32,
+            tls: Option<Upstream>,
+            upstreams: Vec<Upstream>,
+        }
+
+        init_config();
+        register_config(
+            "strict_pool",
+            json!({
+                "max_connctions": 64,
+                "tls": { "host": "a", "verify": true },
+                "upstreams": [{ "host": "b" }, { "host": "c", "weight": 2 }]
+            }),
+        );
+
+        let err = get_namespace_strict::<Pool>("strict_pool").unwrap_err();
+        let ConfigError::UnknownKeys {
+            namespace,
+            mut keys,
+        } = err
+        else {
+            panic!("expected UnknownKeys, got {err:?}");
+        };
+        keys.sort();
+        assert_eq!(namespace, "strict_pool");
+        assert_eq!(
+            keys,
+            vec![
+                "strict_pool.max_connctions",
+                "strict_pool.tls.verify",
+                "strict_pool.upstreams.1.weight"
+            ]
+        );
+
+        let pool = get_namespace::<Pool>("strict_pool").unwrap();
+        assert_eq!(pool.max_connections, 0);
+
+        register_config(
+            "strict_clean",
+            json!({ "max_connections": 8, "upstreams": [] }),
+        );
+        assert_eq!(
+            get_namespace_strict::<Pool>("strict_clean")
+                .unwrap()
+                .max_connections,
+            8
+        );
+    }
+
+    #[test]
+    fn test_type_mismatch_reports_field_path() {
+        #[derive(serde::Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Pool {
+            max_size: u32,
+        }
+
+        #[derive(serde::Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Db {
+            pool: Pool,
+            replicas: Vec<Pool>,
+        }
+
+        init_config();
+        register_config(
+            "path_db",
+            json!({
+                "pool": { "max_size": "lots" },
+                "replicas": []
+            }),
+        );
+        let err = get_namespace::<Db>("path_db").unwrap_err();
+        assert!(
+            matches!(err, ConfigError::TypeMismatch { ref path, .. } if path == "path_db.pool.max_size")
+        );
+        assert_eq!(
+            err.to_string(),
+            r#"type mismatch at "path_db.pool.max_size": invalid type: string "lots", expected u32"#
+        );
+
+        register_config(
+            "path_db",
+            json!({
+                "pool": { "max_size": 1 },
+                "replicas": [{ "max_size": 2 }, { "max_size": -3 }]
+            }),
+        );
+        let err = get_config_as::<Db>("path_db").unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with(r#"type mismatch at "path_db.replicas.1.max_size""#),
+            "{err}"
+        );
+        let err = get_config_as::<Pool>("path_db.replicas.1").unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with(r#"type mismatch at "path_db.replicas.1.max_size""#),
+            "{err}"
+        );
+    }
+
+    /// A value whose `Serialize` impl always fails.
+    struct Unserializable;
+
+    impl Serialize for Unserializable {
+        fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+            Err(serde::ser::Error::custom("refusing to serialize"))
+        }
+    }
+
+    #[test]
+    fn test_try_register_config_reports_serialization_failures() {
+        register_config("try_register", json!({ "kept": true }));
+
+        let err = try_register_config("try_register", Unserializable).unwrap_err();
+        assert!(matches!(err, ConfigError::Serialization { ref key, .. } if key == "try_register"));
+        assert_eq!(
+            err.to_string(),
+            r#"failed to serialize value for "try_register": refusing to serialize"#
+        );
+        assert_eq!(
+            get_config_by_key("try_register"),
+            Some(json!({ "kept": true }))
+        );
+
+        let previous = try_register_config("try_register", json!({ "kept": false })).unwrap();
+        assert_eq!(previous, Some(json!({ "kept": true })));
+    }
+
+    #[test]
+    #[should_panic(expected = "failed to serialize value for \"panicking_register\"")]
+    fn test_register_config_panics_with_the_namespace() {
+        register_config("panicking_register", Unserializable);
+    }
+
+    #[test]
+    fn test_try_get_config_by_key() {
+        register_config("try_get", json!({ "port": 3000, "hosts": ["a", "b"] }));
+        assert_eq!(
+            try_get_config_by_key("try_get.port").unwrap(),
+            Some(json!(3000))
+        );
+        assert_eq!(
+            try_get_config_by_key("try_get.hosts.1").unwrap(),
+            Some(json!("b"))
+        );
+        assert_eq!(try_get_config_by_key("try_get.missing").unwrap(), None);
+        assert!(matches!(
+            try_get_config_by_key("try_get.\"port"),
+            Err(ConfigError::InvalidPath { .. })
+        ));
+        assert_eq!(try_get_config().unwrap()["try_get"]["port"], json!(3000));
+    }
+
+    #[test]
+    fn test_get_or_register_serialization_failure_keeps_the_lock_usable() {
+        let result =
+            std::panic::catch_unwind(|| get_or_register("lazy_unserializable", || Unserializable));
+        let message = *result.unwrap_err().downcast::<String>().unwrap();
+        assert!(message.contains(r#""lazy_unserializable""#), "{message}");
+
+        assert_eq!(try_get_config_by_key("lazy_unserializable").unwrap(), None);
+    }
+
+    #[test]
+    fn test_missing_keys_suggest_close_siblings() {
+        register_config(
+            "suggest_server",
+            json!({ "port": 3000, "host": "a", "hosts": { "api.v1": 1 } }),
+        );
+
+        let err = get_required("suggest_server.prot").unwrap_err();
+        assert!(
+            matches!(err, ConfigError::KeyNotFound { ref suggestions, .. } if suggestions == &["suggest_server.port"])
+        );
+        assert!(
+            err.to_string()
+                .ends_with(r#"; did you mean: ["suggest_server.port"])"#),
+            "{err}"
+        );
+
+        let err = get_required(r#"suggest_server.hosts."api.v2""#).unwrap_err();
+        assert!(
+            matches!(err, ConfigError::KeyNotFound { ref suggestions, .. } if suggestions == &[r#"suggest_server.hosts."api.v1""#])
+        );
+
+        let err = get_namespace::<Value>("suggest_servr").unwrap_err();
+        assert!(
+            matches!(err, ConfigError::KeyNotFound { ref suggestions, .. } if suggestions.contains(&"suggest_server".to_string()))
+        );
+
+        let err = get_required("suggest_server.database").unwrap_err();
+        assert!(
+            matches!(err, ConfigError::KeyNotFound { ref suggestions, .. } if suggestions.is_empty())
+        );
+    }
+
+    #[test]
+    fn test_missing_keys_report_how_far_traversal_got() {
+        register_config(
+            "reach_db",
+            json!({ "primary": { "host": "a" }, "pool": 8, "replicas": [{ "port": 1 }] }),
+        );
+
+        let cases = [
+            ("reach_dbx.replica.read_timeout", "", "reach_dbx"),
+            ("reach_db.replica.read_timeout", "reach_db", "replica"),
+            (
+                "reach_db.primary.read_timeout",
+                "reach_db.primary",
+                "read_timeout",
+            ),
+            ("reach_db.replicas.0.host", "reach_db.replicas.0", "host"),
+        ];
+        for (key, expected_parent, expected_missing) in cases {
+            match get_required(key).unwrap_err() {
+                ConfigError::KeyNotFound {
+                    parent, missing, ..
+                } => {
+                    assert_eq!(parent, expected_parent, "{key}");
+                    assert_eq!(missing, expected_missing, "{key}");
+                }
+                other => panic!("unexpected error for {key}: {other:?}"),
+            }
+        }
+
+        // Paths through scalars, and through arrays by a segment that is not an index, are
+        // shape errors rather than misses.
+        let cases = [
+            (
+                "reach_db.primary.host.inner",
+                "reach_db.primary.host",
+                "string",
+            ),
+            ("reach_db.pool.max", "reach_db.pool", "number"),
+            ("reach_db.replicas.first.port", "reach_db.replicas", "array"),
+        ];
+        for (key, expected_path, expected_type) in cases {
+            match get_required(key).unwrap_err() {
+                ConfigError::NotAnObject {
+                    resolved_path,
+                    found_type,
+                } => {
+                    assert_eq!(resolved_path, expected_path, "{key}");
+                    assert_eq!(found_type, expected_type, "{key}");
+                }
+                other => panic!("unexpected error for {key}: {other:?}"),
+            }
+            // The `Option` API still reports a plain miss.
+            assert_eq!(get_config_by_key(key), None, "{key}");
+        }
+        assert_eq!(
+            get_config_as::<String>("reach_db.pool.max")
+                .unwrap_err()
+                .to_string(),
+            r#"cannot traverse "reach_db.pool": expected an object, found number"#
+        );
+
+        assert_eq!(
+            get_required("reach_db.replica.read_timeout")
+                .unwrap_err()
+                .to_string(),
+            r#"key not found: "reach_db.replica.read_timeout" (resolved up to "reach_db", "replica" not present; available: ["pool", "primary", "replicas"]; did you mean: ["reach_db.replicas"])"#
+        );
+        assert!(
+            get_required("reach_dbx.port").unwrap_err().to_string().starts_with(
+                r#"key not found: "reach_dbx.port" (resolved up to <root>, "reach_dbx" not present; available: ["#
+            )
+        );
+    }
+
+    #[test]
+    fn test_reads_inside_write_callbacks_see_the_previous_snapshot() {
+        register_config("reentrant", json!({ "count": 1 }));
+
+        let seen = update_config(|map| {
+            map.insert("reentrant".to_string(), json!({ "count": 2 }));
+            get_config_by_key("reentrant.count")
+        });
+        assert_eq!(seen, Some(json!(1)));
+        assert_eq!(get_config_by_key("reentrant.count"), Some(json!(2)));
+
+        let doubled = update_config_at("reentrant.count", |value| {
+            let current: u64 = get_config_as("reentrant.count").unwrap();
+            *value = json!(current * 2);
+        });
+        assert!(doubled.is_ok());
+        assert_eq!(get_config_by_key("reentrant.count"), Some(json!(4)));
+
+        let lazy = get_or_register("reentrant_lazy", || get_config_by_key("reentrant.count"));
+        assert_eq!(lazy, json!(4));
+    }
+
+    #[test]
+    fn test_writes_inside_write_callbacks_panic_and_leave_the_store_unchanged() {
+        register_config("nested_write", json!({ "value": 1 }));
+
+        let result = std::panic::catch_unwind(|| {
+            update_config(|map| {
+                map.insert("nested_write".to_string(), json!({ "value": 2 }));
+                register_config("nested_write_inner", json!({}));
+            })
+        });
+        let message = *result.unwrap_err().downcast::<&str>().unwrap();
+        assert_eq!(
+            message,
+            "the configuration cannot be written from inside a write callback"
+        );
+        assert_eq!(get_config_by_key("nested_write.value"), Some(json!(1)));
+        assert_eq!(get_config_by_key("nested_write_inner"), None);
+
+        // The thread can write again once the callback has unwound.
+        set_config_by_key("nested_write.value", 3).unwrap();
+        assert_eq!(get_config_by_key("nested_write.value"), Some(json!(3)));
+    }
+
+    #[test]
+    fn test_register_batch_is_all_or_nothing() {
+        register_batch(|batch| {
+            batch
+                .register("batch_a", json!({ "n": 1 }))
+                .register("batch_b", json!({ "n": 2 }));
+            batch.register("batch_a", json!({ "n": 3 }));
+        })
+        .unwrap();
+        assert_eq!(get_config_by_key("batch_a.n"), Some(json!(3)));
+        assert_eq!(get_config_by_key("batch_b.n"), Some(json!(2)));
+
+        let err = register_batch(|batch| {
+            batch
+                .register("batch_c", json!({}))
+                .register("batch_d", Unserializable)
+                .register("batch_e", json!({}));
+        })
+        .unwrap_err();
+        assert!(matches!(err, ConfigError::Serialization { ref key, .. } if key == "batch_d"));
+        assert_eq!(get_config_by_key("batch_c"), None);
+        assert_eq!(get_config_by_key("batch_e"), None);
+    }
+
+    #[test]
+    fn test_merge_config_rejects_values_over_the_limits() {
+        register_config("merge_limits", json!({ "kept": true }));
+
+        let mut deep = json!(1);
+        for _ in 0..1_000 {
+            deep = json!({ "a": deep });
+        }
+        let err = merge_config(json!({ "merge_limits": { "deep": deep } })).unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigError::LimitExceeded {
+                limit: "max_depth",
+                ..
+            }
+        ));
+
+        let limits = Limits {
+            max_size: 1024,
+            ..Limits::default()
+        };
+        let big = json!({ "blob": "x".repeat(4096) });
+        let err = merge_config_at_with("merge_limits", big.clone(), &limits).unwrap_err();
+        assert!(
+            matches!(err, ConfigError::LimitExceeded { limit: "max_size", ref path, .. } if path == "merge_limits")
+        );
+        let err = merge_config_with(json!({ "merge_limits": big }), &limits).unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigError::LimitExceeded {
+                limit: "max_size",
+                ..
+            }
+        ));
+
+        assert_eq!(
+            get_config_by_key("merge_limits"),
+            Some(json!({ "kept": true }))
+        );
+        merge_config_at_with("merge_limits", json!({ "small": 1 }), &limits).unwrap();
+        assert_eq!(get_config_by_key("merge_limits.small"), Some(json!(1)));
+    }
+
+    #[test]
+    fn test_dotted_namespaces_are_nested_paths() {
+        register_config("dotted_server", json!({ "port": 443 }));
+        assert_eq!(
+            register_config("dotted_server.tls", json!({ "cert": "a.pem" })),
+            None
+        );
+        assert_eq!(
+            get_config_by_key("dotted_server.tls.cert"),
+            Some(json!("a.pem"))
+        );
+        assert_eq!(get_config_by_key("dotted_server.port"), Some(json!(443)));
+
+        let previous = register_config("dotted_server.tls", json!({ "cert": "b.pem" }));
+        assert_eq!(previous, Some(json!({ "cert": "a.pem" })));
+        assert!(!register_config_once("dotted_server.tls", json!({})));
+        assert_eq!(get_or_register("dotted_new.inner.leaf", || 1), json!(1));
+        assert_eq!(
+            get_config_by_key("dotted_new"),
+            Some(json!({ "inner": { "leaf": 1 } }))
+        );
+
+        let tls: Value = get_namespace("dotted_server.tls").unwrap();
+        assert_eq!(tls, json!({ "cert": "b.pem" }));
+        assert_eq!(
+            unregister_config("dotted_server.tls"),
+            Some(json!({ "cert": "b.pem" }))
+        );
+        assert_eq!(
+            get_config_by_key("dotted_server"),
+            Some(json!({ "port": 443 }))
+        );
+
+        // Quoting registers a top-level name that contains a dot.
+        register_config(r#""dotted.literal""#, json!(true));
+        assert_eq!(get_config_by_path(&["dotted.literal"]), Some(json!(true)));
+
+        let err = try_register_config("dotted_server.port.inner", json!(1)).unwrap_err();
+        assert!(
+            matches!(err, ConfigError::NotAnObject { ref resolved_path, .. } if resolved_path == "dotted_server.port")
+        );
+        assert_eq!(
+            get_config_by_key("dotted_server"),
+            Some(json!({ "port": 443 }))
+        );
+    }
+
+    #[test]
+    fn test_empty_and_blank_namespaces_are_rejected() {
+        for namespace in ["", "   ", "blank_ns.", "blank_ns. .x", "..", r#""""#] {
+            let err = try_register_config(namespace, json!({})).unwrap_err();
+            assert!(
+                matches!(err, ConfigError::InvalidPath { .. }),
+                "{namespace:?}: {err}"
+            );
+            assert!(
+                register_batch(|batch| {
+                    batch.register(namespace, 1);
+                })
+                .is_err()
+            );
+            assert_eq!(unregister_config(namespace), None);
+        }
+        assert_eq!(get_config_by_key("blank_ns"), None);
+
+        let result = std::panic::catch_unwind(|| register_config(" ", json!({})));
+        let message = *result.unwrap_err().downcast::<String>().unwrap();
+        assert!(
+            message.starts_with(r#"invalid key " ": namespaces must not be empty"#),
+            "{message}"
+        );
     }
 }
@@ -0,0 +1,425 @@
+//! Error types returned by the fallible configuration accessors.
+
+use std::fmt;
+
+/// Errors that can occur while reading or writing configuration values.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// No value exists at the requested key.
+    KeyNotFound {
+        /// The dot-separated key that was requested.
+        key: String,
+        /// The longest prefix of `key` that exists (empty for the root).
+        parent: String,
+        /// The first segment of `key` not present under `parent`, unescaped.
+        missing: String,
+        /// The keys available under `parent`.
+        available: Vec<String>,
+        /// Up to three existing keys under `parent` whose names are close to the missing
+        /// segment, as full dot-separated keys, nearest first.
+        suggestions: Vec<String>,
+    },
+    /// A value exists at the requested key but could not be deserialized
+    /// into the requested type.
+    TypeMismatch {
+        /// The dot-separated key that was requested.
+        key: String,
+        /// The absolute dot-separated path of the field that failed to deserialize, which
+        /// extends `key` when the failure is inside a nested value.
+        path: String,
+        /// The underlying deserialization error, naming the expected type and the value
+        /// found.
+        source: serde_json::Error,
+    },
+    /// A strict typed read found keys that the target type does not declare.
+    UnknownKeys {
+        /// The namespace that was read.
+        namespace: String,
+        /// The dot-separated paths of every unknown key, including the namespace.
+        keys: Vec<String>,
+    },
+    /// A key could not be parsed, for example because of an unterminated quote.
+    InvalidPath {
+        /// The key that failed to parse.
+        key: String,
+        /// A description of the problem.
+        message: String,
+    },
+    /// An array index in the key path is past the end of the array.
+    IndexOutOfRange {
+        /// The dot-separated key that was requested.
+        key: String,
+        /// The prefix of the key that resolved to the array.
+        resolved_path: String,
+        /// The requested index. Negative indices count from the end of the array.
+        index: isize,
+        /// The length of the array.
+        len: usize,
+    },
+    /// A path segment resolved to a value that is not an object, so it cannot be
+    /// traversed further.
+    NotAnObject {
+        /// The prefix of the key that resolved to the non-object value.
+        resolved_path: String,
+        /// The JSON type found at `resolved_path`.
+        found_type: &'static str,
+    },
+    /// A value was expected to be an array but has a different type.
+    NotAnArray {
+        /// The dot-separated key of the value.
+        key: String,
+        /// The JSON type found at `key`.
+        found_type: &'static str,
+    },
+    /// A query expression could not be parsed.
+    InvalidQuery {
+        /// The query expression.
+        query: String,
+        /// The byte offset in `query` at which parsing failed.
+        position: usize,
+        /// A description of the problem.
+        message: String,
+    },
+    /// The store was read before it was initialized, with strict initialization enabled.
+    NotInitialized,
+    /// A value exceeded one of the configured [`Limits`](crate::config::Limits).
+    LimitExceeded {
+        /// The name of the limit, matching its field in `Limits`, such as `"max_depth"`.
+        limit: &'static str,
+        /// The value of the limit.
+        max: usize,
+        /// The dot-separated key of the value that exceeded it (empty for the root).
+        path: String,
+    },
+    /// A namespace was registered twice under [`RegistrationPolicy::Error`].
+    ///
+    /// [`RegistrationPolicy::Error`]: crate::config::RegistrationPolicy::Error
+    AlreadyRegistered {
+        /// The namespace that was already registered.
+        namespace: String,
+    },
+    /// The configuration was modified after [`freeze_config`] was called.
+    ///
+    /// [`freeze_config`]: crate::config::freeze_config
+    Frozen,
+    /// A value could not be serialized into JSON.
+    Serialization {
+        /// The key or namespace the value was destined for.
+        key: String,
+        /// The underlying serialization error.
+        source: serde_json::Error,
+    },
+    /// A reference inside a value, such as `$VAR` or `~user` in a path, could not be
+    /// expanded.
+    Expansion {
+        /// The dot-separated key of the value.
+        key: String,
+        /// The reference that could not be expanded, as written.
+        reference: String,
+        /// Why it could not be expanded.
+        message: String,
+    },
+    /// A path read from the configuration was required to exist but does not.
+    PathNotFound {
+        /// The dot-separated key of the value.
+        key: String,
+        /// The path after expansion.
+        path: std::path::PathBuf,
+    },
+    /// A configuration file could not be read.
+    Io {
+        /// The file that was being read.
+        path: std::path::PathBuf,
+        /// The underlying I/O error.
+        source: std::io::Error,
+    },
+    /// A configuration document could not be parsed.
+    Parse {
+        /// Where the document came from: a file path, or a description such as `stdin`.
+        origin: String,
+        /// The name of the format, such as `"JSON"`.
+        format: &'static str,
+        /// The 1-based line of the problem, if the parser reports one.
+        line: Option<usize>,
+        /// The 1-based column of the problem, if the parser reports one.
+        column: Option<usize>,
+        /// A description of the problem.
+        message: String,
+    },
+    /// The format of a configuration file could not be told from its name or contents.
+    UnknownFormat {
+        /// The file that was being loaded.
+        path: std::path::PathBuf,
+    },
+    /// A configuration file is in a format whose cargo feature is not enabled.
+    FormatDisabled {
+        /// The name of the format, such as `"TOML"`.
+        format: &'static str,
+        /// The cargo feature that enables it.
+        feature: &'static str,
+    },
+    /// The profile chosen for [`load_profile`](crate::config::load_profile) has no file.
+    UnknownProfile {
+        /// The name of the profile.
+        profile: String,
+        /// The file the profile would be loaded from.
+        path: std::path::PathBuf,
+    },
+    /// A configuration file includes itself, directly or through other files.
+    IncludeCycle {
+        /// The files of the cycle, in order of inclusion, starting and ending with the same
+        /// file.
+        chain: Vec<std::path::PathBuf>,
+    },
+    /// Configuration files include each other more deeply than allowed.
+    IncludeTooDeep {
+        /// The files from the loaded file to the one that is too deep, in order of
+        /// inclusion.
+        chain: Vec<std::path::PathBuf>,
+        /// The deepest nesting allowed.
+        max_depth: usize,
+    },
+    /// A remote configuration document could not be fetched, because the server could not
+    /// be reached or the connection failed.
+    Network {
+        /// The URL that was being fetched.
+        url: String,
+        /// A description of the failure.
+        message: String,
+    },
+    /// A server answered a request for a configuration document with an error status.
+    HttpStatus {
+        /// The URL that was being fetched.
+        url: String,
+        /// The HTTP status code.
+        status: u16,
+    },
+    /// A remote configuration document has a content type that names no known format.
+    UnsupportedContentType {
+        /// The URL that was fetched.
+        url: String,
+        /// The `Content-Type` of the response, if it had one.
+        content_type: Option<String>,
+    },
+    /// Every attempt at fetching a remote configuration document failed.
+    FetchFailed {
+        /// The URL that was being fetched.
+        url: String,
+        /// The failure of each attempt, in order.
+        attempts: Vec<ConfigError>,
+    },
+    /// A [configuration source](crate::config::ConfigSource) failed to load.
+    SourceFailed {
+        /// The [name](crate::config::ConfigSource::name) of the source.
+        name: String,
+        /// Why it failed.
+        error: Box<ConfigError>,
+    },
+    /// A configuration file could not be [watched](crate::config::watch_file).
+    Watch {
+        /// The file that was to be watched.
+        path: std::path::PathBuf,
+        /// A description of the failure.
+        message: String,
+    },
+    /// A background thread, such as that of
+    /// [`start_refresh`](crate::config::start_refresh), could not be spawned.
+    ThreadSpawn {
+        /// The name of the thread.
+        name: String,
+        /// The underlying I/O error.
+        source: std::io::Error,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::KeyNotFound {
+                key,
+                parent,
+                missing,
+                available,
+                suggestions,
+            } => {
+                write!(f, "key not found: \"{key}\" (resolved up to ")?;
+                if parent.is_empty() {
+                    write!(f, "<root>")?;
+                } else {
+                    write!(f, "\"{parent}\"")?;
+                }
+                write!(f, ", \"{missing}\" not present; available: {available:?}")?;
+                if !suggestions.is_empty() {
+                    write!(f, "; did you mean: {suggestions:?}")?;
+                }
+                write!(f, ")")
+            }
+            ConfigError::TypeMismatch { path, source, .. } => {
+                if path.is_empty() {
+                    write!(f, "type mismatch at <root>: {source}")
+                } else {
+                    write!(f, "type mismatch at \"{path}\": {source}")
+                }
+            }
+            ConfigError::UnknownKeys { namespace, keys } => {
+                write!(f, "unknown keys in \"{namespace}\": {keys:?}")
+            }
+            ConfigError::InvalidPath { key, message } => {
+                write!(f, "invalid key \"{key}\": {message}")
+            }
+            ConfigError::IndexOutOfRange {
+                key,
+                resolved_path,
+                index,
+                len,
+            } => write!(
+                f,
+                "index out of range in \"{key}\": \"{resolved_path}\" has {len} elements, index is {index}"
+            ),
+            ConfigError::NotAnObject {
+                resolved_path,
+                found_type,
+            } => write!(
+                f,
+                "cannot traverse \"{resolved_path}\": expected an object, found {found_type}"
+            ),
+            ConfigError::NotAnArray { key, found_type } => {
+                write!(f, "expected an array at \"{key}\", found {found_type}")
+            }
+            ConfigError::InvalidQuery {
+                query,
+                position,
+                message,
+            } => write!(
+                f,
+                "invalid query \"{query}\" at offset {position}: {message}"
+            ),
+            ConfigError::NotInitialized => {
+                write!(f, "configuration read before init_config() was called")
+            }
+            ConfigError::LimitExceeded { limit, max, path } => {
+                if path.is_empty() {
+                    write!(f, "limit {limit} of {max} exceeded at <root>")
+                } else {
+                    write!(f, "limit {limit} of {max} exceeded at \"{path}\"")
+                }
+            }
+            ConfigError::AlreadyRegistered { namespace } => {
+                write!(
+                    f,
+                    "a configuration is already registered under \"{namespace}\""
+                )
+            }
+            ConfigError::Frozen => {
+                write!(
+                    f,
+                    "the configuration is frozen and can no longer be modified"
+                )
+            }
+            ConfigError::Serialization { key, source } => {
+                write!(f, "failed to serialize value for \"{key}\": {source}")
+            }
+            ConfigError::Expansion {
+                key,
+                reference,
+                message,
+            } => write!(f, "cannot expand \"{reference}\" in \"{key}\": {message}"),
+            ConfigError::PathNotFound { key, path } => {
+                write!(f, "path in \"{key}\" does not exist: {}", path.display())
+            }
+            ConfigError::Io { path, source } => {
+                write!(f, "cannot read {}: {source}", path.display())
+            }
+            ConfigError::Parse {
+                origin,
+                format,
+                line,
+                column,
+                message,
+            } => {
+                write!(f, "invalid {format} in {origin}")?;
+                match (line, column) {
+                    (Some(line), Some(column)) => write!(f, " at line {line}, column {column}")?,
+                    (Some(line), None) => write!(f, " at line {line}")?,
+                    _ => {}
+                }
+                write!(f, ": {message}")
+            }
+            ConfigError::UnknownFormat { path } => write!(
+                f,
+                "cannot tell the format of {}; use a known extension such as .json, or name the format",
+                path.display()
+            ),
+            ConfigError::FormatDisabled { format, feature } => write!(
+                f,
+                "loading {format} requires the \"{feature}\" feature of zirv-config"
+            ),
+            ConfigError::UnknownProfile { profile, path } => write!(
+                f,
+                "unknown profile \"{profile}\": {} does not exist",
+                path.display()
+            ),
+            ConfigError::IncludeCycle { chain } => {
+                write!(f, "include cycle: {}", display_chain(chain))
+            }
+            ConfigError::IncludeTooDeep { chain, max_depth } => write!(
+                f,
+                "includes nest more than {max_depth} deep: {}",
+                display_chain(chain)
+            ),
+            ConfigError::Network { url, message } => write!(f, "cannot fetch {url}: {message}"),
+            ConfigError::HttpStatus { url, status } => {
+                write!(f, "fetching {url} failed with HTTP status {status}")
+            }
+            ConfigError::UnsupportedContentType { url, content_type } => match content_type {
+                Some(content_type) => write!(
+                    f,
+                    "cannot tell the format of {url} from its content type \"{content_type}\""
+                ),
+                None => write!(
+                    f,
+                    "cannot tell the format of {url}, which has no content type"
+                ),
+            },
+            ConfigError::FetchFailed { url, attempts } => {
+                write!(f, "fetching {url} failed after {} attempts", attempts.len())?;
+                for (index, err) in attempts.iter().enumerate() {
+                    write!(f, "; attempt {}: {err}", index + 1)?;
+                }
+                Ok(())
+            }
+            ConfigError::SourceFailed { name, error } => {
+                write!(f, "configuration source \"{name}\" failed: {error}")
+            }
+            ConfigError::Watch { path, message } => {
+                write!(f, "cannot watch {}: {message}", path.display())
+            }
+            ConfigError::ThreadSpawn { name, source } => {
+                write!(f, "cannot spawn the thread {name}: {source}")
+            }
+        }
+    }
+}
+
+/// Joins a chain of included files with arrows.
+fn display_chain(chain: &[std::path::PathBuf]) -> String {
+    chain
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::TypeMismatch { source, .. } => Some(source),
+            ConfigError::Serialization { source, .. } => Some(source),
+            ConfigError::Io { source, .. } => Some(source),
+            ConfigError::ThreadSpawn { source, .. } => Some(source),
+            ConfigError::SourceFailed { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
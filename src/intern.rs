@@ -0,0 +1,138 @@
+//! Namespaces whose repeated strings share storage, for the `intern` feature.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+/// A JSON value whose strings, object keys included, are shared: each distinct string is
+/// stored once, however often it appears in the value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Interned {
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(Arc<str>),
+    Array(Box<[Interned]>),
+    /// The entries in the order of the object they came from.
+    Object(Box<[(Arc<str>, Interned)]>),
+}
+
+impl Interned {
+    /// Interns `value`, replacing every string with the first one of the same contents.
+    pub(crate) fn new(value: Value) -> Self {
+        Pool::default().intern(value)
+    }
+
+    /// Copies the value back into an owned JSON value, equal to the one it was made from.
+    pub(crate) fn to_value(&self) -> Value {
+        match self {
+            Interned::Null => Value::Null,
+            Interned::Bool(flag) => Value::Bool(*flag),
+            Interned::Number(number) => Value::Number(number.clone()),
+            Interned::String(text) => Value::String(text.to_string()),
+            Interned::Array(items) => Value::Array(items.iter().map(Interned::to_value).collect()),
+            Interned::Object(entries) => Value::Object(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_value()))
+                    .collect::<Map<String, Value>>(),
+            ),
+        }
+    }
+}
+
+impl Serialize for Interned {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Interned::Null => serializer.serialize_unit(),
+            Interned::Bool(flag) => serializer.serialize_bool(*flag),
+            Interned::Number(number) => number.serialize(serializer),
+            Interned::String(text) => serializer.serialize_str(text),
+            Interned::Array(items) => serializer.collect_seq(items.iter()),
+            Interned::Object(entries) => {
+                serializer.collect_map(entries.iter().map(|(key, value)| (&**key, value)))
+            }
+        }
+    }
+}
+
+/// The distinct strings seen so far while interning a value.
+#[derive(Default)]
+struct Pool {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Pool {
+    fn intern(&mut self, value: Value) -> Interned {
+        match value {
+            Value::Null => Interned::Null,
+            Value::Bool(flag) => Interned::Bool(flag),
+            Value::Number(number) => Interned::Number(number),
+            Value::String(text) => Interned::String(self.string(text)),
+            Value::Array(items) => {
+                Interned::Array(items.into_iter().map(|item| self.intern(item)).collect())
+            }
+            Value::Object(map) => Interned::Object(
+                map.into_iter()
+                    .map(|(key, value)| (self.string(key), self.intern(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns the shared copy of `text`, making it the shared copy if it is new.
+    fn string(&mut self, text: String) -> Arc<str> {
+        if let Some(shared) = self.strings.get(text.as_str()) {
+            return Arc::clone(shared);
+        }
+        let shared: Arc<str> = Arc::from(text);
+        self.strings.insert(Arc::clone(&shared));
+        shared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_interned_round_trips_and_shares_strings() {
+        let value = json!({
+            "routes": [
+                { "method": "GET", "upstream": "billing", "retries": 3, "tls": true },
+                { "method": "GET", "upstream": "billing", "retries": -1.5, "tls": null },
+            ],
+            "GET": "GET",
+        });
+        let interned = Interned::new(value.clone());
+        assert_eq!(interned.to_value(), value);
+        assert_eq!(
+            serde_json::to_string(&interned).unwrap(),
+            serde_json::to_string(&value).unwrap()
+        );
+
+        let Interned::Object(root) = &interned else {
+            panic!("expected an object");
+        };
+        let (key, Interned::String(text)) = &root[0] else {
+            panic!("expected the GET entry first");
+        };
+        // The key, the value and both methods are one string.
+        assert!(Arc::ptr_eq(key, text));
+        let Interned::Array(routes) = &root[1].1 else {
+            panic!("expected the routes");
+        };
+        for route in routes.iter() {
+            let Interned::Object(entries) = route else {
+                panic!("expected a route object");
+            };
+            let (_, Interned::String(method)) = &entries[0] else {
+                panic!("expected the method first");
+            };
+            assert!(Arc::ptr_eq(method, text));
+        }
+    }
+}
@@ -0,0 +1,534 @@
+//! Parsing of dot-separated configuration keys.
+//!
+//! A key is a sequence of segments separated by `.`, such as `server.port`. Segments that
+//! themselves contain dots can be written in two ways:
+//!
+//! - Quoted: `hosts."api.example.com".timeout`. Inside quotes, `\"` and `\\` stand for a
+//!   literal quote and backslash.
+//! - Escaped: `hosts.api\.example\.com.timeout`. A backslash makes the following character
+//!   literal, so `\\` is a backslash and `\"` a quote at the start of a segment.
+//!
+//! An unterminated quote or a trailing backslash is rejected with
+//! [`ConfigError::InvalidPath`].
+//!
+//! Each thread remembers the parsed form of the string keys it looked up most recently, so
+//! a key read over and over is only parsed once. A [`ConfigKey`] can also be parsed once
+//! and passed to the accessors in place of a string.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::ConfigError;
+
+/// A pre-parsed configuration key.
+///
+/// Accessors such as [`get_config_by_key`](crate::config::get_config_by_key) and
+/// [`get_config_as`](crate::config::get_config_as) accept a `ConfigKey` wherever they
+/// accept a string, skipping the parsing step on every call.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use zirv_config::key::ConfigKey;
+///
+/// static SERVER_PORT: ConfigKey = ConfigKey::from_static("server.port");
+///
+/// config::register_config("server", serde_json::json!({ "port": 3000 }));
+/// let port: u16 = config::get_config_as(&SERVER_PORT).unwrap();
+/// assert_eq!(port, 3000);
+///
+/// let host = ConfigKey::parse("server.host").unwrap();
+/// assert_eq!(host.to_string(), "server.host");
+/// assert!(config::get_config_by_key(&host).is_none());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConfigKey {
+    raw: Cow<'static, str>,
+    segments: OnceLock<Vec<Segment<'static>>>,
+}
+
+impl ConfigKey {
+    /// Parses and validates a key, returning [`ConfigError::InvalidPath`] if it is malformed.
+    pub fn parse(key: &str) -> Result<Self, ConfigError> {
+        let segments = into_owned(parse_key(key)?);
+        Ok(ConfigKey {
+            raw: Cow::Owned(key.to_string()),
+            segments: OnceLock::from(segments),
+        })
+    }
+
+    /// Creates a key from a string literal, usable in `const` and `static` items.
+    ///
+    /// The key is parsed on first use; if it is malformed, every lookup through it fails
+    /// with [`ConfigError::InvalidPath`].
+    pub const fn from_static(key: &'static str) -> Self {
+        ConfigKey {
+            raw: Cow::Borrowed(key),
+            segments: OnceLock::new(),
+        }
+    }
+
+    /// Returns the key as originally written.
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    fn segments(&self) -> Result<&[Segment<'static>], ConfigError> {
+        if let Some(segments) = self.segments.get() {
+            return Ok(segments);
+        }
+        // Keys built by `parse` are always parsed already; only `from_static` keys get here.
+        let Cow::Borrowed(raw) = self.raw else {
+            unreachable!("parsed keys store their segments eagerly")
+        };
+        let segments = parse_key(raw)?;
+        Ok(self.segments.get_or_init(|| segments))
+    }
+}
+
+impl fmt::Display for ConfigKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl PartialEq for ConfigKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl Eq for ConfigKey {}
+
+/// A [`ConfigKey`] bound to the type of the value stored at it, usually declared with
+/// [`define_config_key!`](crate::define_config_key).
+///
+/// Keeping typed keys in one place confines key strings, and any typos in them, to a
+/// single file.
+pub struct TypedConfigKey<T> {
+    key: ConfigKey,
+    default: Option<fn() -> T>,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T> TypedConfigKey<T> {
+    /// Creates a typed key without a default.
+    pub const fn new(key: &'static str) -> Self {
+        TypedConfigKey {
+            key: ConfigKey::from_static(key),
+            default: None,
+            _type: PhantomData,
+        }
+    }
+
+    /// Creates a typed key that falls back to `default()` when nothing is stored at it.
+    pub const fn with_default(key: &'static str, default: fn() -> T) -> Self {
+        TypedConfigKey {
+            key: ConfigKey::from_static(key),
+            default: Some(default),
+            _type: PhantomData,
+        }
+    }
+
+    /// Returns the underlying key.
+    pub fn key(&self) -> &ConfigKey {
+        &self.key
+    }
+}
+
+impl<T: DeserializeOwned> TypedConfigKey<T> {
+    /// Reads and deserializes the value stored at this key.
+    ///
+    /// Returns [`ConfigError::KeyNotFound`] if nothing is stored and the key has no
+    /// default, and [`ConfigError::TypeMismatch`] if the value is not a `T`, even when a
+    /// default exists.
+    pub fn get(&self) -> Result<T, ConfigError> {
+        match self.default {
+            Some(default) => crate::config::try_get_config_or_else(&self.key, default),
+            None => crate::config::get_config_as(&self.key),
+        }
+    }
+}
+
+impl<T> fmt::Debug for TypedConfigKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypedConfigKey")
+            .field("key", &self.key.as_str())
+            .field("type", &std::any::type_name::<T>())
+            .finish()
+    }
+}
+
+/// Types that can be used to look up configuration values: strings and [`ConfigKey`].
+pub trait AsConfigKey {
+    /// Parses the key, or borrows its already-parsed form.
+    #[doc(hidden)]
+    fn parsed_key(&self) -> Result<ParsedKey<'_>, ConfigError>;
+}
+
+/// How many string keys each thread keeps parsed. When full, the cache is emptied and
+/// starts over, so keys that are not reused cannot grow it.
+const KEY_CACHE_CAPACITY: usize = 256;
+
+/// Keys longer than this are parsed every time rather than cached.
+const KEY_CACHE_MAX_LEN: usize = 256;
+
+thread_local! {
+    /// Parsed segments of recently looked-up string keys. Only parsing is cached, so it
+    /// never needs invalidating when the configuration changes.
+    static KEY_CACHE: RefCell<HashMap<Box<str>, Rc<[Segment<'static>]>>> =
+        RefCell::new(HashMap::new());
+}
+
+impl AsConfigKey for str {
+    fn parsed_key(&self) -> Result<ParsedKey<'_>, ConfigError> {
+        if self.len() > KEY_CACHE_MAX_LEN {
+            return Ok(ParsedKey {
+                raw: self,
+                segments: Segments::Owned(parse_key(self)?),
+            });
+        }
+        let cached = KEY_CACHE.with_borrow(|cache| cache.get(self).cloned());
+        let segments = match cached {
+            Some(segments) => segments,
+            None => {
+                let segments: Rc<[Segment<'static>]> = into_owned(parse_key(self)?).into();
+                KEY_CACHE.with_borrow_mut(|cache| {
+                    if cache.len() >= KEY_CACHE_CAPACITY {
+                        cache.clear();
+                    }
+                    cache.insert(self.into(), Rc::clone(&segments));
+                });
+                segments
+            }
+        };
+        Ok(ParsedKey {
+            raw: self,
+            segments: Segments::Shared(segments),
+        })
+    }
+}
+
+impl AsConfigKey for String {
+    fn parsed_key(&self) -> Result<ParsedKey<'_>, ConfigError> {
+        self.as_str().parsed_key()
+    }
+}
+
+impl AsConfigKey for ConfigKey {
+    fn parsed_key(&self) -> Result<ParsedKey<'_>, ConfigError> {
+        Ok(ParsedKey {
+            raw: &self.raw,
+            segments: Segments::Borrowed(self.segments()?),
+        })
+    }
+}
+
+impl AsConfigKey for ParsedKey<'_> {
+    fn parsed_key(&self) -> Result<ParsedKey<'_>, ConfigError> {
+        Ok(ParsedKey {
+            raw: self.raw,
+            segments: Segments::Borrowed(&self.segments),
+        })
+    }
+}
+
+impl<K: AsConfigKey + ?Sized> AsConfigKey for &K {
+    fn parsed_key(&self) -> Result<ParsedKey<'_>, ConfigError> {
+        (**self).parsed_key()
+    }
+}
+
+/// A key together with its parsed segments.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct ParsedKey<'a> {
+    pub(crate) raw: &'a str,
+    pub(crate) segments: Segments<'a>,
+}
+
+/// The segments of a [`ParsedKey`], borrowed from a [`ConfigKey`] or another parsed key,
+/// shared with the key cache, or owned.
+#[doc(hidden)]
+#[derive(Debug)]
+pub enum Segments<'a> {
+    Borrowed(&'a [Segment<'a>]),
+    Shared(Rc<[Segment<'static>]>),
+    Owned(Vec<Segment<'a>>),
+}
+
+impl<'a> Deref for Segments<'a> {
+    type Target = [Segment<'a>];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Segments::Borrowed(segments) => segments,
+            Segments::Shared(segments) => segments,
+            Segments::Owned(segments) => segments,
+        }
+    }
+}
+
+impl ParsedKey<'_> {
+    /// Returns the unescaped segment names.
+    pub(crate) fn names(&self) -> Vec<&str> {
+        self.segments
+            .iter()
+            .map(|segment| segment.name.as_ref())
+            .collect()
+    }
+}
+
+/// A single parsed segment of a key.
+#[doc(hidden)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment<'a> {
+    /// The segment after unquoting and unescaping.
+    pub(crate) name: Cow<'a, str>,
+    /// The byte offset in the raw key just past this segment.
+    pub(crate) end: usize,
+}
+
+/// Splits a dot-separated key into its segments, honouring quotes and escapes.
+pub(crate) fn parse_key(key: &str) -> Result<Vec<Segment<'_>>, ConfigError> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    loop {
+        let (name, end) = parse_segment(key, start)?;
+        segments.push(Segment { name, end });
+        if end == key.len() {
+            return Ok(segments);
+        }
+        // `parse_segment` only stops early at a `.` separator.
+        start = end + 1;
+    }
+}
+
+/// Parses the segment starting at byte offset `start`, returning it and its end offset.
+fn parse_segment(key: &str, start: usize) -> Result<(Cow<'_, str>, usize), ConfigError> {
+    let rest = &key[start..];
+    if let Some(quoted) = rest.strip_prefix('"') {
+        let mut name = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '"' => {
+                    let end = start + 1 + offset + 1;
+                    if end < key.len() && !key[end..].starts_with('.') {
+                        return Err(invalid(key, "expected `.` after closing quote"));
+                    }
+                    return Ok((Cow::Owned(name), end));
+                }
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => name.push(escaped),
+                    None => break,
+                },
+                c => name.push(c),
+            }
+        }
+        return Err(invalid(key, "unterminated quote"));
+    }
+
+    let end = rest.find(['.', '\\']).unwrap_or(rest.len());
+    if !rest[end..].starts_with('\\') {
+        return Ok((Cow::Borrowed(&rest[..end]), start + end));
+    }
+    let mut name = rest[..end].to_string();
+    let mut chars = rest[end..].char_indices();
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            '.' => return Ok((Cow::Owned(name), start + end + offset)),
+            '\\' => match chars.next() {
+                Some((_, escaped)) => name.push(escaped),
+                None => return Err(invalid(key, "trailing backslash")),
+            },
+            c => name.push(c),
+        }
+    }
+    Ok((Cow::Owned(name), key.len()))
+}
+
+/// Writes `name` as a key segment, quoting it if it contains dots, quotes or backslashes.
+pub(crate) fn escape_segment(name: &str) -> Cow<'_, str> {
+    if !name.contains(['.', '"', '\\']) {
+        return Cow::Borrowed(name);
+    }
+    let mut quoted = String::with_capacity(name.len() + 2);
+    quoted.push('"');
+    for c in name.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    Cow::Owned(quoted)
+}
+
+/// Detaches parsed segments from the key they were parsed from.
+fn into_owned(segments: Vec<Segment<'_>>) -> Vec<Segment<'static>> {
+    segments
+        .into_iter()
+        .map(|segment| Segment {
+            name: Cow::Owned(segment.name.into_owned()),
+            end: segment.end,
+        })
+        .collect()
+}
+
+fn invalid(key: &str, message: &str) -> ConfigError {
+    ConfigError::InvalidPath {
+        key: key.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(key: &str) -> Vec<String> {
+        parse_key(key)
+            .unwrap()
+            .into_iter()
+            .map(|segment| segment.name.into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_plain_key() {
+        assert_eq!(names("server.port"), vec!["server", "port"]);
+        assert_eq!(names("server"), vec!["server"]);
+        assert_eq!(names("a..b"), vec!["a", "", "b"]);
+        let ends: Vec<usize> = parse_key("ab.c").unwrap().iter().map(|s| s.end).collect();
+        assert_eq!(ends, vec![2, 4]);
+    }
+
+    #[test]
+    fn test_parse_quoted_and_escaped_keys() {
+        assert_eq!(
+            names(r#"hosts."api.example.com".timeout"#),
+            vec!["hosts", "api.example.com", "timeout"]
+        );
+        assert_eq!(
+            names(r"hosts.api\.example\.com.timeout"),
+            vec!["hosts", "api.example.com", "timeout"]
+        );
+        assert_eq!(names(r#"a."say \"hi\"".b"#), vec!["a", r#"say "hi""#, "b"]);
+        assert_eq!(names(r#"a."back\\slash""#), vec!["a", r"back\slash"]);
+        assert_eq!(names(r"a.back\\slash"), vec!["a", r"back\slash"]);
+        assert_eq!(names(r#"a.\"quoted"#), vec!["a", "\"quoted"]);
+        let ends: Vec<usize> = parse_key(r#""a.b".c"#)
+            .unwrap()
+            .iter()
+            .map(|s| s.end)
+            .collect();
+        assert_eq!(ends, vec![5, 7]);
+    }
+
+    #[test]
+    fn test_config_key() {
+        static STATIC_KEY: ConfigKey = ConfigKey::from_static(r#"hosts."api.example.com".timeout"#);
+        let parsed = STATIC_KEY.parsed_key().unwrap();
+        assert_eq!(parsed.names(), vec!["hosts", "api.example.com", "timeout"]);
+        assert_eq!(STATIC_KEY.to_string(), r#"hosts."api.example.com".timeout"#);
+
+        let key = ConfigKey::parse("server.port").unwrap();
+        assert_eq!(key.clone(), key);
+        assert_eq!(key.as_str(), "server.port");
+        assert_eq!(key.parsed_key().unwrap().names(), vec!["server", "port"]);
+
+        assert!(matches!(
+            ConfigKey::parse(r"a.b\"),
+            Err(ConfigError::InvalidPath { .. })
+        ));
+        static INVALID: ConfigKey = ConfigKey::from_static(r#"a."open"#);
+        assert!(matches!(
+            INVALID.parsed_key(),
+            Err(ConfigError::InvalidPath { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_invalid_keys() {
+        for key in [r#"hosts."api.example.com"#, r#"a."b"c"#, r"a.b\"] {
+            let err = parse_key(key).unwrap_err();
+            assert!(matches!(err, ConfigError::InvalidPath { .. }), "{key:?}");
+        }
+        assert!(
+            parse_key(r#"a."open"#)
+                .unwrap_err()
+                .to_string()
+                .contains("unterminated quote")
+        );
+    }
+
+    #[test]
+    fn test_typed_config_key() {
+        crate::define_config_key!(PORT: u16 = "typed_key.port");
+        crate::define_config_key!(WORKERS: u32 = "typed_key.workers", default = 4);
+
+        crate::config::register_config("typed_key", serde_json::json!({ "port": "not a number" }));
+        assert!(matches!(PORT.get(), Err(ConfigError::TypeMismatch { .. })));
+        assert_eq!(WORKERS.get().unwrap(), 4);
+
+        crate::config::register_config(
+            "typed_key",
+            serde_json::json!({ "port": 8080, "workers": "x" }),
+        );
+        assert_eq!(PORT.get().unwrap(), 8080);
+        assert!(matches!(
+            WORKERS.get(),
+            Err(ConfigError::TypeMismatch { .. })
+        ));
+        assert_eq!(PORT.key().as_str(), "typed_key.port");
+    }
+
+    #[test]
+    fn test_escape_segment_round_trips() {
+        assert_eq!(escape_segment("port"), "port");
+        assert_eq!(escape_segment("api.example.com"), r#""api.example.com""#);
+        for name in ["api.example.com", r#"say "hi""#, r"back\slash", "plain"] {
+            let key = format!("hosts.{}", escape_segment(name));
+            assert_eq!(names(&key), vec!["hosts", name]);
+        }
+    }
+
+    #[test]
+    fn test_string_keys_are_cached_per_thread() {
+        let shared = |key: &str| match key.parsed_key().unwrap().segments {
+            Segments::Shared(segments) => segments,
+            other => panic!("not cached: {other:?}"),
+        };
+        let first = shared(r#"cache.hosts."api.example.com""#);
+        let second = shared(r#"cache.hosts."api.example.com""#);
+        assert!(Rc::ptr_eq(&first, &second));
+        assert_eq!(second[2].name, "api.example.com");
+
+        // Invalid keys are reported every time and never cached.
+        assert!(r#"cache."open"#.parsed_key().is_err());
+        assert!(r#"cache."open"#.parsed_key().is_err());
+        assert!(KEY_CACHE.with_borrow(|cache| !cache.contains_key(r#"cache."open"#)));
+
+        // Churning through distinct keys never grows the cache past its capacity.
+        for index in 0..KEY_CACHE_CAPACITY * 3 {
+            format!("churn.key{index}").parsed_key().unwrap();
+            assert!(KEY_CACHE.with_borrow(HashMap::len) <= KEY_CACHE_CAPACITY);
+        }
+
+        let long = "a.".repeat(KEY_CACHE_MAX_LEN);
+        assert!(matches!(
+            long[..long.len() - 1].parsed_key().unwrap().segments,
+            Segments::Owned(_)
+        ));
+    }
+}
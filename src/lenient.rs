@@ -0,0 +1,231 @@
+//! A deserializer over [`Value`] that coerces scalars between strings, numbers and booleans.
+//!
+//! Values that arrive through environment variables or flat files are strings, so a
+//! strict read of `"3000"` into a `u16` fails even though the intent is clear. The
+//! [`Lenient`] deserializer accepts, wherever the target type asks for them:
+//!
+//! - numbers written as strings, such as `"3000"` or `"0.5"`;
+//! - booleans written as the strings `"true"` and `"false"`;
+//! - numbers and booleans where a string is expected.
+//!
+//! Anything else is deserialized exactly as `serde_json` would.
+
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, Deserializer, IntoDeserializer, Unexpected, Visitor};
+use serde_json::Value;
+
+use crate::config::{ConfigError, type_mismatch};
+
+/// Deserializes `value`, read from `key`, into `T`, coercing scalars where needed.
+pub(crate) fn from_value_lenient<T: DeserializeOwned>(
+    key: &str,
+    value: &Value,
+) -> Result<T, ConfigError> {
+    serde_path_to_error::deserialize(Lenient(value)).map_err(|err| {
+        let path = err.path().clone();
+        type_mismatch(key, &path, err.into_inner())
+    })
+}
+
+#[derive(Clone, Copy)]
+struct Lenient<'a>(&'a Value);
+
+/// Generates the integer and float methods, which accept numeric strings.
+macro_rules! deserialize_number {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                match self.0 {
+                    Value::String(text) => visit_numeric_str(text, visitor),
+                    other => other.$method(visitor),
+                }
+            }
+        )*
+    };
+}
+
+fn visit_numeric_str<'de, V: Visitor<'de>>(
+    text: &str,
+    visitor: V,
+) -> Result<V::Value, serde_json::Error> {
+    let trimmed = text.trim();
+    if let Ok(number) = trimmed.parse::<u64>() {
+        visitor.visit_u64(number)
+    } else if let Ok(number) = trimmed.parse::<i64>() {
+        visitor.visit_i64(number)
+    } else if let Ok(number) = trimmed.parse::<f64>() {
+        visitor.visit_f64(number)
+    } else {
+        Err(de::Error::invalid_type(Unexpected::Str(text), &visitor))
+    }
+}
+
+impl<'de> Deserializer<'de> for Lenient<'de> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Array(items) => {
+                let mut seq = SeqDeserializer::new(items.iter().map(Lenient));
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Object(map) => {
+                let mut entries = MapDeserializer::new(
+                    map.iter()
+                        .map(|(key, value)| (key.as_str(), Lenient(value))),
+                );
+                let value = visitor.visit_map(&mut entries)?;
+                entries.end()?;
+                Ok(value)
+            }
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::String(text) => match text.trim() {
+                "true" => visitor.visit_bool(true),
+                "false" => visitor.visit_bool(false),
+                _ => Err(de::Error::invalid_type(Unexpected::Str(text), &visitor)),
+            },
+            other => other.deserialize_bool(visitor),
+        }
+    }
+
+    deserialize_number! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Number(number) => visitor.visit_string(number.to_string()),
+            Value::Bool(flag) => visitor.visit_string(flag.to_string()),
+            other => other.deserialize_str(visitor),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.deserialize_enum(name, variants, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        char bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, serde_json::Error> for Lenient<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Server {
+        port: u16,
+        ratio: f64,
+        offset: i32,
+        tls: bool,
+        name: String,
+        limit: Option<u32>,
+        peers: Vec<u8>,
+    }
+
+    #[test]
+    fn test_coerces_each_direction() {
+        let value = json!({
+            "port": "3000",
+            "ratio": " 0.5 ",
+            "offset": "-7",
+            "tls": "true",
+            "name": 42,
+            "limit": "10",
+            "peers": ["1", 2]
+        });
+        let server: Server = from_value_lenient("server", &value).unwrap();
+        assert_eq!(
+            server,
+            Server {
+                port: 3000,
+                ratio: 0.5,
+                offset: -7,
+                tls: true,
+                name: "42".to_string(),
+                limit: Some(10),
+                peers: vec![1, 2],
+            }
+        );
+
+        assert!(!from_value_lenient::<bool>("flag", &json!("false")).unwrap());
+        assert_eq!(
+            from_value_lenient::<String>("flag", &json!(true)).unwrap(),
+            "true"
+        );
+        assert_eq!(
+            from_value_lenient::<u16>("port", &json!(8080)).unwrap(),
+            8080
+        );
+    }
+
+    #[test]
+    fn test_coercion_failures_report_the_path() {
+        let err = from_value_lenient::<Server>(
+            "server",
+            &json!({
+                "port": "3000abc",
+                "ratio": 1,
+                "offset": 0,
+                "tls": false,
+                "name": "a",
+                "peers": []
+            }),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"type mismatch at "server.port": invalid type: string "3000abc", expected u16"#
+        );
+
+        let err = from_value_lenient::<u8>("small", &json!("300")).unwrap_err();
+        assert!(err.to_string().contains("expected u8"), "{err}");
+        let err = from_value_lenient::<bool>("flag", &json!("yes")).unwrap_err();
+        assert!(matches!(err, ConfigError::TypeMismatch { .. }));
+    }
+}
@@ -4,18 +4,146 @@
 //! from multiple subsystems (such as `server`, `logging`, etc.). The configuration
 //! can be accessed as a whole or by specific keys using the `read_config!` macro.
 
+mod builder;
 pub mod config;
+pub mod error;
+#[cfg(feature = "intern")]
+mod intern;
+pub mod key;
+mod lenient;
+mod limits;
+mod loader;
+#[cfg(feature = "query")]
+pub mod query;
+mod refresh;
+pub mod section;
+mod source;
+mod stats;
+mod suggest;
+mod typed;
+#[cfg(feature = "watch")]
+mod watch;
+
+#[cfg(feature = "derive")]
+pub use zirv_config_derive::ZirvConfig;
+
+/// Support code for the exported macros and `#[derive(ZirvConfig)]`. Not part of the
+/// public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_json;
+
+    use std::sync::{PoisonError, RwLock};
+
+    use serde::Serialize;
+    use serde::de::DeserializeOwned;
+    use serde_json::Value;
+
+    use crate::config::{self, ConfigError};
+
+    /// A field name and a function producing its default value.
+    pub type FieldDefault = (&'static str, fn() -> Result<Value, serde_json::Error>);
+
+    /// Deserializes `namespace` into `T`, filling in `defaults` for absent fields.
+    pub fn load_with_defaults<T: DeserializeOwned>(
+        namespace: &str,
+        defaults: &[FieldDefault],
+    ) -> Result<T, ConfigError> {
+        let mut value: Value = config::get_namespace(namespace)?;
+        if let Value::Object(map) = &mut value {
+            for (field, default) in defaults {
+                if !map.contains_key(*field) {
+                    let default = default().map_err(|source| ConfigError::Serialization {
+                        key: format!("{namespace}.{field}"),
+                        source,
+                    })?;
+                    map.insert(field.to_string(), default);
+                }
+            }
+        }
+        config::deserialize_value(namespace, &value)
+    }
+
+    /// Registers `config` under `namespace`, logging the error if it cannot be registered.
+    pub fn register_logged<T: Serialize>(namespace: &str, config: T) -> Result<(), ConfigError> {
+        config::try_register_config(namespace, config)
+            .map(drop)
+            .inspect_err(|err| log::error!("{err}"))
+    }
+
+    /// Writes `value` at `key`, registering it as `register_config!` does, without logging,
+    /// if `key` is a single segment.
+    pub fn write_key<T: Serialize>(key: &str, value: T) -> Result<(), ConfigError> {
+        match crate::key::parse_key(key) {
+            Ok(segments) if segments.len() == 1 => {
+                config::try_register_config(key, value).map(drop)
+            }
+            _ => config::set_config_by_key(key, value),
+        }
+    }
+
+    /// The per-call-site cache behind `config_value!`: a typed value together with the
+    /// store generation it was read at.
+    pub struct CachedValue<T> {
+        cell: RwLock<Option<(u64, T)>>,
+    }
+
+    impl<T> CachedValue<T> {
+        pub const fn new() -> Self {
+            CachedValue {
+                cell: RwLock::new(None),
+            }
+        }
+    }
+
+    impl<T> Default for CachedValue<T> {
+        fn default() -> Self {
+            CachedValue::new()
+        }
+    }
+
+    impl<T: DeserializeOwned + Clone> CachedValue<T> {
+        /// Returns the cached value if nothing has been written since it was read, and
+        /// reads `key` again otherwise. Errors are returned as they occur, not cached.
+        pub fn get(&self, key: &str) -> Result<T, ConfigError> {
+            let current = config::get_config_version();
+            {
+                let cell = self.cell.read().unwrap_or_else(PoisonError::into_inner);
+                if let Some((generation, value)) = &*cell
+                    && *generation == current
+                {
+                    return Ok(value.clone());
+                }
+            }
+            let (generation, value) = config::get_config_as_versioned::<T>(key);
+            let value = value?;
+            let mut cell = self.cell.write().unwrap_or_else(PoisonError::into_inner);
+            // Another caller may have cached a value read after ours; keep the newer one.
+            if cell
+                .as_ref()
+                .is_none_or(|(cached_at, _)| *cached_at < generation)
+            {
+                *cell = Some((generation, value.clone()));
+            }
+            Ok(value)
+        }
+    }
+}
 
 #[macro_export]
 /// Retrieves the configuration from the global store.
-/// 
+///
 /// Usage:
-/// 
+///
 /// - `read_config!()` returns the entire configuration as a `serde_json::Value`.
 /// - `read_config!("some.key")` returns an `Option<serde_json::Value>` for the specified dot-separated key.
-/// 
+/// - `read_config!("some.key" as Type)` or `read_config!(key, Type)` deserializes the value into `Type`
+///   and returns a `Result<Type, ConfigError>`. `Type` must implement `serde::de::DeserializeOwned`.
+/// - `read_config!(pointer: "/some/key")` returns an `Option<serde_json::Value>` for the specified
+///   RFC 6901 JSON Pointer, which can address keys containing dots.
+///
 /// # Examples
-/// 
+///
 /// ```rust
 /// # use zirv_config::read_config;
 /// // Get full config
@@ -27,20 +155,381 @@ pub mod config;
 ///     println!("Server port: {}", port);
 /// }
 /// ```
+///
+/// Untyped and typed reads side by side:
+///
+/// ```rust
+/// # use zirv_config::{read_config, register_config};
+/// # register_config!("server", serde_json::json!({ "port": 3000, "host": "0.0.0.0" })).unwrap();
+/// // Untyped: a raw `serde_json::Value`.
+/// let port = read_config!("server.port").unwrap().as_u64().unwrap() as u16;
+///
+/// // Typed: deserialized straight into the requested type.
+/// let typed_port = read_config!("server.port" as u16).unwrap();
+/// let host = read_config!("server.host", String).unwrap();
+///
+/// assert_eq!(port, typed_port);
+/// assert_eq!(host, "0.0.0.0");
+///
+/// // JSON Pointer: equivalent to the dot-separated form above.
+/// assert_eq!(read_config!(pointer: "/server/port"), read_config!("server.port"));
+/// ```
+///
+/// Types that cannot be deserialized are rejected at compile time:
+///
+/// ```rust,compile_fail
+/// # use zirv_config::read_config;
+/// struct NotDeserializable;
+/// let _ = read_config!("server.port" as NotDeserializable);
+/// ```
 macro_rules! read_config {
     () => {
         $crate::config::get_config()
     };
+    (pointer: $pointer:expr) => {
+        $crate::config::get_config_by_pointer($pointer)
+    };
+    ($key:literal as $t:ty) => {
+        $crate::config::get_config_as::<$t>($key)
+    };
+    ($key:expr, $t:ty) => {
+        $crate::config::get_config_as::<$t>($key)
+    };
     ($key:expr) => {
         $crate::config::get_config_by_key($key)
     };
 }
 
+#[macro_export]
+/// Reads a typed configuration value into a new `let` binding.
+///
+/// - `read_config_into!(let name: Type = "some.key");` panics with the key and the
+///   underlying error if the value is missing or has the wrong type, which suits
+///   `main`-style setup code.
+/// - `read_config_into!(try let name: Type = "some.key");` propagates the
+///   `ConfigError` with `?` instead, so it can only be used in functions returning a
+///   compatible `Result`.
+///
+/// `Type` may be any `DeserializeOwned` type, from scalars to whole configuration structs.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::{read_config_into, register_config};
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct ServerConfig {
+///     port: u16,
+///     host: String,
+/// }
+///
+/// register_config!("server", ServerConfig { port: 3000, host: "0.0.0.0".to_string() }).unwrap();
+///
+/// read_config_into!(let port: u16 = "server.port");
+/// read_config_into!(let server: ServerConfig = "server");
+/// assert_eq!(port, server.port);
+///
+/// fn host() -> Result<String, zirv_config::config::ConfigError> {
+///     read_config_into!(try let host: String = "server.host");
+///     Ok(host)
+/// }
+/// assert_eq!(host().unwrap(), "0.0.0.0");
+///
+/// fn missing() -> Result<u16, Box<dyn std::error::Error>> {
+///     read_config_into!(try let timeout: u16 = "server.timeout");
+///     Ok(timeout)
+/// }
+/// assert!(missing().is_err());
+/// ```
+///
+/// The plain form panics when the value cannot be read:
+///
+/// ```rust,should_panic
+/// # use zirv_config::read_config_into;
+/// read_config_into!(let port: u16 = "server.port");
+/// ```
+macro_rules! read_config_into {
+    (try let $name:ident : $t:ty = $key:expr) => {
+        let $name: $t = $crate::config::get_config_as::<$t>($key)?;
+    };
+    (let $name:ident : $t:ty = $key:expr) => {
+        let $name: $t = match $crate::config::get_config_as::<$t>($key) {
+            ::core::result::Result::Ok(value) => value,
+            ::core::result::Result::Err(err) => {
+                panic!(
+                    "failed to read config into `{}`: {}",
+                    stringify!($name),
+                    err
+                )
+            }
+        };
+    };
+}
+
+#[macro_export]
+/// Retrieves a whole namespace and deserializes it into a type.
+///
+/// This macro is a thin wrapper around `config::get_namespace::<Type>(namespace)` and
+/// returns a `Result<Type, ConfigError>`. A dotted namespace, such as `"services.billing"`,
+/// is a path to a nested block.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::{read_namespace, register_config};
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct ServerConfig {
+///     port: u16,
+///     host: String,
+/// }
+///
+/// register_config!("server", ServerConfig { port: 3000, host: "0.0.0.0".to_string() }).unwrap();
+///
+/// let server = read_namespace!("server", ServerConfig).unwrap();
+/// assert_eq!(server.port, 3000);
+/// ```
+macro_rules! read_namespace {
+    ($namespace:expr, $t:ty) => {
+        $crate::config::get_namespace::<$t>($namespace)
+    };
+}
+
+#[macro_export]
+/// Retrieves a typed configuration value, or a default if it is not available.
+///
+/// The type is inferred from the default expression, which is only evaluated when needed.
+///
+/// - `read_config_or!("some.key", default)` returns the stored value deserialized into the
+///   type of `default`, falling back to `default` if the key is missing *or* holds a value of
+///   the wrong type.
+/// - `read_config_or!(try "some.key", default)` only falls back when the key is missing and
+///   returns a `Result`, reporting a type mismatch as `ConfigError::TypeMismatch`.
+///
+/// An explicit `null` is a present value, so it reads as `None` when `default` is an
+/// `Option` rather than falling back. Use `config::get_config_entry` to inspect whether a
+/// key is missing, `null` or set.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::{read_config_or, register_config};
+/// register_config!("server", serde_json::json!({ "workers": 8, "host": "0.0.0.0" })).unwrap();
+///
+/// assert_eq!(read_config_or!("server.workers", 4u64), 8);
+/// assert_eq!(read_config_or!("server.threads", 4u64), 4);
+///
+/// // A mismatched type falls back in the plain form, but is an error in the `try` form.
+/// assert_eq!(read_config_or!("server.host", 4u64), 4);
+/// assert!(read_config_or!(try "server.host", 4u64).is_err());
+///
+/// // `null` means "explicitly unset", not "use the default".
+/// register_config!("logging", serde_json::json!({ "file": null })).unwrap();
+/// assert_eq!(read_config_or!("logging.file", Some(String::from("app.log"))), None);
+/// ```
+macro_rules! read_config_or {
+    (try $key:expr, $default:expr) => {
+        $crate::config::try_get_config_or_else($key, || $default)
+    };
+    ($key:expr, $default:expr) => {
+        $crate::config::get_config_or_else($key, || $default)
+    };
+}
+
+#[macro_export]
+/// Reads a typed configuration value, caching it at the call site until the configuration
+/// changes.
+///
+/// `config_value!("some.key": Type)` returns a `Result<Type, ConfigError>`, like
+/// `config::get_config_as`. The first call reads and deserializes the value; later calls
+/// return a clone of it as long as nothing has been written to the configuration since,
+/// and read it again otherwise. This suits values read in tight loops. `Type` must
+/// implement `serde::de::DeserializeOwned` and `Clone`, and the key must be a literal.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::{config_value, register_config, write_config};
+/// register_config!("server", serde_json::json!({ "port": 3000 })).unwrap();
+///
+/// let port = || config_value!("server.port": u16);
+/// assert_eq!(port().unwrap(), 3000);
+///
+/// write_config!("server.port", 8080).unwrap();
+/// assert_eq!(port().unwrap(), 8080);
+/// ```
+macro_rules! config_value {
+    ($key:literal : $t:ty) => {{
+        static CACHED: $crate::__private::CachedValue<$t> = $crate::__private::CachedValue::new();
+        CACHED.get($key)
+    }};
+}
+
+#[macro_export]
+/// Retrieves a configuration value that must be present.
+///
+/// Expands to `config::get_required(key)`, returning a `Result<serde_json::Value, ConfigError>`
+/// whose error names the missing key, how far its path resolved and the keys available there.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::{register_config, require_config};
+/// fn main() -> Result<(), zirv_config::config::ConfigError> {
+///     register_config!("db", serde_json::json!({ "url": "postgres://localhost/app" })).unwrap();
+///
+///     // Fail fast at startup if required settings are missing.
+///     let url = require_config!("db.url")?;
+///     assert_eq!(url, "postgres://localhost/app");
+///     assert!(require_config!("db.password").is_err());
+///     Ok(())
+/// }
+/// ```
+macro_rules! require_config {
+    ($key:expr) => {
+        $crate::config::get_required($key)
+    };
+}
+
+#[macro_export]
+/// Loads a JSON configuration file into the store.
+///
+/// `load_config_file!(path)` expands to `config::load_json_file(path)`, deep-merging the
+/// file's top-level keys as namespaces, and `load_config_file!(namespace, path)` to
+/// `config::load_json_file_into(namespace, path)`, mounting the whole file under
+/// `namespace`. Both return a `Result<(), ConfigError>`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::{load_config_file, read_config};
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("cache.json");
+/// std::fs::write(&path, r#"{ "ttl": 60 }"#).unwrap();
+///
+/// load_config_file!("cache", &path).unwrap();
+/// assert_eq!(read_config!("cache.ttl", u64).unwrap(), 60);
+/// ```
+macro_rules! load_config_file {
+    ($path:expr) => {
+        $crate::config::load_json_file($path)
+    };
+    ($namespace:expr, $path:expr) => {
+        $crate::config::load_json_file_into($namespace, $path)
+    };
+}
+
+#[macro_export]
+/// Checks whether a configuration value exists at a dot-separated key.
+///
+/// Expands to `config::has_config(key)`. Keys holding an explicit `null` count as present.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::{has_config, register_config};
+/// register_config!("tls", serde_json::json!({ "cert_path": "/etc/tls/cert.pem" })).unwrap();
+///
+/// if has_config!("tls.cert_path") {
+///     println!("TLS enabled");
+/// }
+/// assert!(!has_config!("tls.key_path"));
+/// ```
+macro_rules! has_config {
+    ($key:expr) => {
+        $crate::config::has_config($key)
+    };
+}
+
+#[macro_export]
+/// Lists the registered top-level namespaces.
+///
+/// Expands to `config::config_namespaces()`, returning a sorted `Vec<String>`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::{config_namespaces, register_config};
+/// register_config!("server", serde_json::json!({ "port": 3000 })).unwrap();
+/// println!("Registered subsystems: {:?}", config_namespaces!());
+/// assert!(config_namespaces!().contains(&"server".to_string()));
+/// ```
+macro_rules! config_namespaces {
+    () => {
+        $crate::config::config_namespaces()
+    };
+}
+
+#[macro_export]
+/// Finds all configuration values matching a wildcard pattern.
+///
+/// Expands to `config::query_config(pattern)`, where `*` matches one path segment and `**`
+/// any number of segments. Returns a `Vec<(String, serde_json::Value)>` of matched paths and
+/// their values.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::{query_config, register_config};
+/// register_config!("services", serde_json::json!({
+///     "auth": { "port": 8001 },
+///     "billing": { "port": 8002 }
+/// })).unwrap();
+///
+/// for (path, port) in query_config!("services.*.port") {
+///     println!("{path} = {port}");
+/// }
+/// assert_eq!(query_config!("services.*.port").len(), 2);
+/// ```
+macro_rules! query_config {
+    ($pattern:expr) => {
+        $crate::config::query_config($pattern)
+    };
+}
+
+#[macro_export]
+/// Declares a static, typed configuration key.
+///
+/// `define_config_key!(NAME: Type = "some.key")` expands to a
+/// `static NAME: TypedConfigKey<Type>`, whose `get()` returns a
+/// `Result<Type, ConfigError>`. The key is parsed once, on first use. A
+/// `default = expr` suffix makes `get()` return `expr` when nothing is stored at the key;
+/// a value of the wrong type is still an error. Attributes and a visibility may precede
+/// the name.
+///
+/// # Examples
+///
+/// ```rust
+/// use zirv_config::{define_config_key, register_config};
+///
+/// define_config_key!(
+///     /// The port the HTTP server listens on.
+///     pub SERVER_PORT: u16 = "server.port"
+/// );
+/// define_config_key!(SERVER_WORKERS: usize = "server.workers", default = 4);
+///
+/// register_config!("server", serde_json::json!({ "port": 3000 })).unwrap();
+///
+/// assert_eq!(SERVER_PORT.get().unwrap(), 3000);
+/// assert_eq!(SERVER_WORKERS.get().unwrap(), 4);
+/// ```
+macro_rules! define_config_key {
+    ($(#[$meta:meta])* $vis:vis $name:ident : $t:ty = $key:literal, default = $default:expr $(,)?) => {
+        $(#[$meta])*
+        $vis static $name: $crate::key::TypedConfigKey<$t> =
+            $crate::key::TypedConfigKey::with_default($key, || $default);
+    };
+    ($(#[$meta:meta])* $vis:vis $name:ident : $t:ty = $key:literal $(,)?) => {
+        $(#[$meta])*
+        $vis static $name: $crate::key::TypedConfigKey<$t> = $crate::key::TypedConfigKey::new($key);
+    };
+}
+
 #[macro_export]
 /// Registers a configuration block under a given namespace.
 ///
-/// This macro is a thin wrapper around the underlying
-/// `config::register_config(namespace, config)` function.
+/// This macro wraps `config::try_register_config(namespace, config)`. It returns
+/// `Result<(), ConfigError>` rather than panicking: if the block cannot be registered, for
+/// example because its `Serialize` impl fails, the error is logged through the `log` crate
+/// and returned. The error names the namespace.
 ///
 /// # Examples
 ///
@@ -53,19 +542,131 @@ macro_rules! read_config {
 /// }
 ///
 /// let server_config = ServerConfig { port: 3000, host: "0.0.0.0".to_string() };
-/// register_config!("server", server_config);
+/// register_config!("server", server_config).unwrap();
+///
+/// // JSON object keys must be strings, so this map cannot be registered.
+/// let bad = std::collections::HashMap::from([((0, 0), true)]);
+/// let err = register_config!("grid", bad).unwrap_err();
+/// assert!(err.to_string().contains("\"grid\""));
 /// ```
 macro_rules! register_config {
-    ($namespace:expr, $config:expr) => {{
-        $crate::config::register_config($namespace, $config);
-    }};
+    ($namespace:expr, $config:expr) => {
+        $crate::__private::register_logged($namespace, $config)
+    };
+}
+
+#[macro_export]
+/// Registers several configuration blocks at once, so readers see all of them or none.
+///
+/// This macro wraps `config::register_batch` and returns `Result<(), ConfigError>`; if
+/// any block fails to serialize, none are registered.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::{read_config, register_configs};
+/// use serde_json::json;
+///
+/// register_configs!(
+///     "server" => json!({ "port": 3000 }),
+///     "db" => json!({ "url": "postgres://localhost/app" }),
+///     "metrics" => json!({ "enabled": true }),
+/// )
+/// .unwrap();
+/// assert_eq!(read_config!("metrics.enabled"), Some(json!(true)));
+/// ```
+macro_rules! register_configs {
+    ($($namespace:expr => $config:expr),+ $(,)?) => {
+        $crate::config::register_batch(|batch| {
+            $(batch.register($namespace, $config);)+
+        })
+    };
+}
+
+#[macro_export]
+/// Registers a configuration block unless the namespace is already registered.
+///
+/// This macro is a thin wrapper around `config::register_config_once(namespace, config)`
+/// and returns `true` if the block was inserted.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::{read_config, register_config_once};
+/// use serde_json::json;
+///
+/// assert!(register_config_once!("retry", json!({ "attempts": 3 })));
+/// assert!(!register_config_once!("retry", json!({ "attempts": 5 })));
+/// assert_eq!(read_config!("retry.attempts"), Some(json!(3)));
+/// ```
+macro_rules! register_config_once {
+    ($namespace:expr, $config:expr) => {
+        $crate::config::register_config_once($namespace, $config)
+    };
+}
+
+#[macro_export]
+/// Removes the configuration block registered under a given namespace.
+///
+/// This macro is a thin wrapper around `config::unregister_config(namespace)` and returns
+/// the removed block as an `Option<serde_json::Value>`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::{read_config, register_config, unregister_config};
+/// register_config!("plugin", serde_json::json!({ "enabled": true })).unwrap();
+///
+/// let removed = unregister_config!("plugin");
+/// assert_eq!(removed, Some(serde_json::json!({ "enabled": true })));
+/// assert!(read_config!("plugin").is_none());
+/// ```
+macro_rules! unregister_config {
+    ($namespace:expr) => {
+        $crate::config::unregister_config($namespace)
+    };
+}
+
+#[macro_export]
+/// Writes a configuration value at a dot-separated key path.
+///
+/// This macro wraps `config::set_config_by_key(key, value)` and returns its
+/// `Result<(), ConfigError>`, so callers can handle paths that conflict with existing
+/// non-object values. The value may be any `Serialize` expression.
+///
+/// A key without dots registers the whole namespace, just like `register_config!`, through
+/// `config::try_register_config`: the registration policy applies, and the block is kept
+/// by `config::reload_config` like any other registered one.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::{read_config, write_config};
+/// use serde_json::json;
+///
+/// // Replace a whole namespace.
+/// write_config!("server", json!({ "port": 8080, "host": "0.0.0.0" })).unwrap();
+///
+/// // Update a single leaf.
+/// write_config!("server.port", 8081).unwrap();
+///
+/// assert_eq!(read_config!("server.port"), Some(json!(8081)));
+/// assert_eq!(read_config!("server.host"), Some(json!("0.0.0.0")));
+///
+/// // Writing through a scalar is an error.
+/// assert!(write_config!("server.host.name", "x").is_err());
+/// ```
+macro_rules! write_config {
+    ($key:expr, $value:expr) => {
+        $crate::__private::write_key($key, $value)
+    };
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde_json::{json, Value};
     use serde::Serialize;
+    use serde_json::{Value, json};
 
     // A dummy configuration struct for testing.
     #[derive(Serialize)]
@@ -86,16 +687,23 @@ mod tests {
     fn test_register_and_read_full_config() {
         setup();
         // Register a dummy server configuration.
-        register_config!("server", DummyConfig { 
-            port: 3000, 
-            host: "0.0.0.0".to_string() 
-        });
-        
+        register_config!(
+            "server",
+            DummyConfig {
+                port: 3000,
+                host: "0.0.0.0".to_string()
+            }
+        )
+        .unwrap();
+
         // Retrieve the full configuration.
         let full = read_config!();
         // It should be a JSON object containing a key "server".
         if let Value::Object(map) = full {
-            assert!(map.contains_key("server"), "Expected key 'server' not found");
+            assert!(
+                map.contains_key("server"),
+                "Expected key 'server' not found"
+            );
             if let Some(Value::Object(server_obj)) = map.get("server") {
                 // Verify the values.
                 assert_eq!(server_obj.get("port").unwrap(), &json!(3000));
@@ -123,4 +731,126 @@ mod tests {
         let missing = read_config!("server.nonexistent");
         assert!(missing.is_none());
     }
+
+    #[test]
+    fn test_read_config_typed() {
+        setup();
+        register_config!(
+            "typed_read",
+            DummyConfig {
+                port: 4000,
+                host: "127.0.0.1".to_string()
+            }
+        )
+        .unwrap();
+
+        let port: u16 = read_config!("typed_read.port" as u16).unwrap();
+        assert_eq!(port, 4000);
+
+        let key = "typed_read.host";
+        let host = read_config!(key, String).unwrap();
+        assert_eq!(host, "127.0.0.1");
+
+        let err = read_config!("typed_read.host" as u16).unwrap_err();
+        assert!(matches!(err, config::ConfigError::TypeMismatch { .. }));
+    }
+
+    #[test]
+    fn test_config_value_rereads_after_writes() {
+        fn workers() -> Result<u32, config::ConfigError> {
+            config_value!("cached_site.workers": u32)
+        }
+
+        // Errors are not cached.
+        assert!(workers().is_err());
+        register_config!("cached_site", json!({ "workers": 4 })).unwrap();
+        assert_eq!(workers().unwrap(), 4);
+        assert_eq!(workers().unwrap(), 4);
+
+        write_config!("cached_site.workers", 16).unwrap();
+        assert_eq!(workers().unwrap(), 16);
+        config::update_config(|map| map["cached_site"]["workers"] = json!(2));
+        assert_eq!(workers().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_read_config_or() {
+        setup();
+        register_config!(
+            "read_or",
+            DummyConfig {
+                port: 5000,
+                host: "localhost".to_string()
+            }
+        )
+        .unwrap();
+
+        assert_eq!(read_config_or!("read_or.port", 80u16), 5000);
+        assert_eq!(read_config_or!("read_or.workers", 4usize), 4);
+        assert_eq!(
+            read_config_or!("read_or.host", String::from("0.0.0.0")),
+            "localhost"
+        );
+        assert_eq!(read_config_or!(try "read_or.workers", 4usize).unwrap(), 4);
+        assert!(read_config_or!(try "read_or.host", 0u16).is_err());
+    }
+
+    #[test]
+    fn test_require_config() {
+        setup();
+        register_config!(
+            "required",
+            DummyConfig {
+                port: 6000,
+                host: "db.local".to_string()
+            }
+        )
+        .unwrap();
+
+        assert_eq!(require_config!("required.port").unwrap(), json!(6000));
+        let message = require_config!("required.url").unwrap_err().to_string();
+        assert!(message.contains("required.url"), "{message}");
+    }
+
+    #[test]
+    fn test_write_config() {
+        setup();
+        write_config!("write", json!({ "port": 7000, "host": "0.0.0.0" })).unwrap();
+        write_config!("write.port", 7001).unwrap();
+
+        assert_eq!(read_config!("write.port"), Some(json!(7001)));
+        assert_eq!(read_config!("write.host"), Some(json!("0.0.0.0")));
+        assert!(write_config!("write.port.inner", 1).is_err());
+    }
+
+    #[test]
+    fn test_remove_config_by_key() {
+        setup();
+        write_config!("remove_macro", json!({ "keep": 1, "drop": 2 })).unwrap();
+
+        assert_eq!(
+            config::remove_config_by_key("remove_macro.drop"),
+            Some(json!(2))
+        );
+        assert!(read_config!("remove_macro.drop").is_none());
+        assert_eq!(read_config!("remove_macro.keep"), Some(json!(1)));
+    }
+
+    struct Unserializable;
+
+    impl serde::Serialize for Unserializable {
+        fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+            Err(serde::ser::Error::custom("cannot serialize"))
+        }
+    }
+
+    #[test]
+    fn test_register_config_macro_returns_serialization_errors() {
+        let err = register_config!("macro_unserializable", Unserializable).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"failed to serialize value for "macro_unserializable": cannot serialize"#
+        );
+        assert_eq!(read_config!("macro_unserializable"), None);
+    }
 }
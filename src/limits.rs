@@ -0,0 +1,204 @@
+//! Limits on the shape of configuration merged in from untrusted sources.
+//!
+//! Configuration fetched from a remote endpoint or read from a user-supplied file can be
+//! arbitrarily large or deeply nested. [`Limits`] bounds the nesting depth, the serialized
+//! size and the length of any single array, and [`Limits::check`] rejects a value that
+//! exceeds them with [`ConfigError::LimitExceeded`] before it reaches the store.
+
+use std::io;
+
+use serde_json::Value;
+
+use crate::config::ConfigError;
+use crate::key::escape_segment;
+
+/// Bounds on the shape of a configuration value.
+///
+/// The defaults are generous enough for any hand-written configuration while keeping a
+/// hostile payload from exhausting the stack or memory. Override individual fields with
+/// struct update syntax.
+///
+/// # Examples
+///
+/// ```rust
+/// use zirv_config::config::{self, ConfigError, Limits};
+///
+/// let limits = Limits { max_array_len: 2, ..Limits::default() };
+/// let err = config::merge_config_with(serde_json::json!({ "peers": [1, 2, 3] }), &limits)
+///     .unwrap_err();
+/// assert!(matches!(err, ConfigError::LimitExceeded { limit: "max_array_len", .. }));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The deepest nesting of objects and arrays allowed, counting the outermost one as 1.
+    /// Defaults to 128, the same depth `serde_json` allows when parsing.
+    pub max_depth: usize,
+    /// The largest size allowed for the value serialized as compact JSON, in bytes.
+    /// Defaults to 64 MiB.
+    pub max_size: usize,
+    /// The most elements allowed in any single array. Defaults to 100,000.
+    pub max_array_len: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_depth: 128,
+            max_size: 64 * 1024 * 1024,
+            max_array_len: 100_000,
+        }
+    }
+}
+
+impl Limits {
+    /// Checks `value`, to be stored at the dot-separated `key`, against these limits.
+    ///
+    /// Depth and array lengths are checked first without recursion, so a value too deep
+    /// to serialize safely is rejected before its size is measured.
+    pub fn check(&self, key: &str, value: &Value) -> Result<(), ConfigError> {
+        let exceeded = |limit, max, path: String| ConfigError::LimitExceeded { limit, max, path };
+
+        let mut pending = vec![(value, 1, key.to_string())];
+        while let Some((value, depth, path)) = pending.pop() {
+            let children: Box<dyn Iterator<Item = (String, &Value)>> = match value {
+                Value::Object(map) => Box::new(
+                    map.iter()
+                        .map(|(name, child)| (escape_segment(name).into_owned(), child)),
+                ),
+                Value::Array(items) => {
+                    if items.len() > self.max_array_len {
+                        return Err(exceeded("max_array_len", self.max_array_len, path));
+                    }
+                    Box::new(
+                        items
+                            .iter()
+                            .enumerate()
+                            .map(|(index, child)| (index.to_string(), child)),
+                    )
+                }
+                _ => continue,
+            };
+            if depth > self.max_depth {
+                return Err(exceeded("max_depth", self.max_depth, path));
+            }
+            for (segment, child) in children {
+                if child.is_object() || child.is_array() {
+                    let child_path = match path.as_str() {
+                        "" => segment,
+                        path => format!("{path}.{segment}"),
+                    };
+                    pending.push((child, depth + 1, child_path));
+                }
+            }
+        }
+
+        let mut counter = SizeCounter {
+            written: 0,
+            max: self.max_size,
+        };
+        if serde_json::to_writer(&mut counter, value).is_err() {
+            return Err(exceeded("max_size", self.max_size, key.to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Counts the bytes written to it, failing once more than `max` have been written.
+struct SizeCounter {
+    written: usize,
+    max: usize,
+}
+
+impl io::Write for SizeCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written += buf.len();
+        if self.written > self.max {
+            return Err(io::Error::other("size limit exceeded"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn nested(depth: usize) -> Value {
+        let mut value = json!(1);
+        for _ in 0..depth {
+            value = json!({ "a": value });
+        }
+        value
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let limits = Limits::default();
+        assert!(limits.check("", &nested(128)).is_ok());
+
+        let err = limits.check("", &nested(1_000)).unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigError::LimitExceeded {
+                limit: "max_depth",
+                max: 128,
+                ..
+            }
+        ));
+
+        let limits = Limits {
+            max_depth: 2,
+            ..Limits::default()
+        };
+        let err = limits
+            .check("root", &json!({ "a": { "b": { "c": 1 } } }))
+            .unwrap_err();
+        assert!(matches!(err, ConfigError::LimitExceeded { ref path, .. } if path == "root.a.b"));
+        assert!(limits.check("root", &json!({ "a": [1, 2] })).is_ok());
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let limits = Limits {
+            max_size: 1024,
+            ..Limits::default()
+        };
+        assert!(
+            limits
+                .check("", &json!({ "key": "x".repeat(1000) }))
+                .is_ok()
+        );
+
+        let err = limits
+            .check("blob", &json!({ "key": "x".repeat(2000) }))
+            .unwrap_err();
+        assert!(
+            matches!(err, ConfigError::LimitExceeded { limit: "max_size", max: 1024, ref path } if path == "blob")
+        );
+        assert_eq!(
+            err.to_string(),
+            r#"limit max_size of 1024 exceeded at "blob""#
+        );
+    }
+
+    #[test]
+    fn test_array_length_limit() {
+        let limits = Limits {
+            max_array_len: 3,
+            ..Limits::default()
+        };
+        assert!(limits.check("", &json!({ "peers": [1, 2, 3] })).is_ok());
+
+        let err = limits
+            .check("", &json!({ "groups": [[1], [1, 2, 3, 4]] }))
+            .unwrap_err();
+        assert!(
+            matches!(err, ConfigError::LimitExceeded { limit: "max_array_len", ref path, .. } if path == "groups.1")
+        );
+    }
+}
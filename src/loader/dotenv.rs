@@ -0,0 +1,385 @@
+//! The `.env` loader.
+//!
+//! A `.env` file holds one `KEY=VALUE` assignment per line:
+//!
+//! - Blank lines and lines starting with `#` are ignored, and an `export ` prefix before
+//!   the key is allowed, so the file can also be sourced by a shell.
+//! - Keys consist of ASCII letters, digits, `_` and `.`, and do not start with a digit.
+//! - Unquoted values run to the end of the line or to a `#` preceded by whitespace, which
+//!   starts a comment, and are trimmed.
+//! - Values in single quotes are taken literally. Values in double quotes may use the
+//!   escapes `\n`, `\r`, `\t`, `\"`, `\\` and `\$`; any other backslash is kept. Either
+//!   kind of quoted value may span several lines, and may be followed by a comment.
+//!
+//! `$VAR` references are not expanded, and the process environment is not modified.
+//! A key assigned twice keeps its last value, with a warning.
+
+use std::io;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use super::{insert_nested, lenient_scalar, merge_document, parse_error, read_file};
+use crate::config::ConfigError;
+
+/// How [`load_dotenv_with`] maps the variables of a `.env` file into the configuration.
+///
+/// A variable is only loaded if its name starts with `prefix`. The rest of the name is
+/// split on `separator` and lowercased into a key path, so with the prefix `APP_` and the
+/// default separator `__`, `APP_SERVER__PORT` becomes `server.port` and `APP_DB_URL`
+/// becomes `db_url`. Override individual fields with struct update syntax.
+///
+/// # Examples
+///
+/// ```rust
+/// use zirv_config::config::DotenvOptions;
+///
+/// let options = DotenvOptions { prefix: "APP_".to_string(), ..DotenvOptions::default() };
+/// assert_eq!(options.separator, "__");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DotenvOptions {
+    /// The prefix a variable's name must start with to be loaded, which is removed from
+    /// its key. Defaults to the empty string, which loads every variable.
+    pub prefix: String,
+    /// The separator between the segments of a key path. Defaults to `__`. An empty
+    /// separator maps each variable to a single key.
+    pub separator: String,
+    /// Whether unquoted integers and `true` and `false`, in any case, are stored as numbers
+    /// and booleans. Defaults to `false`, which stores every value as a string.
+    pub lenient: bool,
+    /// Whether a missing file is treated as empty rather than an error. Defaults to `false`.
+    pub allow_missing: bool,
+}
+
+impl Default for DotenvOptions {
+    fn default() -> Self {
+        DotenvOptions {
+            prefix: String::new(),
+            separator: "__".to_string(),
+            lenient: false,
+            allow_missing: false,
+        }
+    }
+}
+
+/// Parses a `.env` document read from `origin` into a JSON object.
+pub(super) fn parse(
+    origin: &str,
+    text: &str,
+    options: &DotenvOptions,
+) -> Result<Value, ConfigError> {
+    let mut root = Map::new();
+    let mut lines = text.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let line = if index == 0 {
+            line.trim_start_matches('\u{feff}')
+        } else {
+            line
+        };
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        let line_number = index + 1;
+        let position = (line_number, line.len() - content.len() + 1);
+        let error = |message: String| parse_error(origin, "dotenv", Some(position), message);
+
+        let content = content
+            .strip_prefix("export")
+            .filter(|rest| rest.starts_with([' ', '\t']))
+            .map_or(content, str::trim_start);
+        let Some((name, rest)) = content.split_once('=') else {
+            return Err(error("expected `KEY=VALUE`".to_string()));
+        };
+        let name = name.trim_end();
+        if !is_valid_name(name) {
+            return Err(error(format!("\"{name}\" is not a valid variable name")));
+        }
+
+        let rest = rest.trim_start();
+        let (value, quoted) = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let mut raw = rest[1..].to_string();
+                loop {
+                    if let Some((value, after)) = closing_quote(&raw, quote) {
+                        let after = after.trim_start();
+                        if !after.is_empty() && !after.starts_with('#') {
+                            return Err(error(format!(
+                                "unexpected text after the quoted value of \"{name}\""
+                            )));
+                        }
+                        break (value, true);
+                    }
+                    let Some((_, next)) = lines.next() else {
+                        return Err(error(format!(
+                            "the value of \"{name}\" has no closing {quote}"
+                        )));
+                    };
+                    raw.push('\n');
+                    raw.push_str(next);
+                }
+            }
+            _ => {
+                let end = rest
+                    .match_indices('#')
+                    .find(|&(at, _)| rest[..at].ends_with([' ', '\t']))
+                    .map_or(rest.len(), |(at, _)| at);
+                (rest[..end].trim_end().to_string(), false)
+            }
+        };
+
+        let Some(segments) = key_segments(name, options) else {
+            continue;
+        };
+        if segments.iter().any(String::is_empty) {
+            return Err(error(format!(
+                "\"{name}\" maps to a key with an empty segment"
+            )));
+        }
+        let value = if options.lenient && !quoted {
+            lenient_scalar(&value)
+        } else {
+            Value::String(value)
+        };
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        if insert_nested(&mut root, &segments, value).map_err(error)? {
+            log::warn!(
+                "{origin}:{line_number}: \"{name}\" is set more than once; the last value is used"
+            );
+        }
+    }
+    Ok(Value::Object(root))
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Finds the end of a value opened by `quote`, returning the unescaped value and the text
+/// after the closing quote, or `None` if `raw` does not close it.
+fn closing_quote(raw: &str, quote: char) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = raw.char_indices();
+    while let Some((at, c)) = chars.next() {
+        match c {
+            c if c == quote => return Some((value, &raw[at + 1..])),
+            '\\' if quote == '"' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 'r')) => value.push('\r'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, escaped @ ('"' | '\\' | '$'))) => value.push(escaped),
+                Some((_, other)) => {
+                    value.push('\\');
+                    value.push(other);
+                }
+                None => value.push('\\'),
+            },
+            other => value.push(other),
+        }
+    }
+    None
+}
+
+/// Maps a variable name to the segments of its key path, or `None` if it lacks the prefix.
+pub(super) fn key_segments(name: &str, options: &DotenvOptions) -> Option<Vec<String>> {
+    let rest = name.strip_prefix(options.prefix.as_str())?;
+    let segments = if options.separator.is_empty() {
+        vec![rest.to_lowercase()]
+    } else {
+        rest.split(options.separator.as_str())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    Some(segments)
+}
+
+/// Loads a `.env` file into the store, mapping each variable to a key path with the
+/// default [`DotenvOptions`]: every variable is loaded, `__` separates the segments of
+/// its key, names are lowercased, and values are strings.
+///
+/// Pass `".env"` to load the file in the current directory. See the
+/// [parsing rules](self) for quoting and comments. Fails with [`ConfigError::Io`] if the
+/// file cannot be read, [`ConfigError::Parse`], naming the file and line, if a line is
+/// malformed or two variables map to conflicting keys, and
+/// [`ConfigError::LimitExceeded`] if it exceeds the default
+/// [`Limits`](crate::config::Limits). Nothing is merged on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join(".env");
+/// std::fs::write(&path, "# local overrides\nSERVER__PORT=8080\nexport GREETING=\"hello there\"\n").unwrap();
+///
+/// config::load_dotenv(&path).unwrap();
+/// assert_eq!(config::get_config_by_key("server.port"), Some(serde_json::json!("8080")));
+/// assert_eq!(config::get_config_by_key("greeting"), Some(serde_json::json!("hello there")));
+/// ```
+pub fn load_dotenv(path: impl AsRef<Path>) -> Result<(), ConfigError> {
+    load_dotenv_with(path, &DotenvOptions::default())
+}
+
+/// Loads a `.env` file into the store as [`load_dotenv`] does, mapping variables to keys
+/// and typing values as `options` specify.
+///
+/// If `options.allow_missing` is set, a missing file loads nothing and succeeds, which
+/// suits a `.env` file that only exists on developer machines.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config::{self, DotenvOptions};
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join(".env");
+/// std::fs::write(&path, "APP_WORKER__THREADS=8\nAPP_WORKER__NAME='8'\nOTHER_TOOL=ignored\n").unwrap();
+///
+/// let options = DotenvOptions {
+///     prefix: "APP_".to_string(),
+///     lenient: true,
+///     ..DotenvOptions::default()
+/// };
+/// config::load_dotenv_with(&path, &options).unwrap();
+/// assert_eq!(config::get_config_by_key("worker.threads"), Some(serde_json::json!(8)));
+/// // Quoted values stay strings.
+/// assert_eq!(config::get_config_by_key("worker.name"), Some(serde_json::json!("8")));
+/// assert_eq!(config::get_config_by_key("other_tool"), None);
+///
+/// let missing = DotenvOptions { allow_missing: true, ..DotenvOptions::default() };
+/// config::load_dotenv_with(dir.path().join("missing.env"), &missing).unwrap();
+/// ```
+pub fn load_dotenv_with(
+    path: impl AsRef<Path>,
+    options: &DotenvOptions,
+) -> Result<(), ConfigError> {
+    let path = path.as_ref();
+    let text = match read_file(path) {
+        Err(ConfigError::Io { source, .. })
+            if options.allow_missing && source.kind() == io::ErrorKind::NotFound =>
+        {
+            return Ok(());
+        }
+        text => text?,
+    };
+    let document = parse(&path.display().to_string(), &text, options)?;
+    merge_document(document, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::get_config_by_key;
+    use serde_json::json;
+
+    fn parse_str(text: &str) -> Result<Value, ConfigError> {
+        parse(".env", text, &DotenvOptions::default())
+    }
+
+    #[test]
+    fn test_quoting_and_comments() {
+        let text = "# local development\n\nPLAIN=some value  # trailing comment\nexport EXPORTED=yes\n\
+                    DOUBLE=\"  spaced out  \" # comment\nSINGLE='single $HOME \\n'\nHASH=abc#def\n\
+                    ESCAPES=\"tab\\there \\\"quoted\\\" \\$HOME \\q\"\nEMPTY=\nEMPTY_QUOTED=\"\"\n\
+                    MULTI=\"first\nsecond\"\nDOTTED.NAME=1\n  INDENTED = padded\n";
+        assert_eq!(
+            parse_str(text).unwrap(),
+            json!({
+                "plain": "some value",
+                "exported": "yes",
+                "double": "  spaced out  ",
+                "single": "single $HOME \\n",
+                "hash": "abc#def",
+                "escapes": "tab\there \"quoted\" $HOME \\q",
+                "empty": "",
+                "empty_quoted": "",
+                "multi": "first\nsecond",
+                "dotted.name": "1",
+                "indented": "padded",
+            })
+        );
+    }
+
+    #[test]
+    fn test_prefix_and_separator_mapping() {
+        let options = DotenvOptions {
+            prefix: "APP_".to_string(),
+            ..DotenvOptions::default()
+        };
+        let text = "APP_SERVER__PORT=8080\nAPP_SERVER__TLS__ENABLED=true\nAPP_DB_URL=postgres://localhost\nPATH=/usr/bin\n";
+        assert_eq!(
+            parse(".env", text, &options).unwrap(),
+            json!({
+                "server": { "port": "8080", "tls": { "enabled": "true" } },
+                "db_url": "postgres://localhost",
+            })
+        );
+
+        let lenient = DotenvOptions {
+            lenient: true,
+            separator: String::new(),
+            ..options
+        };
+        assert_eq!(
+            parse(".env", text, &lenient).unwrap(),
+            json!({ "server__port": 8080, "server__tls__enabled": true, "db_url": "postgres://localhost" })
+        );
+    }
+
+    #[test]
+    fn test_malformed_lines_are_rejected() {
+        let cases = [
+            ("JUST_A_NAME\n", "expected `KEY=VALUE`"),
+            ("1ST=value\n", "\"1ST\" is not a valid variable name"),
+            (
+                "QUOTED=\"open\nstill open\n",
+                "the value of \"QUOTED\" has no closing \"",
+            ),
+            (
+                "QUOTED='done' extra\n",
+                "unexpected text after the quoted value of \"QUOTED\"",
+            ),
+            (
+                "SERVER____PORT=1\n",
+                "\"SERVER____PORT\" maps to a key with an empty segment",
+            ),
+            (
+                "SERVER=1\nSERVER__PORT=2\n",
+                "\"server.port\" cannot be nested under \"server\", which has a value",
+            ),
+        ];
+        for (text, message) in cases {
+            let err = parse_str(text).unwrap_err();
+            assert!(
+                matches!(&err, ConfigError::Parse { format: "dotenv", message: actual, .. } if actual == message),
+                "{text:?}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join(".env");
+
+        let err = load_dotenv(&missing).unwrap_err();
+        assert!(matches!(err, ConfigError::Io { ref path, .. } if path == &missing));
+
+        let options = DotenvOptions {
+            allow_missing: true,
+            ..DotenvOptions::default()
+        };
+        load_dotenv_with(&missing, &options).unwrap();
+
+        std::fs::write(&missing, "DOTENV_MISSING_FLAG=\"now present\"\n").unwrap();
+        load_dotenv_with(&missing, &options).unwrap();
+        assert_eq!(
+            get_config_by_key("dotenv_missing_flag"),
+            Some(json!("now present"))
+        );
+    }
+}
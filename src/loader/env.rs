@@ -0,0 +1,105 @@
+//! Loading configuration from the environment variables of the process.
+
+use std::env;
+
+use serde_json::{Map, Value};
+
+use super::dotenv::{DotenvOptions, key_segments};
+use super::{insert_nested, lenient_scalar};
+use crate::config::{ConfigError, ConfigSource};
+
+/// The environment variables of the process as a [`ConfigSource`], mapped to key paths as
+/// [`load_dotenv_with`](crate::config::load_dotenv_with) maps the variables of a file.
+///
+/// The variables are read anew on every load. Variables whose name or value is not
+/// Unicode, that map to a key with an empty segment, or whose key conflicts with that of
+/// another variable, such as `APP_DB=x` next to `APP_DB__HOST=y`, are skipped with a
+/// warning, since the environment is seldom entirely under the application's control.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use zirv_config::config::{ConfigSource, EnvSource};
+///
+/// unsafe { std::env::set_var("BILLING_SERVER__PORT", "8080") };
+/// let source = EnvSource::new("BILLING");
+/// assert_eq!(source.load().unwrap(), json!({ "server": { "port": "8080" } }));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvSource {
+    options: DotenvOptions,
+    name: String,
+}
+
+impl EnvSource {
+    /// A source of the variables named `PREFIX_...`, such as `APP_SERVER__PORT` for the
+    /// prefix `APP`, which becomes `server.port`. Values are strings. An empty prefix
+    /// takes every variable.
+    pub fn new(prefix: &str) -> EnvSource {
+        let prefix = if prefix.is_empty() || prefix.ends_with('_') {
+            prefix.to_string()
+        } else {
+            format!("{prefix}_")
+        };
+        EnvSource::with_options(DotenvOptions {
+            prefix,
+            ..DotenvOptions::default()
+        })
+    }
+
+    /// A source of the variables that `options` selects, mapped as it describes. Its
+    /// `prefix` is used as is, and `allow_missing` does not apply.
+    pub fn with_options(options: DotenvOptions) -> EnvSource {
+        EnvSource {
+            name: format!("environment variables {}*", options.prefix),
+            options,
+        }
+    }
+
+    /// Reads the variables of the source, in order of name, each with its key path and
+    /// value.
+    pub(crate) fn variables(&self) -> Vec<(String, Vec<String>, Value)> {
+        let mut variables: Vec<_> = env::vars_os()
+            .filter_map(|(name, value)| {
+                let name = name.into_string().ok()?;
+                let segments = key_segments(&name, &self.options)?;
+                let Ok(value) = value.into_string() else {
+                    log::warn!("skipping the environment variable {name}: its value is not Unicode");
+                    return None;
+                };
+                if segments.iter().any(String::is_empty) {
+                    log::warn!(
+                        "skipping the environment variable {name}: it maps to a key with an empty segment"
+                    );
+                    return None;
+                }
+                let value = if self.options.lenient {
+                    lenient_scalar(&value)
+                } else {
+                    Value::String(value)
+                };
+                Some((name, segments, value))
+            })
+            .collect();
+        variables.sort_by(|a, b| a.0.cmp(&b.0));
+        variables
+    }
+}
+
+impl ConfigSource for EnvSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn load(&self) -> Result<Value, ConfigError> {
+        let mut root = Map::new();
+        for (name, segments, value) in self.variables() {
+            let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+            if let Err(message) = insert_nested(&mut root, &segments, value) {
+                log::warn!("skipping the environment variable {name}: {message}");
+            }
+        }
+        Ok(Value::Object(root))
+    }
+}
@@ -0,0 +1,279 @@
+//! Choosing a loader by file format.
+//!
+//! [`load_file`] picks the format from the file's extension, compared without regard to
+//! case:
+//!
+//! | Extension                | Format                 |
+//! |--------------------------|------------------------|
+//! | `.json`                  | [`Format::Json`]       |
+//! | `.toml`                  | [`Format::Toml`]       |
+//! | `.yaml`, `.yml`          | [`Format::Yaml`]       |
+//! | `.ini`                   | [`Format::Ini`]        |
+//! | `.properties`            | [`Format::Properties`] |
+//! | `.hcl`                   | [`Format::Hcl`]        |
+//! | `.env`, or a file `.env` | [`Format::Dotenv`]     |
+//!
+//! A file with any other extension, or none, is sniffed: a document starting with `{` is
+//! JSON, and one starting with a `---` or `%YAML` marker is YAML. Anything else is
+//! rejected with [`ConfigError::UnknownFormat`] rather than guessed at.
+
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use super::{merge_document, parse_error, read_file};
+use crate::config::ConfigError;
+
+/// The format of a configuration file, for [`load_file_as`].
+///
+/// Every format can be named, but formats behind a cargo feature can only be loaded with
+/// that feature enabled; otherwise loading fails with [`ConfigError::FormatDisabled`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// JSON, as loaded by [`load_json_file`](crate::config::load_json_file).
+    Json,
+    /// TOML, as loaded by `load_toml_file`. Requires the `toml` feature.
+    Toml,
+    /// YAML, as loaded by `load_yaml_file`. Requires the `yaml` feature.
+    Yaml,
+    /// INI, as loaded by `load_ini_file`, with every value a string. Requires the `ini`
+    /// feature.
+    Ini,
+    /// Java `.properties`, as loaded by `load_properties_file`. Requires the `properties`
+    /// feature.
+    Properties,
+    /// HCL, as loaded by `load_hcl_file`. Requires the `hcl` feature.
+    Hcl,
+    /// `.env` files, as loaded by [`load_dotenv`](crate::config::load_dotenv), with the
+    /// default [`DotenvOptions`](crate::config::DotenvOptions).
+    Dotenv,
+}
+
+impl Format {
+    /// Returns the format that a file extension, given without its dot, stands for.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use zirv_config::config::Format;
+    ///
+    /// assert_eq!(Format::from_extension("YML"), Some(Format::Yaml));
+    /// assert_eq!(Format::from_extension("conf"), None);
+    /// ```
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        let extension = extension.to_ascii_lowercase();
+        Some(match extension.as_str() {
+            "json" => Format::Json,
+            "toml" => Format::Toml,
+            "yaml" | "yml" => Format::Yaml,
+            "ini" => Format::Ini,
+            "properties" => Format::Properties,
+            "hcl" => Format::Hcl,
+            "env" => Format::Dotenv,
+            _ => return None,
+        })
+    }
+
+    /// Returns the format that the name of the file at `path` stands for, from its
+    /// extension, or [`Format::Dotenv`] for a file named `.env`.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Format> {
+        let path = path.as_ref();
+        if path.file_name().is_some_and(|name| name == ".env") {
+            return Some(Format::Dotenv);
+        }
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Format::from_extension)
+    }
+
+    /// Returns the name of the format, as used in [`ConfigError::Parse`].
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Json => "JSON",
+            Format::Toml => "TOML",
+            Format::Yaml => "YAML",
+            Format::Ini => "INI",
+            Format::Properties => "properties",
+            Format::Hcl => "HCL",
+            Format::Dotenv => "dotenv",
+        }
+    }
+
+    /// Guesses the format of a document from its first characters.
+    fn sniff(text: &str) -> Option<Format> {
+        let start = text.trim_start_matches('\u{feff}').trim_start();
+        if start.starts_with('{') {
+            Some(Format::Json)
+        } else if start.starts_with("---") || start.starts_with("%YAML") {
+            Some(Format::Yaml)
+        } else {
+            None
+        }
+    }
+
+    /// Parses a document read from `origin` with the loader for this format.
+    pub(super) fn parse(self, origin: &str, text: &str) -> Result<Value, ConfigError> {
+        match self {
+            Format::Json => super::json::parse(origin, text),
+            #[cfg(feature = "toml")]
+            Format::Toml => super::toml::parse(origin, text),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => super::yaml::parse(origin, text),
+            #[cfg(feature = "ini")]
+            Format::Ini => super::ini::parse(origin, text, false),
+            #[cfg(feature = "properties")]
+            Format::Properties => super::properties::parse(origin, text),
+            #[cfg(feature = "hcl")]
+            Format::Hcl => super::hcl::parse(origin, text),
+            Format::Dotenv => super::dotenv::parse(origin, text, &Default::default()),
+            // Formats whose features are disabled.
+            #[allow(unreachable_patterns)]
+            disabled => Err(ConfigError::FormatDisabled {
+                format: disabled.name(),
+                feature: match disabled {
+                    Format::Toml => "toml",
+                    Format::Yaml => "yaml",
+                    Format::Ini => "ini",
+                    Format::Properties => "properties",
+                    Format::Hcl => "hcl",
+                    Format::Json | Format::Dotenv => unreachable!("always enabled"),
+                },
+            }),
+        }
+    }
+}
+
+/// Loads a configuration file in the format given by its extension and deep-merges it
+/// into the store, exactly as the loader for that format does.
+///
+/// See the [detection rules](self) for the extensions recognized and how files with
+/// other names are sniffed. Fails with [`ConfigError::UnknownFormat`] if the format
+/// cannot be told, [`ConfigError::FormatDisabled`] if its cargo feature is not enabled,
+/// and otherwise as the loader for the format does. Nothing is merged on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("cache.json");
+/// std::fs::write(&path, r#"{ "cache": { "ttl": 60 } }"#).unwrap();
+///
+/// config::load_file(&path).unwrap();
+/// assert_eq!(config::get_config_by_key("cache.ttl"), Some(serde_json::json!(60)));
+/// ```
+pub fn load_file(path: impl AsRef<Path>) -> Result<(), ConfigError> {
+    let path = path.as_ref();
+    let (_, document) = parse_file(path, Format::from_path(path))?;
+    merge_document(document, None)
+}
+
+/// Loads a configuration file in the given format, whatever its name, and deep-merges it
+/// into the store, exactly as the loader for that format does.
+///
+/// Fails with [`ConfigError::FormatDisabled`] if the cargo feature for the format is not
+/// enabled, and otherwise as the loader for the format does. Nothing is merged on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// use zirv_config::config::{self, Format};
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("settings.conf");
+/// std::fs::write(&path, r#"{ "queue": { "workers": 4 } }"#).unwrap();
+///
+/// config::load_file_as(&path, Format::Json).unwrap();
+/// assert_eq!(config::get_config_by_key("queue.workers"), Some(serde_json::json!(4)));
+/// ```
+pub fn load_file_as(path: impl AsRef<Path>, format: Format) -> Result<(), ConfigError> {
+    let (_, document) = parse_file(path.as_ref(), Some(format))?;
+    merge_document(document, None)
+}
+
+/// Reads and parses a file that is layered with others into a single document, which
+/// must then be an object.
+pub(super) fn parse_layer(
+    path: &Path,
+    format: Option<Format>,
+) -> Result<(Format, Map<String, Value>), ConfigError> {
+    match parse_file(path, format)? {
+        (format, Value::Object(document)) => Ok((format, document)),
+        (format, _) => Err(parse_error(
+            &path.display().to_string(),
+            format.name(),
+            None,
+            "expected an object at the top level",
+        )),
+    }
+}
+
+/// Loads `text`, read from the file at `path`, as [`load_file_as`] does if `format` is
+/// given and as [`load_file`] does otherwise.
+#[cfg(feature = "watch")]
+pub(crate) fn load_text(
+    path: &Path,
+    format: Option<Format>,
+    text: &str,
+) -> Result<(), ConfigError> {
+    let format = format.or_else(|| Format::from_path(path));
+    let (_, document) = parse_text(path, format, text)?;
+    merge_document(document, None)
+}
+
+/// Reads and parses the file at `path` in `format`, or in the format sniffed from its
+/// contents if none is given.
+fn parse_file(path: &Path, format: Option<Format>) -> Result<(Format, Value), ConfigError> {
+    let text = read_file(path)?;
+    parse_text(path, format, &text)
+}
+
+/// Parses `text`, read from the file at `path`, as [`parse_file`] does.
+fn parse_text(
+    path: &Path,
+    format: Option<Format>,
+    text: &str,
+) -> Result<(Format, Value), ConfigError> {
+    let Some(format) = format.or_else(|| Format::sniff(text)) else {
+        return Err(ConfigError::UnknownFormat {
+            path: path.to_path_buf(),
+        });
+    };
+    Ok((format, format.parse(&path.display().to_string(), text)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_from_path() {
+        let cases = [
+            ("config/app.json", Some(Format::Json)),
+            ("Cargo.TOML", Some(Format::Toml)),
+            ("deploy.yml", Some(Format::Yaml)),
+            ("deploy.yaml", Some(Format::Yaml)),
+            ("legacy.ini", Some(Format::Ini)),
+            ("application.properties", Some(Format::Properties)),
+            ("main.hcl", Some(Format::Hcl)),
+            (".env", Some(Format::Dotenv)),
+            ("staging.env", Some(Format::Dotenv)),
+            ("settings.conf", None),
+            ("Makefile", None),
+        ];
+        for (path, format) in cases {
+            assert_eq!(Format::from_path(path), format, "{path}");
+        }
+    }
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(
+            Format::sniff("\u{feff}  \n{ \"a\": 1 }"),
+            Some(Format::Json)
+        );
+        assert_eq!(Format::sniff("---\na: 1\n"), Some(Format::Yaml));
+        assert_eq!(Format::sniff("%YAML 1.2\n---\na: 1\n"), Some(Format::Yaml));
+        assert_eq!(Format::sniff("[server]\nport = 1\n"), None);
+        assert_eq!(Format::sniff("a: 1\n"), None);
+    }
+}
@@ -0,0 +1,330 @@
+//! The HCL loader.
+//!
+//! HCL bodies become objects: each attribute becomes a key, and each block an object
+//! under its type, nested once more under each of its labels, so `service "web" { port =
+//! 80 }` is stored as `service.web.port`. A block with the same type and labels as an
+//! earlier one, or with the name of an attribute, is rejected rather than merged, as
+//! repeated blocks have no single object form.
+//!
+//! Only literal values are loaded: strings, numbers, booleans, `null`, and arrays and
+//! objects of them. Heredocs are strings, with the indentation of `<<-` heredocs removed.
+//! Expressions that HCL would evaluate, such as variables, function calls, operators,
+//! conditionals, `for` expressions and string interpolations, are rejected rather than
+//! evaluated, since the loader has no variables or functions to evaluate them with.
+//! `$${` and `%%{` in a string stand for a literal `${` and `%{`.
+
+use std::path::Path;
+
+use ::hcl::expr::{Expression, ObjectKey, TemplateExpr};
+use ::hcl::structure::{Body, Structure};
+use ::hcl::template::{Element, Template};
+use serde_json::{Map, Number, Value};
+
+use super::{child_path, merge_document, parse_error, read_file};
+use crate::config::ConfigError;
+use crate::key::escape_segment;
+
+/// Parses an HCL document read from `origin` into a JSON object.
+pub(super) fn parse(origin: &str, text: &str) -> Result<Value, ConfigError> {
+    let body = ::hcl::parse(text).map_err(|err| match err {
+        ::hcl::Error::Parse(err) => {
+            let location = err.location();
+            parse_error(
+                origin,
+                "HCL",
+                Some((location.line(), location.column())),
+                err.message(),
+            )
+        }
+        other => parse_error(origin, "HCL", None, other.to_string()),
+    })?;
+    let mut map = Map::new();
+    convert_body(&mut map, "", body)
+        .map_err(|message| parse_error(origin, "HCL", None, message))?;
+    Ok(Value::Object(map))
+}
+
+fn convert_body(map: &mut Map<String, Value>, path: &str, body: Body) -> Result<(), String> {
+    for structure in body {
+        match structure {
+            Structure::Attribute(attribute) => {
+                let name = attribute.key.into_inner();
+                let path = child_path(path, &escape_segment(&name));
+                if map.contains_key(&name) {
+                    return Err(format!("\"{path}\" is both an attribute and a block"));
+                }
+                let value = convert_expression(&path, attribute.expr)?;
+                map.insert(name, value);
+            }
+            Structure::Block(block) => {
+                let mut names = vec![block.identifier.into_inner()];
+                names.extend(block.labels.into_iter().map(|label| label.into_inner()));
+                let (last, parents) = names.split_last().expect("a block has a type");
+                let mut target = &mut *map;
+                let mut block_path = path.to_string();
+                for name in parents {
+                    block_path = child_path(&block_path, &escape_segment(name));
+                    match target
+                        .entry(name.as_str())
+                        .or_insert_with(|| Value::Object(Map::new()))
+                    {
+                        Value::Object(child) => target = child,
+                        _ => {
+                            return Err(format!(
+                                "\"{block_path}\" is both an attribute and a block"
+                            ));
+                        }
+                    }
+                }
+                block_path = child_path(&block_path, &escape_segment(last));
+                if target.contains_key(last) {
+                    return Err(format!("block \"{block_path}\" is defined more than once"));
+                }
+                let mut child = Map::new();
+                convert_body(&mut child, &block_path, block.body)?;
+                target.insert(last.clone(), Value::Object(child));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn convert_expression(path: &str, expression: Expression) -> Result<Value, String> {
+    let unsupported =
+        |what: &str| format!("\"{path}\" is {what}; only literal values are supported");
+    Ok(match expression {
+        Expression::Null => Value::Null,
+        Expression::Bool(flag) => Value::Bool(flag),
+        Expression::Number(number) => {
+            if let Some(number) = number.as_i64() {
+                Value::from(number)
+            } else if let Some(number) = number.as_u64() {
+                Value::from(number)
+            } else {
+                number
+                    .as_f64()
+                    .and_then(Number::from_f64)
+                    .map(Value::Number)
+                    .ok_or_else(|| unsupported("a number that JSON cannot represent"))?
+            }
+        }
+        Expression::String(text) => Value::String(text),
+        Expression::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    convert_expression(&child_path(path, &index.to_string()), item)
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        Expression::Object(object) => {
+            let mut map = Map::with_capacity(object.len());
+            for (key, value) in object {
+                let name = match key {
+                    ObjectKey::Identifier(name) => name.into_inner(),
+                    ObjectKey::Expression(Expression::String(name)) => name,
+                    ObjectKey::Expression(_) => {
+                        return Err(unsupported("an object with a computed key"));
+                    }
+                    _ => return Err(unsupported("an object with an unknown kind of key")),
+                };
+                let value = convert_expression(&child_path(path, &escape_segment(&name)), value)?;
+                map.insert(name, value);
+            }
+            Value::Object(map)
+        }
+        Expression::TemplateExpr(template) => {
+            let literal = Template::from_expr(&template).ok().and_then(|parsed| {
+                parsed
+                    .elements()
+                    .iter()
+                    .map(|element| match element {
+                        Element::Literal(text) => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect::<Option<String>>()
+            });
+            match (literal, *template) {
+                (Some(text), TemplateExpr::Heredoc(_)) => Value::String(text),
+                _ => {
+                    return Err(unsupported(
+                        "a string template with interpolations or directives",
+                    ));
+                }
+            }
+        }
+        Expression::Variable(_) => return Err(unsupported("a variable reference")),
+        Expression::Traversal(_) => return Err(unsupported("an attribute or index traversal")),
+        Expression::FuncCall(_) => return Err(unsupported("a function call")),
+        Expression::Parenthesis(_) => return Err(unsupported("a parenthesized expression")),
+        Expression::Conditional(_) => return Err(unsupported("a conditional expression")),
+        Expression::Operation(_) => return Err(unsupported("an operation")),
+        Expression::ForExpr(_) => return Err(unsupported("a for expression")),
+        _ => return Err(unsupported("an unsupported expression")),
+    })
+}
+
+/// Loads an HCL file and deep-merges its top-level attributes and blocks into the store,
+/// each acting as a namespace, as [`load_json_file`](crate::config::load_json_file) does
+/// for JSON.
+///
+/// See the [conversion rules](self) for how blocks and labels are nested. Fails with
+/// [`ConfigError::Io`] if the file cannot be read, [`ConfigError::Parse`], naming the
+/// file, if it is not valid HCL, uses an expression other than a literal value or
+/// repeats a block, and [`ConfigError::LimitExceeded`] if it exceeds the default
+/// [`Limits`](crate::config::Limits). Nothing is merged on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("services.hcl");
+/// std::fs::write(&path, "service \"web\" {\n  port = 8080\n}\n").unwrap();
+///
+/// config::load_hcl_file(&path).unwrap();
+/// assert_eq!(config::get_config_by_key("service.web.port"), Some(serde_json::json!(8080)));
+/// ```
+pub fn load_hcl_file(path: impl AsRef<Path>) -> Result<(), ConfigError> {
+    let path = path.as_ref();
+    let document = parse(&path.display().to_string(), &read_file(path)?)?;
+    merge_document(document, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse_str(text: &str) -> Result<Value, ConfigError> {
+        parse("main.hcl", text)
+    }
+
+    #[test]
+    fn test_blocks_and_labels_nest() {
+        let text = r#"
+            region = "eu-west-1"
+
+            service "web" {
+              port     = 8080
+              replicas = 3
+              ratio    = 0.25
+              public   = true
+              owner    = null
+              tags     = ["frontend", "edge"]
+              limits   = { cpu = "500m", "memory" = "1Gi" }
+
+              health {
+                path     = "/healthz"
+                interval = 10
+              }
+            }
+
+            service "worker" {
+              port = 9000
+            }
+
+            listener "tcp" "internal" {
+              address = "127.0.0.1:7000"
+            }
+
+            motd = <<-EOT
+              Welcome
+                to the fleet
+              EOT
+            escaped = "$${not_a_variable}"
+        "#;
+        assert_eq!(
+            parse_str(text).unwrap(),
+            json!({
+                "region": "eu-west-1",
+                "service": {
+                    "web": {
+                        "port": 8080,
+                        "replicas": 3,
+                        "ratio": 0.25,
+                        "public": true,
+                        "owner": null,
+                        "tags": ["frontend", "edge"],
+                        "limits": { "cpu": "500m", "memory": "1Gi" },
+                        "health": { "path": "/healthz", "interval": 10 },
+                    },
+                    "worker": { "port": 9000 },
+                },
+                "listener": { "tcp": { "internal": { "address": "127.0.0.1:7000" } } },
+                "motd": "Welcome\n  to the fleet\n",
+                "escaped": "${not_a_variable}",
+            })
+        );
+    }
+
+    #[test]
+    fn test_expressions_are_rejected() {
+        let cases = [
+            (
+                "port = var.port\n",
+                "\"port\" is an attribute or index traversal",
+            ),
+            (
+                "service \"web\" {\n  name = upper(\"web\")\n}\n",
+                "\"service.web.name\" is a function call",
+            ),
+            (
+                "url = \"http://${host}:80\"\n",
+                "\"url\" is a string template with interpolations or directives",
+            ),
+            ("workers = 2 * 4\n", "\"workers\" is an operation"),
+            (
+                "tier = local ? \"a\" : \"b\"\n",
+                "\"tier\" is a conditional expression",
+            ),
+            ("ids = [for s in list : s]\n", "\"ids\" is a for expression"),
+            (
+                "nested = { items = [1, region] }\n",
+                "\"nested.items.1\" is a variable reference",
+            ),
+            (
+                "motd = <<EOT\nhello ${name}\nEOT\n",
+                "\"motd\" is a string template with interpolations or directives",
+            ),
+        ];
+        for (text, message) in cases {
+            let err = parse_str(text).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("invalid HCL in main.hcl: {message}; only literal values are supported"),
+                "{text:?}"
+            );
+        }
+        assert_eq!(parse_str("offset = -5\n").unwrap(), json!({ "offset": -5 }));
+    }
+
+    #[test]
+    fn test_repeated_blocks_are_rejected() {
+        let err = parse_str("service \"web\" {}\nservice \"web\" {}\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid HCL in main.hcl: block \"service.web\" is defined more than once"
+        );
+        let err = parse_str("service = 1\nservice \"web\" {}\n").unwrap_err();
+        assert!(
+            err.to_string()
+                .ends_with("\"service\" is both an attribute and a block"),
+            "{err}"
+        );
+
+        let err = parse_str("port = 1\nport = 2\n").unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ConfigError::Parse {
+                    line: Some(2),
+                    column: Some(1),
+                    ..
+                }
+            ),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! Resolving `$include` keys, which pull other configuration files into a file.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use super::format::{Format, parse_layer};
+use super::{child_path, merge_document, parse_error};
+use crate::config::{ConfigError, merge_map};
+use crate::key::escape_segment;
+
+/// The key that names the files to include into an object.
+pub const INCLUDE_KEY: &str = "$include";
+
+/// How [`load_file_with_includes`] resolves includes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludeOptions {
+    /// How deeply includes may nest: files included by the loaded file are at depth 1,
+    /// files they include at depth 2, and so on. Defaults to 8.
+    pub max_depth: usize,
+}
+
+impl Default for IncludeOptions {
+    fn default() -> Self {
+        IncludeOptions { max_depth: 8 }
+    }
+}
+
+/// Loads a configuration file as [`load_file`](crate::config::load_file) does, resolving
+/// the files it includes, and deep-merges the result into the store.
+///
+/// An object may name files to include under the key [`INCLUDE_KEY`], `$include`, as a
+/// path or a list of paths. In TOML, the key is quoted: `"$include" = ["db.toml"]`.
+/// Relative paths are resolved against the directory of the including file, not the
+/// working directory, and each file is loaded in the format given by its extension. The
+/// included files are deep-merged into the object in the order given, and the object's
+/// own keys are merged over them, so the including file has the last word. Included
+/// files may include others, up to [`max_depth`](IncludeOptions::max_depth).
+///
+/// Fails with [`ConfigError::IncludeCycle`] if a file includes itself, directly or
+/// through others, and [`ConfigError::IncludeTooDeep`] if includes nest too deeply, both
+/// of which list the chain of files. Also fails as [`load_file`](crate::config::load_file)
+/// does for any of the files, with [`ConfigError::Io`] naming an included file that does
+/// not exist, and with [`ConfigError::Parse`] if a file's top level is not an object or
+/// `$include` is neither a path nor a list of paths. Nothing is merged on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// use zirv_config::config::{self, IncludeOptions};
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let dir = dir.path();
+/// std::fs::create_dir(dir.join("parts")).unwrap();
+/// std::fs::write(dir.join("parts/db.json"), r#"{ "host": "db.internal", "port": 5432 }"#).unwrap();
+/// std::fs::write(
+///     dir.join("app.json"),
+///     r#"{ "db": { "$include": "parts/db.json", "port": 6432 } }"#,
+/// )
+/// .unwrap();
+///
+/// config::load_file_with_includes(dir.join("app.json"), &IncludeOptions::default()).unwrap();
+/// assert_eq!(
+///     config::get_config_by_key("db"),
+///     Some(serde_json::json!({ "host": "db.internal", "port": 6432 }))
+/// );
+/// ```
+pub fn load_file_with_includes(
+    path: impl AsRef<Path>,
+    options: &IncludeOptions,
+) -> Result<(), ConfigError> {
+    let document = resolve_file(path.as_ref(), options, &mut Vec::new())?;
+    merge_document(Value::Object(document), None)
+}
+
+/// A file being resolved: its path as given and its canonical path.
+type Link = (PathBuf, PathBuf);
+
+/// Loads the file at `path`, included through `chain`, and resolves its includes.
+fn resolve_file(
+    path: &Path,
+    options: &IncludeOptions,
+    chain: &mut Vec<Link>,
+) -> Result<Map<String, Value>, ConfigError> {
+    let canonical = fs::canonicalize(path).map_err(|source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let cycle_start = chain.iter().position(|(_, seen)| *seen == canonical);
+    if cycle_start.is_some() || chain.len() > options.max_depth {
+        let start = cycle_start.unwrap_or(0);
+        let mut files: Vec<PathBuf> = chain[start..]
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect();
+        files.push(path.to_path_buf());
+        return Err(match cycle_start {
+            Some(_) => ConfigError::IncludeCycle { chain: files },
+            None => ConfigError::IncludeTooDeep {
+                chain: files,
+                max_depth: options.max_depth,
+            },
+        });
+    }
+
+    let (format, mut document) = parse_layer(path, Format::from_path(path))?;
+    chain.push((path.to_path_buf(), canonical));
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let file = File { path, dir, format };
+    let resolved = resolve_object(&file, &mut document, "", options, chain);
+    chain.pop();
+    resolved?;
+    Ok(document)
+}
+
+/// The file whose objects are being resolved.
+struct File<'a> {
+    path: &'a Path,
+    dir: &'a Path,
+    format: Format,
+}
+
+/// Resolves the includes of `map`, at the key path `key` of `file`, and of the objects
+/// nested in it.
+fn resolve_object(
+    file: &File<'_>,
+    map: &mut Map<String, Value>,
+    key: &str,
+    options: &IncludeOptions,
+    chain: &mut Vec<Link>,
+) -> Result<(), ConfigError> {
+    for (name, value) in map.iter_mut() {
+        if let Value::Object(child) = value {
+            resolve_object(
+                file,
+                child,
+                &child_path(key, &escape_segment(name)),
+                options,
+                chain,
+            )?;
+        }
+    }
+    let Some(include) = map.remove(INCLUDE_KEY) else {
+        return Ok(());
+    };
+    let paths = match &include {
+        Value::String(path) => Some(vec![path.as_str()]),
+        Value::Array(paths) => paths.iter().map(Value::as_str).collect(),
+        _ => None,
+    };
+    let Some(paths) = paths else {
+        return Err(parse_error(
+            &file.path.display().to_string(),
+            file.format.name(),
+            None,
+            format!(
+                "\"{}\" must be a path or a list of paths",
+                child_path(key, &escape_segment(INCLUDE_KEY))
+            ),
+        ));
+    };
+    let mut merged = Map::new();
+    for path in paths {
+        merge_map(
+            &mut merged,
+            resolve_file(&file.dir.join(path), options, chain)?,
+        );
+    }
+    merge_map(&mut merged, std::mem::take(map));
+    *map = merged;
+    Ok(())
+}
@@ -0,0 +1,257 @@
+//! The INI loader.
+//!
+//! An INI file is read line by line:
+//!
+//! - `[section]` starts a section, which becomes a namespace of the same name. Its name is
+//!   used as is, so `[server.tls]` is the single namespace `server.tls` rather than `tls`
+//!   nested in `server`. A section that appears twice is merged into one.
+//! - `key = value` sets an entry of the current section. Entries before the first section
+//!   form the global area and become keys at the root; a section may not share its name
+//!   with one of them. The line is split at its first `=`, so the value may itself
+//!   contain `=`. Surrounding whitespace is trimmed from keys and values, and a value
+//!   wrapped in matching double or single quotes is unquoted.
+//! - Lines whose first non-blank character is `;` or `#` are comments, as are blank lines.
+//!   Comments take up a whole line: `;` and `#` within a value are part of it.
+//!
+//! Values are strings. When loaded leniently, unquoted values that are integers or `true`
+//! and `false` in any case become numbers and booleans. A key repeated within a section
+//! keeps its last value, with a warning.
+
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use super::{lenient_scalar, merge_document, parse_error, read_file};
+use crate::config::ConfigError;
+
+/// Parses an INI document read from `origin` into a JSON object.
+pub(super) fn parse(origin: &str, text: &str, lenient: bool) -> Result<Value, ConfigError> {
+    let mut root = Map::new();
+    let mut section: Option<String> = None;
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = if index == 0 {
+            line.trim_start_matches('\u{feff}')
+        } else {
+            line
+        };
+        let content = line.trim();
+        if content.is_empty() || content.starts_with([';', '#']) {
+            continue;
+        }
+        let column = line.len() - line.trim_start().len() + 1;
+        let error =
+            |message: &str| parse_error(origin, "INI", Some((line_number, column)), message);
+
+        if let Some(header) = content.strip_prefix('[') {
+            let Some((name, rest)) = header.split_once(']') else {
+                return Err(error("unterminated section header"));
+            };
+            let rest = rest.trim_start();
+            if !rest.is_empty() && !rest.starts_with([';', '#']) {
+                return Err(error("unexpected text after the section header"));
+            }
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(error("empty section name"));
+            }
+            if root.get(name).is_some_and(|existing| !existing.is_object()) {
+                return Err(error(&format!(
+                    "section [{name}] has the same name as a global key"
+                )));
+            }
+            root.entry(name)
+                .or_insert_with(|| Value::Object(Map::new()));
+            section = Some(name.to_string());
+            continue;
+        }
+
+        let Some((key, value)) = content.split_once('=') else {
+            return Err(error("expected `key = value`"));
+        };
+        let key = key.trim_end();
+        if key.is_empty() {
+            return Err(error("empty key"));
+        }
+        let value = convert(value.trim_start(), lenient);
+        let entries = match &section {
+            Some(name) => match root.get_mut(name) {
+                Some(Value::Object(entries)) => entries,
+                _ => unreachable!("sections are inserted as objects"),
+            },
+            None => &mut root,
+        };
+        if entries.insert(key.to_string(), value).is_some() {
+            log::warn!(
+                "{origin}:{line_number}: \"{key}\" is set more than once{}; the last value is used",
+                section
+                    .as_ref()
+                    .map_or_else(String::new, |name| format!(" in [{name}]"))
+            );
+        }
+    }
+    Ok(Value::Object(root))
+}
+
+fn convert(value: &str, lenient: bool) -> Value {
+    for quote in ['"', '\''] {
+        if let Some(unquoted) = value
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return Value::String(unquoted.to_string());
+        }
+    }
+    if lenient {
+        lenient_scalar(value)
+    } else {
+        Value::String(value.to_string())
+    }
+}
+
+/// Loads an INI file and deep-merges it into the store, each section as a namespace and
+/// the entries before the first section as keys at the root.
+///
+/// Every value is stored as a string; use [`load_ini_file_lenient`] to store integers and
+/// booleans as such. See the [parsing rules](self) for comments, quoting and repeated
+/// keys. Fails with [`ConfigError::Io`] if the file cannot be read,
+/// [`ConfigError::Parse`], naming the file and line, if a line is neither a section
+/// header, an entry nor a comment, and [`ConfigError::LimitExceeded`] if it exceeds the
+/// default [`Limits`](crate::config::Limits). Nothing is merged on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("legacy.ini");
+/// std::fs::write(&path, "; legacy settings\nname = billing\n\n[db]\nport = 5432\n").unwrap();
+///
+/// config::load_ini_file(&path).unwrap();
+/// assert_eq!(config::get_config_by_key("name"), Some(serde_json::json!("billing")));
+/// assert_eq!(config::get_config_by_key("db.port"), Some(serde_json::json!("5432")));
+/// ```
+pub fn load_ini_file(path: impl AsRef<Path>) -> Result<(), ConfigError> {
+    load(path.as_ref(), false)
+}
+
+/// Loads an INI file as [`load_ini_file`] does, storing unquoted integers as numbers and
+/// `true` and `false`, in any case, as booleans.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("worker.ini");
+/// std::fs::write(&path, "[worker]\nthreads = 8\nenabled = TRUE\nversion = \"2\"\n").unwrap();
+///
+/// config::load_ini_file_lenient(&path).unwrap();
+/// assert_eq!(config::get_config_by_key("worker.threads"), Some(serde_json::json!(8)));
+/// assert_eq!(config::get_config_by_key("worker.enabled"), Some(serde_json::json!(true)));
+/// // Quoted values stay strings.
+/// assert_eq!(config::get_config_by_key("worker.version"), Some(serde_json::json!("2")));
+/// ```
+pub fn load_ini_file_lenient(path: impl AsRef<Path>) -> Result<(), ConfigError> {
+    load(path.as_ref(), true)
+}
+
+fn load(path: &Path, lenient: bool) -> Result<(), ConfigError> {
+    let document = parse(&path.display().to_string(), &read_file(path)?, lenient)?;
+    merge_document(document, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sections_global_area_and_comments() {
+        let text = "\u{feff}; global settings\napp = billing\n# another comment\n\n\
+                    [server]\n  host = 0.0.0.0  \nport=8080\n\n[server.tls] ; inline after header\ncert = /etc/cert.pem\n\
+                    [server]\nworkers = 4\n";
+        assert_eq!(
+            parse("test", text, false).unwrap(),
+            json!({
+                "app": "billing",
+                "server": { "host": "0.0.0.0", "port": "8080", "workers": "4" },
+                "server.tls": { "cert": "/etc/cert.pem" },
+            })
+        );
+    }
+
+    #[test]
+    fn test_values_containing_equals_and_comment_characters() {
+        let text = "[db]\nurl = postgres://host/app?sslmode=require&opt=a=b\ncolor = #fff ; not a comment\nempty =\nquoted = \" padded \"\n";
+        assert_eq!(
+            parse("test", text, false).unwrap(),
+            json!({ "db": {
+                "url": "postgres://host/app?sslmode=require&opt=a=b",
+                "color": "#fff ; not a comment",
+                "empty": "",
+                "quoted": " padded ",
+            } })
+        );
+    }
+
+    #[test]
+    fn test_lenient_typing() {
+        let text = "[worker]\nthreads = 8\nneg = -3\nratio = 0.5\nenabled = True\noff = false\nyes = yes\nport = \"8080\"\nflag = 'true'\n";
+        assert_eq!(
+            parse("test", text, true).unwrap(),
+            json!({ "worker": {
+                "threads": 8, "neg": -3, "ratio": "0.5", "enabled": true, "off": false,
+                "yes": "yes", "port": "8080", "flag": "true",
+            } })
+        );
+        let strict = parse("test", text, false).unwrap();
+        assert_eq!(strict["worker"]["threads"], json!("8"));
+        assert_eq!(strict["worker"]["enabled"], json!("True"));
+    }
+
+    #[test]
+    fn test_duplicate_keys_keep_the_last_value() {
+        let text =
+            "level = info\nlevel = debug\n[cache]\nttl = 60\nttl = 120\n[cache]\nttl = 300\n";
+        assert_eq!(
+            parse("test", text, false).unwrap(),
+            json!({ "level": "debug", "cache": { "ttl": "300" } })
+        );
+    }
+
+    #[test]
+    fn test_malformed_lines_are_rejected() {
+        let cases = [
+            (
+                "[server]\n  just some text\n",
+                2,
+                3,
+                "expected `key = value`",
+            ),
+            ("[server\nport = 1\n", 1, 1, "unterminated section header"),
+            (
+                "[server] port = 1\n",
+                1,
+                1,
+                "unexpected text after the section header",
+            ),
+            ("[ ]\n", 1, 1, "empty section name"),
+            ("= value\n", 1, 1, "empty key"),
+            (
+                "server = a\n[server]\n",
+                2,
+                1,
+                "section [server] has the same name as a global key",
+            ),
+        ];
+        for (text, line, column, message) in cases {
+            let err = parse("app.ini", text, false).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("invalid INI in app.ini at line {line}, column {column}: {message}"),
+                "{text:?}"
+            );
+        }
+    }
+}
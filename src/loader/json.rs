@@ -0,0 +1,152 @@
+//! The JSON loader.
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use super::{merge_document, parse_error, read_file};
+use crate::config::ConfigError;
+
+/// Parses a JSON document read from `origin`.
+pub(super) fn parse(origin: &str, text: &str) -> Result<Value, ConfigError> {
+    serde_json::from_str(text).map_err(|err| {
+        let message = err.to_string();
+        // `serde_json` appends the position to its message; it is reported separately.
+        let message = match message.rfind(" at line ") {
+            Some(end) => message[..end].to_string(),
+            None => message,
+        };
+        parse_error(origin, "JSON", Some((err.line(), err.column())), message)
+    })
+}
+
+/// Loads a JSON file and deep-merges its top-level keys into the store, each acting as a
+/// namespace.
+///
+/// Fails with [`ConfigError::Io`] if the file cannot be read, [`ConfigError::Parse`],
+/// naming the file, line and column, if it is not valid JSON, [`ConfigError::NotAnObject`]
+/// if its top level is not an object, and [`ConfigError::LimitExceeded`] if it exceeds the
+/// default [`Limits`](crate::config::Limits). Nothing is merged on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("app.json");
+/// std::fs::write(&path, r#"{ "server": { "port": 8080 }, "db": { "pool": 10 } }"#).unwrap();
+///
+/// config::load_json_file(&path).unwrap();
+/// assert_eq!(config::get_config_by_key("server.port"), Some(serde_json::json!(8080)));
+/// ```
+pub fn load_json_file(path: impl AsRef<Path>) -> Result<(), ConfigError> {
+    load(path.as_ref(), None)
+}
+
+/// Loads a JSON file and deep-merges it under `namespace`, which may be a dot-separated
+/// key path.
+///
+/// The document may be any JSON value. Fails as [`load_json_file`] does, and as
+/// [`merge_config_at`](crate::config::merge_config_at) does if `namespace` cannot hold it.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("db.json");
+/// std::fs::write(&path, r#"{ "url": "postgres://localhost/app" }"#).unwrap();
+///
+/// config::load_json_file_into("db", &path).unwrap();
+/// assert_eq!(config::get_config_by_key("db.url"), Some(serde_json::json!("postgres://localhost/app")));
+/// ```
+pub fn load_json_file_into(namespace: &str, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+    load(path.as_ref(), Some(namespace))
+}
+
+fn load(path: &Path, namespace: Option<&str>) -> Result<(), ConfigError> {
+    let origin = path.display().to_string();
+    let document = parse(&origin, &read_file(path)?)?;
+    merge_document(document, namespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::get_config_by_key;
+    use serde_json::json;
+    use std::fs;
+
+    #[test]
+    fn test_load_json_file_at_root_and_into_namespace() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root.json");
+        fs::write(
+            &root,
+            r#"{ "json_root_server": { "port": 80 }, "json_root_db": { "pool": 4 } }"#,
+        )
+        .unwrap();
+        load_json_file(&root).unwrap();
+        assert_eq!(get_config_by_key("json_root_server.port"), Some(json!(80)));
+        assert_eq!(get_config_by_key("json_root_db.pool"), Some(json!(4)));
+
+        // Later files deep-merge into what is there.
+        let overrides = dir.path().join("overrides.json");
+        fs::write(&overrides, r#"{ "json_root_server": { "host": "::" } }"#).unwrap();
+        load_json_file(&overrides).unwrap();
+        assert_eq!(
+            get_config_by_key("json_root_server"),
+            Some(json!({ "port": 80, "host": "::" }))
+        );
+
+        let mounted = dir.path().join("mounted.json");
+        fs::write(&mounted, r#"["a", "b"]"#).unwrap();
+        load_json_file_into("json_mounted.peers", &mounted).unwrap();
+        assert_eq!(
+            get_config_by_key("json_mounted.peers"),
+            Some(json!(["a", "b"]))
+        );
+        // Only objects can be merged at the root.
+        let err = load_json_file(&mounted).unwrap_err();
+        assert!(matches!(err, ConfigError::NotAnObject { .. }));
+    }
+
+    #[test]
+    fn test_load_json_file_errors_name_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.json");
+        fs::write(&path, "{\n  \"json_broken\": {\n    \"port\": 80,\n  }\n}").unwrap();
+
+        let err = load_json_file(&path).unwrap_err();
+        match &err {
+            ConfigError::Parse {
+                origin,
+                format,
+                line,
+                column,
+                ..
+            } => {
+                assert_eq!(origin, &path.display().to_string());
+                assert_eq!(*format, "JSON");
+                assert_eq!((*line, *column), (Some(4), Some(3)));
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "invalid JSON in {} at line 4, column 3: trailing comma",
+                path.display()
+            )
+        );
+        assert_eq!(get_config_by_key("json_broken"), None);
+
+        let missing = dir.path().join("missing.json");
+        let err = load_json_file(&missing).unwrap_err();
+        assert!(matches!(err, ConfigError::Io { ref path, .. } if path == &missing));
+        assert!(
+            err.to_string()
+                .starts_with(&format!("cannot read {}: ", missing.display()))
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Loading a fixed stack of configuration files, such as shipped defaults under a local
+//! override.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use super::format::{Format, parse_layer};
+use super::merge_document;
+use crate::config::{ConfigError, ConfigSource, merge_map};
+
+/// A file in the stack passed to [`load_layered`].
+///
+/// A layer is also a [`ConfigSource`] for [`reload_config`](crate::config::reload_config),
+/// named by its path, which loads the file as [`load_layered`] does and yields an empty
+/// document for an optional file that is missing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layer {
+    path: PathBuf,
+    name: String,
+    required: bool,
+}
+
+impl Layer {
+    /// A layer that must exist: loading fails if it is missing.
+    pub fn required(path: impl Into<PathBuf>) -> Layer {
+        Layer::new(path.into(), true)
+    }
+
+    /// A layer that is skipped if it is missing, such as a developer's local override.
+    pub fn optional(path: impl Into<PathBuf>) -> Layer {
+        Layer::new(path.into(), false)
+    }
+
+    fn new(path: PathBuf, required: bool) -> Layer {
+        Layer {
+            name: path.display().to_string(),
+            path,
+            required,
+        }
+    }
+
+    /// The path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Tells whether loading fails if the file is missing.
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+}
+
+impl ConfigSource for Layer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn load(&self) -> Result<Value, ConfigError> {
+        let (document, _) = combine_layers(std::slice::from_ref(self))?;
+        Ok(Value::Object(document))
+    }
+}
+
+/// The outcome of [`load_layered`]: which layers were loaded and which optional ones were
+/// missing, each in the order given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LayerReport {
+    /// The paths of the layers that were loaded.
+    pub loaded: Vec<PathBuf>,
+    /// The paths of the optional layers that did not exist.
+    pub missing: Vec<PathBuf>,
+}
+
+/// Loads a stack of configuration files in order and deep-merges them into the store, so
+/// that each layer overrides the ones before it.
+///
+/// Each file is loaded in the format given by its extension, as
+/// [`load_file`](crate::config::load_file) does. An [optional](Layer::optional) layer
+/// that does not exist is skipped and listed in the returned report; any other error,
+/// including a missing [required](Layer::required) layer, which fails with
+/// [`ConfigError::Io`], aborts the load. Every layer is parsed before anything is merged,
+/// so nothing is merged on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// use zirv_config::config::{self, Layer};
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let dir = dir.path();
+/// std::fs::write(dir.join("default.json"), r#"{ "db": { "host": "localhost", "port": 5432 } }"#).unwrap();
+///
+/// let report = config::load_layered(&[
+///     Layer::required(dir.join("default.json")),
+///     Layer::optional(dir.join("local.json")),
+/// ])
+/// .unwrap();
+/// assert_eq!(report.missing, [dir.join("local.json")]);
+/// assert_eq!(config::get_config_by_key("db.port"), Some(serde_json::json!(5432)));
+/// ```
+pub fn load_layered(layers: &[Layer]) -> Result<LayerReport, ConfigError> {
+    let (combined, report) = combine_layers(layers)?;
+    merge_document(Value::Object(combined), None)?;
+    Ok(report)
+}
+
+/// Parses `layers` and deep-merges them, in order, into a single document.
+pub(super) fn combine_layers(
+    layers: &[Layer],
+) -> Result<(Map<String, Value>, LayerReport), ConfigError> {
+    let mut report = LayerReport::default();
+    let mut combined = Map::new();
+    for layer in layers {
+        match parse_layer(&layer.path, Format::from_path(&layer.path)) {
+            Ok((_, document)) => {
+                merge_map(&mut combined, document);
+                report.loaded.push(layer.path.clone());
+            }
+            Err(ConfigError::Io { source, .. })
+                if !layer.required && source.kind() == io::ErrorKind::NotFound =>
+            {
+                log::debug!(
+                    "skipping {}: optional layer not found",
+                    layer.path.display()
+                );
+                report.missing.push(layer.path.clone());
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok((combined, report))
+}
+
+/// Loads `default.toml` from `dir`, which must exist, and `local.toml` over it if it
+/// exists, as [`load_layered`] does.
+///
+/// This is the usual layout of a `config` directory whose defaults are committed and
+/// whose local overrides are ignored by version control.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let dir = dir.path();
+/// std::fs::write(dir.join("default.toml"), "[log]\nlevel = \"info\"\nformat = \"json\"\n").unwrap();
+/// std::fs::write(dir.join("local.toml"), "[log]\nlevel = \"debug\"\n").unwrap();
+///
+/// config::load_standard_layout(dir).unwrap();
+/// assert_eq!(
+///     config::get_config_by_key("log"),
+///     Some(serde_json::json!({ "level": "debug", "format": "json" }))
+/// );
+/// ```
+#[cfg(feature = "toml")]
+pub fn load_standard_layout(dir: impl AsRef<Path>) -> Result<LayerReport, ConfigError> {
+    let dir = dir.as_ref();
+    load_layered(&[
+        Layer::required(dir.join("default.toml")),
+        Layer::optional(dir.join("local.toml")),
+    ])
+}
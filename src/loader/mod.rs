@@ -0,0 +1,198 @@
+//! Loading configuration from files.
+//!
+//! Each loader parses a document into a JSON value and deep-merges it into the store, as
+//! [`merge_config`] does: at the root, where each top-level key acts as a namespace, or
+//! under a namespace given by the caller. Documents are checked against the default
+//! [`Limits`] before anything is read or merged.
+
+mod dir;
+mod dotenv;
+mod env;
+mod format;
+#[cfg(feature = "hcl")]
+mod hcl;
+mod include;
+#[cfg(feature = "ini")]
+mod ini;
+mod json;
+mod layered;
+#[cfg(feature = "toml")]
+mod profile;
+#[cfg(feature = "properties")]
+mod properties;
+mod reader;
+#[cfg(feature = "remote")]
+mod remote;
+#[cfg(feature = "toml")]
+mod toml;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::config::{ConfigError, Limits, merge_config, merge_config_at};
+
+#[cfg(feature = "hcl")]
+pub use self::hcl::load_hcl_file;
+#[cfg(feature = "toml")]
+pub use self::toml::{load_toml_file, load_toml_str};
+pub use dir::load_dir;
+pub use dotenv::{DotenvOptions, load_dotenv, load_dotenv_with};
+pub use env::EnvSource;
+#[cfg(feature = "watch")]
+pub(crate) use format::load_text;
+pub use format::{Format, load_file, load_file_as};
+pub use include::{INCLUDE_KEY, IncludeOptions, load_file_with_includes};
+#[cfg(feature = "ini")]
+pub use ini::{load_ini_file, load_ini_file_lenient};
+pub use json::{load_json_file, load_json_file_into};
+#[cfg(feature = "toml")]
+pub use layered::load_standard_layout;
+pub use layered::{Layer, LayerReport, load_layered};
+#[cfg(feature = "toml")]
+pub use profile::{PROFILE_KEY, ProfileOptions, load_profile};
+#[cfg(feature = "properties")]
+pub use properties::load_properties_file;
+pub use reader::{load_reader, load_stdin};
+#[cfg(feature = "remote")]
+pub use remote::{FetchPolicy, RemoteOptions, RemoteSource, Validators, load_url};
+#[cfg(feature = "yaml")]
+pub use yaml::{load_yaml_file, load_yaml_profiles, load_yaml_str};
+
+/// Reads the file at `path` as UTF-8 text, refusing files larger than the default
+/// [`Limits::max_size`].
+pub(crate) fn read_file(path: &Path) -> Result<String, ConfigError> {
+    let io_error = |source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let max = Limits::default().max_size;
+    let len = fs::metadata(path).map_err(io_error)?.len();
+    if len > max as u64 {
+        return Err(ConfigError::LimitExceeded {
+            limit: "max_size",
+            max,
+            path: String::new(),
+        });
+    }
+    fs::read_to_string(path).map_err(io_error)
+}
+
+/// Reads `reader` to its end as UTF-8 text, refusing text larger than the default
+/// [`Limits::max_size`] as soon as it is exceeded.
+fn read_limited(
+    reader: impl Read,
+    io_error: impl FnOnce(io::Error) -> ConfigError,
+) -> Result<String, ConfigError> {
+    let max = Limits::default().max_size;
+    let mut text = String::new();
+    reader
+        .take(max as u64 + 1)
+        .read_to_string(&mut text)
+        .map_err(io_error)?;
+    if text.len() > max {
+        return Err(ConfigError::LimitExceeded {
+            limit: "max_size",
+            max,
+            path: String::new(),
+        });
+    }
+    Ok(text)
+}
+
+/// Merges a parsed document into the store, under `namespace` or at the root.
+fn merge_document(document: Value, namespace: Option<&str>) -> Result<(), ConfigError> {
+    match namespace {
+        Some(namespace) => merge_config_at(namespace, document),
+        None => merge_config(document),
+    }
+}
+
+/// Builds a [`ConfigError::Parse`] for a document from `origin`.
+fn parse_error(
+    origin: &str,
+    format: &'static str,
+    position: Option<(usize, usize)>,
+    message: impl Into<String>,
+) -> ConfigError {
+    ConfigError::Parse {
+        origin: origin.to_string(),
+        format,
+        line: position.map(|(line, _)| line),
+        column: position.map(|(_, column)| column),
+        message: message.into(),
+    }
+}
+
+/// Stores `value` at the path of `segments` in `root`, creating the objects along it, and
+/// tells whether it replaced an earlier value.
+///
+/// Fails if a key along the path already holds a value, or the path itself already has
+/// keys nested under it, since a key cannot be both.
+fn insert_nested(
+    root: &mut Map<String, Value>,
+    segments: &[&str],
+    value: Value,
+) -> Result<bool, String> {
+    let key = segments.join(".");
+    let (last, parents) = segments.split_last().expect("a key has a segment");
+    let mut map = root;
+    for (depth, segment) in parents.iter().enumerate() {
+        match map
+            .entry(*segment)
+            .or_insert_with(|| Value::Object(Map::new()))
+        {
+            Value::Object(child) => map = child,
+            _ => {
+                return Err(format!(
+                    "\"{key}\" cannot be nested under \"{}\", which has a value",
+                    segments[..=depth].join(".")
+                ));
+            }
+        }
+    }
+    match map.get(*last) {
+        Some(Value::Object(_)) => Err(format!(
+            "\"{key}\" cannot have a value, as other keys are nested under it"
+        )),
+        _ => Ok(map.insert(last.to_string(), value).is_some()),
+    }
+}
+
+/// Types an unquoted value read from a flat file, as the lenient loaders do: integers
+/// become numbers and `true` and `false`, in any case, booleans.
+fn lenient_scalar(value: &str) -> Value {
+    if let Ok(number) = value.parse::<i64>() {
+        Value::from(number)
+    } else if value.eq_ignore_ascii_case("true") {
+        Value::Bool(true)
+    } else if value.eq_ignore_ascii_case("false") {
+        Value::Bool(false)
+    } else {
+        Value::String(value.to_string())
+    }
+}
+
+/// Appends an escaped `segment` to the dot-separated `path`.
+fn child_path(path: &str, segment: &str) -> String {
+    match path {
+        "" => segment.to_string(),
+        path => format!("{path}.{segment}"),
+    }
+}
+
+/// Converts a byte offset into `text` to a 1-based line and column, counting columns in
+/// characters.
+#[cfg(feature = "toml")]
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
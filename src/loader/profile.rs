@@ -0,0 +1,117 @@
+//! Loading the configuration of a deployment profile, chosen by an environment variable.
+
+use std::env;
+use std::path::Path;
+
+use serde_json::{Value, json};
+
+use super::layered::{Layer, combine_layers};
+use super::merge_document;
+use crate::config::{ConfigError, merge_map};
+
+/// The key under which [`load_profile`] records the active profile.
+pub const PROFILE_KEY: &str = "zirv.profile";
+
+/// How [`load_profile`] chooses the profile. Override individual fields with struct update
+/// syntax.
+///
+/// # Examples
+///
+/// ```rust
+/// use zirv_config::config::ProfileOptions;
+///
+/// let options = ProfileOptions { env_var: "MYAPP_ENV".to_string(), ..ProfileOptions::default() };
+/// assert_eq!(options.default_profile, "development");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileOptions {
+    /// The environment variable that names the profile. Defaults to `APP_ENV`.
+    pub env_var: String,
+    /// The profile used when the variable is unset or empty. Defaults to `development`.
+    pub default_profile: String,
+    /// Whether a profile without a file falls back to the default profile, with a warning,
+    /// rather than failing with [`ConfigError::UnknownProfile`]. Defaults to `false`.
+    pub fall_back: bool,
+}
+
+impl Default for ProfileOptions {
+    fn default() -> Self {
+        ProfileOptions {
+            env_var: "APP_ENV".to_string(),
+            default_profile: "development".to_string(),
+            fall_back: false,
+        }
+    }
+}
+
+/// Loads `default.toml` from `dir` and the file of the active profile over it, such as
+/// `production.toml`, deep-merges them into the store and returns the profile.
+///
+/// The profile is read from the environment variable in `options`, or is the default
+/// profile if the variable is unset or empty. Profile names consist of ASCII letters,
+/// digits, `-` and `_`. The file of the default profile is optional; that of any other
+/// profile must exist, or loading fails with [`ConfigError::UnknownProfile`] unless
+/// [`fall_back`](ProfileOptions::fall_back) is set. The active profile is recorded under
+/// [`PROFILE_KEY`], replacing any value the files give it.
+///
+/// Files are loaded as [`load_layered`](crate::config::load_layered) loads them, so
+/// `default.toml` must exist and nothing is merged on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// use zirv_config::config::{self, ProfileOptions};
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let dir = dir.path();
+/// std::fs::write(dir.join("default.toml"), "[server]\nport = 8080\nworkers = 2\n").unwrap();
+/// std::fs::write(dir.join("production.toml"), "[server]\nworkers = 16\n").unwrap();
+///
+/// let options = ProfileOptions { env_var: "DOC_APP_ENV".to_string(), ..ProfileOptions::default() };
+/// unsafe { std::env::set_var("DOC_APP_ENV", "production") };
+///
+/// assert_eq!(config::load_profile(dir, &options).unwrap(), "production");
+/// assert_eq!(config::get_config_by_key("server.workers"), Some(serde_json::json!(16)));
+/// assert_eq!(config::get_config_by_key("zirv.profile"), Some(serde_json::json!("production")));
+/// ```
+pub fn load_profile(
+    dir: impl AsRef<Path>,
+    options: &ProfileOptions,
+) -> Result<String, ConfigError> {
+    let dir = dir.as_ref();
+    let mut profile = env::var(&options.env_var)
+        .ok()
+        .filter(|profile| !profile.is_empty())
+        .unwrap_or_else(|| options.default_profile.clone());
+    let is_valid = |profile: &str| {
+        !profile.is_empty()
+            && profile
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    let mut path = dir.join(format!("{profile}.toml"));
+    if !is_valid(&profile) || (profile != options.default_profile && !path.is_file()) {
+        if !options.fall_back {
+            return Err(ConfigError::UnknownProfile { profile, path });
+        }
+        log::warn!(
+            "profile \"{profile}\" has no {}; falling back to \"{}\"",
+            path.display(),
+            options.default_profile
+        );
+        profile = options.default_profile.clone();
+        path = dir.join(format!("{profile}.toml"));
+        if !is_valid(&profile) {
+            return Err(ConfigError::UnknownProfile { profile, path });
+        }
+    }
+
+    let (mut combined, _) = combine_layers(&[
+        Layer::required(dir.join("default.toml")),
+        Layer::optional(path),
+    ])?;
+    if let Value::Object(record) = json!({ "zirv": { "profile": profile } }) {
+        merge_map(&mut combined, record);
+    }
+    merge_document(Value::Object(combined), None)?;
+    Ok(profile)
+}
@@ -0,0 +1,271 @@
+//! The `.properties` loader.
+//!
+//! Files are read as `java.util.Properties` reads them, except that they are decoded as
+//! UTF-8:
+//!
+//! - Lines whose first non-blank character is `#` or `!` are comments, as are blank lines.
+//! - A line ending in an odd number of backslashes continues on the next line, with the
+//!   next line's leading whitespace dropped.
+//! - The key ends at the first unescaped `=`, `:` or whitespace, and the value is the rest
+//!   of the line after that separator and any whitespace around it. A line with only a key
+//!   sets it to the empty string.
+//! - `\t`, `\n`, `\r` and `\f` are escapes for control characters, `\uXXXX` for a UTF-16
+//!   code unit, with surrogate pairs written as two escapes, and a backslash before any
+//!   other character stands for that character, as in `\=` or `\ `.
+//!
+//! Keys are split on dots into nested objects, so `db.pool.max=20` is stored as `max`
+//! in `pool` in the `db` namespace. Values are strings. A key repeated in the file keeps
+//! its last value, with a warning. A key cannot both hold a value and have keys nested
+//! under it: `a.b=1` together with `a.b.c=2`, in either order, is rejected. So are keys
+//! with an empty segment, such as `a..b`.
+
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use super::{insert_nested, merge_document, parse_error, read_file};
+use crate::config::ConfigError;
+
+/// Parses a `.properties` document read from `origin` into a JSON object.
+pub(super) fn parse(origin: &str, text: &str) -> Result<Value, ConfigError> {
+    let blank = [' ', '\t', '\x0c'];
+    let mut root = Map::new();
+    let mut lines = text.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let line = if index == 0 {
+            line.trim_start_matches('\u{feff}')
+        } else {
+            line
+        };
+        let content = line.trim_start_matches(blank);
+        if content.is_empty() || content.starts_with(['#', '!']) {
+            continue;
+        }
+        let position = (index + 1, line.len() - content.len() + 1);
+        let error = |message: String| parse_error(origin, "properties", Some(position), message);
+
+        let mut logical = String::new();
+        let mut natural = content;
+        while let Some(continued) = continuation(natural) {
+            logical.push_str(continued);
+            match lines.next() {
+                Some((_, next)) => natural = next.trim_start_matches(blank),
+                None => {
+                    natural = "";
+                    break;
+                }
+            }
+        }
+        logical.push_str(natural);
+
+        let (key, value) = split_entry(&logical).map_err(error)?;
+        let segments: Vec<&str> = key.split('.').collect();
+        if segments.iter().any(|segment| segment.is_empty()) {
+            return Err(error(format!("\"{key}\" has an empty segment")));
+        }
+        if insert_nested(&mut root, &segments, Value::String(value)).map_err(error)? {
+            log::warn!(
+                "{origin}:{}: \"{key}\" is set more than once; the last value is used",
+                position.0
+            );
+        }
+    }
+    Ok(Value::Object(root))
+}
+
+/// Returns `line` without its final backslash if it ends in an odd number of them, which
+/// continues it on the next line.
+fn continuation(line: &str) -> Option<&str> {
+    let backslashes = line.len() - line.trim_end_matches('\\').len();
+    (backslashes % 2 == 1).then(|| &line[..line.len() - 1])
+}
+
+/// Splits a logical line into its unescaped key and value.
+fn split_entry(line: &str) -> Result<(String, String), String> {
+    let is_blank = |c: char| matches!(c, ' ' | '\t' | '\x0c');
+    let mut chars = line.chars().peekable();
+    let mut key = String::new();
+    while let Some(&next) = chars.peek() {
+        if next == '=' || next == ':' || is_blank(next) {
+            break;
+        }
+        chars.next();
+        match next {
+            '\\' => key.push(unescape(&mut chars)?),
+            other => key.push(other),
+        }
+    }
+    while chars.next_if(|&c| is_blank(c)).is_some() {}
+    if chars.next_if(|&c| c == '=' || c == ':').is_some() {
+        while chars.next_if(|&c| is_blank(c)).is_some() {}
+    }
+    let mut value = String::new();
+    while let Some(next) = chars.next() {
+        match next {
+            '\\' => value.push(unescape(&mut chars)?),
+            other => value.push(other),
+        }
+    }
+    Ok((key, value))
+}
+
+/// Decodes the escape following a backslash.
+fn unescape(chars: &mut impl Iterator<Item = char>) -> Result<char, String> {
+    Ok(match chars.next() {
+        Some('t') => '\t',
+        Some('n') => '\n',
+        Some('r') => '\r',
+        Some('f') => '\x0c',
+        Some('u') => {
+            let unit = code_unit(chars)?;
+            if !(0xD800..0xDC00).contains(&unit) {
+                return char::from_u32(unit.into())
+                    .ok_or_else(|| format!("\\u{unit:04X} is a lone low surrogate"));
+            }
+            let low = match (chars.next(), chars.next()) {
+                (Some('\\'), Some('u')) => code_unit(chars)?,
+                _ => return Err(format!("\\u{unit:04X} must be followed by a low surrogate")),
+            };
+            char::decode_utf16([unit, low])
+                .next()
+                .and_then(Result::ok)
+                .ok_or_else(|| format!("\\u{unit:04X} must be followed by a low surrogate"))?
+        }
+        Some(other) => other,
+        None => unreachable!("a trailing backslash continues the line"),
+    })
+}
+
+/// Reads the four hexadecimal digits of a `\u` escape.
+fn code_unit(chars: &mut impl Iterator<Item = char>) -> Result<u16, String> {
+    let digits: String = chars.take(4).collect();
+    if digits.len() == 4 && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(u16::from_str_radix(&digits, 16).expect("four hexadecimal digits"))
+    } else {
+        Err(format!("malformed \\u escape \"\\u{digits}\""))
+    }
+}
+
+/// Loads a `.properties` file and deep-merges it into the store, with dotted keys nested
+/// into objects and each first segment acting as a namespace.
+///
+/// See the [parsing rules](self) for continuations, escapes and conflicting keys. Fails
+/// with [`ConfigError::Io`] if the file cannot be read, [`ConfigError::Parse`], naming
+/// the file and line, if an escape is malformed or keys conflict, and
+/// [`ConfigError::LimitExceeded`] if it exceeds the default
+/// [`Limits`](crate::config::Limits). Nothing is merged on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("application.properties");
+/// std::fs::write(&path, "server.port=8080\ndb.pool.max: 20\n").unwrap();
+///
+/// config::load_properties_file(&path).unwrap();
+/// assert_eq!(config::get_config_by_key("server.port"), Some(serde_json::json!("8080")));
+/// assert_eq!(config::get_config_by_key("db.pool"), Some(serde_json::json!({ "max": "20" })));
+/// ```
+pub fn load_properties_file(path: impl AsRef<Path>) -> Result<(), ConfigError> {
+    let path = path.as_ref();
+    let document = parse(&path.display().to_string(), &read_file(path)?)?;
+    merge_document(document, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse_str(text: &str) -> Result<Value, ConfigError> {
+        parse("app.properties", text)
+    }
+
+    #[test]
+    fn test_separators_and_comments() {
+        let text = "# comment\n! also a comment\n\nserver.port=8080\nserver.host : 0.0.0.0\n\
+                    app.name   billing service\n  db.pool.max = 20\ndb.pool.min=\ndb.flag\n";
+        assert_eq!(
+            parse_str(text).unwrap(),
+            json!({
+                "server": { "port": "8080", "host": "0.0.0.0" },
+                "app": { "name": "billing service" },
+                "db": { "pool": { "max": "20", "min": "" }, "flag": "" },
+            })
+        );
+    }
+
+    #[test]
+    fn test_line_continuations() {
+        let text = "app.fruits = apple, banana, \\\n           cherry, \\\n    date\n\
+                    app.path = C:\\\\temp\\\\\napp.after = 1\napp.last = end\\";
+        assert_eq!(
+            parse_str(text).unwrap(),
+            json!({ "app": {
+                "fruits": "apple, banana, cherry, date",
+                // An even number of backslashes is escaped backslashes, not a continuation.
+                "path": "C:\\temp\\",
+                "after": "1",
+                "last": "end",
+            } })
+        );
+        // A comment line does not continue, even if it ends in a backslash.
+        assert_eq!(parse_str("# note \\\na=1\n").unwrap(), json!({ "a": "1" }));
+    }
+
+    #[test]
+    fn test_escapes() {
+        let text = "msg.greeting = caf\\u00e9 \\u2603 \\uD83D\\uDE00\nmsg.tabbed = a\\tb\\nc\n\
+                    key\\=with\\:separators\\ and\\ spaces = value\nmsg.lead = \\  padded\nmsg.other = \\q\n";
+        assert_eq!(
+            parse_str(text).unwrap(),
+            json!({
+                "msg": {
+                    "greeting": "café ☃ 😀",
+                    "tabbed": "a\tb\nc",
+                    "lead": "  padded",
+                    "other": "q",
+                },
+                "key=with:separators and spaces": "value",
+            })
+        );
+
+        let err = parse_str("a = ok\nb = \\u12G4\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid properties in app.properties at line 2, column 1: malformed \\u escape \"\\u12G4\""
+        );
+        let err = parse_str("b = \\uD83D!\n").unwrap_err();
+        assert!(
+            err.to_string()
+                .ends_with("\\uD83D must be followed by a low surrogate"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_nesting_conflicts_are_rejected() {
+        let err = parse_str("a.b=1\na.b.c=2\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid properties in app.properties at line 2, column 1: \"a.b.c\" cannot be nested under \"a.b\", which has a value"
+        );
+        let err = parse_str("a.b.c=2\n  a.b=1\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid properties in app.properties at line 2, column 3: \"a.b\" cannot have a value, as other keys are nested under it"
+        );
+        let err = parse_str("a..b=1\n").unwrap_err();
+        assert!(
+            err.to_string().ends_with("\"a..b\" has an empty segment"),
+            "{err}"
+        );
+
+        // Siblings and repeated keys are fine; the last value wins.
+        assert_eq!(
+            parse_str("a.b.c=1\na.b.d=2\na.b.c=3\n").unwrap(),
+            json!({ "a": { "b": { "c": "3", "d": "2" } } })
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! Loading configuration from streams rather than files.
+
+use std::io::{self, IsTerminal, Read};
+use std::path::PathBuf;
+
+use super::format::Format;
+use super::{merge_document, read_limited};
+use crate::config::ConfigError;
+
+/// Reads a document in `format` from `reader` to end of file and deep-merges it into the
+/// store, as the loader for that format does for a file.
+///
+/// Errors name the origin `<reader>`. Fails with [`ConfigError::Io`] if reading fails or
+/// the document is not UTF-8, [`ConfigError::LimitExceeded`] if it is larger than the
+/// default [`Limits::max_size`](crate::config::Limits::max_size), which is checked as the
+/// document is read, and otherwise as the loader for the format does. Nothing is merged
+/// on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::io::Cursor;
+/// use zirv_config::config::{self, Format};
+///
+/// config::load_reader(Cursor::new(r#"{ "worker": { "threads": 4 } }"#), Format::Json).unwrap();
+/// assert_eq!(config::get_config_by_key("worker.threads"), Some(serde_json::json!(4)));
+/// ```
+pub fn load_reader(reader: impl Read, format: Format) -> Result<(), ConfigError> {
+    load("<reader>", reader, format)
+}
+
+/// Reads a document in `format` from standard input to end of file and deep-merges it
+/// into the store, as [`load_reader`] does, for programs run as `render-config | app
+/// --config -`.
+///
+/// This reads until standard input is closed, so it only returns once the program
+/// writing to it exits or closes the pipe. If standard input is a terminal, it fails at
+/// once with [`ConfigError::Io`] rather than waiting for someone to type a document.
+/// Errors name the origin `stdin`.
+pub fn load_stdin(format: Format) -> Result<(), ConfigError> {
+    let stdin = io::stdin();
+    if stdin.is_terminal() {
+        return Err(ConfigError::Io {
+            path: PathBuf::from("stdin"),
+            source: io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "standard input is a terminal, not a piped document",
+            ),
+        });
+    }
+    load("stdin", stdin.lock(), format)
+}
+
+fn load(origin: &str, reader: impl Read, format: Format) -> Result<(), ConfigError> {
+    let text = read_limited(reader, |source| ConfigError::Io {
+        path: PathBuf::from(origin),
+        source,
+    })?;
+    merge_document(format.parse(origin, &text)?, None)
+}
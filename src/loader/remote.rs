@@ -0,0 +1,460 @@
+//! Loading configuration over HTTP(S).
+
+use std::fmt;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{Map, Value};
+
+use super::format::Format;
+use super::{merge_document, read_limited};
+use crate::config::{ConfigError, ConfigSource};
+
+/// How [`load_url`] fetches and reads a remote document. Override individual fields with
+/// struct update syntax.
+///
+/// # Examples
+///
+/// ```rust
+/// use zirv_config::config::{Format, RemoteOptions};
+///
+/// let options = RemoteOptions {
+///     bearer_token: Some("s3cr3t".to_string()),
+///     format: Some(Format::Toml),
+///     ..RemoteOptions::default()
+/// };
+/// assert!(options.headers.is_empty());
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct RemoteOptions {
+    /// The format of the document. Defaults to `None`, which takes the format from the
+    /// `Content-Type` of the response.
+    pub format: Option<Format>,
+    /// A token sent as `Authorization: Bearer <token>`. Defaults to `None`.
+    pub bearer_token: Option<String>,
+    /// Further headers sent with the request, as name and value. Defaults to none.
+    pub headers: Vec<(String, String)>,
+    /// How long to wait for the server and how often to retry. Defaults to a single
+    /// attempt with the default timeouts.
+    pub policy: FetchPolicy,
+}
+
+/// Shows the bearer token and the values of the headers as `<redacted>`, so that logging
+/// the options, or a [`RemoteSource`], does not leak credentials.
+impl fmt::Debug for RemoteOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let headers: Vec<(&str, Redacted)> = self
+            .headers
+            .iter()
+            .map(|(name, _)| (name.as_str(), Redacted))
+            .collect();
+        f.debug_struct("RemoteOptions")
+            .field("format", &self.format)
+            .field(
+                "bearer_token",
+                &self.bearer_token.as_ref().map(|_| Redacted),
+            )
+            .field("headers", &headers)
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+/// A secret left out of `Debug` output.
+struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+/// The timeouts and retries of [`load_url`], part of [`RemoteOptions`]. Override
+/// individual fields with struct update syntax.
+///
+/// A failed attempt is retried if the server could not be reached, the request timed out,
+/// or the server answered `408`, `429` or a `5xx` status: failures that may pass, such as
+/// a service that is still starting. Other failures, such as a `404` or a document that
+/// does not parse, would fail again and end the fetch at once.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use zirv_config::config::FetchPolicy;
+///
+/// // Retry for about 3 seconds at boot: after 200ms, 400ms, 800ms and 1600ms.
+/// let policy = FetchPolicy {
+///     retries: 4,
+///     backoff: Duration::from_millis(200),
+///     ..FetchPolicy::default()
+/// };
+/// assert!(!policy.fail_open);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchPolicy {
+    /// The longest to wait for a connection to the server. Defaults to 5 seconds.
+    pub connect_timeout: Duration,
+    /// The longest a single attempt may take, from connecting to reading the whole body.
+    /// Defaults to 30 seconds.
+    pub total_timeout: Duration,
+    /// How many times a failed attempt is retried. Defaults to 0.
+    pub retries: u32,
+    /// The delay before the first retry, which doubles before each retry after it.
+    /// Defaults to 500 milliseconds.
+    pub backoff: Duration,
+    /// Whether a fetch that fails for good is logged as a warning and otherwise ignored,
+    /// leaving the configuration as it was, rather than returned as an error. Defaults to
+    /// `false`.
+    pub fail_open: bool,
+}
+
+impl Default for FetchPolicy {
+    fn default() -> Self {
+        FetchPolicy {
+            connect_timeout: Duration::from_secs(5),
+            total_timeout: Duration::from_secs(30),
+            retries: 0,
+            backoff: Duration::from_millis(500),
+            fail_open: false,
+        }
+    }
+}
+
+/// Fetches a configuration document with an HTTP GET request and deep-merges it into the
+/// store, as [`load_file`](crate::config::load_file) does for a file.
+///
+/// Unless [`RemoteOptions::format`] names the format, it is taken from the `Content-Type`
+/// of the response: `application/json` and other `+json` types are JSON,
+/// `application/toml` is TOML, and `application/yaml`, `application/x-yaml` and
+/// `text/yaml` are YAML. Parse errors name the URL.
+///
+/// Each attempt is bounded by the timeouts of [`RemoteOptions::policy`], and retried as
+/// it describes. If more than one attempt was made, the last failure is reported as
+/// [`ConfigError::FetchFailed`], which lists the failure of every attempt; a single
+/// attempt reports its own failure. With [`FetchPolicy::fail_open`], failures are logged
+/// instead and the call succeeds, leaving the configuration as it was.
+///
+/// Fails with [`ConfigError::Network`] if the server cannot be reached, the request times
+/// out or the response cannot be read, [`ConfigError::HttpStatus`] if it answers with an
+/// error status, [`ConfigError::UnsupportedContentType`] if the format cannot be told from
+/// the content type, [`ConfigError::LimitExceeded`] if the body exceeds the default
+/// [`Limits::max_size`](crate::config::Limits::max_size), and otherwise as the loader for
+/// the format does. Nothing is merged on failure.
+pub fn load_url(url: &str, options: &RemoteOptions) -> Result<(), ConfigError> {
+    RemoteSource::new(url, options.clone()).load().map(drop)
+}
+
+/// The validators of the last document a [`RemoteSource`] loaded, which it sends with its
+/// next request so that the server can answer that the document has not changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validators {
+    /// The `ETag` of the document, sent back as `If-None-Match`.
+    pub etag: Option<String>,
+    /// The `Last-Modified` date of the document, sent back as `If-Modified-Since`.
+    pub last_modified: Option<String>,
+}
+
+/// A remote configuration document that is loaded again and again, such as by polling,
+/// without downloading or merging it again while it is unchanged.
+///
+/// Each [`load`](RemoteSource::load) fetches the document as [`load_url`] does, but
+/// remembers the `ETag` and `Last-Modified` headers of the response and sends them as
+/// `If-None-Match` and `If-Modified-Since` with the next request. A server that answers
+/// `304 Not Modified` leaves the store, and its version, untouched.
+///
+/// A `RemoteSource` is also a [`ConfigSource`], whose [`load`](ConfigSource::load) returns
+/// the document for [`reload_config`](crate::config::reload_config) to layer rather than
+/// merging it, and returns the last document it fetched when the server answers that it
+/// has not changed. With [`FetchPolicy::fail_open`], that last document, or an empty one,
+/// is also returned when the fetch fails.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use zirv_config::config::{RemoteOptions, RemoteSource};
+///
+/// let source = RemoteSource::new("https://config.internal/billing.json", RemoteOptions::default());
+/// source.load().unwrap();
+/// // Later, only merges if the document changed in the meantime.
+/// if !source.load().unwrap() {
+///     println!("unchanged since {:?}", source.validators().last_modified);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct RemoteSource {
+    url: String,
+    options: RemoteOptions,
+    agent: ureq::Agent,
+    last: Mutex<Last>,
+}
+
+/// The last document a [`RemoteSource`] fetched, with its validators.
+#[derive(Debug, Default)]
+struct Last {
+    validators: Validators,
+    document: Option<Value>,
+}
+
+/// The outcome of a successful fetch.
+enum Fetched {
+    NotModified,
+    Changed(Value, Validators),
+}
+
+impl RemoteSource {
+    /// A source for the document at `url`, fetched with `options`.
+    pub fn new(url: impl Into<String>, options: RemoteOptions) -> RemoteSource {
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(options.policy.connect_timeout)
+            .timeout(options.policy.total_timeout)
+            .build();
+        RemoteSource {
+            url: url.into(),
+            options,
+            agent,
+            last: Mutex::new(Last::default()),
+        }
+    }
+
+    /// The URL of the document.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The validators that the next request will send, from the last document loaded.
+    pub fn validators(&self) -> Validators {
+        self.last().validators.clone()
+    }
+
+    fn last(&self) -> MutexGuard<'_, Last> {
+        self.last.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn remember(&self, document: Value, validators: Validators) {
+        *self.last() = Last {
+            validators,
+            document: Some(document),
+        };
+    }
+
+    /// Fetches the document and deep-merges it into the store if it changed since the
+    /// last load, telling whether it did.
+    ///
+    /// Returns `false` without merging if the server answers `304 Not Modified`, or if the
+    /// fetch failed under [`FetchPolicy::fail_open`]. Otherwise fails as [`load_url`] does,
+    /// keeping the validators of the last document that loaded.
+    pub fn load(&self) -> Result<bool, ConfigError> {
+        let result = self.fetch_retrying().and_then(|fetched| match fetched {
+            Fetched::NotModified => Ok(false),
+            Fetched::Changed(document, validators) => {
+                merge_document(document.clone(), None)?;
+                self.remember(document, validators);
+                Ok(true)
+            }
+        });
+        match result {
+            Err(err) if self.options.policy.fail_open => {
+                log::warn!("{err}; keeping the current configuration");
+                Ok(false)
+            }
+            result => result,
+        }
+    }
+
+    /// Fetches the document, retrying as [`RemoteOptions::policy`] describes.
+    fn fetch_retrying(&self) -> Result<Fetched, ConfigError> {
+        let policy = &self.options.policy;
+        let mut failures = Vec::new();
+        let mut backoff = policy.backoff;
+        loop {
+            match self.fetch() {
+                Ok(fetched) => return Ok(fetched),
+                Err(err) => {
+                    let retry = is_transient(&err) && failures.len() < policy.retries as usize;
+                    failures.push(err);
+                    if !retry {
+                        return Err(if failures.len() == 1 {
+                            failures.remove(0)
+                        } else {
+                            ConfigError::FetchFailed {
+                                url: self.url.clone(),
+                                attempts: failures,
+                            }
+                        });
+                    }
+                    log::debug!(
+                        "fetching {} failed, retrying in {backoff:?}: {}",
+                        self.url,
+                        failures[failures.len() - 1]
+                    );
+                    thread::sleep(backoff);
+                    backoff = backoff.saturating_mul(2);
+                }
+            }
+        }
+    }
+
+    /// Makes a single attempt at fetching and parsing the document.
+    fn fetch(&self) -> Result<Fetched, ConfigError> {
+        let url = self.url.as_str();
+        let options = &self.options;
+        let mut request = self.agent.get(url);
+        if let Some(token) = &options.bearer_token {
+            request = request.set("Authorization", &format!("Bearer {token}"));
+        }
+        for (name, value) in &options.headers {
+            request = request.set(name, value);
+        }
+        let validators = self.validators();
+        if let Some(etag) = &validators.etag {
+            request = request.set("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.set("If-Modified-Since", last_modified);
+        }
+        let response = request.call().map_err(|err| match err {
+            ureq::Error::Status(status, _) => ConfigError::HttpStatus {
+                url: url.to_string(),
+                status,
+            },
+            ureq::Error::Transport(transport) => ConfigError::Network {
+                url: url.to_string(),
+                message: transport.to_string(),
+            },
+        })?;
+        if response.status() == 304 {
+            log::debug!("{url} has not changed");
+            return Ok(Fetched::NotModified);
+        }
+
+        let format = match options.format {
+            Some(format) => format,
+            None => {
+                let content_type = response.header("Content-Type");
+                content_type
+                    .and_then(format_of_content_type)
+                    .ok_or_else(|| ConfigError::UnsupportedContentType {
+                        url: url.to_string(),
+                        content_type: content_type.map(str::to_string),
+                    })?
+            }
+        };
+        let fetched = Validators {
+            etag: response.header("ETag").map(str::to_string),
+            last_modified: response.header("Last-Modified").map(str::to_string),
+        };
+        let text = read_limited(response.into_reader(), |source| ConfigError::Network {
+            url: url.to_string(),
+            message: source.to_string(),
+        })?;
+        Ok(Fetched::Changed(format.parse(url, &text)?, fetched))
+    }
+}
+
+impl ConfigSource for RemoteSource {
+    fn name(&self) -> &str {
+        &self.url
+    }
+
+    fn load(&self) -> Result<Value, ConfigError> {
+        let fetched = match self.fetch_retrying() {
+            Ok(fetched) => fetched,
+            Err(err) if self.options.policy.fail_open => {
+                log::warn!("{err}; keeping the last document fetched");
+                Fetched::NotModified
+            }
+            Err(err) => return Err(err),
+        };
+        match fetched {
+            Fetched::NotModified => Ok(self
+                .last()
+                .document
+                .clone()
+                .unwrap_or_else(|| Value::Object(Map::new()))),
+            Fetched::Changed(document, validators) => {
+                self.remember(document.clone(), validators);
+                Ok(document)
+            }
+        }
+    }
+}
+
+/// Tells whether a failed attempt may succeed if it is retried.
+fn is_transient(err: &ConfigError) -> bool {
+    match err {
+        ConfigError::Network { .. } => true,
+        ConfigError::HttpStatus { status, .. } => matches!(status, 408 | 429 | 500..=599),
+        _ => false,
+    }
+}
+
+/// Returns the format that a `Content-Type` header names, ignoring its parameters.
+fn format_of_content_type(content_type: &str) -> Option<Format> {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    match essence.as_str() {
+        "application/json" => Some(Format::Json),
+        "application/toml" => Some(Format::Toml),
+        "application/yaml" | "application/x-yaml" | "text/yaml" => Some(Format::Yaml),
+        essence if essence.starts_with("application/") && essence.ends_with("+json") => {
+            Some(Format::Json)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_of_content_type() {
+        let cases = [
+            ("application/json", Some(Format::Json)),
+            ("Application/JSON; charset=utf-8", Some(Format::Json)),
+            ("application/vnd.config+json", Some(Format::Json)),
+            ("application/toml", Some(Format::Toml)),
+            ("application/x-yaml", Some(Format::Yaml)),
+            ("text/yaml; charset=utf-8", Some(Format::Yaml)),
+            ("text/html", None),
+            ("text/plain", None),
+        ];
+        for (content_type, format) in cases {
+            assert_eq!(
+                format_of_content_type(content_type),
+                format,
+                "{content_type}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_debug_redacts_credentials() {
+        let options = RemoteOptions {
+            bearer_token: Some("s3cr3t-token".to_string()),
+            headers: vec![
+                (
+                    "Authorization".to_string(),
+                    "Basic dXNlcjpwYXNz".to_string(),
+                ),
+                ("X-Api-Key".to_string(), "k3y".to_string()),
+            ],
+            ..RemoteOptions::default()
+        };
+        let source = RemoteSource::new("https://config.internal/app.json", options.clone());
+        for debug in [format!("{options:?}"), format!("{source:?}")] {
+            for secret in ["s3cr3t-token", "dXNlcjpwYXNz", "k3y"] {
+                assert!(!debug.contains(secret), "{debug}");
+            }
+            assert!(debug.contains("bearer_token: Some(<redacted>)"), "{debug}");
+            assert!(debug.contains("(\"X-Api-Key\", <redacted>)"), "{debug}");
+        }
+        assert!(format!("{:?}", RemoteOptions::default()).contains("bearer_token: None"));
+    }
+}
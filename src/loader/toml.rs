@@ -0,0 +1,197 @@
+//! The TOML loader.
+//!
+//! TOML maps onto JSON almost directly: tables become objects, arrays become arrays, and
+//! strings and booleans are carried over as they are. The remaining types are converted
+//! as follows:
+//!
+//! - Integers stay integers and floats stay floats, so `1.0` is stored as a float and
+//!   cannot be read back as a `u16`. `nan` and `inf` have no JSON representation and are
+//!   rejected.
+//! - Datetimes become strings. Offset datetimes are written in RFC 3339, such as
+//!   `"1979-05-27T07:32:00Z"`; local datetimes, dates and times keep the same layout
+//!   without the parts they lack, such as `"1979-05-27T07:32:00"`, `"1979-05-27"` and
+//!   `"07:32:00"`. Seconds are always written, even where TOML allows them to be omitted.
+
+use std::path::Path;
+
+use ::toml::value::{Datetime, Table, Value as TomlValue};
+use serde_json::{Map, Number, Value};
+
+use super::{child_path, line_column, merge_document, parse_error, read_file};
+use crate::config::ConfigError;
+use crate::key::escape_segment;
+
+/// Parses a TOML document read from `origin` into a JSON object.
+pub(super) fn parse(origin: &str, text: &str) -> Result<Value, ConfigError> {
+    let table: Table = ::toml::from_str(text).map_err(|err| {
+        let position = err.span().map(|span| line_column(text, span.start));
+        parse_error(origin, "TOML", position, err.message().trim_end())
+    })?;
+    convert_table(origin, "", table)
+}
+
+fn convert_table(origin: &str, path: &str, table: Table) -> Result<Value, ConfigError> {
+    let mut map = Map::with_capacity(table.len());
+    for (name, value) in table {
+        let child = child_path(path, &escape_segment(&name));
+        map.insert(name, convert(origin, &child, value)?);
+    }
+    Ok(Value::Object(map))
+}
+
+fn convert(origin: &str, path: &str, value: TomlValue) -> Result<Value, ConfigError> {
+    Ok(match value {
+        TomlValue::String(text) => Value::String(text),
+        TomlValue::Integer(number) => Value::from(number),
+        TomlValue::Float(number) => match Number::from_f64(number) {
+            Some(number) => Value::Number(number),
+            None => {
+                return Err(parse_error(
+                    origin,
+                    "TOML",
+                    None,
+                    format!("\"{path}\" is {number}, which cannot be represented in JSON"),
+                ));
+            }
+        },
+        TomlValue::Boolean(flag) => Value::Bool(flag),
+        TomlValue::Datetime(datetime) => Value::String(format_datetime(datetime)),
+        TomlValue::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| convert(origin, &child_path(path, &index.to_string()), item))
+                .collect::<Result<_, _>>()?,
+        ),
+        TomlValue::Table(table) => convert_table(origin, path, table)?,
+    })
+}
+
+/// Writes `datetime` as RFC 3339, or the part of it that is present, always with seconds.
+fn format_datetime(mut datetime: Datetime) -> String {
+    if let Some(time) = &mut datetime.time {
+        time.second.get_or_insert(0);
+    }
+    datetime.to_string()
+}
+
+/// Loads a TOML file and deep-merges its top-level tables into the store, each acting as
+/// a namespace, as [`load_json_file`](crate::config::load_json_file) does for JSON.
+///
+/// See the [conversion rules](self) for how TOML types are stored. Fails with
+/// [`ConfigError::Io`] if the file cannot be read, [`ConfigError::Parse`], naming the
+/// file, line and column, if it is not valid TOML or holds a float JSON cannot represent,
+/// and [`ConfigError::LimitExceeded`] if it exceeds the default
+/// [`Limits`](crate::config::Limits). Nothing is merged on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("config.toml");
+/// std::fs::write(&path, "[server]\nport = 8080\nstarted = 1979-05-27T07:32:00Z\n").unwrap();
+///
+/// config::load_toml_file(&path).unwrap();
+/// assert_eq!(config::get_config_by_key("server.port"), Some(serde_json::json!(8080)));
+/// assert_eq!(
+///     config::get_config_by_key("server.started"),
+///     Some(serde_json::json!("1979-05-27T07:32:00Z"))
+/// );
+/// ```
+pub fn load_toml_file(path: impl AsRef<Path>) -> Result<(), ConfigError> {
+    let path = path.as_ref();
+    let document = parse(&path.display().to_string(), &read_file(path)?)?;
+    merge_document(document, None)
+}
+
+/// Parses a TOML document and deep-merges its top-level tables into the store, as
+/// [`load_toml_file`] does.
+///
+/// Parse errors name the origin `<string>`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::load_toml_str("[cache]\nttl = 60\nratio = 0.5\n").unwrap();
+/// assert_eq!(config::get_config_by_key("cache.ttl"), Some(serde_json::json!(60)));
+/// assert_eq!(config::get_config_by_key("cache.ratio"), Some(serde_json::json!(0.5)));
+/// ```
+pub fn load_toml_str(text: &str) -> Result<(), ConfigError> {
+    merge_document(parse("<string>", text)?, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_scalars_keep_their_type() {
+        let value = parse(
+            "test",
+            "int = 1\nfloat = 1.0\nneg = -7\nexp = 5e+22\nhex = 0xff\nflag = true\nlist = [1, 2.5, \"x\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            json!({ "int": 1, "float": 1.0, "neg": -7, "exp": 5e22, "hex": 255, "flag": true, "list": [1, 2.5, "x"] })
+        );
+        assert!(value["int"].is_i64());
+        assert!(value["float"].is_f64());
+    }
+
+    #[test]
+    fn test_datetimes_become_strings() {
+        let value = parse(
+            "test",
+            "odt = 1979-05-27T07:32:00Z\n\
+             offset = 1979-05-27T00:32:00.999999-07:00\n\
+             space = 1979-05-27 07:32:00Z\n\
+             ldt = 1979-05-27T07:32:00\n\
+             date = 1979-05-27\n\
+             time = 07:32:00.5\n\
+             short = 07:32\n",
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "odt": "1979-05-27T07:32:00Z",
+                "offset": "1979-05-27T00:32:00.999999-07:00",
+                "space": "1979-05-27T07:32:00Z",
+                "ldt": "1979-05-27T07:32:00",
+                "date": "1979-05-27",
+                "time": "07:32:00.5",
+                "short": "07:32:00",
+            })
+        );
+    }
+
+    #[test]
+    fn test_non_finite_floats_are_rejected() {
+        let err = parse("test", "[limits]\nmax = [1.0, inf]\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid TOML in test: \"limits.max.1\" is inf, which cannot be represented in JSON"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_report_the_position() {
+        let err = parse("config.toml", "[server]\nport = 80\nport = 81\n").unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ConfigError::Parse {
+                    format: "TOML",
+                    line: Some(3),
+                    column: Some(1),
+                    ..
+                }
+            ),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,592 @@
+//! The YAML loader.
+//!
+//! Documents are read under the YAML 1.2 core schema, so plain scalars are resolved as
+//! follows and quoted scalars are always strings:
+//!
+//! - `~`, `null`, `Null`, `NULL` and an empty value become `null`.
+//! - `true` and `false`, also capitalized or in upper case, become booleans. YAML 1.1
+//!   spellings such as `yes`, `on` and `n` stay strings.
+//! - Decimal integers and `0x`/`0o` integers become integers, and decimal floats such as
+//!   `1.5` or `6.02e23` become floats. `.inf` and `.nan` have no JSON representation and
+//!   are rejected.
+//! - Anything else, including `1_000` and `2024-03-01`, stays a string.
+//!
+//! The core tags `!!str`, `!!int`, `!!float`, `!!bool` and `!!null` force a type; other
+//! tags are ignored. Mapping keys that are not strings are stringified as their JSON
+//! form, so `1: a` and `true: b` become the keys `"1"` and `"true"`. Keys that are
+//! sequences or mappings are rejected, as is a mapping that repeats a key, including one
+//! that only repeats it once stringified, such as `1` and `"1"`. Aliases are expanded,
+//! and `<<` is an ordinary key, as YAML 1.2 defines no merge keys.
+//!
+//! A file holds a single document, except for [`load_yaml_profiles`], which reads a
+//! document per profile.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde_json::{Map, Number, Value};
+use yaml_rust2::parser::{Event, Parser, Tag};
+use yaml_rust2::scanner::{Marker, TScalarStyle};
+
+use super::{merge_document, parse_error, read_file};
+use crate::config::{ConfigError, merge_map};
+
+/// The most nodes aliases may expand to in one document, which keeps a document of
+/// nested aliases from exhausting memory.
+const MAX_ALIAS_NODES: usize = 1_000_000;
+
+/// Parses a YAML document read from `origin`. An empty document is an empty object.
+pub(super) fn parse(origin: &str, text: &str) -> Result<Value, ConfigError> {
+    Ok(parse_stream(origin, text, true)?
+        .pop()
+        .unwrap_or_else(|| Value::Object(Map::new())))
+}
+
+/// Parses the documents of a YAML stream read from `origin`, failing at the second one if
+/// `single` is set. An empty document, such as one of only `---`, is `null`.
+fn parse_stream(origin: &str, text: &str, single: bool) -> Result<Vec<Value>, ConfigError> {
+    let error = |mark: Marker, message: String| {
+        parse_error(origin, "YAML", Some((mark.line(), mark.col() + 1)), message)
+    };
+    let mut parser = Parser::new_from_str(text);
+    let mut builder = Builder {
+        single,
+        ..Builder::default()
+    };
+    loop {
+        let (event, mark) = parser
+            .next_token()
+            .map_err(|err| error(*err.marker(), err.info().to_string()))?;
+        if event == Event::StreamEnd {
+            break;
+        }
+        builder
+            .on_event(event)
+            .map_err(|message| error(mark, message))?;
+    }
+    Ok(builder.documents)
+}
+
+/// Builds JSON values from parser events, one per document.
+#[derive(Default)]
+struct Builder {
+    single: bool,
+    stack: Vec<Frame>,
+    /// Anchored values of the current document, with the number of nodes in each.
+    anchors: HashMap<usize, (Value, usize)>,
+    alias_nodes: usize,
+    root: Option<Value>,
+    documents: Vec<Value>,
+}
+
+/// A collection being built, with the anchor it was given (0 for none) and the number of
+/// nodes it holds so far.
+enum Frame {
+    Sequence {
+        anchor: usize,
+        nodes: usize,
+        items: Vec<Value>,
+    },
+    Mapping {
+        anchor: usize,
+        nodes: usize,
+        map: Map<String, Value>,
+        /// The key awaiting its value.
+        key: Option<String>,
+    },
+}
+
+impl Builder {
+    fn on_event(&mut self, event: Event) -> Result<(), String> {
+        match event {
+            Event::DocumentStart if self.single && !self.documents.is_empty() => {
+                return Err("expected a single document, found another".to_string());
+            }
+            Event::DocumentEnd => {
+                let root = self.root.take();
+                self.documents.push(root.unwrap_or(Value::Null));
+                self.anchors.clear();
+                self.alias_nodes = 0;
+            }
+            Event::Scalar(text, style, anchor, tag) => {
+                let value = resolve_scalar(text, style, tag.as_ref())?;
+                self.complete(value, anchor, 1)?;
+            }
+            Event::Alias(anchor) => {
+                let (value, nodes) = self
+                    .anchors
+                    .get(&anchor)
+                    .ok_or("alias refers to an unknown anchor")?;
+                self.alias_nodes += nodes;
+                if self.alias_nodes > MAX_ALIAS_NODES {
+                    return Err(format!(
+                        "aliases expand to more than {MAX_ALIAS_NODES} nodes"
+                    ));
+                }
+                let (value, nodes) = (value.clone(), *nodes);
+                self.complete(value, 0, nodes)?;
+            }
+            Event::SequenceStart(anchor, _) => {
+                self.reject_collection_key("a sequence")?;
+                self.stack.push(Frame::Sequence {
+                    anchor,
+                    nodes: 1,
+                    items: Vec::new(),
+                });
+            }
+            Event::MappingStart(anchor, _) => {
+                self.reject_collection_key("a mapping")?;
+                self.stack.push(Frame::Mapping {
+                    anchor,
+                    nodes: 1,
+                    map: Map::new(),
+                    key: None,
+                });
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                let (value, anchor, nodes) = match self.stack.pop() {
+                    Some(Frame::Sequence {
+                        anchor,
+                        nodes,
+                        items,
+                    }) => (Value::Array(items), anchor, nodes),
+                    Some(Frame::Mapping {
+                        anchor, nodes, map, ..
+                    }) => (Value::Object(map), anchor, nodes),
+                    None => unreachable!("the parser emits balanced collection events"),
+                };
+                self.complete(value, anchor, nodes)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn reject_collection_key(&self, found: &str) -> Result<(), String> {
+        match self.stack.last() {
+            Some(Frame::Mapping { key: None, .. }) => {
+                Err(format!("mapping keys must be scalars, found {found}"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Places a finished value of `count` nodes into the collection being built.
+    fn complete(&mut self, value: Value, anchor: usize, count: usize) -> Result<(), String> {
+        if anchor > 0 {
+            self.anchors.insert(anchor, (value.clone(), count));
+        }
+        match self.stack.last_mut() {
+            None => self.root = Some(value),
+            Some(Frame::Sequence { nodes, items, .. }) => {
+                *nodes += count;
+                items.push(value);
+            }
+            Some(Frame::Mapping {
+                nodes, map, key, ..
+            }) => match key.take() {
+                Some(key) => {
+                    *nodes += count;
+                    map.insert(key, value);
+                }
+                None => {
+                    let name = key_name(value)?;
+                    if map.contains_key(&name) {
+                        return Err(format!("duplicate key \"{name}\""));
+                    }
+                    *key = Some(name);
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+fn resolve_scalar(text: String, style: TScalarStyle, tag: Option<&Tag>) -> Result<Value, String> {
+    let core_tag = tag
+        .filter(|tag| tag.handle == "tag:yaml.org,2002:")
+        .map(|tag| tag.suffix.as_str());
+    let expected = match core_tag {
+        Some("str") => return Ok(Value::String(text)),
+        Some(kind @ ("null" | "bool" | "int" | "float")) => kind,
+        _ if style != TScalarStyle::Plain => return Ok(Value::String(text)),
+        _ => return resolve_plain(&text),
+    };
+    let value = resolve_plain(&text)?;
+    match (expected, &value) {
+        ("null", Value::Null) | ("bool", Value::Bool(_)) => Ok(value),
+        ("int", Value::Number(number)) if !number.is_f64() => Ok(value),
+        ("float", Value::Number(number)) => Ok(number.as_f64().map_or(value, Value::from)),
+        _ => Err(format!("{text:?} is not a valid !!{expected}")),
+    }
+}
+
+/// Resolves a plain scalar under the YAML 1.2 core schema.
+fn resolve_plain(text: &str) -> Result<Value, String> {
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return Ok(Value::Null),
+        "true" | "True" | "TRUE" => return Ok(Value::Bool(true)),
+        "false" | "False" | "FALSE" => return Ok(Value::Bool(false)),
+        ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" | "-.inf" | "-.Inf" | "-.INF"
+        | ".nan" | ".NaN" | ".NAN" => {
+            return Err(format!("{text} cannot be represented in JSON"));
+        }
+        _ => {}
+    }
+    let radix = |prefix, radix| {
+        let digits = text.strip_prefix(prefix)?;
+        u64::from_str_radix(digits, radix)
+            .ok()
+            .filter(|_| !digits.starts_with('+'))
+    };
+    if let Some(number) = radix("0x", 16).or_else(|| radix("0o", 8)) {
+        return Ok(Value::from(number));
+    }
+    let unsigned = text.strip_prefix(['-', '+']).unwrap_or(text);
+    if !unsigned.is_empty() && unsigned.bytes().all(|byte| byte.is_ascii_digit()) {
+        if let Ok(number) = text.parse::<i64>() {
+            return Ok(Value::from(number));
+        }
+        if let Some(number) = unsigned
+            .parse::<u64>()
+            .ok()
+            .filter(|_| !text.starts_with('-'))
+        {
+            return Ok(Value::from(number));
+        }
+    }
+    if !is_core_float(unsigned) {
+        return Ok(Value::String(text.to_string()));
+    }
+    text.parse::<f64>()
+        .ok()
+        .and_then(Number::from_f64)
+        .map(Value::Number)
+        .ok_or_else(|| format!("{text} cannot be represented in JSON"))
+}
+
+/// Tells whether `text`, without its sign, matches the core schema's float pattern,
+/// `( \. [0-9]+ | [0-9]+ ( \. [0-9]* )? ) ( [eE] [-+]? [0-9]+ )?`.
+fn is_core_float(text: &str) -> bool {
+    let digits = |text: &str| text.bytes().take_while(u8::is_ascii_digit).count();
+    let whole = digits(text);
+    let mut rest = &text[whole..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let len = digits(fraction);
+        if whole == 0 && len == 0 {
+            return false;
+        }
+        rest = &fraction[len..];
+    } else if whole == 0 {
+        return false;
+    }
+    match rest.strip_prefix(['e', 'E']) {
+        None => rest.is_empty(),
+        Some(exponent) => {
+            let exponent = exponent.strip_prefix(['-', '+']).unwrap_or(exponent);
+            !exponent.is_empty() && digits(exponent) == exponent.len()
+        }
+    }
+}
+
+/// Stringifies a scalar mapping key as its JSON form.
+fn key_name(key: Value) -> Result<String, String> {
+    match key {
+        Value::String(name) => Ok(name),
+        Value::Array(_) => Err("mapping keys must be scalars, found a sequence".to_string()),
+        Value::Object(_) => Err("mapping keys must be scalars, found a mapping".to_string()),
+        scalar => Ok(scalar.to_string()),
+    }
+}
+
+/// Loads a YAML file and deep-merges its top-level keys into the store, each acting as a
+/// namespace, as [`load_json_file`](crate::config::load_json_file) does for JSON.
+///
+/// See the [conversion rules](self) for how YAML scalars and keys are stored. The file
+/// must hold a single document. Fails with [`ConfigError::Io`] if the file cannot be
+/// read, [`ConfigError::Parse`], naming the file, line and column, if it is not valid
+/// YAML or breaks one of those rules, [`ConfigError::NotAnObject`] if its top level is
+/// not a mapping, and [`ConfigError::LimitExceeded`] if it exceeds the default
+/// [`Limits`](crate::config::Limits). Nothing is merged on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("config.yaml");
+/// std::fs::write(&path, "server:\n  port: 8080\n  debug: yes\n").unwrap();
+///
+/// config::load_yaml_file(&path).unwrap();
+/// assert_eq!(config::get_config_by_key("server.port"), Some(serde_json::json!(8080)));
+/// // `yes` is a string in YAML 1.2.
+/// assert_eq!(config::get_config_by_key("server.debug"), Some(serde_json::json!("yes")));
+/// ```
+pub fn load_yaml_file(path: impl AsRef<Path>) -> Result<(), ConfigError> {
+    let path = path.as_ref();
+    let document = parse(&path.display().to_string(), &read_file(path)?)?;
+    merge_document(document, None)
+}
+
+/// Parses a YAML document and deep-merges its top-level keys into the store, as
+/// [`load_yaml_file`] does.
+///
+/// Parse errors name the origin `<string>`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::load_yaml_str("cache:\n  ttl: 60\n  backend: ~\n").unwrap();
+/// assert_eq!(config::get_config_by_key("cache.ttl"), Some(serde_json::json!(60)));
+/// assert_eq!(config::get_config_by_key("cache.backend"), Some(serde_json::Value::Null));
+/// ```
+pub fn load_yaml_str(text: &str) -> Result<(), ConfigError> {
+    merge_document(parse("<string>", text)?, None)
+}
+
+/// Loads a YAML file of several documents, Spring Boot style, and deep-merges the
+/// documents that apply to `active_profile` into the store.
+///
+/// Documents without a `profile` key are base documents, and are always applied. A
+/// document whose `profile` names `active_profile`, either as a string or in a list of
+/// strings, is applied after every base document; documents for other profiles are
+/// ignored. Each kind is applied in file order, and the `profile` key itself is not
+/// stored. Empty documents are skipped.
+///
+/// Fails as [`load_yaml_file`] does, and with [`ConfigError::Parse`], naming the
+/// document's 1-based index, if a document is not a mapping or its `profile` is neither a
+/// string nor a list of strings. Nothing is merged on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("config.yaml");
+/// std::fs::write(&path, "\
+/// server:
+///   port: 8080
+///   workers: 2
+/// ---
+/// profile: production
+/// server:
+///   workers: 16
+/// ---
+/// profile: [development, test]
+/// server:
+///   port: 3000
+/// ").unwrap();
+///
+/// config::load_yaml_profiles(&path, "production").unwrap();
+/// assert_eq!(config::get_config_by_key("server"), Some(serde_json::json!({ "port": 8080, "workers": 16 })));
+/// ```
+pub fn load_yaml_profiles(path: impl AsRef<Path>, active_profile: &str) -> Result<(), ConfigError> {
+    let path = path.as_ref();
+    let origin = path.display().to_string();
+    let mut base = Map::new();
+    let mut active = Map::new();
+    for (index, document) in parse_stream(&origin, &read_file(path)?, false)?
+        .into_iter()
+        .enumerate()
+    {
+        let error = |message: &str| {
+            parse_error(
+                &origin,
+                "YAML",
+                None,
+                format!("document {}: {message}", index + 1),
+            )
+        };
+        let mut document = match document {
+            Value::Object(document) => document,
+            Value::Null => continue,
+            _ => return Err(error("expected a mapping at the top level")),
+        };
+        let target = match document.remove("profile") {
+            None => &mut base,
+            Some(profile) => {
+                let profiles = match &profile {
+                    Value::String(name) => vec![name.as_str()],
+                    Value::Array(names) => names
+                        .iter()
+                        .map(Value::as_str)
+                        .collect::<Option<_>>()
+                        .ok_or_else(|| {
+                        error("`profile` must be a string or a list of strings")
+                    })?,
+                    _ => return Err(error("`profile` must be a string or a list of strings")),
+                };
+                if !profiles.contains(&active_profile) {
+                    continue;
+                }
+                &mut active
+            }
+        };
+        merge_map(target, document);
+    }
+    merge_map(&mut base, active);
+    merge_document(Value::Object(base), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse_str(text: &str) -> Result<Value, ConfigError> {
+        parse("test", text)
+    }
+
+    #[test]
+    fn test_nested_document() {
+        let value = parse_str(
+            "server:\n  host: 0.0.0.0\n  ports: [80, 443]\n  tls:\n    enabled: true\n\
+             jobs:\n  - name: cleanup\n    retries: 3\n  - name: report\n",
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "server": { "host": "0.0.0.0", "ports": [80, 443], "tls": { "enabled": true } },
+                "jobs": [{ "name": "cleanup", "retries": 3 }, { "name": "report" }],
+            })
+        );
+        assert_eq!(parse_str("").unwrap(), json!({}));
+        assert_eq!(parse_str("# only a comment\n").unwrap(), json!({}));
+    }
+
+    #[test]
+    fn test_scalars_follow_yaml_1_2() {
+        let value = parse_str(
+            "tilde: ~\nnull: null\nupper: NULL\nempty:\nyes: yes\noff: off\nbool: True\n\
+             int: -42\nplus: +7\nhex: 0x1F\noctal: 0o17\nleading: 012\nbig: 18446744073709551615\n\
+             float: 1.5\nexp: 6.02e23\ndot: .5\nint_float: 1.0\nnumeric_string: \"8080\"\n\
+             underscored: 1_000\ndate: 2024-03-01\nversion: 1.2.3\n",
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "tilde": null, "null": null, "upper": null, "empty": null,
+                "yes": "yes", "off": "off", "bool": true,
+                "int": -42, "plus": 7, "hex": 31, "octal": 15, "leading": 12,
+                "big": 18446744073709551615u64,
+                "float": 1.5, "exp": 6.02e23, "dot": 0.5, "int_float": 1.0,
+                "numeric_string": "8080", "underscored": "1_000", "date": "2024-03-01",
+                "version": "1.2.3",
+            })
+        );
+        assert!(value["int_float"].is_f64());
+
+        let tagged = parse_str("port: !!str 8080\nratio: !!float 1\ncount: !!int \"3\"\n").unwrap();
+        assert_eq!(tagged, json!({ "port": "8080", "ratio": 1.0, "count": 3 }));
+        let err = parse_str("count: !!int three\n").unwrap_err();
+        assert!(
+            err.to_string()
+                .ends_with(": \"three\" is not a valid !!int"),
+            "{err}"
+        );
+
+        let err = parse_str("limits:\n  max: .inf\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid YAML in test at line 2, column 8: .inf cannot be represented in JSON"
+        );
+    }
+
+    #[test]
+    fn test_non_string_keys() {
+        let value =
+            parse_str("codes:\n  404: not found\n  true: yes\n  ~: none\n  1.5: ratio\n").unwrap();
+        assert_eq!(
+            value,
+            json!({ "codes": { "404": "not found", "true": "yes", "null": "none", "1.5": "ratio" } })
+        );
+
+        let err = parse_str("routes:\n  ? [a, b]\n  : both\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid YAML in test at line 2, column 5: mapping keys must be scalars, found a sequence"
+        );
+        let err = parse_str("base: &base { a: 1 }\nother:\n  *base : 2\n").unwrap_err();
+        assert!(
+            err.to_string()
+                .ends_with("mapping keys must be scalars, found a mapping"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_keys_are_rejected() {
+        let err = parse_str("server:\n  port: 80\n  host: localhost\n  port: 81\n").unwrap_err();
+        match &err {
+            ConfigError::Parse {
+                line,
+                column,
+                message,
+                ..
+            } => {
+                assert_eq!((*line, *column), (Some(4), Some(3)));
+                assert_eq!(message, "duplicate key \"port\"");
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+        // Keys that only collide once stringified are duplicates too.
+        let err = parse_str("codes:\n  1: one\n  \"1\": also one\n").unwrap_err();
+        assert!(err.to_string().ends_with("duplicate key \"1\""), "{err}");
+        // The same key in different mappings is fine.
+        assert!(parse_str("a:\n  port: 1\nb:\n  port: 2\n").is_ok());
+    }
+
+    #[test]
+    fn test_aliases_are_expanded() {
+        let value =
+            parse_str("defaults: &defaults\n  retries: 3\njob:\n  settings: *defaults\n").unwrap();
+        assert_eq!(value["job"]["settings"], json!({ "retries": 3 }));
+
+        let mut bomb = String::from("a: &a [x, x, x, x, x, x, x, x, x, x]\n");
+        for (name, previous) in ["b", "c", "d", "e", "f", "g"]
+            .iter()
+            .zip(["a", "b", "c", "d", "e", "f"])
+        {
+            bomb.push_str(&format!("{name}: &{name} [*{previous}, *{previous}, *{previous}, *{previous}, *{previous}, *{previous}, *{previous}, *{previous}, *{previous}, *{previous}]\n"));
+        }
+        let err = parse_str(&bomb).unwrap_err();
+        assert!(
+            err.to_string()
+                .ends_with("aliases expand to more than 1000000 nodes"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_streams_of_documents() {
+        let documents = parse_stream("test", "a: &x 1\n---\n---\nb: 2\n...\n", false).unwrap();
+        assert_eq!(
+            documents,
+            [json!({ "a": 1 }), Value::Null, json!({ "b": 2 })]
+        );
+
+        // Anchors do not carry over into the next document.
+        let err = parse_stream("test", "a: &x 1\n---\nb: *x\n", false).unwrap_err();
+        assert!(
+            err.to_string()
+                .ends_with("alias refers to an unknown anchor"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_single_document_only() {
+        let err = parse_str("a: 1\n---\nb: 2\n").unwrap_err();
+        assert!(
+            matches!(err, ConfigError::Parse { line: Some(2), ref message, .. } if message == "expected a single document, found another"),
+            "{err}"
+        );
+        assert!(matches!(
+            parse_str("a: [1\n"),
+            Err(ConfigError::Parse { line: Some(_), .. })
+        ));
+    }
+}
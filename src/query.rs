@@ -0,0 +1,413 @@
+//! Expression queries over the global configuration.
+//!
+//! Supports a small subset of JMESPath, enough to pick values out of the configuration
+//! without pulling the whole tree into application code:
+//!
+//! - Field access: `server.port`, or `"api.example.com".timeout` for keys that are not
+//!   plain identifiers.
+//! - Indexing: `upstreams[0]`, `upstreams[-1]`.
+//! - Projections: `upstreams[*].host` over arrays and `services.*.port` over object values.
+//! - Filters: `upstreams[?weight > `0`].host`, comparing a field of each element against a
+//!   literal with `==`, `!=`, `<`, `<=`, `>` or `>=`. Literals are JSON in backticks,
+//!   `'raw strings'` or bare numbers.
+//!
+//! As in JMESPath, the expression following a projection or filter is applied to each
+//! element, and `null` results are dropped from the output.
+
+use serde_json::{Map, Value};
+
+use crate::config::{ConfigError, get_config_arc};
+
+/// Evaluates a query expression against the global configuration.
+///
+/// Returns [`ConfigError::InvalidQuery`] if the expression cannot be parsed. Expressions
+/// that do not match anything evaluate to `Value::Null`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("lb", json!({
+///     "upstreams": [
+///         { "host": "a", "weight": 0 },
+///         { "host": "b", "weight": 5 }
+///     ]
+/// }));
+///
+/// assert_eq!(config::query("lb.upstreams[*].host").unwrap(), json!(["a", "b"]));
+/// assert_eq!(config::query("lb.upstreams[?weight > `0`].host").unwrap(), json!(["b"]));
+/// ```
+pub fn query(expr: &str) -> Result<Value, ConfigError> {
+    let steps = Parser::new(expr).parse()?;
+    Ok(evaluate_root(&steps, &get_config_arc()))
+}
+
+/// Applies `steps` to the root of the configuration, borrowing from the snapshot so that
+/// only the result is cloned.
+fn evaluate_root(steps: &[Step], root: &Map<String, Value>) -> Value {
+    match steps.split_first() {
+        None => Value::Object(root.clone()),
+        Some((Step::Field(name), rest)) => root
+            .get(name)
+            .map_or(Value::Null, |child| evaluate(rest, child)),
+        Some((Step::ValueProjection, rest)) => project(rest, root.values()),
+        Some((Step::Index(_) | Step::ArrayProjection | Step::Filter(_), _)) => Value::Null,
+    }
+}
+
+/// A single step of a parsed query.
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Field(String),
+    Index(isize),
+    ArrayProjection,
+    ValueProjection,
+    Filter(Comparison),
+}
+
+/// A filter condition comparing a field of each element against a literal.
+#[derive(Debug, Clone, PartialEq)]
+struct Comparison {
+    field: Vec<Step>,
+    op: Op,
+    literal: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+fn evaluate(steps: &[Step], value: &Value) -> Value {
+    let Some((step, rest)) = steps.split_first() else {
+        return value.clone();
+    };
+    match step {
+        Step::Field(name) => match value.get(name) {
+            Some(child) => evaluate(rest, child),
+            None => Value::Null,
+        },
+        Step::Index(index) => match value {
+            Value::Array(items) => {
+                let position = if *index < 0 {
+                    items.len().checked_sub(index.unsigned_abs())
+                } else {
+                    Some(index.unsigned_abs())
+                };
+                match position.and_then(|position| items.get(position)) {
+                    Some(child) => evaluate(rest, child),
+                    None => Value::Null,
+                }
+            }
+            _ => Value::Null,
+        },
+        Step::ArrayProjection => match value {
+            Value::Array(items) => project(rest, items.iter()),
+            _ => Value::Null,
+        },
+        Step::ValueProjection => match value {
+            Value::Object(map) => project(rest, map.values()),
+            _ => Value::Null,
+        },
+        Step::Filter(comparison) => match value {
+            Value::Array(items) => {
+                project(rest, items.iter().filter(|item| comparison.matches(item)))
+            }
+            _ => Value::Null,
+        },
+    }
+}
+
+/// Applies `steps` to each of `items`, dropping `null` results.
+fn project<'a>(steps: &[Step], items: impl Iterator<Item = &'a Value>) -> Value {
+    Value::Array(
+        items
+            .map(|item| evaluate(steps, item))
+            .filter(|result| !result.is_null())
+            .collect(),
+    )
+}
+
+impl Comparison {
+    fn matches(&self, item: &Value) -> bool {
+        let actual = evaluate(&self.field, item);
+        match self.op {
+            Op::Eq => actual == self.literal,
+            Op::Ne => actual != self.literal,
+            op => match (actual.as_f64(), self.literal.as_f64()) {
+                (Some(actual), Some(expected)) => match op {
+                    Op::Lt => actual < expected,
+                    Op::Le => actual <= expected,
+                    Op::Gt => actual > expected,
+                    _ => actual >= expected,
+                },
+                _ => false,
+            },
+        }
+    }
+}
+
+/// A recursive-descent parser for query expressions.
+struct Parser<'a> {
+    expr: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(expr: &'a str) -> Self {
+        Parser { expr, pos: 0 }
+    }
+
+    fn parse(mut self) -> Result<Vec<Step>, ConfigError> {
+        let steps = self.parse_path()?;
+        self.skip_whitespace();
+        if self.pos < self.expr.len() {
+            return Err(self.error("unexpected trailing input"));
+        }
+        if steps.is_empty() {
+            return Err(self.error("empty expression"));
+        }
+        Ok(steps)
+    }
+
+    /// Parses a sequence of field accesses, indices, projections and filters.
+    fn parse_path(&mut self) -> Result<Vec<Step>, ConfigError> {
+        let mut steps = Vec::new();
+        self.skip_whitespace();
+        if self.eat('@') {
+            // The current element; only meaningful inside filters.
+        } else if self.peek().is_some_and(|c| c != '[') {
+            steps.push(self.parse_segment()?);
+        }
+        loop {
+            if self.eat('.') {
+                steps.push(self.parse_segment()?);
+            } else if self.eat('[') {
+                steps.push(self.parse_bracket()?);
+            } else {
+                return Ok(steps);
+            }
+        }
+    }
+
+    /// Parses the part after a `.`, or the first segment of an expression.
+    fn parse_segment(&mut self) -> Result<Step, ConfigError> {
+        if self.eat('*') {
+            return Ok(Step::ValueProjection);
+        }
+        if self.eat('"') {
+            let start = self.pos;
+            let end = self.expr[start..]
+                .find('"')
+                .ok_or_else(|| self.error("unterminated quoted identifier"))?;
+            self.pos = start + end + 1;
+            return Ok(Step::Field(self.expr[start..start + end].to_string()));
+        }
+        let identifier = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+        if identifier.is_empty() {
+            return Err(self.error("expected an identifier"));
+        }
+        Ok(Step::Field(identifier.to_string()))
+    }
+
+    /// Parses the contents of `[...]` after the opening bracket.
+    fn parse_bracket(&mut self) -> Result<Step, ConfigError> {
+        self.skip_whitespace();
+        let step = if self.eat('*') {
+            Step::ArrayProjection
+        } else if self.eat('?') {
+            Step::Filter(self.parse_comparison()?)
+        } else {
+            let number = self.take_while(|c| c == '-' || c.is_ascii_digit());
+            let index = number
+                .parse()
+                .map_err(|_| self.error("expected an index, `*` or `?`"))?;
+            Step::Index(index)
+        };
+        self.skip_whitespace();
+        if !self.eat(']') {
+            return Err(self.error("expected `]`"));
+        }
+        Ok(step)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Comparison, ConfigError> {
+        let field = self.parse_path()?;
+        self.skip_whitespace();
+        let op = if self.eat_str("==") {
+            Op::Eq
+        } else if self.eat_str("!=") {
+            Op::Ne
+        } else if self.eat_str("<=") {
+            Op::Le
+        } else if self.eat_str(">=") {
+            Op::Ge
+        } else if self.eat('<') {
+            Op::Lt
+        } else if self.eat('>') {
+            Op::Gt
+        } else {
+            return Err(self.error("expected a comparison operator"));
+        };
+        self.skip_whitespace();
+        let literal = self.parse_literal()?;
+        Ok(Comparison { field, op, literal })
+    }
+
+    fn parse_literal(&mut self) -> Result<Value, ConfigError> {
+        for (quote, raw) in [('`', false), ('\'', true)] {
+            if self.eat(quote) {
+                let start = self.pos;
+                let end = self.expr[start..]
+                    .find(quote)
+                    .ok_or_else(|| self.error("unterminated literal"))?;
+                self.pos = start + end + 1;
+                let text = &self.expr[start..start + end];
+                if raw {
+                    return Ok(Value::String(text.to_string()));
+                }
+                return serde_json::from_str(text).map_err(|_| self.error("invalid JSON literal"));
+            }
+        }
+        let number = self.take_while(|c| c == '-' || c == '.' || c.is_ascii_digit());
+        serde_json::from_str(number).map_err(|_| self.error("expected a literal"))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.expr[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, expected: &str) -> bool {
+        if self.expr[self.pos..].starts_with(expected) {
+            self.pos += expected.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(&predicate) {
+            self.pos += self.peek().map_or(0, char::len_utf8);
+        }
+        &self.expr[start..self.pos]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn error(&self, message: &str) -> ConfigError {
+        ConfigError::InvalidQuery {
+            query: self.expr.to_string(),
+            position: self.pos,
+            message: message.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::register_config;
+    use serde_json::json;
+
+    #[test]
+    fn test_query_projection() {
+        register_config(
+            "query_lb",
+            json!({
+                "upstreams": [
+                    { "host": "a", "weight": 0 },
+                    { "host": "b", "weight": 5 },
+                    { "host": "c", "weight": 10, "tags": ["primary"] }
+                ],
+                "services": { "auth": { "port": 1 }, "billing": { "port": 2 } }
+            }),
+        );
+
+        assert_eq!(
+            query("query_lb.upstreams[*].host").unwrap(),
+            json!(["a", "b", "c"])
+        );
+        assert_eq!(query("query_lb.upstreams[-1].host").unwrap(), json!("c"));
+        assert_eq!(
+            query("query_lb.upstreams[*].tags[0]").unwrap(),
+            json!(["primary"])
+        );
+        assert_eq!(query("query_lb.services.*.port").unwrap(), json!([1, 2]));
+        assert_eq!(query("query_lb.missing").unwrap(), Value::Null);
+        // The root is an object, so only field and value steps apply to it.
+        assert!(
+            query("*.upstreams[0].host")
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .contains(&json!("a"))
+        );
+        assert_eq!(query("[0]").unwrap(), Value::Null);
+        assert_eq!(query("[*]").unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_query_filter() {
+        register_config(
+            "query_filter",
+            json!({
+                "upstreams": [
+                    { "host": "a", "weight": 0, "zone": "eu" },
+                    { "host": "b", "weight": 5, "zone": "us" },
+                    { "host": "c", "weight": 10, "zone": "eu" }
+                ]
+            }),
+        );
+
+        assert_eq!(
+            query("query_filter.upstreams[?weight > `0`].host").unwrap(),
+            json!(["b", "c"])
+        );
+        assert_eq!(
+            query("query_filter.upstreams[?weight >= 5].host").unwrap(),
+            json!(["b", "c"])
+        );
+        assert_eq!(
+            query("query_filter.upstreams[?weight < 5].host").unwrap(),
+            json!(["a"])
+        );
+        assert_eq!(
+            query("query_filter.upstreams[?zone == 'eu'].host").unwrap(),
+            json!(["a", "c"])
+        );
+        assert_eq!(
+            query("query_filter.upstreams[?zone != 'eu'].weight").unwrap(),
+            json!([5])
+        );
+    }
+
+    #[test]
+    fn test_query_parse_errors() {
+        for expr in ["", "a[", "a[?b]", "a.'b'", "a[?b == `{`]", "a b"] {
+            assert!(
+                matches!(query(expr), Err(ConfigError::InvalidQuery { .. })),
+                "{expr:?} should not parse"
+            );
+        }
+    }
+}
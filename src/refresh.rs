@@ -0,0 +1,158 @@
+//! Reloading a configuration source on a schedule, from a background thread.
+
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use crate::config::ConfigError;
+
+/// Something that [`start_refresh`] can load again and again, merging what changed into
+/// the store.
+///
+/// Any `Fn() -> Result<(), ConfigError>` is a source, so a closure calling one of the
+/// loaders will do, as will a [`RemoteSource`](crate::config::RemoteSource) with the
+/// `remote` feature. A refresh that fails must leave the store as it was, as the loaders
+/// of this crate do.
+pub trait Refresh: Send + 'static {
+    /// Loads the source once and merges it into the store.
+    fn refresh(&self) -> Result<(), ConfigError>;
+}
+
+impl<F> Refresh for F
+where
+    F: Fn() -> Result<(), ConfigError> + Send + 'static,
+{
+    fn refresh(&self) -> Result<(), ConfigError> {
+        self()
+    }
+}
+
+#[cfg(feature = "remote")]
+impl Refresh for crate::config::RemoteSource {
+    fn refresh(&self) -> Result<(), ConfigError> {
+        self.load().map(drop)
+    }
+}
+
+/// The outcome of the refreshes so far, returned by [`RefreshHandle::status`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RefreshStatus {
+    /// When a refresh last succeeded, or `None` if none has yet.
+    pub last_success: Option<SystemTime>,
+    /// When a refresh last failed, or `None` if none has yet.
+    pub last_error: Option<SystemTime>,
+    /// The error of the refresh that last failed, even if later ones succeeded.
+    pub last_error_message: Option<String>,
+    /// The number of refreshes that succeeded.
+    pub successes: u64,
+    /// The number of refreshes that failed.
+    pub failures: u64,
+}
+
+/// A running [`start_refresh`] worker. Stopping or dropping the handle ends it.
+#[derive(Debug)]
+pub struct RefreshHandle {
+    status: Arc<Mutex<RefreshStatus>>,
+    stop: Option<Sender<()>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl RefreshHandle {
+    /// The outcome of the refreshes so far.
+    pub fn status(&self) -> RefreshStatus {
+        self.status
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Stops the worker and waits for it to finish. A refresh in progress completes
+    /// first; no further refresh starts.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        // Dropping the sender wakes the worker from its wait.
+        self.stop.take();
+        if let Some(worker) = self.worker.take()
+            && worker.join().is_err()
+        {
+            log::error!("the configuration refresh worker panicked");
+        }
+    }
+}
+
+impl Drop for RefreshHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Spawns a thread that refreshes `source` every `interval`, until the returned handle
+/// is stopped or dropped.
+///
+/// The first refresh happens one `interval` after the call, since the source is
+/// expected to have been loaded at startup. A refresh that fails is logged as a warning
+/// and recorded in the [status](RefreshHandle::status), and the worker carries on: the
+/// store keeps the configuration of the last refresh that succeeded, and the next tick
+/// tries again.
+///
+/// Fails with [`ConfigError::ThreadSpawn`] if the thread cannot be spawned.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use zirv_config::config;
+///
+/// # let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("flags.json");
+/// std::fs::write(&path, r#"{ "flags": { "beta": false } }"#).unwrap();
+/// config::load_json_file(&path).unwrap();
+///
+/// let handle =
+///     config::start_refresh(move || config::load_json_file(&path), Duration::from_secs(30)).unwrap();
+/// assert_eq!(handle.status().successes, 0);
+/// handle.stop();
+/// ```
+pub fn start_refresh(
+    source: impl Refresh,
+    interval: Duration,
+) -> Result<RefreshHandle, ConfigError> {
+    let status = Arc::new(Mutex::new(RefreshStatus::default()));
+    let recorded = Arc::clone(&status);
+    let (stop, stopped) = mpsc::channel::<()>();
+    let name = "zirv-config-refresh";
+    let worker = thread::Builder::new()
+        .name(name.to_string())
+        .spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                let result = source.refresh();
+                let now = SystemTime::now();
+                let mut status = recorded.lock().unwrap_or_else(PoisonError::into_inner);
+                match result {
+                    Ok(()) => {
+                        status.last_success = Some(now);
+                        status.successes += 1;
+                    }
+                    Err(err) => {
+                        log::warn!("refreshing the configuration failed: {err}");
+                        status.last_error = Some(now);
+                        status.last_error_message = Some(err.to_string());
+                        status.failures += 1;
+                    }
+                }
+            }
+        })
+        .map_err(|source| ConfigError::ThreadSpawn {
+            name: name.to_string(),
+            source,
+        })?;
+    Ok(RefreshHandle {
+        status,
+        stop: Some(stop),
+        worker: Some(worker),
+    })
+}
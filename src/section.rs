@@ -0,0 +1,131 @@
+//! A trait tying a configuration struct to its namespace.
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::config::{self, ConfigError};
+
+/// A configuration struct registered under a fixed namespace.
+///
+/// Implementing this trait keeps the namespace string in one place, so call sites read
+/// `ServerConfig::load()` instead of repeating `"server"`. Only [`NAMESPACE`] needs to be
+/// provided; the methods call into [`config`](crate::config).
+///
+/// With the `derive` feature, `#[derive(ZirvConfig)]` implements this trait.
+///
+/// [`NAMESPACE`]: ConfigSection::NAMESPACE
+///
+/// # Examples
+///
+/// ```rust
+/// use serde::{Deserialize, Serialize};
+/// use zirv_config::config::ConfigSection;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct ServerConfig {
+///     port: u16,
+/// }
+///
+/// impl ConfigSection for ServerConfig {
+///     const NAMESPACE: &'static str = "server";
+/// }
+///
+/// assert!(ServerConfig { port: 3000 }.register().is_none());
+/// assert_eq!(ServerConfig::load().unwrap().port, 3000);
+/// ```
+pub trait ConfigSection: Serialize + DeserializeOwned {
+    /// The namespace this configuration is registered under.
+    const NAMESPACE: &'static str;
+
+    /// Registers `self` under [`Self::NAMESPACE`], returning the block it replaced, if any.
+    fn register(self) -> Option<Value> {
+        config::register_config(Self::NAMESPACE, self)
+    }
+
+    /// Deserializes the block registered under [`Self::NAMESPACE`].
+    fn load() -> Result<Self, ConfigError> {
+        config::get_namespace(Self::NAMESPACE)
+    }
+
+    /// Like [`load`](Self::load), but returns `default` if the namespace is missing or
+    /// does not have the shape of `Self`.
+    fn load_or(default: Self) -> Self {
+        Self::load().unwrap_or(default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Server {
+        port: u16,
+    }
+
+    impl ConfigSection for Server {
+        const NAMESPACE: &'static str = "section_server";
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Logging {
+        level: String,
+    }
+
+    impl ConfigSection for Logging {
+        const NAMESPACE: &'static str = "section_logging";
+    }
+
+    #[test]
+    fn test_sections_do_not_collide() {
+        assert_eq!(Server { port: 1 }.register(), None);
+        assert_eq!(
+            Logging {
+                level: "info".to_string()
+            }
+            .register(),
+            None
+        );
+        assert_eq!(
+            Server { port: 2 }.register(),
+            Some(serde_json::json!({ "port": 1 }))
+        );
+
+        assert_eq!(Server::load().unwrap(), Server { port: 2 });
+        assert_eq!(
+            Logging::load().unwrap(),
+            Logging {
+                level: "info".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_load_or_falls_back() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Missing {
+            enabled: bool,
+        }
+
+        impl ConfigSection for Missing {
+            const NAMESPACE: &'static str = "section_missing";
+        }
+
+        assert_eq!(
+            Missing::load_or(Missing { enabled: true }),
+            Missing { enabled: true }
+        );
+        config::register_config("section_missing", serde_json::json!({ "enabled": "yes" }));
+        assert_eq!(
+            Missing::load_or(Missing { enabled: true }),
+            Missing { enabled: true }
+        );
+        config::register_config("section_missing", serde_json::json!({ "enabled": false }));
+        assert_eq!(
+            Missing::load_or(Missing { enabled: true }),
+            Missing { enabled: false }
+        );
+    }
+}
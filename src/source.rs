@@ -0,0 +1,403 @@
+//! Configuration sources, layered by priority and loaded together by [`reload_config`].
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use serde_json::{Map, Value};
+
+use crate::config::{
+    ConfigError, Limits, merge_map, parse_namespace, rebuild_config, rebuild_namespace,
+    value_type_name,
+};
+
+/// A place configuration comes from, such as a file, the environment or a remote
+/// endpoint, that [`reload_config`] can load again whenever it is asked to.
+///
+/// Sources are [added](add_source) with a priority; when they are loaded together, the
+/// documents of higher-priority sources are deep-merged over those of lower ones. A
+/// [`Layer`](crate::config::Layer) is a file source, and with the `remote` feature a
+/// [`RemoteSource`](crate::config::RemoteSource) is a remote one.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::{Value, json};
+/// use zirv_config::config::{self, ConfigError, ConfigSource};
+///
+/// struct Defaults;
+///
+/// impl ConfigSource for Defaults {
+///     fn name(&self) -> &str {
+///         "defaults"
+///     }
+///
+///     fn load(&self) -> Result<Value, ConfigError> {
+///         Ok(json!({ "http": { "port": 8080, "workers": 4 } }))
+///     }
+/// }
+///
+/// config::add_source(Box::new(Defaults), -10);
+/// config::register_config("http", json!({ "port": 3000 }));
+/// config::reload_config().unwrap();
+/// assert_eq!(config::get_config_by_key("http"), Some(json!({ "port": 3000, "workers": 4 })));
+/// ```
+pub trait ConfigSource: Send + Sync {
+    /// A name for the source in errors and logs, such as the path of a file.
+    fn name(&self) -> &str;
+
+    /// Loads the source, returning its document, which must be an object whose keys are
+    /// namespaces. Called by every [`reload_config`].
+    fn load(&self) -> Result<Value, ConfigError>;
+
+    /// The top-level namespaces the source provides, if it can tell without loading, for
+    /// [`reload_namespace`] to decide whether to load it.
+    ///
+    /// Defaults to `None`, in which case [`reload_namespace`] goes by the namespaces of the
+    /// document the source loaded last, and loads it if it has not loaded yet.
+    fn namespaces(&self) -> Option<Vec<String>> {
+        None
+    }
+}
+
+/// The priority of the blocks [registered](crate::config::register_config) by the
+/// application, and of the values it writes at runtime, relative to the
+/// [sources](ConfigSource) of [`reload_config`].
+///
+/// A source [added](add_source) with a higher priority overrides the registered blocks,
+/// and one with a lower priority provides defaults for them. A source added at this same
+/// priority overrides them too, as sources of equal priority apply in the order they were
+/// added, after the registered blocks.
+pub const APPLICATION_PRIORITY: i32 = 0;
+
+#[derive(Clone)]
+struct Registered {
+    priority: i32,
+    source: Arc<dyn ConfigSource>,
+    /// The document of the last reload that loaded the source, for
+    /// [`ReloadOptions::keep_failed`].
+    last: Arc<Mutex<Option<Map<String, Value>>>>,
+}
+
+/// The sources added with [`add_source`], ordered by priority, then by when they were
+/// added.
+static SOURCES: Mutex<Vec<Registered>> = Mutex::new(Vec::new());
+
+/// Serializes reloads, so that a slow one cannot install its result over a later one.
+static RELOAD: Mutex<()> = Mutex::new(());
+
+/// Adds a configuration source that [`reload_config`] loads with the given priority.
+///
+/// Sources with a higher priority override those with a lower one, and sources of equal
+/// priority apply in the order they were added. Blocks registered by the application sit
+/// at [`APPLICATION_PRIORITY`]. Adding a source loads nothing: call [`reload_config`] once
+/// the sources are in place.
+pub fn add_source(source: Box<dyn ConfigSource>, priority: i32) {
+    insert_source(
+        &mut SOURCES.lock().unwrap_or_else(PoisonError::into_inner),
+        Registered::new(source, priority),
+    );
+}
+
+impl Registered {
+    fn new(source: Box<dyn ConfigSource>, priority: i32) -> Registered {
+        Registered {
+            priority,
+            source: Arc::from(source),
+            last: Arc::default(),
+        }
+    }
+}
+
+/// Inserts `source` into `sources` after those of lower or equal priority.
+fn insert_source(sources: &mut Vec<Registered>, source: Registered) {
+    let index = sources.partition_point(|registered| registered.priority <= source.priority);
+    sources.insert(index, source);
+}
+
+/// Removes the sources [added](add_source) under `name`, telling whether there were any.
+/// The configuration they provided stays until the next [`reload_config`].
+pub fn remove_source(name: &str) -> bool {
+    let mut sources = SOURCES.lock().unwrap_or_else(PoisonError::into_inner);
+    let before = sources.len();
+    sources.retain(|registered| registered.source.name() != name);
+    sources.len() < before
+}
+
+/// How [`reload_config_with`] treats a source that fails to load. Override individual
+/// fields with struct update syntax.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadOptions {
+    /// Whether a source that fails is skipped, logged as a warning and reported, with the
+    /// document it loaded last layered in its place, rather than failing the reload.
+    /// A source that has never loaded contributes nothing. Defaults to `false`.
+    pub keep_failed: bool,
+}
+
+/// The outcome of a reload, returned by [`reload_config`].
+#[derive(Debug, Default)]
+pub struct ReloadReport {
+    /// Every source, in the order it was layered.
+    pub sources: Vec<SourceStatus>,
+    /// The number of keys, flattened as [`flatten_config`](crate::config::flatten_config)
+    /// does, whose values were added, removed or changed by the reload.
+    pub keys_changed: usize,
+}
+
+impl ReloadReport {
+    /// The sources that failed and were skipped under [`ReloadOptions::keep_failed`].
+    pub fn failed(&self) -> impl Iterator<Item = &SourceStatus> {
+        self.sources.iter().filter(|status| status.error.is_some())
+    }
+}
+
+/// How a single source fared in a reload, part of [`ReloadReport`].
+#[derive(Debug)]
+pub struct SourceStatus {
+    /// The [name](ConfigSource::name) of the source.
+    pub name: String,
+    /// The priority it was [added](add_source) with.
+    pub priority: i32,
+    /// Why it failed, as a [`ConfigError::SourceFailed`], or `None` if it loaded.
+    pub error: Option<ConfigError>,
+}
+
+/// Loads every [added](add_source) source, layers them by priority along with the blocks
+/// the application [registered](crate::config::register_config), and replaces the
+/// configuration with the result, reporting how each source fared and how many keys
+/// changed.
+///
+/// The new configuration is built off to the side and swapped in as a whole, so readers
+/// see either the old configuration or the new one, never a mix. Values written at
+/// runtime, such as by [`set_config_by_key`](crate::config::set_config_by_key), or merged
+/// by the loader functions, are layered over the registered blocks, at the same priority,
+/// so they are kept; [`update_config`](crate::config::update_config) and
+/// [`replace_config`](crate::config::replace_config) keep every namespace they changed as
+/// they left it. A value removed at runtime comes back if a source or a registered block
+/// provides it. [Raw namespaces](crate::config::register_raw_config) are left as they are.
+///
+/// Fails with [`ConfigError::SourceFailed`] if a source fails to load or its document is
+/// not an object or exceeds the default [`Limits`], naming the source, and with
+/// [`ConfigError::Frozen`] once the configuration is frozen. The configuration is left as
+/// it was on failure. Use [`reload_config_with`] to skip failing sources instead.
+///
+/// # Examples
+///
+/// ```rust
+/// use zirv_config::config::{self, Layer};
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("app.json");
+///
+/// std::fs::write(&path, r#"{ "app": { "workers": 4 } }"#).unwrap();
+/// config::add_source(Box::new(Layer::required(&path)), 10);
+/// config::reload_config().unwrap();
+///
+/// std::fs::write(&path, r#"{ "app": { "workers": 8, "queue": "jobs" } }"#).unwrap();
+/// let report = config::reload_config().unwrap();
+/// assert_eq!(report.keys_changed, 2);
+/// assert_eq!(config::get_config_by_key("app.workers"), Some(serde_json::json!(8)));
+/// ```
+pub fn reload_config() -> Result<ReloadReport, ConfigError> {
+    reload_config_with(&ReloadOptions::default())
+}
+
+/// Reloads the configuration as [`reload_config`] does, treating failing sources as
+/// `options` says.
+///
+/// Under [`ReloadOptions::keep_failed`], a source that fails is listed with its error in
+/// [`ReloadReport::sources`], and the document it loaded last is used in its place, so
+/// that an unreachable endpoint does not take away the configuration it provided. The
+/// reload then only fails once the configuration is frozen.
+pub fn reload_config_with(options: &ReloadOptions) -> Result<ReloadReport, ConfigError> {
+    let (loaded, keys_changed) = install_sources(Vec::new(), options)?;
+    Ok(ReloadReport {
+        sources: loaded
+            .into_iter()
+            .map(|source| SourceStatus {
+                name: source.name,
+                priority: source.priority,
+                error: source.error,
+            })
+            .collect(),
+        keys_changed,
+    })
+}
+
+/// Reloads a single namespace: loads only the sources that provide it, layers their
+/// blocks for it by priority along with the block the application
+/// [registered](crate::config::register_config) there, and replaces the block at
+/// `namespace` with the result, leaving the rest of the configuration untouched.
+///
+/// Suits a namespace, such as `features`, that comes from a file that changes often while
+/// everything else is static, and keeps runtime writes to other namespaces. A dotted
+/// `namespace` is a path, as in [`register_config`](crate::config::register_config), and
+/// only the block at that path is replaced. A source is loaded if its
+/// [declared](ConfigSource::namespaces) namespaces, or else those of the document it
+/// loaded last, include the first segment of `namespace`; a source that starts providing a
+/// namespace it did not provide before is picked up by the next [`reload_config`]. If no
+/// source or registered block provides the namespace any longer, it is removed.
+///
+/// Fails with [`ConfigError::InvalidPath`] if `namespace` is invalid, as
+/// [`reload_config`] does if a source that is loaded fails, and with
+/// [`ConfigError::NotAnObject`] if a parent segment of `namespace` holds a value that is
+/// not an object. The configuration is left as it was on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use zirv_config::config::{self, Layer};
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("features.json");
+///
+/// std::fs::write(&path, r#"{ "features": { "beta": false } }"#).unwrap();
+/// config::add_source(Box::new(Layer::required(&path)), 10);
+/// config::reload_config().unwrap();
+///
+/// config::set_config_by_key("server.port", 8080).unwrap();
+/// std::fs::write(&path, r#"{ "features": { "beta": true } }"#).unwrap();
+/// config::reload_namespace("features").unwrap();
+/// assert_eq!(config::get_config_by_key("features.beta"), Some(json!(true)));
+/// assert_eq!(config::get_config_by_key("server.port"), Some(json!(8080)));
+/// ```
+pub fn reload_namespace(namespace: &str) -> Result<(), ConfigError> {
+    let top = parse_namespace(namespace)?[0].name.to_string();
+    let _reload = RELOAD.lock().unwrap_or_else(PoisonError::into_inner);
+    let sources: Vec<Registered> = SOURCES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .filter(|registered| registered.provides(&top))
+        .cloned()
+        .collect();
+
+    let mut documents = Vec::with_capacity(sources.len());
+    for registered in &sources {
+        documents.push(load_source(registered.source.as_ref())?);
+    }
+    let mut below = Map::new();
+    let mut above = Map::new();
+    for (registered, document) in sources.iter().zip(&documents) {
+        let layer = if registered.priority < APPLICATION_PRIORITY {
+            &mut below
+        } else {
+            &mut above
+        };
+        merge_map(layer, document.clone());
+    }
+    rebuild_namespace(namespace, &below, &above)?;
+
+    for (registered, document) in sources.iter().zip(documents) {
+        *registered
+            .last
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(document);
+    }
+    Ok(())
+}
+
+impl Registered {
+    /// Tells whether the source provides the top-level namespace `top`, or may.
+    fn provides(&self, top: &str) -> bool {
+        if let Some(namespaces) = self.source.namespaces() {
+            return namespaces.iter().any(|namespace| namespace == top);
+        }
+        match &*self.last.lock().unwrap_or_else(PoisonError::into_inner) {
+            Some(document) => document.contains_key(top),
+            None => true,
+        }
+    }
+}
+
+/// The document a source contributed to a reload.
+pub(crate) struct Loaded {
+    pub(crate) name: String,
+    pub(crate) priority: i32,
+    pub(crate) document: Map<String, Value>,
+    pub(crate) error: Option<ConfigError>,
+}
+
+/// Reloads the configuration as [`reload_config_with`] does, with the `added` sources as
+/// if they had been [added](add_source), returning the document of every source in the
+/// order they were layered and the number of keys changed. The `added` sources are only
+/// added if the reload succeeds.
+pub(crate) fn install_sources(
+    added: Vec<(Box<dyn ConfigSource>, i32)>,
+    options: &ReloadOptions,
+) -> Result<(Vec<Loaded>, usize), ConfigError> {
+    let _reload = RELOAD.lock().unwrap_or_else(PoisonError::into_inner);
+    let added: Vec<Registered> = added
+        .into_iter()
+        .map(|(source, priority)| Registered::new(source, priority))
+        .collect();
+    let mut sources = SOURCES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    for source in &added {
+        insert_source(&mut sources, source.clone());
+    }
+
+    let mut loaded = Vec::with_capacity(sources.len());
+    for registered in &sources {
+        let name = registered.source.name().to_string();
+        let (document, error) = match load_source(registered.source.as_ref()) {
+            Ok(document) => (document, None),
+            Err(err) if options.keep_failed => {
+                log::warn!("{err}; keeping the configuration it loaded last");
+                let last = registered
+                    .last
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                (last.clone().unwrap_or_default(), Some(err))
+            }
+            Err(err) => return Err(err),
+        };
+        loaded.push(Loaded {
+            name,
+            priority: registered.priority,
+            document,
+            error,
+        });
+    }
+    let mut below = Map::new();
+    let mut above = Map::new();
+    for source in &loaded {
+        let layer = if source.priority < APPLICATION_PRIORITY {
+            &mut below
+        } else {
+            &mut above
+        };
+        merge_map(layer, source.document.clone());
+    }
+    let keys_changed = rebuild_config(below, above)?;
+
+    for (registered, source) in sources.iter().zip(&loaded) {
+        if source.error.is_none() {
+            *registered
+                .last
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = Some(source.document.clone());
+        }
+    }
+    let mut sources = SOURCES.lock().unwrap_or_else(PoisonError::into_inner);
+    for source in added {
+        insert_source(&mut sources, source);
+    }
+    Ok((loaded, keys_changed))
+}
+
+/// Loads `source` and checks that its document can be merged.
+fn load_source(source: &dyn ConfigSource) -> Result<Map<String, Value>, ConfigError> {
+    let failed = |error| ConfigError::SourceFailed {
+        name: source.name().to_string(),
+        error: Box::new(error),
+    };
+    let document = source.load().map_err(failed)?;
+    Limits::default().check("", &document).map_err(failed)?;
+    match document {
+        Value::Object(document) => Ok(document),
+        other => Err(failed(ConfigError::NotAnObject {
+            resolved_path: String::new(),
+            found_type: value_type_name(&other),
+        })),
+    }
+}
@@ -0,0 +1,186 @@
+//! Size and shape statistics of the stored configuration, for finding out which namespace
+//! is responsible for its memory and serialization cost.
+
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+use crate::config::with_config;
+
+/// Statistics about the whole configuration, returned by [`config_stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigStats {
+    /// The number of object keys at every level, including the namespaces themselves.
+    pub keys: usize,
+    /// The deepest nesting of objects and arrays, counting the root object as 1, as
+    /// [`Limits::max_depth`](crate::config::Limits::max_depth) does.
+    pub max_depth: usize,
+    /// The size of the configuration serialized as compact JSON, in bytes.
+    pub size: usize,
+    /// The same statistics for each namespace, by name.
+    pub namespaces: BTreeMap<String, NamespaceStats>,
+}
+
+/// Statistics about a single namespace, part of [`ConfigStats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NamespaceStats {
+    /// The number of object keys at every level below the namespace.
+    pub keys: usize,
+    /// The deepest nesting of objects and arrays below the namespace, counting its own
+    /// block as 1. A namespace holding a scalar has depth 0.
+    pub max_depth: usize,
+    /// The size of the block serialized as compact JSON, in bytes, not counting its name.
+    pub size: usize,
+}
+
+/// Computes the key count, depth and serialized size of the configuration and of each
+/// namespace, in a single pass over the current snapshot.
+///
+/// Nothing is serialized, so this is cheap enough to serve from a debug endpoint. [Raw
+/// namespaces](crate::config::register_raw_config) count as the JSON they hold.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("server", json!({ "host": "::", "tls": { "enabled": true } }));
+///
+/// let stats = config::config_stats();
+/// let server = stats.namespaces["server"];
+/// assert_eq!((server.keys, server.max_depth), (3, 2));
+/// assert_eq!(server.size, r#"{"host":"::","tls":{"enabled":true}}"#.len());
+/// assert_eq!(stats.size, serde_json::to_vec(&config::get_config()).unwrap().len());
+/// ```
+pub fn config_stats() -> ConfigStats {
+    with_config(collect)
+}
+
+fn collect(map: &Map<String, Value>) -> ConfigStats {
+    let mut stats = ConfigStats {
+        keys: map.len(),
+        max_depth: 1,
+        // The braces, plus a comma between each pair of namespaces.
+        size: 2 + map.len().saturating_sub(1),
+        namespaces: BTreeMap::new(),
+    };
+    for (name, value) in map {
+        let namespace = measure(value);
+        stats.keys += namespace.keys;
+        stats.max_depth = stats.max_depth.max(namespace.max_depth + 1);
+        stats.size += string_size(name) + 1 + namespace.size;
+        stats.namespaces.insert(name.clone(), namespace);
+    }
+    stats
+}
+
+/// Measures `value` without recursion, so deeply nested values cannot overflow the stack.
+fn measure(value: &Value) -> NamespaceStats {
+    let mut stats = NamespaceStats::default();
+    let mut pending = vec![(value, 1)];
+    while let Some((value, depth)) = pending.pop() {
+        stats.size += match value {
+            Value::Null => 4,
+            Value::Bool(true) => 4,
+            Value::Bool(false) => 5,
+            Value::Number(number) => number.to_string().len(),
+            Value::String(string) => string_size(string),
+            Value::Array(items) => {
+                stats.max_depth = stats.max_depth.max(depth);
+                pending.extend(items.iter().map(|item| (item, depth + 1)));
+                2 + items.len().saturating_sub(1)
+            }
+            Value::Object(map) => {
+                stats.max_depth = stats.max_depth.max(depth);
+                stats.keys += map.len();
+                pending.extend(map.values().map(|child| (child, depth + 1)));
+                let keys: usize = map.keys().map(|key| string_size(key) + 1).sum();
+                2 + keys + map.len().saturating_sub(1)
+            }
+        };
+    }
+    stats
+}
+
+/// Returns the length of `string` serialized as a JSON string, quotes and escapes included.
+fn string_size(string: &str) -> usize {
+    let escapes: usize = string
+        .bytes()
+        .map(|byte| match byte {
+            b'"' | b'\\' | b'\n' | b'\r' | b'\t' | 0x08 | 0x0c => 1,
+            0x00..=0x1f => 5,
+            _ => 0,
+        })
+        .sum();
+    2 + string.len() + escapes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sizes_match_serialized_json() {
+        let values = [
+            json!(null),
+            json!(false),
+            json!(-12.5e30),
+            json!("tab\there \"quoted\" \u{1} é"),
+            json!([]),
+            json!({}),
+            json!({ "a": [1, { "b": null }], "c\nd": { "e": [true, "x"] } }),
+        ];
+        for value in values {
+            assert_eq!(
+                measure(&value).size,
+                serde_json::to_vec(&value).unwrap().len(),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_counts_keys_and_depth() {
+        let fixture = json!({
+            "server": { "host": "::", "ports": [80, 443], "tls": { "cert": "a", "key": "b" } },
+            "flag": true,
+            "routes": [{ "path": "/" }, { "path": "/api", "methods": ["GET"] }],
+        });
+        let Value::Object(map) = fixture else {
+            unreachable!()
+        };
+        let stats = collect(&map);
+
+        assert_eq!(
+            stats.namespaces["server"],
+            NamespaceStats {
+                keys: 5,
+                max_depth: 2,
+                size: 59
+            }
+        );
+        assert_eq!(
+            stats.namespaces["flag"],
+            NamespaceStats {
+                keys: 0,
+                max_depth: 0,
+                size: 4
+            }
+        );
+        assert_eq!(stats.namespaces["routes"].keys, 3);
+        assert_eq!(stats.namespaces["routes"].max_depth, 3);
+        assert_eq!(stats.keys, 3 + 5 + 3);
+        assert_eq!(stats.max_depth, 4);
+        assert_eq!(stats.size, serde_json::to_vec(&map).unwrap().len());
+        assert_eq!(
+            collect(&Map::new()),
+            ConfigStats {
+                max_depth: 1,
+                size: 2,
+                ..ConfigStats::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,76 @@
+//! "Did you mean" suggestions for keys that do not exist.
+
+/// The most suggestions reported for a single miss.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Returns up to three of `candidates` closest to `target`, nearest first.
+///
+/// Closeness is the optimal string alignment distance, so a swapped pair of adjacent
+/// characters (`prot` for `port`) counts as one edit. Candidates more than a third of the
+/// target's length away, with a minimum allowance of one edit, are not suggested.
+pub(crate) fn closest<'a>(
+    target: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<&'a str> {
+    let limit = (target.chars().count() / 3).max(1);
+    let mut scored: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(target, candidate), candidate))
+        .filter(|&(distance, _)| distance <= limit)
+        .collect();
+    scored.sort();
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// Counts the insertions, deletions, substitutions and adjacent transpositions needed to
+/// turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Three rows of the dynamic programming table: two back, one back and current.
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("port", "port"), 0);
+        assert_eq!(edit_distance("prot", "port"), 1);
+        assert_eq!(edit_distance("host", "hosts"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_closest() {
+        let keys = ["port", "host", "ports", "timeout", "tls"];
+        assert_eq!(closest("prot", keys), vec!["port"]);
+        assert_eq!(closest("post", keys), vec!["host", "port"]);
+        assert_eq!(closest("timout", keys), vec!["timeout"]);
+        assert!(closest("database", keys).is_empty());
+    }
+}
@@ -0,0 +1,1253 @@
+//! Typed accessors for values that are commonly written in more than one way.
+//!
+//! Each helper reads a single key and interprets it more generously than a plain serde
+//! read, for example accepting `"on"` as a boolean. Because they are separate functions,
+//! code that wants strict reads keeps using [`get_config_as`](crate::config::get_config_as).
+//! Values that cannot be interpreted return [`ConfigError::TypeMismatch`] at the key.
+
+use std::ffi::OsString;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::de::{Error as _, Unexpected};
+use serde_json::Value;
+
+use crate::config::{ConfigError, get_required};
+use crate::key::AsConfigKey;
+
+/// Reads a flag-like value, accepting the common spellings of a boolean.
+///
+/// Accepts JSON booleans, the numbers `1` and `0`, and the strings `true`/`false`,
+/// `1`/`0`, `yes`/`no` and `on`/`off` in any case. Anything else returns
+/// [`ConfigError::TypeMismatch`] listing the accepted spellings.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("features", json!({ "dark_mode": "On", "beta": 0, "new_ui": "enabled" }));
+///
+/// assert!(config::get_bool_lenient("features.dark_mode").unwrap());
+/// assert!(!config::get_bool_lenient("features.beta").unwrap());
+/// assert!(config::get_bool_lenient("features.new_ui").is_err());
+/// ```
+pub fn get_bool_lenient(key: impl AsConfigKey) -> Result<bool, ConfigError> {
+    let key = key.parsed_key()?;
+    let value = get_required(&key)?;
+    let parsed = match &value {
+        Value::Bool(flag) => Some(*flag),
+        Value::Number(number) => match number.as_u64() {
+            Some(1) => Some(true),
+            Some(0) => Some(false),
+            _ => None,
+        },
+        Value::String(text) => match text.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Some(true),
+            "false" | "0" | "no" | "off" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    };
+    parsed.ok_or_else(|| {
+        mismatch(
+            key.raw,
+            &value,
+            "a boolean (true/false, 1/0, yes/no or on/off)",
+        )
+    })
+}
+
+/// Reads a list of strings, splitting a single comma-separated string into elements.
+///
+/// A JSON array of strings is returned as is. A string such as
+/// `"https://a.com, https://b.com,"` is split on commas, with whitespace trimmed from each
+/// element and empty elements dropped, which lets environment variables express lists.
+/// Use [`get_string_list_with`] for values whose elements contain commas.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use serde_json::json;
+///
+/// config::register_config("cors", json!({
+///     "origins": "https://a.com, https://b.com,",
+///     "methods": ["GET", "POST"]
+/// }));
+///
+/// assert_eq!(config::get_string_list("cors.origins").unwrap(), ["https://a.com", "https://b.com"]);
+/// assert_eq!(config::get_string_list("cors.methods").unwrap(), ["GET", "POST"]);
+/// ```
+pub fn get_string_list(key: impl AsConfigKey) -> Result<Vec<String>, ConfigError> {
+    get_string_list_with(key, ",")
+}
+
+/// Like [`get_string_list`], but splits strings on `delimiter` instead of commas.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("search", serde_json::json!({ "paths": "/usr/a,b;/opt/c" }));
+/// assert_eq!(config::get_string_list_with("search.paths", ";").unwrap(), ["/usr/a,b", "/opt/c"]);
+/// ```
+pub fn get_string_list_with(
+    key: impl AsConfigKey,
+    delimiter: &str,
+) -> Result<Vec<String>, ConfigError> {
+    let key = key.parsed_key()?;
+    match get_required(&key)? {
+        Value::String(text) => Ok(text
+            .split(delimiter)
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect()),
+        Value::Array(items) => items
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| match item {
+                Value::String(text) => Ok(text),
+                other => Err(mismatch(
+                    &format!("{}.{index}", key.raw),
+                    &other,
+                    "a string",
+                )),
+            })
+            .collect(),
+        other => Err(mismatch(
+            key.raw,
+            &other,
+            "a list of strings or a delimited string",
+        )),
+    }
+}
+
+/// Reads a duration, such as a timeout or an interval.
+///
+/// Accepts:
+///
+/// - strings made of one or more `<integer><unit>` pairs, such as `"30s"`, `"5m"`,
+///   `"1h30m"` or `"250 ms"`. Units are `ns`, `us`, `ms`, `s`, `m`, `h`, `d` and `w`, plus
+///   long forms like `sec`, `mins` or `hours`;
+/// - integers, and strings of digits, interpreted as milliseconds;
+/// - floats, interpreted as (fractional) seconds.
+///
+/// Anything else returns [`ConfigError::TypeMismatch`] quoting the value and the
+/// accepted formats.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use std::time::Duration;
+///
+/// config::register_config("http", serde_json::json!({
+///     "timeout": "1m30s",
+///     "poll_ms": 1500,
+///     "grace": 0.25
+/// }));
+///
+/// assert_eq!(config::get_duration("http.timeout").unwrap(), Duration::from_secs(90));
+/// assert_eq!(config::get_duration("http.poll_ms").unwrap(), Duration::from_millis(1500));
+/// assert_eq!(config::get_duration("http.grace").unwrap(), Duration::from_millis(250));
+/// ```
+pub fn get_duration(key: impl AsConfigKey) -> Result<Duration, ConfigError> {
+    let key = key.parsed_key()?;
+    let value = get_required(&key)?;
+    let parsed = match &value {
+        Value::Number(number) => match number.as_u64() {
+            Some(millis) => Some(Duration::from_millis(millis)),
+            None if number.is_f64() => number
+                .as_f64()
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok()),
+            None => None,
+        },
+        Value::String(text) => parse_duration(text),
+        _ => None,
+    };
+    parsed.ok_or_else(|| {
+        mismatch(
+            key.raw,
+            &value,
+            r#"a duration such as "30s", "5m" or "1h30m", integer milliseconds, or float seconds"#,
+        )
+    })
+}
+
+/// Parses a humantime-style duration string, or a string of digits as milliseconds.
+fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    if !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()) {
+        return text.parse().ok().map(Duration::from_millis);
+    }
+
+    let mut rest = text;
+    let mut total = Duration::ZERO;
+    let mut parts = 0;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let amount: u64 = rest[..digits].parse().ok()?;
+        rest = rest[digits..].trim_start();
+        let letters = rest
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(rest.len());
+        let unit_nanos: u64 = match &rest[..letters] {
+            "ns" | "nsec" | "nanos" => 1,
+            "us" | "µs" | "usec" | "micros" => 1_000,
+            "ms" | "msec" | "millis" => 1_000_000,
+            "s" | "sec" | "secs" | "second" | "seconds" => 1_000_000_000,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60 * 1_000_000_000,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3_600 * 1_000_000_000,
+            "d" | "day" | "days" => 86_400 * 1_000_000_000,
+            "w" | "week" | "weeks" => 604_800 * 1_000_000_000,
+            _ => return None,
+        };
+        total = total.checked_add(Duration::from_nanos(amount.checked_mul(unit_nanos)?))?;
+        rest = rest[letters..].trim_start();
+        parts += 1;
+    }
+    (parts > 0).then_some(total)
+}
+
+/// Reads a filesystem path, expanding `~` and environment variables.
+///
+/// The value must be a string. Expansion follows shell conventions:
+///
+/// - A leading `~` or `~/` is replaced by the current user's home directory (`$HOME`, or
+///   `%USERPROFILE%` on Windows). On Unix, a leading `~user` is replaced by that user's
+///   home directory from `/etc/passwd`.
+/// - `$VAR` and `${VAR}` are replaced by the value of the environment variable. `$$`
+///   stands for a literal `$`, as does a `$` not followed by a variable name.
+///
+/// An unset variable or unknown user returns [`ConfigError::Expansion`] naming the
+/// reference. Use [`get_existing_path`] to also require the path to exist.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// # if std::env::var_os("HOME").is_none() { return; }
+/// use std::path::PathBuf;
+///
+/// config::register_config("paths", serde_json::json!({
+///     "cache": "~/.cache/app",
+///     "certs": "${HOME}/certs"
+/// }));
+///
+/// let home = PathBuf::from(std::env::var_os("HOME").unwrap());
+/// assert_eq!(config::get_path("paths.cache").unwrap(), home.join(".cache/app"));
+/// assert_eq!(config::get_path("paths.certs").unwrap(), home.join("certs"));
+/// ```
+pub fn get_path(key: impl AsConfigKey) -> Result<PathBuf, ConfigError> {
+    let key = key.parsed_key()?;
+    let value = get_required(&key)?;
+    let Value::String(text) = &value else {
+        return Err(mismatch(key.raw, &value, "a path string"));
+    };
+    expand_path(key.raw, text, |name| std::env::var_os(name))
+}
+
+/// Like [`get_path`], but returns [`ConfigError::PathNotFound`] if the expanded path does
+/// not exist.
+pub fn get_existing_path(key: impl AsConfigKey) -> Result<PathBuf, ConfigError> {
+    let key = key.parsed_key()?;
+    let path = get_path(&key)?;
+    if !path.exists() {
+        return Err(ConfigError::PathNotFound {
+            key: key.raw.to_string(),
+            path,
+        });
+    }
+    Ok(path)
+}
+
+/// Expands `~` and `$VAR` references in `text`, looking variables up with `var`.
+fn expand_path(
+    key: &str,
+    text: &str,
+    var: impl Fn(&str) -> Option<OsString>,
+) -> Result<PathBuf, ConfigError> {
+    let expansion_error = |reference: &str, message: &str| ConfigError::Expansion {
+        key: key.to_string(),
+        reference: reference.to_string(),
+        message: message.to_string(),
+    };
+
+    let mut expanded = OsString::new();
+    let mut rest = text;
+    if let Some(after_tilde) = text.strip_prefix('~') {
+        let end = after_tilde.find(['/', '\\']).unwrap_or(after_tilde.len());
+        let user = &after_tilde[..end];
+        let home = if user.is_empty() {
+            var("HOME")
+                .or_else(|| var("USERPROFILE"))
+                .ok_or_else(|| expansion_error("~", "the home directory is not set"))?
+        } else {
+            user_home(user).ok_or_else(|| expansion_error(&format!("~{user}"), "unknown user"))?
+        };
+        expanded.push(home);
+        rest = &after_tilde[end..];
+    }
+
+    while let Some(dollar) = rest.find('$') {
+        expanded.push(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        if let Some(after) = after.strip_prefix('$') {
+            expanded.push("$");
+            rest = after;
+        } else if let Some(braced) = after.strip_prefix('{') {
+            let close = braced
+                .find('}')
+                .ok_or_else(|| expansion_error("${", "unterminated `${`"))?;
+            let name = &braced[..close];
+            let value = var(name).ok_or_else(|| {
+                expansion_error(&format!("${{{name}}}"), "the variable is not set")
+            })?;
+            expanded.push(value);
+            rest = &braced[close + 1..];
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            let name = &after[..end];
+            if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+                expanded.push("$");
+            } else {
+                let value = var(name).ok_or_else(|| {
+                    expansion_error(&format!("${name}"), "the variable is not set")
+                })?;
+                expanded.push(value);
+            }
+            rest = &after[end..];
+        }
+    }
+    expanded.push(rest);
+    Ok(PathBuf::from(expanded))
+}
+
+/// Looks up the home directory of `user` in `/etc/passwd`.
+#[cfg(unix)]
+fn user_home(user: &str) -> Option<OsString> {
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        (fields.len() >= 6 && fields[0] == user).then(|| OsString::from(fields[5]))
+    })
+}
+
+#[cfg(not(unix))]
+fn user_home(_user: &str) -> Option<OsString> {
+    None
+}
+
+/// Reads a byte size, such as a cache size or an upload limit.
+///
+/// Accepts integers as a number of bytes, and strings made of a number and an optional
+/// unit, such as `"512MiB"`, `"2 GB"` or `"1.5GiB"`. Units are case-insensitive: `B`,
+/// decimal `KB`, `MB`, `GB`, `TB`, `PB` and `EB` (powers of 1000), and binary `KiB`,
+/// `MiB`, `GiB`, `TiB`, `PiB` and `EiB` (powers of 1024). Fractional sizes are rounded
+/// down to a whole byte. Sizes past `u64::MAX` bytes return
+/// [`ConfigError::TypeMismatch`] rather than wrapping.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("cache", serde_json::json!({ "max": "512MiB", "chunk": 4096 }));
+///
+/// assert_eq!(config::get_size("cache.max").unwrap(), 512 * 1024 * 1024);
+/// assert_eq!(config::get_size("cache.chunk").unwrap(), 4096);
+/// ```
+pub fn get_size(key: impl AsConfigKey) -> Result<u64, ConfigError> {
+    let key = key.parsed_key()?;
+    let value = get_required(&key)?;
+    match &value {
+        Value::Number(number) => number
+            .as_u64()
+            .ok_or_else(|| mismatch(key.raw, &value, "a whole number of bytes")),
+        Value::String(text) => parse_size(text)
+            .map_err(|message| parse_failure(key.raw, format!("invalid size {text:?}: {message}"))),
+        other => Err(mismatch(
+            key.raw,
+            other,
+            r#"a size such as 4096, "512MiB" or "2GB""#,
+        )),
+    }
+}
+
+/// Parses a size with an optional decimal or binary unit suffix into bytes.
+fn parse_size(text: &str) -> Result<u64, &'static str> {
+    let text = text.trim();
+    let number_end = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = (&text[..number_end], text[number_end..].trim_start());
+    let multiplier: u128 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "mb" => 1000u128.pow(2),
+        "gb" => 1000u128.pow(3),
+        "tb" => 1000u128.pow(4),
+        "pb" => 1000u128.pow(5),
+        "eb" => 1000u128.pow(6),
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        "pib" => 1 << 50,
+        "eib" => 1 << 60,
+        _ => return Err("unknown unit, expected B, KB, MB, GB, TB, PB, EB or KiB through EiB"),
+    };
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() && fraction.is_empty() || fraction.contains('.') {
+        return Err("expected a number");
+    }
+    // Scale the digits as an integer so fractional sizes are exact before rounding down.
+    let digits: u128 = format!("{whole}{fraction}")
+        .parse()
+        .map_err(|_| "the size is too large")?;
+    let scale = 10u128
+        .checked_pow(fraction.len() as u32)
+        .ok_or("too many decimal places")?;
+    let bytes = digits
+        .checked_mul(multiplier)
+        .ok_or("the size is too large")?
+        / scale;
+    u64::try_from(bytes).map_err(|_| "the size is too large")
+}
+
+/// Reads a socket address, from either a `"host:port"` string or a `{ host, port }` object.
+///
+/// The host must be an IP address; IPv6 addresses are written in brackets in the string
+/// form (`"[::1]:8080"`) and may be written with or without them in the object form. The
+/// port may be a number or a string of digits. Malformed values return
+/// [`ConfigError::TypeMismatch`] with the parse failure.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use std::net::SocketAddr;
+///
+/// config::register_config("listen", serde_json::json!({
+///     "http": "0.0.0.0:8080",
+///     "admin": { "host": "::1", "port": 9090 }
+/// }));
+///
+/// assert_eq!(config::get_socket_addr("listen.http").unwrap(), "0.0.0.0:8080".parse::<SocketAddr>().unwrap());
+/// assert_eq!(config::get_socket_addr("listen.admin").unwrap(), "[::1]:9090".parse::<SocketAddr>().unwrap());
+/// ```
+pub fn get_socket_addr(key: impl AsConfigKey) -> Result<SocketAddr, ConfigError> {
+    let key = key.parsed_key()?;
+    let value = get_required(&key)?;
+    match &value {
+        Value::String(text) => text.trim().parse().map_err(|err| {
+            parse_failure(key.raw, format!("invalid socket address {text:?}: {err}"))
+        }),
+        Value::Object(map) => {
+            let host = match map.get("host") {
+                Some(Value::String(host)) => parse_ip(&format!("{}.host", key.raw), host)?,
+                Some(other) => {
+                    return Err(mismatch(
+                        &format!("{}.host", key.raw),
+                        other,
+                        "an IP address",
+                    ));
+                }
+                None => return Err(parse_failure(key.raw, "missing field `host`".to_string())),
+            };
+            let port = match map.get("port") {
+                Some(Value::Number(port)) => {
+                    port.as_u64().and_then(|port| u16::try_from(port).ok())
+                }
+                Some(Value::String(port)) => port.trim().parse().ok(),
+                Some(_) => None,
+                None => return Err(parse_failure(key.raw, "missing field `port`".to_string())),
+            };
+            let port = port.ok_or_else(|| {
+                mismatch(&format!("{}.port", key.raw), &map["port"], "a port number")
+            })?;
+            Ok(SocketAddr::new(host, port))
+        }
+        other => Err(mismatch(
+            key.raw,
+            other,
+            r#"a socket address such as "0.0.0.0:8080" or { "host", "port" }"#,
+        )),
+    }
+}
+
+/// Reads an IPv4 or IPv6 address.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// use std::net::{IpAddr, Ipv4Addr};
+///
+/// config::register_config("dns", serde_json::json!({ "resolver": "1.1.1.1", "bad": "1.1.1" }));
+///
+/// assert_eq!(config::get_ip("dns.resolver").unwrap(), IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)));
+/// assert!(config::get_ip("dns.bad").is_err());
+/// ```
+pub fn get_ip(key: impl AsConfigKey) -> Result<IpAddr, ConfigError> {
+    let key = key.parsed_key()?;
+    match get_required(&key)? {
+        Value::String(text) => parse_ip(key.raw, &text),
+        other => Err(mismatch(key.raw, &other, "an IP address")),
+    }
+}
+
+/// Parses an IP address, allowing IPv6 addresses to be wrapped in brackets.
+fn parse_ip(key: &str, text: &str) -> Result<IpAddr, ConfigError> {
+    let trimmed = text.trim();
+    let unbracketed = trimmed
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .unwrap_or(trimmed);
+    unbracketed
+        .parse()
+        .map_err(|err| parse_failure(key, format!("invalid IP address {text:?}: {err}")))
+}
+
+/// Reads binary data stored as a base64 string.
+///
+/// Both the standard (`+/`) and URL-safe (`-_`) alphabets are accepted, with or without
+/// `=` padding. Since such values are often secrets, errors describe the problem without
+/// echoing the value. Use [`get_bytes_exact`] to also check the decoded length.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("auth", serde_json::json!({ "hmac_key": "aGVsbG8=" }));
+/// assert_eq!(config::get_bytes("auth.hmac_key").unwrap(), b"hello");
+/// ```
+pub fn get_bytes(key: impl AsConfigKey) -> Result<Vec<u8>, ConfigError> {
+    let key = key.parsed_key()?;
+    match get_required(&key)? {
+        Value::String(text) => decode_base64(&text)
+            .map_err(|message| parse_failure(key.raw, format!("invalid base64: {message}"))),
+        // Deliberately not `mismatch`, which would include the value in the message.
+        _ => Err(parse_failure(
+            key.raw,
+            "expected a base64 string".to_string(),
+        )),
+    }
+}
+
+/// Like [`get_bytes`], but also requires the decoded data to be exactly `len` bytes long,
+/// which catches truncated keys early.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("auth", serde_json::json!({ "hmac_key": "aGVsbG8" }));
+/// assert!(config::get_bytes_exact("auth.hmac_key", 5).is_ok());
+/// assert!(config::get_bytes_exact("auth.hmac_key", 32).is_err());
+/// ```
+pub fn get_bytes_exact(key: impl AsConfigKey, len: usize) -> Result<Vec<u8>, ConfigError> {
+    let key = key.parsed_key()?;
+    let bytes = get_bytes(&key)?;
+    if bytes.len() != len {
+        return Err(parse_failure(
+            key.raw,
+            format!("expected {len} bytes of base64 data, found {}", bytes.len()),
+        ));
+    }
+    Ok(bytes)
+}
+
+/// Decodes standard or URL-safe base64, with optional padding.
+fn decode_base64(text: &str) -> Result<Vec<u8>, String> {
+    let text = text.trim();
+    let unpadded = text.trim_end_matches('=');
+    let padding = text.len() - unpadded.len();
+    if padding > 2 || (padding > 0 && !text.len().is_multiple_of(4)) {
+        return Err("incorrect padding".to_string());
+    }
+    if unpadded.len() % 4 == 1 {
+        return Err("invalid length".to_string());
+    }
+
+    let mut bytes = Vec::with_capacity(unpadded.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for (offset, c) in unpadded.bytes().enumerate() {
+        let sextet = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return Err(format!("invalid character at offset {offset}")),
+        };
+        buffer = (buffer << 6) | u32::from(sextet);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    // Leftover bits must be zero, or the input was not produced by an encoder.
+    if buffer & ((1 << bits) - 1) != 0 {
+        return Err("trailing bits are not zero".to_string());
+    }
+    Ok(bytes)
+}
+
+/// Reads a UUID in hyphenated, simple, braced or URN form.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("tenant", serde_json::json!({
+///     "id": "urn:uuid:67e55044-10b1-426f-9247-bb680e5fe0c8"
+/// }));
+///
+/// let id = config::get_uuid("tenant.id").unwrap();
+/// assert_eq!(id.to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+/// ```
+#[cfg(feature = "uuid")]
+pub fn get_uuid(key: impl AsConfigKey) -> Result<uuid::Uuid, ConfigError> {
+    let key = key.parsed_key()?;
+    match get_required(&key)? {
+        Value::String(text) => parse_uuid(key.raw, &text),
+        other => Err(mismatch(key.raw, &other, "a UUID string")),
+    }
+}
+
+/// Reads a list of UUIDs, from an array or a comma-separated string as
+/// [`get_string_list`] does.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("cluster", serde_json::json!({
+///     "nodes": ["67e55044-10b1-426f-9247-bb680e5fe0c8", "67e5504410b1426f9247bb680e5fe0c9"]
+/// }));
+/// assert_eq!(config::get_uuid_list("cluster.nodes").unwrap().len(), 2);
+/// ```
+#[cfg(feature = "uuid")]
+pub fn get_uuid_list(key: impl AsConfigKey) -> Result<Vec<uuid::Uuid>, ConfigError> {
+    let key = key.parsed_key()?;
+    get_string_list(&key)?
+        .iter()
+        .enumerate()
+        .map(|(index, text)| parse_uuid(&format!("{}.{index}", key.raw), text))
+        .collect()
+}
+
+#[cfg(feature = "uuid")]
+fn parse_uuid(key: &str, text: &str) -> Result<uuid::Uuid, ConfigError> {
+    uuid::Uuid::parse_str(text.trim())
+        .map_err(|err| parse_failure(key, format!("invalid UUID {text:?}: {err}")))
+}
+
+/// Reads a timestamp as a UTC [`chrono::DateTime`].
+///
+/// Accepts RFC 3339 strings such as `"2024-03-01T02:00:00+01:00"`, which are converted
+/// to UTC, and epoch timestamps written as integers or integer strings. An epoch value
+/// whose magnitude is below 100 billion is read as seconds, and anything larger as
+/// milliseconds, so every second-precision timestamp before the year 5138 and every
+/// millisecond timestamp after March 1973 is read as intended.
+///
+/// Timestamps without an offset, such as `"2024-03-01T02:00:00"`, are rejected with
+/// [`ConfigError::TypeMismatch`] rather than guessed to be UTC.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("maintenance", serde_json::json!({
+///     "start": "2024-03-01T02:00:00+01:00",
+///     "end": 1709258400
+/// }));
+///
+/// let start = config::get_datetime("maintenance.start").unwrap();
+/// let end = config::get_datetime("maintenance.end").unwrap();
+/// assert_eq!(start.to_rfc3339(), "2024-03-01T01:00:00+00:00");
+/// assert_eq!((end - start).num_hours(), 1);
+/// ```
+#[cfg(feature = "chrono")]
+pub fn get_datetime(key: impl AsConfigKey) -> Result<chrono::DateTime<chrono::Utc>, ConfigError> {
+    let key = key.parsed_key()?;
+    match read_timestamp(key.raw, get_required(&key)?)? {
+        Timestamp::Text(text) => chrono::DateTime::parse_from_rfc3339(text.trim())
+            .map(|datetime| datetime.to_utc())
+            .map_err(|err| {
+                parse_failure(
+                    key.raw,
+                    format!("invalid RFC 3339 timestamp {text:?}: {err}"),
+                )
+            }),
+        Timestamp::EpochMillis(millis) => chrono::DateTime::from_timestamp_millis(millis)
+            .ok_or_else(|| {
+                parse_failure(
+                    key.raw,
+                    format!("epoch timestamp {millis}ms is out of range"),
+                )
+            }),
+    }
+}
+
+/// Reads a timestamp as a [`time::OffsetDateTime`].
+///
+/// Accepts the same formats as [`get_datetime`], with the same rule for telling epoch
+/// seconds from milliseconds. RFC 3339 strings keep their offset, and epoch timestamps
+/// are returned in UTC. Timestamps without an offset are rejected.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zirv_config::config;
+/// config::register_config("certs", serde_json::json!({ "expiry": "2030-01-01T00:00:00-05:00" }));
+///
+/// let expiry = config::get_offset_datetime("certs.expiry").unwrap();
+/// assert_eq!(expiry.offset().whole_hours(), -5);
+/// assert_eq!(expiry.unix_timestamp(), 1893474000);
+/// ```
+#[cfg(feature = "time")]
+pub fn get_offset_datetime(key: impl AsConfigKey) -> Result<time::OffsetDateTime, ConfigError> {
+    let key = key.parsed_key()?;
+    match read_timestamp(key.raw, get_required(&key)?)? {
+        Timestamp::Text(text) => {
+            time::OffsetDateTime::parse(text.trim(), &time::format_description::well_known::Rfc3339)
+                .map_err(|err| {
+                    parse_failure(
+                        key.raw,
+                        format!("invalid RFC 3339 timestamp {text:?}: {err}"),
+                    )
+                })
+        }
+        Timestamp::EpochMillis(millis) => time::OffsetDateTime::from_unix_timestamp_nanos(
+            i128::from(millis) * 1_000_000,
+        )
+        .map_err(|_| {
+            parse_failure(
+                key.raw,
+                format!("epoch timestamp {millis}ms is out of range"),
+            )
+        }),
+    }
+}
+
+/// Epoch values at or above this magnitude are milliseconds, and below it seconds.
+#[cfg(any(feature = "chrono", feature = "time"))]
+const EPOCH_MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+/// A timestamp as written in the config, before it is parsed by a date-time library.
+#[cfg(any(feature = "chrono", feature = "time"))]
+enum Timestamp {
+    Text(String),
+    EpochMillis(i64),
+}
+
+/// Splits a stored timestamp into RFC 3339 text or epoch milliseconds.
+#[cfg(any(feature = "chrono", feature = "time"))]
+fn read_timestamp(key: &str, value: Value) -> Result<Timestamp, ConfigError> {
+    let epoch = match &value {
+        Value::Number(number) => number.as_i64(),
+        Value::String(text) => text.trim().parse::<i64>().ok(),
+        _ => None,
+    };
+    match (epoch, value) {
+        (Some(epoch), _) if epoch.unsigned_abs() < EPOCH_MILLIS_THRESHOLD as u64 => {
+            let millis = epoch.checked_mul(1000).ok_or_else(|| {
+                parse_failure(key, format!("epoch timestamp {epoch}s is out of range"))
+            })?;
+            Ok(Timestamp::EpochMillis(millis))
+        }
+        (Some(epoch), _) => Ok(Timestamp::EpochMillis(epoch)),
+        (None, Value::String(text)) => Ok(Timestamp::Text(text)),
+        (None, other) => Err(mismatch(
+            key,
+            &other,
+            "an RFC 3339 timestamp or epoch seconds or milliseconds",
+        )),
+    }
+}
+
+/// Builds a [`ConfigError::TypeMismatch`] at `key` carrying `message`.
+fn parse_failure(key: &str, message: String) -> ConfigError {
+    ConfigError::TypeMismatch {
+        key: key.to_string(),
+        path: key.to_string(),
+        source: serde_json::Error::custom(message),
+    }
+}
+
+/// Builds a [`ConfigError::TypeMismatch`] for `value` at `key`, which is not `expected`.
+fn mismatch(key: &str, value: &Value, expected: &str) -> ConfigError {
+    let unexpected = match value {
+        Value::Null => Unexpected::Unit,
+        Value::Bool(flag) => Unexpected::Bool(*flag),
+        Value::Number(number) => match (number.as_u64(), number.as_i64(), number.as_f64()) {
+            (Some(n), _, _) => Unexpected::Unsigned(n),
+            (_, Some(n), _) => Unexpected::Signed(n),
+            (_, _, Some(n)) => Unexpected::Float(n),
+            _ => Unexpected::Other("number"),
+        },
+        Value::String(text) => Unexpected::Str(text),
+        Value::Array(_) => Unexpected::Seq,
+        Value::Object(_) => Unexpected::Map,
+    };
+    ConfigError::TypeMismatch {
+        key: key.to_string(),
+        path: key.to_string(),
+        source: serde_json::Error::invalid_value(unexpected, &expected),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::register_config;
+    use serde_json::json;
+
+    #[test]
+    fn test_get_bool_lenient_spellings() {
+        register_config(
+            "lenient_flags",
+            json!({
+                "t1": true, "t2": 1, "t3": "true", "t4": "1", "t5": "yes", "t6": "on", "t7": " YES ",
+                "f1": false, "f2": 0, "f3": "false", "f4": "0", "f5": "no", "f6": "off", "f7": "Off"
+            }),
+        );
+        for i in 1..=7 {
+            assert!(
+                get_bool_lenient(format!("lenient_flags.t{i}")).unwrap(),
+                "t{i}"
+            );
+            assert!(
+                !get_bool_lenient(format!("lenient_flags.f{i}")).unwrap(),
+                "f{i}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_bool_lenient_rejects() {
+        register_config("lenient_reject", json!({ "word": "enabled", "number": 2 }));
+        let err = get_bool_lenient("lenient_reject.word").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"type mismatch at "lenient_reject.word": invalid value: string "enabled", expected a boolean (true/false, 1/0, yes/no or on/off)"#
+        );
+        assert!(matches!(
+            get_bool_lenient("lenient_reject.number"),
+            Err(ConfigError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            get_bool_lenient("lenient_reject.missing"),
+            Err(ConfigError::KeyNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_get_string_list() {
+        register_config(
+            "lenient_list",
+            json!({
+                "array": ["a", "b,c"],
+                "string": "a, b ,c",
+                "trailing": "a,,b, ,",
+                "empty": "",
+                "mixed": ["a", 1],
+                "number": 3
+            }),
+        );
+        assert_eq!(get_string_list("lenient_list.array").unwrap(), ["a", "b,c"]);
+        assert_eq!(
+            get_string_list("lenient_list.string").unwrap(),
+            ["a", "b", "c"]
+        );
+        assert_eq!(
+            get_string_list("lenient_list.trailing").unwrap(),
+            ["a", "b"]
+        );
+        assert!(get_string_list("lenient_list.empty").unwrap().is_empty());
+        assert_eq!(
+            get_string_list_with("lenient_list.string", " ,").unwrap(),
+            ["a, b", "c"]
+        );
+
+        let err = get_string_list("lenient_list.mixed").unwrap_err();
+        assert!(
+            matches!(err, ConfigError::TypeMismatch { ref path, .. } if path == "lenient_list.mixed.1")
+        );
+        assert!(get_string_list("lenient_list.number").is_err());
+    }
+
+    #[test]
+    fn test_get_duration_forms() {
+        register_config(
+            "lenient_duration",
+            json!({
+                "secs": "30s",
+                "mins": "5m",
+                "compound": "1h30m",
+                "spaced": "2 hours 15 min",
+                "sub_second": "1s500ms",
+                "millis": 1500,
+                "millis_string": "1500",
+                "fraction": 0.5,
+                "fortnights": "5 fortnights",
+                "negative": -1,
+                "bare_unit": "s",
+                "flag": true
+            }),
+        );
+        let duration = |key: &str| get_duration(format!("lenient_duration.{key}"));
+        assert_eq!(duration("secs").unwrap(), Duration::from_secs(30));
+        assert_eq!(duration("mins").unwrap(), Duration::from_secs(300));
+        assert_eq!(duration("compound").unwrap(), Duration::from_secs(5400));
+        assert_eq!(duration("spaced").unwrap(), Duration::from_secs(8100));
+        assert_eq!(duration("sub_second").unwrap(), Duration::from_millis(1500));
+        assert_eq!(duration("millis").unwrap(), Duration::from_millis(1500));
+        assert_eq!(
+            duration("millis_string").unwrap(),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(duration("fraction").unwrap(), Duration::from_millis(500));
+
+        let err = duration("fortnights").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains(r#"invalid value: string "5 fortnights", expected a duration"#),
+            "{err}"
+        );
+        for key in ["negative", "bare_unit", "flag"] {
+            assert!(
+                matches!(duration(key), Err(ConfigError::TypeMismatch { .. })),
+                "{key}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_expand_path() {
+        let var = |name: &str| match name {
+            "HOME" => Some(OsString::from("/home/app")),
+            "DATA" => Some(OsString::from("/srv/data")),
+            _ => None,
+        };
+        let expand = |text: &str| expand_path("paths.test", text, var);
+        assert_eq!(expand("~").unwrap(), PathBuf::from("/home/app"));
+        assert_eq!(expand("~/logs").unwrap(), PathBuf::from("/home/app/logs"));
+        assert_eq!(expand("$DATA/db").unwrap(), PathBuf::from("/srv/data/db"));
+        assert_eq!(expand("${DATA}db").unwrap(), PathBuf::from("/srv/datadb"));
+        assert_eq!(expand("/cost/$$5/$").unwrap(), PathBuf::from("/cost/$5/$"));
+        assert_eq!(expand("/a/~b").unwrap(), PathBuf::from("/a/~b"));
+
+        let err = expand("${MISSING}/x").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"cannot expand "${MISSING}" in "paths.test": the variable is not set"#
+        );
+        assert!(
+            matches!(expand("$MISSING"), Err(ConfigError::Expansion { ref reference, .. }) if reference == "$MISSING")
+        );
+        assert!(matches!(
+            expand("${DATA"),
+            Err(ConfigError::Expansion { .. })
+        ));
+        assert!(matches!(
+            expand("~no_such_user_here/x"),
+            Err(ConfigError::Expansion { .. })
+        ));
+    }
+
+    #[test]
+    fn test_get_path_from_env() {
+        let dir = std::env::temp_dir();
+        // SAFETY: the variable name is unique to this test, so nothing else reads or
+        // writes it concurrently.
+        unsafe { std::env::set_var("ZIRV_CONFIG_TEST_PATH_DIR", &dir) };
+        register_config(
+            "lenient_path",
+            json!({
+                "existing": "${ZIRV_CONFIG_TEST_PATH_DIR}",
+                "missing": "$ZIRV_CONFIG_TEST_PATH_DIR/zirv-config-does-not-exist",
+                "number": 1
+            }),
+        );
+
+        assert_eq!(get_path("lenient_path.existing").unwrap(), dir);
+        assert_eq!(get_existing_path("lenient_path.existing").unwrap(), dir);
+        assert_eq!(
+            get_path("lenient_path.missing").unwrap(),
+            dir.join("zirv-config-does-not-exist")
+        );
+        assert!(matches!(
+            get_existing_path("lenient_path.missing"),
+            Err(ConfigError::PathNotFound { .. })
+        ));
+        assert!(matches!(
+            get_path("lenient_path.number"),
+            Err(ConfigError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_get_socket_addr_forms() {
+        register_config(
+            "lenient_net",
+            json!({
+                "v4": "127.0.0.1:8080",
+                "v6": "[::1]:443",
+                "object": { "host": "10.0.0.1", "port": 5432 },
+                "object_v6": { "host": "[fe80::1]", "port": "53" },
+                "no_port": "127.0.0.1",
+                "hostname": "localhost:80",
+                "bad_port": { "host": "10.0.0.1", "port": 70000 },
+                "missing_host": { "port": 1 }
+            }),
+        );
+        let addr = |key: &str| get_socket_addr(format!("lenient_net.{key}"));
+        assert_eq!(
+            addr("v4").unwrap(),
+            "127.0.0.1:8080".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            addr("v6").unwrap(),
+            "[::1]:443".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            addr("object").unwrap(),
+            "10.0.0.1:5432".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            addr("object_v6").unwrap(),
+            "[fe80::1]:53".parse::<SocketAddr>().unwrap()
+        );
+
+        let err = addr("no_port").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"type mismatch at "lenient_net.no_port": invalid socket address "127.0.0.1": invalid socket address syntax"#
+        );
+        assert!(addr("hostname").is_err());
+        assert!(
+            matches!(addr("bad_port"), Err(ConfigError::TypeMismatch { ref path, .. }) if path == "lenient_net.bad_port.port")
+        );
+        assert!(
+            addr("missing_host")
+                .unwrap_err()
+                .to_string()
+                .contains("missing field `host`")
+        );
+    }
+
+    #[test]
+    fn test_get_ip() {
+        register_config(
+            "lenient_ip",
+            json!({ "v4": "10.1.2.3", "v6": "[2001:db8::1]", "bad": "10.1.2", "number": 1 }),
+        );
+        assert_eq!(
+            get_ip("lenient_ip.v4").unwrap(),
+            "10.1.2.3".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            get_ip("lenient_ip.v6").unwrap(),
+            "2001:db8::1".parse::<IpAddr>().unwrap()
+        );
+        assert!(
+            get_ip("lenient_ip.bad")
+                .unwrap_err()
+                .to_string()
+                .contains(r#"invalid IP address "10.1.2""#)
+        );
+        assert!(get_ip("lenient_ip.number").is_err());
+    }
+
+    #[test]
+    fn test_get_bytes() {
+        register_config(
+            "lenient_bytes",
+            json!({
+                "padded": "aGVsbG8gd29ybGQ=",
+                "unpadded": "aGVsbG8gd29ybGQ",
+                "url_safe": "-_8",
+                "standard": "+/8=",
+                "empty": "",
+                "corrupt": "c2VjcmV0*c2VjcmV",
+                "bad_padding": "aGVsbG8===",
+                "bad_length": "aGVsb",
+                "number": 12
+            }),
+        );
+        let bytes = |key: &str| get_bytes(format!("lenient_bytes.{key}"));
+        assert_eq!(bytes("padded").unwrap(), b"hello world");
+        assert_eq!(bytes("unpadded").unwrap(), b"hello world");
+        assert_eq!(bytes("url_safe").unwrap(), [0xfb, 0xff]);
+        assert_eq!(bytes("standard").unwrap(), [0xfb, 0xff]);
+        assert!(bytes("empty").unwrap().is_empty());
+
+        let err = bytes("corrupt").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"type mismatch at "lenient_bytes.corrupt": invalid base64: invalid character at offset 8"#
+        );
+        assert!(!err.to_string().contains("c2VjcmV0"));
+        for key in ["bad_padding", "bad_length", "number"] {
+            assert!(
+                matches!(bytes(key), Err(ConfigError::TypeMismatch { .. })),
+                "{key}"
+            );
+        }
+
+        assert_eq!(
+            get_bytes_exact("lenient_bytes.padded", 11).unwrap(),
+            b"hello world"
+        );
+        let err = get_bytes_exact("lenient_bytes.padded", 32).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("expected 32 bytes of base64 data, found 11"),
+            "{err}"
+        );
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_get_uuid_forms() {
+        register_config(
+            "lenient_uuid",
+            json!({
+                "hyphenated": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+                "simple": "67e5504410b1426f9247bb680e5fe0c8",
+                "urn": "urn:uuid:67e55044-10b1-426f-9247-bb680e5fe0c8",
+                "braced": "{67e55044-10b1-426f-9247-bb680e5fe0c8}",
+                "malformed": "67e55044-10b1-426f-9247",
+                "list": ["67e55044-10b1-426f-9247-bb680e5fe0c8", "not-a-uuid"]
+            }),
+        );
+        let expected = uuid::Uuid::from_u128(0x67e55044_10b1_426f_9247_bb680e5fe0c8);
+        for form in ["hyphenated", "simple", "urn", "braced"] {
+            assert_eq!(
+                get_uuid(format!("lenient_uuid.{form}")).unwrap(),
+                expected,
+                "{form}"
+            );
+        }
+
+        let err = get_uuid("lenient_uuid.malformed").unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with(r#"type mismatch at "lenient_uuid.malformed": invalid UUID"#),
+            "{err}"
+        );
+        let err = get_uuid_list("lenient_uuid.list").unwrap_err();
+        assert!(
+            matches!(err, ConfigError::TypeMismatch { ref path, .. } if path == "lenient_uuid.list.1")
+        );
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("0"), Ok(0));
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("512MiB"), Ok(536_870_912));
+        assert_eq!(parse_size("512 mib"), Ok(536_870_912));
+        assert_eq!(parse_size("2GB"), Ok(2_000_000_000));
+        assert_eq!(parse_size("1.5GiB"), Ok(1_610_612_736));
+        assert_eq!(parse_size("1.5kb"), Ok(1500));
+        assert_eq!(parse_size("0.1KiB"), Ok(102));
+        assert_eq!(parse_size(".5KB"), Ok(500));
+        assert_eq!(parse_size("16EiB"), Err("the size is too large"));
+        assert_eq!(parse_size("18446744073709551615B"), Ok(u64::MAX));
+        assert_eq!(
+            parse_size("18446744073709551616"),
+            Err("the size is too large")
+        );
+        assert!(parse_size("5 fortnights").is_err());
+        assert!(parse_size("1.2.3MB").is_err());
+        assert!(parse_size("MB").is_err());
+    }
+
+    #[test]
+    fn test_get_size() {
+        register_config(
+            "lenient_size",
+            json!({ "text": "2 MiB", "bytes": 10, "negative": -1, "huge": "20EB" }),
+        );
+        assert_eq!(get_size("lenient_size.text").unwrap(), 2 * 1024 * 1024);
+        assert_eq!(get_size("lenient_size.bytes").unwrap(), 10);
+        assert!(get_size("lenient_size.negative").is_err());
+        let err = get_size("lenient_size.huge").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"type mismatch at "lenient_size.huge": invalid size "20EB": the size is too large"#
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_get_datetime() {
+        register_config(
+            "lenient_chrono",
+            json!({
+                "offset": "2024-03-01T02:00:00+01:00",
+                "utc": "2024-03-01T01:00:00Z",
+                "seconds": 1709254800,
+                "seconds_text": "1709254800",
+                "millis": 1709254800000u64,
+                "naive": "2024-03-01T01:00:00",
+                "flag": true,
+                "min": i64::MIN
+            }),
+        );
+        let expected = chrono::DateTime::from_timestamp(1709254800, 0).unwrap();
+        for form in ["offset", "utc", "seconds", "seconds_text", "millis"] {
+            assert_eq!(
+                get_datetime(format!("lenient_chrono.{form}")).unwrap(),
+                expected,
+                "{form}"
+            );
+        }
+
+        let err = get_datetime("lenient_chrono.naive").unwrap_err();
+        assert!(
+            err.to_string().contains("invalid RFC 3339 timestamp"),
+            "{err}"
+        );
+        assert!(matches!(
+            get_datetime("lenient_chrono.flag"),
+            Err(ConfigError::TypeMismatch { .. })
+        ));
+        let err = get_datetime("lenient_chrono.min").unwrap_err();
+        assert!(err.to_string().contains("out of range"), "{err}");
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_get_offset_datetime() {
+        register_config(
+            "lenient_time",
+            json!({
+                "offset": "2024-03-01T02:00:00+01:00",
+                "seconds": 1709254800,
+                "millis": 1709254800000u64,
+                "naive": "2024-03-01T01:00:00"
+            }),
+        );
+        let offset = get_offset_datetime("lenient_time.offset").unwrap();
+        assert_eq!(offset.offset().whole_hours(), 1);
+        assert_eq!(offset.unix_timestamp(), 1709254800);
+        assert_eq!(get_offset_datetime("lenient_time.seconds").unwrap(), offset);
+        assert_eq!(get_offset_datetime("lenient_time.millis").unwrap(), offset);
+        assert!(get_offset_datetime("lenient_time.naive").is_err());
+    }
+}
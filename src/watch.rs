@@ -0,0 +1,316 @@
+//! Reloading a configuration file when it changes on disk, with `notify`.
+
+use std::ffi::OsString;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::config::{ConfigError, Format};
+use crate::loader::{load_text, read_file};
+
+/// How [`watch_file`] reloads a file. Override individual fields with struct update
+/// syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchOptions {
+    /// The format of the file. Defaults to `None`, which takes the format from the
+    /// extension, as [`load_file`](crate::config::load_file) does.
+    pub format: Option<Format>,
+    /// How long the file must go without changing before it is reloaded, so that the
+    /// burst of events of a single save, such as a truncation followed by several writes,
+    /// leads to a single reload of the finished file. Defaults to 250 milliseconds.
+    pub debounce: Duration,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        WatchOptions {
+            format: None,
+            debounce: Duration::from_millis(250),
+        }
+    }
+}
+
+/// What the worker of a [`WatchHandle`] has done so far.
+#[derive(Debug, Default)]
+struct WatchState {
+    events: u64,
+    reloads: u64,
+    last_error: Option<String>,
+}
+
+/// What the watcher sends the worker.
+enum Message {
+    Event(notify::Result<Event>),
+    Stop,
+}
+
+/// A running [`watch_file`] watcher. Stopping or dropping the handle ends it.
+#[derive(Debug)]
+pub struct WatchHandle {
+    state: Arc<Mutex<WatchState>>,
+    watcher: Option<RecommendedWatcher>,
+    stop: Sender<Message>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl WatchHandle {
+    /// The number of events seen that may have changed the file, many of which may lead
+    /// to a single reload.
+    pub fn events(&self) -> u64 {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .events
+    }
+
+    /// The number of times the file was reloaded successfully.
+    pub fn reloads(&self) -> u64 {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .reloads
+    }
+
+    /// The error of the last reload, if it failed, in which case the store kept the
+    /// configuration it had. Cleared by the next reload that succeeds.
+    pub fn last_error(&self) -> Option<String> {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .last_error
+            .clone()
+    }
+
+    /// Stops watching the file and waits for the worker to finish. A reload in progress
+    /// completes first; no further reload starts, even for a change still waiting out
+    /// the debounce window.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        // Dropping the watcher ends its events; the worker may still be waiting for one.
+        self.watcher.take();
+        let _ = self.stop.send(Message::Stop);
+        if let Some(worker) = self.worker.take()
+            && worker.join().is_err()
+        {
+            log::error!("the configuration file watcher panicked");
+        }
+    }
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Watches the file at `path` and, each time it changes, loads it as
+/// [`load_file`](crate::config::load_file) does, deep-merging it into the store, which
+/// bumps the
+/// [version](crate::config::get_config_version) of the configuration. Watching ends when
+/// the returned handle is stopped or dropped.
+///
+/// The file is not loaded when watching starts, since it is expected to have been loaded
+/// at startup. The directory of the file is watched rather than the file itself, so that
+/// the file is still followed when it is replaced by renaming another file over it, as
+/// editors do on save, or when a symbolic link to it is swapped, as Kubernetes does to
+/// update a mounted ConfigMap. While the file is missing nothing is loaded, and it is
+/// loaded again when it reappears.
+///
+/// The events of a change are coalesced: the file is reloaded once no event has come for
+/// the [debounce](WatchOptions::debounce) window, and not at all if its contents are
+/// the same as those loaded last, or as when watching started. The
+/// [events](WatchHandle::events) and [reloads](WatchHandle::reloads) of the handle count
+/// both.
+///
+/// A reload that fails, such as one of a file that no longer parses, is logged as a
+/// warning and recorded as the [last error](WatchHandle::last_error); the store keeps the
+/// configuration it had, and the next change is loaded again.
+///
+/// Fails with [`ConfigError::Watch`] if `path` has no file name, or if its directory
+/// cannot be watched, for example because it does not exist, or if the thread that
+/// reloads it cannot be spawned.
+///
+/// # Examples
+///
+/// ```rust
+/// use zirv_config::config::{self, WatchOptions};
+///
+/// # let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("flags.json");
+/// std::fs::write(&path, r#"{ "flags": { "beta": false } }"#).unwrap();
+/// config::load_file(&path).unwrap();
+///
+/// let handle = config::watch_file(&path, &WatchOptions::default()).unwrap();
+/// assert_eq!(handle.last_error(), None);
+/// handle.stop();
+/// ```
+pub fn watch_file(
+    path: impl AsRef<Path>,
+    options: &WatchOptions,
+) -> Result<WatchHandle, ConfigError> {
+    let path = path.as_ref().to_path_buf();
+    let watch_error = |message: String| ConfigError::Watch {
+        path: path.clone(),
+        message,
+    };
+    let name = path
+        .file_name()
+        .ok_or_else(|| watch_error("the path does not name a file".to_string()))?
+        .to_os_string();
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let (events, messages) = mpsc::channel();
+    let stop = events.clone();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = events.send(Message::Event(event));
+    })
+    .map_err(|err| watch_error(err.to_string()))?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|err| watch_error(err.to_string()))?;
+
+    let state = Arc::new(Mutex::new(WatchState::default()));
+    // Looked at before returning, so that a change made right after is not missed.
+    let worker = Worker {
+        target: fs::canonicalize(&path).ok(),
+        digest: fs::read_to_string(&path).ok().as_deref().map(digest),
+        path: path.clone(),
+        name,
+        format: options.format,
+        debounce: options.debounce,
+        state: Arc::clone(&state),
+    };
+    let worker = thread::Builder::new()
+        .name("zirv-config-watch".to_string())
+        .spawn(move || worker.run(messages))
+        .map_err(|err| watch_error(format!("cannot spawn the watcher thread: {err}")))?;
+    Ok(WatchHandle {
+        state,
+        watcher: Some(watcher),
+        stop,
+        worker: Some(worker),
+    })
+}
+
+/// The worker of a [`WatchHandle`], with what it knows of the file.
+struct Worker {
+    path: PathBuf,
+    name: OsString,
+    format: Option<Format>,
+    debounce: Duration,
+    /// Where the path resolved to after the last event, through any symbolic links.
+    target: Option<PathBuf>,
+    /// The hash of the contents that were last loaded.
+    digest: Option<u64>,
+    state: Arc<Mutex<WatchState>>,
+}
+
+impl Worker {
+    /// Waits for events and reloads the file once they have stopped coming for the
+    /// debounce window, until told to stop.
+    fn run(mut self, messages: Receiver<Message>) {
+        let mut due: Option<Instant> = None;
+        loop {
+            let message = match due {
+                Some(at) => {
+                    match messages.recv_timeout(at.saturating_duration_since(Instant::now())) {
+                        Ok(message) => message,
+                        Err(RecvTimeoutError::Timeout) => {
+                            due = None;
+                            self.reload();
+                            continue;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                None => match messages.recv() {
+                    Ok(message) => message,
+                    Err(_) => break,
+                },
+            };
+            let event = match message {
+                Message::Stop => break,
+                Message::Event(Ok(event)) => event,
+                Message::Event(Err(err)) => {
+                    log::warn!("watching {} failed: {err}", self.path.display());
+                    continue;
+                }
+            };
+            if self.concerns(&event) {
+                self.state
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .events += 1;
+                due = Some(Instant::now() + self.debounce);
+            }
+        }
+    }
+
+    /// Tells whether `event` may have changed the file.
+    fn concerns(&mut self, event: &Event) -> bool {
+        // Reading the file is itself an access; reacting to it would never stop.
+        if event.kind.is_access() {
+            return false;
+        }
+        // A swapped symbolic link changes the file without an event naming it.
+        let resolved = fs::canonicalize(&self.path).ok();
+        let named = event
+            .paths
+            .iter()
+            .any(|changed| changed.file_name() == Some(self.name.as_os_str()));
+        let swapped = resolved != self.target;
+        self.target = resolved;
+        named || swapped
+    }
+
+    /// Loads the file, unless it is missing or holds what was loaded last.
+    fn reload(&mut self) {
+        if !self.path.exists() {
+            return;
+        }
+        let result = read_file(&self.path).and_then(|text| {
+            let digest = digest(&text);
+            if self.digest == Some(digest) {
+                return Ok(false);
+            }
+            // Remembered even if loading fails, so that the same mistake is not
+            // reported again.
+            self.digest = Some(digest);
+            load_text(&self.path, self.format, &text).map(|()| true)
+        });
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        match result {
+            Ok(false) => {}
+            Ok(true) => {
+                state.reloads += 1;
+                state.last_error = None;
+            }
+            Err(err) => {
+                log::warn!(
+                    "reloading {} failed, keeping the previous configuration: {err}",
+                    self.path.display()
+                );
+                state.last_error = Some(err.to_string());
+            }
+        }
+    }
+}
+
+/// Hashes the contents of the file, to tell whether they changed.
+fn digest(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
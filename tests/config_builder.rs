@@ -0,0 +1,91 @@
+use serde_json::json;
+use zirv_config::config::{self, ConfigBuilder, ConfigError};
+
+// Building installs the whole configuration, so this binary holds a single test.
+#[test]
+fn test_builder_precedence_and_report() {
+    let dir = tempfile::tempdir().unwrap();
+    let default = dir.path().join("default.json");
+    let local = dir.path().join("local.json");
+    let missing = dir.path().join("missing.json");
+    std::fs::write(
+        &default,
+        r#"{ "builder": { "host": "localhost", "port": 80, "workers": 2, "level": "info" } }"#,
+    )
+    .unwrap();
+    std::fs::write(&local, r#"{ "builder": { "port": 81, "workers": 4 } }"#).unwrap();
+    unsafe {
+        std::env::set_var("BUILDER_TEST_BUILDER__WORKERS", "8");
+        std::env::set_var("BUILDER_TEST_BUILDER__LEVEL", "debug");
+        std::env::set_var("BUILDER_TEST_BUILDER__LEVLE", "trace");
+    }
+    config::register_config("builder", json!({ "host": "0.0.0.0", "tls": false }));
+
+    // An override through a scalar fails the build before anything is loaded.
+    let err = ConfigBuilder::new()
+        .file(&default)
+        .overrides([("builder", json!(1)), ("builder.port", json!(2))])
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, ConfigError::NotAnObject { .. }), "{err}");
+    assert_eq!(config::get_config_by_key("builder.port"), None);
+
+    // The steps are given out of order; precedence is fixed by their kind.
+    let report = ConfigBuilder::new()
+        .overrides([("builder.level", "warn")])
+        .overrides([("builder.tls", true)])
+        .env_prefix("BUILDER_TEST")
+        .file(&default)
+        .file_optional(&missing)
+        .file_optional(&local)
+        .build()
+        .unwrap();
+    assert_eq!(
+        config::get_config_by_key("builder"),
+        Some(json!({
+            "host": "localhost",
+            "port": 81,
+            "workers": "8",
+            "level": "warn",
+            "levle": "trace",
+            "tls": true,
+        }))
+    );
+    assert_eq!(
+        report.sources,
+        [
+            default.display().to_string(),
+            local.display().to_string(),
+            "environment variables BUILDER_TEST_*".to_string(),
+            "overrides".to_string(),
+        ]
+    );
+    assert_eq!(report.missing_files, [missing.as_path()]);
+    assert_eq!(report.unknown_env_vars, ["BUILDER_TEST_BUILDER__LEVLE"]);
+    assert_eq!(
+        report.warnings(),
+        [
+            format!("optional file {} does not exist", missing.display()),
+            "environment variable BUILDER_TEST_BUILDER__LEVLE sets a key that nothing else defines"
+                .to_string(),
+        ]
+    );
+
+    // The steps are sources now, so a reload loads them again.
+    std::fs::write(&local, r#"{ "builder": { "port": 82 } }"#).unwrap();
+    config::reload_config().unwrap();
+    assert_eq!(config::get_config_by_key("builder.port"), Some(json!(82)));
+    assert_eq!(
+        config::get_config_by_key("builder.workers"),
+        Some(json!("8"))
+    );
+
+    // A required file that is missing fails the build and leaves everything as it was.
+    let err = ConfigBuilder::new().file(&missing).build().unwrap_err();
+    assert!(
+        matches!(&err, ConfigError::SourceFailed { name, .. } if *name == missing.display().to_string()),
+        "{err}"
+    );
+    config::reload_config().unwrap();
+    assert_eq!(config::get_config_by_key("builder.port"), Some(json!(82)));
+}
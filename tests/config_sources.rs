@@ -0,0 +1,353 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use serde_json::{Value, json};
+use zirv_config::config::{
+    self, APPLICATION_PRIORITY, ConfigError, ConfigSource, Layer, ReloadOptions,
+};
+
+/// Sources are global and every reload loads all of them, so the tests take turns.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+fn serial() -> MutexGuard<'static, ()> {
+    SERIAL.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A source whose document the test can change between reloads, or make fail.
+struct ToySource {
+    name: &'static str,
+    document: Arc<Mutex<Result<Value, String>>>,
+}
+
+impl ToySource {
+    fn add(
+        name: &'static str,
+        document: Value,
+        priority: i32,
+    ) -> Arc<Mutex<Result<Value, String>>> {
+        let document = Arc::new(Mutex::new(Ok(document)));
+        config::add_source(
+            Box::new(ToySource {
+                name,
+                document: Arc::clone(&document),
+            }),
+            priority,
+        );
+        document
+    }
+}
+
+impl ConfigSource for ToySource {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn load(&self) -> Result<Value, ConfigError> {
+        self.document
+            .lock()
+            .unwrap()
+            .clone()
+            .map_err(|message| ConfigError::Network {
+                url: "toy://".to_string(),
+                message,
+            })
+    }
+}
+
+#[test]
+fn test_sources_layer_by_priority_around_registered_blocks() {
+    let _serial = serial();
+    // Added out of order, to show that the priority decides, not the order of adding.
+    let overrides = ToySource::add(
+        "overrides",
+        json!({ "sources_order": { "port": 9000 } }),
+        20,
+    );
+    ToySource::add(
+        "defaults",
+        json!({ "sources_order": { "host": "localhost", "port": 80, "debug": false, "tls": false } }),
+        -10,
+    );
+    ToySource::add(
+        "environment",
+        json!({ "sources_order": { "port": 8080, "debug": true } }),
+        10,
+    );
+    config::register_config(
+        "sources_order",
+        json!({ "host": "0.0.0.0", "port": 3000, "tls": true }),
+    );
+
+    config::reload_config().unwrap();
+    assert_eq!(
+        config::get_config_by_key("sources_order"),
+        Some(json!({ "host": "0.0.0.0", "port": 9000, "debug": true, "tls": true }))
+    );
+
+    // A reload picks up changed documents and keeps runtime writes over the registered
+    // blocks, beneath the sources above them.
+    *overrides.lock().unwrap() = Ok(json!({ "sources_order": { "tls": false } }));
+    config::set_config_by_key("sources_order.host", "example.com").unwrap();
+    config::set_config_by_key("sources_order.port", 1234).unwrap();
+    config::reload_config().unwrap();
+    assert_eq!(
+        config::get_config_by_key("sources_order"),
+        Some(json!({ "host": "example.com", "port": 8080, "debug": true, "tls": false }))
+    );
+
+    // A failing source fails the reload, naming itself, and leaves the configuration as
+    // it was.
+    *overrides.lock().unwrap() = Err("connection refused".to_string());
+    let err = config::reload_config().unwrap_err();
+    *overrides.lock().unwrap() = Ok(json!({ "sources_order": { "port": 1 } }));
+    assert_eq!(
+        err.to_string(),
+        "configuration source \"overrides\" failed: cannot fetch toy://: connection refused"
+    );
+    assert_eq!(
+        config::get_config_by_key("sources_order.port"),
+        Some(json!(8080))
+    );
+
+    // Unregistered blocks no longer sit over the sources below them.
+    config::unregister_config("sources_order");
+    config::reload_config().unwrap();
+    assert_eq!(
+        config::get_config_by_key("sources_order"),
+        Some(json!({ "host": "localhost", "port": 1, "debug": true, "tls": false }))
+    );
+}
+
+#[test]
+fn test_a_source_at_the_application_priority_overrides_registered_blocks() {
+    let _serial = serial();
+    config::register_config("sources_tie", json!({ "level": "registered" }));
+    ToySource::add(
+        "tie",
+        json!({ "sources_tie": { "level": "source" } }),
+        APPLICATION_PRIORITY,
+    );
+    config::reload_config().unwrap();
+    assert_eq!(
+        config::get_config_by_key("sources_tie.level"),
+        Some(json!("source"))
+    );
+}
+
+#[test]
+fn test_reloads_keep_loaded_files_and_runtime_writes() {
+    let _serial = serial();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("loaded.json");
+    std::fs::write(
+        &path,
+        r#"{ "sources_loaded": { "workers": 4, "queue": "jobs" } }"#,
+    )
+    .unwrap();
+    config::load_json_file(&path).unwrap();
+    ToySource::add(
+        "below_loaded",
+        json!({ "sources_loaded": { "workers": 1, "region": "eu" } }),
+        -5,
+    );
+    config::set_config_by_key("sources_loaded.queue", "urgent").unwrap();
+    config::push_config("sources_loaded.tags", "a").unwrap();
+    config::merge_config(json!({ "sources_merged": { "enabled": true } })).unwrap();
+    config::transaction(|tx| {
+        tx.set("sources_loaded.mode", "tx");
+    })
+    .unwrap();
+    config::update_config(|map| map.insert("sources_updated".to_string(), json!({ "n": 1 })));
+    config::set_config_by_key("sources_loaded.dropped", true).unwrap();
+    config::remove_config_by_key("sources_loaded.dropped").unwrap();
+
+    config::reload_config().unwrap();
+    assert_eq!(
+        config::get_config_by_key("sources_loaded"),
+        Some(
+            json!({ "workers": 4, "queue": "urgent", "region": "eu", "tags": ["a"], "mode": "tx" })
+        )
+    );
+    assert_eq!(
+        config::get_config_by_key("sources_merged.enabled"),
+        Some(json!(true))
+    );
+    assert_eq!(
+        config::get_config_by_key("sources_updated.n"),
+        Some(json!(1))
+    );
+
+    // A block registered over runtime writes replaces them, in reloads too.
+    config::register_config("sources_merged", json!({ "level": 2 }));
+    config::reload_config().unwrap();
+    assert_eq!(
+        config::get_config_by_key("sources_merged"),
+        Some(json!({ "level": 2 }))
+    );
+
+    assert!(config::remove_source("below_loaded"));
+    config::unregister_config("sources_loaded");
+    config::unregister_config("sources_merged");
+    config::unregister_config("sources_updated");
+}
+
+#[test]
+fn test_layers_are_file_sources() {
+    let _serial = serial();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("base.json");
+    std::fs::write(&path, r#"{ "sources_file": { "workers": 2 } }"#).unwrap();
+    let local = dir.path().join("local.json");
+    config::add_source(Box::new(Layer::required(&path)), -1);
+    config::add_source(Box::new(Layer::optional(&local)), 1);
+
+    config::reload_config().unwrap();
+    assert_eq!(
+        config::get_config_by_key("sources_file.workers"),
+        Some(json!(2))
+    );
+
+    std::fs::write(&path, r#"{ "sources_file": { "workers": 8 } }"#).unwrap();
+    assert_eq!(
+        config::get_config_by_key("sources_file.workers"),
+        Some(json!(2))
+    );
+    config::reload_config().unwrap();
+    assert_eq!(
+        config::get_config_by_key("sources_file.workers"),
+        Some(json!(8))
+    );
+
+    assert!(config::remove_source(&path.display().to_string()));
+    assert!(config::remove_source(&local.display().to_string()));
+    assert!(!config::remove_source(&local.display().to_string()));
+}
+
+#[test]
+fn test_reload_report_and_keeping_failed_sources() {
+    let _serial = serial();
+    let flaky = ToySource::add("flaky", json!({ "sources_keep": { "a": 1, "b": 2 } }), 5);
+    let steady = ToySource::add("steady", json!({ "sources_keep": { "c": 3 } }), 6);
+    config::reload_config().unwrap();
+
+    *flaky.lock().unwrap() = Err("timed out".to_string());
+    *steady.lock().unwrap() = Ok(json!({ "sources_keep": { "c": 4, "d": [5, 6] } }));
+    assert!(config::reload_config().is_err());
+    assert_eq!(config::get_config_by_key("sources_keep.c"), Some(json!(3)));
+
+    let options = ReloadOptions { keep_failed: true };
+    let report = config::reload_config_with(&options).unwrap();
+    *flaky.lock().unwrap() = Ok(json!({ "sources_keep": { "a": 1 } }));
+    assert_eq!(
+        config::get_config_by_key("sources_keep"),
+        Some(json!({ "a": 1, "b": 2, "c": 4, "d": [5, 6] }))
+    );
+    // `c` changed, and `d.0` and `d.1` were added.
+    assert_eq!(report.keys_changed, 3);
+    let failed: Vec<_> = report.failed().map(|status| status.name.as_str()).collect();
+    assert_eq!(failed, ["flaky"]);
+    let steady_status = report
+        .sources
+        .iter()
+        .find(|status| status.name == "steady")
+        .unwrap();
+    assert_eq!(steady_status.priority, 6);
+    assert!(steady_status.error.is_none());
+
+    // Once the source recovers, its new document replaces the kept one.
+    let report = config::reload_config().unwrap();
+    assert_eq!(report.keys_changed, 1);
+    assert_eq!(config::get_config_by_key("sources_keep.b"), None);
+    assert!(config::remove_source("flaky"));
+    assert!(config::remove_source("steady"));
+}
+
+/// A source that declares the namespaces it provides.
+struct Declared(Value);
+
+impl ConfigSource for Declared {
+    fn name(&self) -> &str {
+        "declared"
+    }
+
+    fn load(&self) -> Result<Value, ConfigError> {
+        Ok(self.0.clone())
+    }
+
+    fn namespaces(&self) -> Option<Vec<String>> {
+        Some(vec!["sources_features".to_string()])
+    }
+}
+
+#[test]
+fn test_reload_namespace_replaces_only_that_namespace() {
+    let _serial = serial();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("features.json");
+    std::fs::write(
+        &path,
+        r#"{ "sources_features": { "beta": false, "dark_mode": false }, "sources_limits": { "rps": 10 } }"#,
+    )
+    .unwrap();
+    config::add_source(Box::new(Layer::required(&path)), 10);
+    let server = ToySource::add("server", json!({ "sources_server": { "port": 80 } }), 5);
+    config::register_config("sources_features", json!({ "beta": true, "search": true }));
+    config::reload_config().unwrap();
+
+    // The server block changes in memory and in its source, and the file changes.
+    config::set_config_by_key("sources_server.port", 8080).unwrap();
+    *server.lock().unwrap() = Ok(json!({ "sources_server": { "port": 1 } }));
+    std::fs::write(
+        &path,
+        r#"{ "sources_features": { "dark_mode": true }, "sources_limits": { "rps": 99 } }"#,
+    )
+    .unwrap();
+    config::add_source(
+        Box::new(Declared(json!({ "sources_features": { "search": false } }))),
+        20,
+    );
+
+    config::reload_namespace("sources_features").unwrap();
+    assert_eq!(
+        config::get_config_by_key("sources_features"),
+        Some(json!({ "beta": true, "dark_mode": true, "search": false }))
+    );
+    // Other namespaces keep their runtime writes, even those of the reloaded file.
+    assert_eq!(
+        config::get_config_by_key("sources_server.port"),
+        Some(json!(8080))
+    );
+    assert_eq!(
+        config::get_config_by_key("sources_limits.rps"),
+        Some(json!(10))
+    );
+
+    // A nested namespace replaces just that block.
+    std::fs::write(
+        &path,
+        r#"{ "sources_features": { "dark_mode": false, "beta": false } }"#,
+    )
+    .unwrap();
+    config::set_config_by_key("sources_features.search", true).unwrap();
+    config::reload_namespace("sources_features.dark_mode").unwrap();
+    assert_eq!(
+        config::get_config_by_key("sources_features"),
+        Some(json!({ "beta": true, "dark_mode": false, "search": true }))
+    );
+
+    // A failing source fails the reload and leaves the namespace as it was.
+    std::fs::write(&path, "{ not json").unwrap();
+    assert!(config::reload_namespace("sources_features").is_err());
+    assert_eq!(
+        config::get_config_by_key("sources_features.search"),
+        Some(json!(true))
+    );
+    assert!(matches!(
+        config::reload_namespace("sources_features..beta"),
+        Err(ConfigError::InvalidPath { .. })
+    ));
+
+    config::unregister_config("sources_features");
+    assert!(config::remove_source(&path.display().to_string()));
+    assert!(config::remove_source("server"));
+    assert!(config::remove_source("declared"));
+}
@@ -0,0 +1,37 @@
+use serde_json::json;
+use zirv_config::config::{self, ConfigError};
+
+#[test]
+fn test_config_version_counts_successful_writes() {
+    assert_eq!(config::get_config_version(), 0);
+
+    config::register_config("server", json!({ "port": 3000 }));
+    assert_eq!(config::get_config_version(), 1);
+    config::set_config_by_key("server.port", 8080).unwrap();
+    assert_eq!(config::get_config_version(), 2);
+    config::merge_config(json!({ "server": { "host": "::" } })).unwrap();
+    config::push_config("server.aliases", "api").unwrap();
+    config::remove_config_by_key("server.aliases");
+    assert_eq!(config::get_config_version(), 5);
+    config::transaction(|tx| {
+        tx.set("server.port", 1).set("server.workers", 4);
+    })
+    .unwrap();
+    assert_eq!(config::get_config_version(), 6);
+
+    // Reads leave the version alone.
+    let (snapshot, version) = config::get_config_versioned();
+    assert_eq!(version, 6);
+    assert_eq!(snapshot["server"]["port"], json!(1));
+    config::get_config();
+    config::get_config_by_key("server.port");
+    config::get_config_as::<u16>("server.port").unwrap();
+    assert_eq!(config::get_config_version(), 6);
+
+    // So do failed writes.
+    let err = config::set_config_by_key("server.port.inner", 1).unwrap_err();
+    assert!(matches!(err, ConfigError::NotAnObject { .. }));
+    assert!(config::try_register_config(" ", json!({})).is_err());
+    assert!(config::merge_config(json!([1])).is_err());
+    assert_eq!(config::get_config_version(), 6);
+}
@@ -0,0 +1,100 @@
+use std::panic;
+
+use serde_json::json;
+use zirv_config::config::{self, ConfigError};
+
+#[test]
+fn test_freeze_config() {
+    // Before freezing, every mutator works.
+    config::register_config("server", json!({ "port": 8080 }));
+    config::set_config_by_key("server.host", "localhost").unwrap();
+    config::merge_config_at("server", json!({ "workers": 4 })).unwrap();
+    assert!(!config::is_frozen());
+
+    config::freeze_config();
+    config::freeze_config();
+    assert!(config::is_frozen());
+
+    // Fallible mutators return `Frozen`.
+    let err = config::try_register_config("db", json!({})).unwrap_err();
+    assert!(matches!(err, ConfigError::Frozen));
+    assert_eq!(
+        err.to_string(),
+        "the configuration is frozen and can no longer be modified"
+    );
+    let err = config::set_config_by_key("server.port", 9090).unwrap_err();
+    assert!(matches!(err, ConfigError::Frozen));
+    assert!(matches!(
+        config::push_config("peers", 1),
+        Err(ConfigError::Frozen)
+    ));
+    assert!(matches!(
+        config::merge_config(json!({ "a": 1 })),
+        Err(ConfigError::Frozen)
+    ));
+    assert!(matches!(
+        config::transaction(|tx| {
+            tx.set("server.port", 1);
+        }),
+        Err(ConfigError::Frozen)
+    ));
+    assert!(matches!(
+        config::register_batch(|batch| {
+            batch.register("db", json!({}));
+        }),
+        Err(ConfigError::Frozen)
+    ));
+    assert!(matches!(
+        zirv_config::register_config!("db", json!({})),
+        Err(ConfigError::Frozen)
+    ));
+
+    // Panicking mutators panic with the same message.
+    let message = |result: std::thread::Result<()>| {
+        let payload = result.unwrap_err();
+        payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_default()
+    };
+    let frozen = "the configuration is frozen and can no longer be modified";
+    assert_eq!(
+        message(panic::catch_unwind(|| drop(config::register_config(
+            "db", 1
+        )))),
+        frozen
+    );
+    assert_eq!(
+        message(panic::catch_unwind(|| config::update_config(|_| ()))),
+        frozen
+    );
+    assert_eq!(
+        message(panic::catch_unwind(|| drop(config::replace_config(
+            Default::default()
+        )))),
+        frozen
+    );
+    assert_eq!(
+        message(panic::catch_unwind(|| drop(config::unregister_config(
+            "server"
+        )))),
+        frozen
+    );
+    assert_eq!(
+        message(panic::catch_unwind(|| drop(config::get_or_register(
+            "db",
+            || 1
+        )))),
+        frozen
+    );
+
+    // Reads are unaffected, including `get_or_register` on a registered namespace.
+    assert_eq!(
+        config::get_config_by_key("server"),
+        Some(json!({ "host": "localhost", "port": 8080, "workers": 4 }))
+    );
+    assert_eq!(config::get_required("server.port").unwrap(), json!(8080));
+    assert_eq!(config::get_config_by_key("db"), None);
+    assert_eq!(config::get_or_register("server", || 0)["port"], json!(8080));
+    assert_eq!(config::get_config_as::<u16>("server.workers").unwrap(), 4);
+}
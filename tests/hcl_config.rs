@@ -0,0 +1,62 @@
+#![cfg(feature = "hcl")]
+
+use serde_json::json;
+use zirv_config::{config, read_config};
+
+const SERVICES_HCL: &str = r#"
+# Managed alongside the Terraform modules.
+cluster = "prod-eu"
+
+service "api" {
+  port     = 8443
+  replicas = 4
+
+  tls {
+    enabled = true
+    cert    = "/etc/tls/api.pem"
+  }
+}
+
+service "worker" {
+  port    = 9000
+  queues  = ["default", "mail"]
+  backoff = { initial = 0.5, max = 30 }
+}
+"#;
+
+#[test]
+fn test_load_hcl_file_end_to_end() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("services.hcl");
+    std::fs::write(&path, SERVICES_HCL).unwrap();
+    config::load_hcl_file(&path).unwrap();
+
+    assert_eq!(read_config!("cluster", String).unwrap(), "prod-eu");
+    assert_eq!(read_config!("service.api.port" as u16).unwrap(), 8443);
+    assert!(read_config!("service.api.tls.enabled", bool).unwrap());
+    assert_eq!(
+        read_config!("service.worker.queues"),
+        Some(json!(["default", "mail"]))
+    );
+    assert_eq!(
+        read_config!("service.worker.backoff.initial", f64).unwrap(),
+        0.5
+    );
+
+    // A file with an expression is rejected as a whole.
+    let computed = dir.path().join("computed.hcl");
+    std::fs::write(
+        &computed,
+        "service \"api\" {\n  replicas = 2\n  port = var.api_port\n}\n",
+    )
+    .unwrap();
+    let err = config::load_hcl_file(&computed).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "invalid HCL in {}: \"service.api.port\" is an attribute or index traversal; only literal values are supported",
+            computed.display()
+        )
+    );
+    assert_eq!(read_config!("service.api.replicas" as u32).unwrap(), 4);
+}
@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use serde_json::json;
+use zirv_config::config::{self, ConfigError, IncludeOptions};
+
+fn write(path: &Path, contents: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, contents).unwrap();
+}
+
+#[test]
+fn test_nested_includes_resolve_relative_to_the_including_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    write(
+        &dir.join("app.json"),
+        r#"{ "$include": "conf/base.json", "include_app": { "name": "billing", "db": { "$include": ["conf/db.json"], "pool": 20 } } }"#,
+    );
+    // `base.json` lives in `conf`, so its include resolves against `conf`, not `dir`.
+    write(
+        &dir.join("conf/base.json"),
+        r#"{ "$include": "shared/defaults.json", "include_app": { "name": "base", "region": "eu" } }"#,
+    );
+    write(
+        &dir.join("conf/shared/defaults.json"),
+        r#"{ "include_app": { "region": "us", "debug": false } }"#,
+    );
+    write(
+        &dir.join("conf/db.json"),
+        r#"{ "host": "db.internal", "pool": 5 }"#,
+    );
+
+    // The temporary directory is not the working directory, so the includes can only
+    // resolve against the files that name them.
+    config::load_file_with_includes(dir.join("app.json"), &IncludeOptions::default()).unwrap();
+    assert_eq!(
+        config::get_config_by_key("include_app"),
+        Some(json!({
+            "name": "billing",
+            "region": "eu",
+            "debug": false,
+            "db": { "host": "db.internal", "pool": 20 },
+        }))
+    );
+    assert_eq!(config::get_config_by_key("$include"), None);
+
+    let err =
+        config::load_file_with_includes(dir.join("app.json"), &IncludeOptions { max_depth: 1 })
+            .unwrap_err();
+    match err {
+        ConfigError::IncludeTooDeep { chain, max_depth } => {
+            assert_eq!(max_depth, 1);
+            assert_eq!(
+                chain,
+                [
+                    dir.join("app.json"),
+                    dir.join("conf/base.json"),
+                    dir.join("conf/shared/defaults.json"),
+                ]
+            );
+        }
+        other => panic!("unexpected error: {other}"),
+    }
+}
+
+#[test]
+fn test_include_cycle_is_reported_with_its_chain() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    write(
+        &dir.join("root.json"),
+        r#"{ "$include": "a.json", "include_cycle": 1 }"#,
+    );
+    write(&dir.join("a.json"), r#"{ "$include": "sub/b.json" }"#);
+    write(&dir.join("sub/b.json"), r#"{ "$include": ["../a.json"] }"#);
+
+    let err = config::load_file_with_includes(dir.join("root.json"), &IncludeOptions::default())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "include cycle: {} -> {} -> {}",
+            dir.join("a.json").display(),
+            dir.join("sub/b.json").display(),
+            dir.join("sub/../a.json").display()
+        )
+    );
+    assert_eq!(config::get_config_by_key("include_cycle"), None);
+
+    // Including the same file twice, side by side, is not a cycle.
+    write(
+        &dir.join("twice.json"),
+        r#"{ "include_twice": { "x": { "$include": "leaf.json" }, "y": { "$include": "leaf.json" } } }"#,
+    );
+    write(&dir.join("leaf.json"), r#"{ "v": 1 }"#);
+    config::load_file_with_includes(dir.join("twice.json"), &IncludeOptions::default()).unwrap();
+    assert_eq!(
+        config::get_config_by_key("include_twice"),
+        Some(json!({ "x": { "v": 1 }, "y": { "v": 1 } }))
+    );
+}
+
+#[test]
+fn test_missing_and_malformed_includes() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    write(
+        &dir.join("app.json"),
+        r#"{ "include_missing": { "$include": "secrets.json" } }"#,
+    );
+    let err = config::load_file_with_includes(dir.join("app.json"), &IncludeOptions::default())
+        .unwrap_err();
+    assert!(
+        matches!(&err, ConfigError::Io { path, .. } if *path == dir.join("secrets.json")),
+        "{err}"
+    );
+    assert_eq!(config::get_config_by_key("include_missing"), None);
+
+    write(
+        &dir.join("app.json"),
+        r#"{ "include_malformed": { "$include": 5 } }"#,
+    );
+    let err = config::load_file_with_includes(dir.join("app.json"), &IncludeOptions::default())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "invalid JSON in {}: \"include_malformed.$include\" must be a path or a list of paths",
+            dir.join("app.json").display()
+        )
+    );
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "ini")]
+
+use zirv_config::{config, read_config};
+
+const LEGACY_INI: &str = "\
+; Deployed by the old provisioning scripts.
+environment = production
+
+[database]
+host = db.internal
+port = 5432
+dsn = host=db.internal port=5432 sslmode=require
+pool = 10
+pool = 20
+
+[features]
+audit = true
+";
+
+#[test]
+fn test_load_ini_file_end_to_end() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("legacy.ini");
+    std::fs::write(&path, LEGACY_INI).unwrap();
+
+    config::load_ini_file(&path).unwrap();
+    assert_eq!(read_config!("environment", String).unwrap(), "production");
+    assert_eq!(read_config!("database.port", String).unwrap(), "5432");
+    assert_eq!(
+        read_config!("database.dsn", String).unwrap(),
+        "host=db.internal port=5432 sslmode=require"
+    );
+    assert_eq!(read_config!("database.pool", String).unwrap(), "20");
+    assert!(read_config!("features.audit", bool).is_err());
+
+    // Loading leniently again types the same entries.
+    config::load_ini_file_lenient(&path).unwrap();
+    assert_eq!(read_config!("database.port" as u16).unwrap(), 5432);
+    assert!(read_config!("features.audit", bool).unwrap());
+    assert_eq!(
+        read_config!("database.host", String).unwrap(),
+        "db.internal"
+    );
+}
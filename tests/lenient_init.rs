@@ -0,0 +1,44 @@
+use std::sync::Mutex;
+
+use log::{Level, Log, Metadata, Record};
+use serde_json::json;
+use zirv_config::config;
+
+/// Records every warning logged during the test.
+struct CaptureLogger(Mutex<Vec<String>>);
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CaptureLogger = CaptureLogger(Mutex::new(Vec::new()));
+
+#[test]
+fn test_lenient_mode_reads_an_uninitialized_store_as_empty() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Warn);
+
+    assert_eq!(config::try_get_config().unwrap(), json!({}));
+    assert_eq!(config::try_get_config_by_key("server.port").unwrap(), None);
+    assert!(matches!(
+        config::get_required("server.port"),
+        Err(config::ConfigError::KeyNotFound { .. })
+    ));
+    assert_eq!(config::get_config(), json!({}));
+
+    // The warning is logged once, not on every read.
+    assert_eq!(
+        *LOGGER.0.lock().unwrap(),
+        vec!["configuration read before init_config() was called"]
+    );
+}
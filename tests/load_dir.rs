@@ -0,0 +1,73 @@
+use serde_json::json;
+use zirv_config::config::{self, ConfigError};
+
+#[test]
+fn test_load_dir_layers_files_in_name_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    // Written out of order, to check that the file names decide.
+    std::fs::write(
+        dir.join("20-override.json"),
+        r#"{ "conf_d": { "db": { "port": 6432 }, "cache": "redis" } }"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("10-base.json"),
+        r#"{ "conf_d": { "db": { "host": "db.internal", "port": 5432 }, "cache": "memory" } }"#,
+    )
+    .unwrap();
+
+    config::load_dir(dir).unwrap();
+    assert_eq!(
+        config::get_config_by_key("conf_d"),
+        Some(json!({ "db": { "host": "db.internal", "port": 6432 }, "cache": "redis" }))
+    );
+}
+
+#[test]
+fn test_load_dir_skips_other_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    std::fs::write(dir.join("10-app.json"), r#"{ "conf_skip": { "a": 1 } }"#).unwrap();
+    std::fs::write(dir.join("README.md"), "# not configuration").unwrap();
+    std::fs::write(dir.join("20-app.json.bak"), "{ broken").unwrap();
+    std::fs::write(
+        dir.join(".30-hidden.json"),
+        r#"{ "conf_skip": { "a": 3 } }"#,
+    )
+    .unwrap();
+    std::fs::create_dir(dir.join("40-nested.json")).unwrap();
+
+    config::load_dir(dir).unwrap();
+    assert_eq!(
+        config::get_config_by_key("conf_skip"),
+        Some(json!({ "a": 1 }))
+    );
+}
+
+#[test]
+fn test_load_dir_aborts_on_a_failing_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    std::fs::write(dir.join("10-good.json"), r#"{ "conf_fail": { "a": 1 } }"#).unwrap();
+    std::fs::write(dir.join("20-bad.json"), r#"{ "conf_fail": "#).unwrap();
+
+    let err = config::load_dir(dir).unwrap_err();
+    match &err {
+        ConfigError::Parse { origin, .. } => assert!(origin.ends_with("20-bad.json"), "{err}"),
+        other => panic!("unexpected error: {other}"),
+    }
+    // Earlier files are not merged either.
+    assert_eq!(config::get_config_by_key("conf_fail"), None);
+
+    std::fs::write(dir.join("20-bad.json"), "[1, 2]").unwrap();
+    let err = config::load_dir(dir).unwrap_err();
+    assert!(
+        err.to_string()
+            .ends_with("20-bad.json: expected an object at the top level"),
+        "{err}"
+    );
+
+    let err = config::load_dir(dir.join("missing")).unwrap_err();
+    assert!(matches!(err, ConfigError::Io { .. }), "{err}");
+}
@@ -0,0 +1,175 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+use zirv_config::config::{self, ConfigError, Format};
+
+fn write(dir: &Path, name: &str, contents: &str) -> PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn test_load_file_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write(
+        dir.path(),
+        "app.JSON",
+        r#"{ "detect_json": { "port": 8080 } }"#,
+    );
+    config::load_file(&path).unwrap();
+    assert_eq!(
+        config::get_config_by_key("detect_json"),
+        Some(json!({ "port": 8080 }))
+    );
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_load_file_toml() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write(dir.path(), "app.toml", "[detect_toml]\nport = 8080\n");
+    config::load_file(&path).unwrap();
+    assert_eq!(
+        config::get_config_by_key("detect_toml"),
+        Some(json!({ "port": 8080 }))
+    );
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_load_file_yaml() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write(dir.path(), "app.yaml", "detect_yaml:\n  port: 8080\n");
+    config::load_file(&path).unwrap();
+    let path = write(dir.path(), "app.yml", "detect_yml:\n  port: 8081\n");
+    config::load_file(&path).unwrap();
+    assert_eq!(
+        config::get_config_by_key("detect_yaml.port"),
+        Some(json!(8080))
+    );
+    assert_eq!(
+        config::get_config_by_key("detect_yml.port"),
+        Some(json!(8081))
+    );
+}
+
+#[cfg(feature = "ini")]
+#[test]
+fn test_load_file_ini() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write(dir.path(), "legacy.ini", "[detect_ini]\nport = 8080\n");
+    config::load_file(&path).unwrap();
+    // As with `load_ini_file`, values stay strings.
+    assert_eq!(
+        config::get_config_by_key("detect_ini.port"),
+        Some(json!("8080"))
+    );
+}
+
+#[cfg(feature = "properties")]
+#[test]
+fn test_load_file_properties() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write(
+        dir.path(),
+        "application.properties",
+        "detect_properties.server.port=8080\n",
+    );
+    config::load_file(&path).unwrap();
+    assert_eq!(
+        config::get_config_by_key("detect_properties.server.port"),
+        Some(json!("8080"))
+    );
+}
+
+#[cfg(feature = "hcl")]
+#[test]
+fn test_load_file_hcl() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write(
+        dir.path(),
+        "main.hcl",
+        "detect_hcl \"web\" {\n  port = 8080\n}\n",
+    );
+    config::load_file(&path).unwrap();
+    assert_eq!(
+        config::get_config_by_key("detect_hcl.web.port"),
+        Some(json!(8080))
+    );
+}
+
+#[test]
+fn test_load_file_dotenv() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write(dir.path(), ".env", "DETECT_DOTENV__PORT=8080\n");
+    config::load_file(&path).unwrap();
+    assert_eq!(
+        config::get_config_by_key("detect_dotenv.port"),
+        Some(json!("8080"))
+    );
+}
+
+#[test]
+fn test_load_file_sniffs_unknown_extensions() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write(
+        dir.path(),
+        "settings.conf",
+        "\n  { \"detect_sniffed\": true }\n",
+    );
+    config::load_file(&path).unwrap();
+    assert_eq!(
+        config::get_config_by_key("detect_sniffed"),
+        Some(json!(true))
+    );
+
+    let path = write(dir.path(), "settings.cfg", "[detect_unknown]\nport = 1\n");
+    let err = config::load_file(&path).unwrap_err();
+    assert!(
+        matches!(&err, ConfigError::UnknownFormat { path: p } if *p == path),
+        "{err}"
+    );
+    assert!(
+        err.to_string().starts_with("cannot tell the format of "),
+        "{err}"
+    );
+    assert_eq!(config::get_config_by_key("detect_unknown"), None);
+
+    let path = write(dir.path(), "Configfile", "detect_unknown = 1\n");
+    assert!(matches!(
+        config::load_file(&path),
+        Err(ConfigError::UnknownFormat { .. })
+    ));
+}
+
+#[test]
+fn test_load_file_as_overrides_the_extension() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write(dir.path(), "app.yaml", r#"{ "detect_override": [1, 2] }"#);
+    config::load_file_as(&path, Format::Json).unwrap();
+    assert_eq!(
+        config::get_config_by_key("detect_override"),
+        Some(json!([1, 2]))
+    );
+
+    // Errors are those of the chosen loader.
+    let path = write(dir.path(), "broken.txt", "{ \"detect_broken\": ");
+    let err = config::load_file_as(&path, Format::Json).unwrap_err();
+    assert!(
+        matches!(err, ConfigError::Parse { format: "JSON", .. }),
+        "{err}"
+    );
+}
+
+#[cfg(not(feature = "toml"))]
+#[test]
+fn test_load_file_disabled_format() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write(dir.path(), "app.toml", "[detect_disabled]\nport = 1\n");
+    let err = config::load_file(&path).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "loading TOML requires the \"toml\" feature of zirv-config"
+    );
+}
@@ -0,0 +1,119 @@
+use serde_json::json;
+use zirv_config::config::{self, ConfigError, Layer};
+
+#[test]
+fn test_load_layered_both_present() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    std::fs::write(
+        dir.join("default.json"),
+        r#"{ "layers_both": { "host": "localhost", "port": 5432, "debug": false } }"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("local.json"),
+        r#"{ "layers_both": { "debug": true } }"#,
+    )
+    .unwrap();
+
+    let report = config::load_layered(&[
+        Layer::required(dir.join("default.json")),
+        Layer::optional(dir.join("local.json")),
+    ])
+    .unwrap();
+    assert_eq!(
+        report.loaded,
+        [dir.join("default.json"), dir.join("local.json")]
+    );
+    assert!(report.missing.is_empty());
+    assert_eq!(
+        config::get_config_by_key("layers_both"),
+        Some(json!({ "host": "localhost", "port": 5432, "debug": true }))
+    );
+}
+
+#[test]
+fn test_load_layered_optional_missing() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    std::fs::write(
+        dir.join("default.json"),
+        r#"{ "layers_local_missing": { "debug": false } }"#,
+    )
+    .unwrap();
+
+    let report = config::load_layered(&[
+        Layer::required(dir.join("default.json")),
+        Layer::optional(dir.join("local.json")),
+    ])
+    .unwrap();
+    assert_eq!(report.loaded, [dir.join("default.json")]);
+    assert_eq!(report.missing, [dir.join("local.json")]);
+    assert_eq!(
+        config::get_config_by_key("layers_local_missing.debug"),
+        Some(json!(false))
+    );
+}
+
+#[test]
+fn test_load_layered_required_missing() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    std::fs::write(
+        dir.join("local.json"),
+        r#"{ "layers_default_missing": { "debug": true } }"#,
+    )
+    .unwrap();
+
+    let err = config::load_layered(&[
+        Layer::required(dir.join("default.json")),
+        Layer::optional(dir.join("local.json")),
+    ])
+    .unwrap_err();
+    assert!(
+        matches!(&err, ConfigError::Io { path, .. } if *path == dir.join("default.json")),
+        "{err}"
+    );
+    // The optional layer is not merged on its own.
+    assert_eq!(config::get_config_by_key("layers_default_missing"), None);
+
+    // A broken optional layer is an error, not a missing one.
+    std::fs::write(dir.join("default.json"), "{}").unwrap();
+    std::fs::write(dir.join("local.json"), "{ broken").unwrap();
+    let err = config::load_layered(&[
+        Layer::required(dir.join("default.json")),
+        Layer::optional(dir.join("local.json")),
+    ])
+    .unwrap_err();
+    assert!(matches!(err, ConfigError::Parse { .. }), "{err}");
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_load_standard_layout() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    std::fs::write(
+        dir.join("default.toml"),
+        "[layout]\nworkers = 4\nname = \"app\"\n",
+    )
+    .unwrap();
+
+    let report = config::load_standard_layout(dir).unwrap();
+    assert_eq!(report.missing, [dir.join("local.toml")]);
+    assert_eq!(config::get_config_by_key("layout.workers"), Some(json!(4)));
+
+    std::fs::write(dir.join("local.toml"), "[layout]\nworkers = 1\n").unwrap();
+    let report = config::load_standard_layout(dir).unwrap();
+    assert!(report.missing.is_empty());
+    assert_eq!(
+        config::get_config_by_key("layout"),
+        Some(json!({ "workers": 1, "name": "app" }))
+    );
+
+    std::fs::remove_file(dir.join("default.toml")).unwrap();
+    assert!(matches!(
+        config::load_standard_layout(dir),
+        Err(ConfigError::Io { .. })
+    ));
+}
@@ -0,0 +1,105 @@
+use std::io::Cursor;
+
+use serde_json::json;
+use zirv_config::config::{self, ConfigError, Format};
+
+fn load(text: &str, format: Format) -> Result<(), ConfigError> {
+    config::load_reader(Cursor::new(text.as_bytes()), format)
+}
+
+#[test]
+fn test_load_reader_json() {
+    load(r#"{ "reader_json": { "port": 8080 } }"#, Format::Json).unwrap();
+    assert_eq!(
+        config::get_config_by_key("reader_json.port"),
+        Some(json!(8080))
+    );
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_load_reader_toml() {
+    load("[reader_toml]\nport = 8080\n", Format::Toml).unwrap();
+    assert_eq!(
+        config::get_config_by_key("reader_toml.port"),
+        Some(json!(8080))
+    );
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_load_reader_yaml() {
+    load("reader_yaml:\n  port: 8080\n", Format::Yaml).unwrap();
+    assert_eq!(
+        config::get_config_by_key("reader_yaml.port"),
+        Some(json!(8080))
+    );
+}
+
+#[cfg(feature = "ini")]
+#[test]
+fn test_load_reader_ini() {
+    load("[reader_ini]\nport = 8080\n", Format::Ini).unwrap();
+    assert_eq!(
+        config::get_config_by_key("reader_ini.port"),
+        Some(json!("8080"))
+    );
+}
+
+#[cfg(feature = "properties")]
+#[test]
+fn test_load_reader_properties() {
+    load("reader_properties.port=8080\n", Format::Properties).unwrap();
+    assert_eq!(
+        config::get_config_by_key("reader_properties.port"),
+        Some(json!("8080"))
+    );
+}
+
+#[cfg(feature = "hcl")]
+#[test]
+fn test_load_reader_hcl() {
+    load("reader_hcl {\n  port = 8080\n}\n", Format::Hcl).unwrap();
+    assert_eq!(
+        config::get_config_by_key("reader_hcl.port"),
+        Some(json!(8080))
+    );
+}
+
+#[test]
+fn test_load_reader_dotenv() {
+    load("READER_DOTENV__PORT=8080\n", Format::Dotenv).unwrap();
+    assert_eq!(
+        config::get_config_by_key("reader_dotenv.port"),
+        Some(json!("8080"))
+    );
+}
+
+#[test]
+fn test_load_reader_errors_name_the_reader() {
+    let err = load("{ \"reader_broken\": ", Format::Json).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("invalid JSON in <reader> at line 1"),
+        "{err}"
+    );
+
+    let err = config::load_reader(Cursor::new(vec![b'{', 0xff, b'}']), Format::Json).unwrap_err();
+    assert!(
+        err.to_string().starts_with("cannot read <reader>: "),
+        "{err}"
+    );
+
+    let huge = " ".repeat(config::Limits::default().max_size + 1);
+    let err = load(&huge, Format::Json).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            ConfigError::LimitExceeded {
+                limit: "max_size",
+                ..
+            }
+        ),
+        "{err}"
+    );
+}
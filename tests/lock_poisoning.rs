@@ -0,0 +1,34 @@
+use std::thread;
+
+use serde_json::{Value, json};
+use zirv_config::config;
+
+#[test]
+fn test_store_recovers_after_a_panic_while_locked() {
+    config::register_config("app", json!({ "name": "demo" }));
+
+    // `get_or_register` runs its closure under the store lock, so with the default `std`
+    // backend this panic poisons it.
+    let result = thread::spawn(|| {
+        config::get_or_register("lazy", || -> Value { panic!("boom") });
+    })
+    .join();
+    assert!(result.is_err());
+
+    assert_eq!(config::get_config_by_key("app.name"), Some(json!("demo")));
+    assert_eq!(config::get_config_by_key("lazy"), None);
+    config::set_config_by_key("app.name", "recovered").unwrap();
+    assert_eq!(
+        config::try_get_config().unwrap()["app"]["name"],
+        "recovered"
+    );
+    assert_eq!(config::get_or_register("lazy", || 1), json!(1));
+
+    // A second poisoning is recovered from as well.
+    let result = thread::spawn(|| config::update_config(|_| panic!("again"))).join();
+    assert!(result.is_err());
+    assert_eq!(
+        config::try_get_config_by_key("lazy").unwrap(),
+        Some(json!(1))
+    );
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "toml")]
+
+use std::path::Path;
+
+use serde_json::json;
+use zirv_config::config::{self, ConfigError, ProfileOptions};
+
+/// Writes a `config` directory in which each profile sets `profile_app.db_host`.
+fn write_layout(dir: &Path) {
+    std::fs::write(
+        dir.join("default.toml"),
+        "[profile_app]\ndb_host = \"localhost\"\nport = 8080\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("staging.toml"),
+        "[profile_app]\ndb_host = \"db.staging\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("production.toml"),
+        "[profile_app]\ndb_host = \"db.prod\"\nport = 443\n",
+    )
+    .unwrap();
+}
+
+fn options(env_var: &str) -> ProfileOptions {
+    ProfileOptions {
+        env_var: env_var.to_string(),
+        ..ProfileOptions::default()
+    }
+}
+
+// Each profile is loaded in turn in one test, as they all write the same keys, and only
+// this test loads successfully.
+#[test]
+fn test_load_profile_from_env_var() {
+    let dir = tempfile::tempdir().unwrap();
+    write_layout(dir.path());
+    let options = options("ZIRV_CONFIG_TEST_PROFILE");
+
+    // Unset: the default profile, whose file is optional.
+    unsafe { std::env::remove_var("ZIRV_CONFIG_TEST_PROFILE") };
+    assert_eq!(
+        config::load_profile(dir.path(), &options).unwrap(),
+        "development"
+    );
+    assert_eq!(
+        config::get_config_by_key("profile_app"),
+        Some(json!({ "db_host": "localhost", "port": 8080 }))
+    );
+    assert_eq!(
+        config::get_config_by_key(config::PROFILE_KEY),
+        Some(json!("development"))
+    );
+
+    unsafe { std::env::set_var("ZIRV_CONFIG_TEST_PROFILE", "staging") };
+    assert_eq!(
+        config::load_profile(dir.path(), &options).unwrap(),
+        "staging"
+    );
+    assert_eq!(
+        config::get_config_by_key("profile_app"),
+        Some(json!({ "db_host": "db.staging", "port": 8080 }))
+    );
+    assert_eq!(
+        config::get_config_by_key("zirv.profile"),
+        Some(json!("staging"))
+    );
+
+    unsafe { std::env::set_var("ZIRV_CONFIG_TEST_PROFILE", "production") };
+    assert_eq!(
+        config::load_profile(dir.path(), &options).unwrap(),
+        "production"
+    );
+    assert_eq!(
+        config::get_config_by_key("profile_app"),
+        Some(json!({ "db_host": "db.prod", "port": 443 }))
+    );
+    assert_eq!(
+        config::get_config_by_key("zirv.profile"),
+        Some(json!("production"))
+    );
+
+    unsafe { std::env::set_var("ZIRV_CONFIG_TEST_PROFILE", "qa") };
+    let fallback = ProfileOptions {
+        fall_back: true,
+        default_profile: "staging".to_string(),
+        ..options
+    };
+    assert_eq!(
+        config::load_profile(dir.path(), &fallback).unwrap(),
+        "staging"
+    );
+    assert_eq!(
+        config::get_config_by_key("zirv.profile"),
+        Some(json!("staging"))
+    );
+}
+
+#[test]
+fn test_load_profile_unknown() {
+    let dir = tempfile::tempdir().unwrap();
+    write_layout(dir.path());
+    let options = options("ZIRV_CONFIG_TEST_PROFILE_UNKNOWN");
+
+    for profile in ["qa", "../default"] {
+        unsafe { std::env::set_var("ZIRV_CONFIG_TEST_PROFILE_UNKNOWN", profile) };
+        let err = config::load_profile(dir.path(), &options).unwrap_err();
+        assert!(
+            matches!(&err, ConfigError::UnknownProfile { profile: p, .. } if p == profile),
+            "{err}"
+        );
+    }
+    unsafe { std::env::set_var("ZIRV_CONFIG_TEST_PROFILE_UNKNOWN", "qa") };
+    let err = config::load_profile(dir.path(), &options).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "unknown profile \"qa\": {} does not exist",
+            dir.path().join("qa.toml").display()
+        )
+    );
+}
+
+#[test]
+fn test_load_profile_requires_defaults() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("production.toml"),
+        "[profile_no_defaults]\na = 1\n",
+    )
+    .unwrap();
+    unsafe { std::env::set_var("ZIRV_CONFIG_TEST_PROFILE_DEFAULTS", "production") };
+
+    let err = config::load_profile(dir.path(), &options("ZIRV_CONFIG_TEST_PROFILE_DEFAULTS"))
+        .unwrap_err();
+    assert!(matches!(err, ConfigError::Io { .. }), "{err}");
+    assert_eq!(config::get_config_by_key("profile_no_defaults"), None);
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "properties")]
+
+use zirv_config::{config, read_config};
+
+const APPLICATION_PROPERTIES: &str = "\
+# Generated by the ops tooling.
+server.port=8080
+server.address: 0.0.0.0
+db.pool.max=20
+db.pool.min=2
+db.url=jdbc:postgresql://db.internal/app?ssl=true
+greeting.text=Hall\\u00e5 \\
+    verden
+";
+
+#[test]
+fn test_load_properties_file_end_to_end() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("application.properties");
+    std::fs::write(&path, APPLICATION_PROPERTIES).unwrap();
+    config::load_properties_file(&path).unwrap();
+
+    assert_eq!(read_config!("server.port", String).unwrap(), "8080");
+    assert_eq!(read_config!("server.address", String).unwrap(), "0.0.0.0");
+    assert_eq!(read_config!("db.pool.max", String).unwrap(), "20");
+    assert_eq!(
+        read_config!("db.url", String).unwrap(),
+        "jdbc:postgresql://db.internal/app?ssl=true"
+    );
+    assert_eq!(
+        read_config!("greeting.text", String).unwrap(),
+        "Hallå verden"
+    );
+    // Values are strings, which the lenient reader converts.
+    assert_eq!(
+        config::get_config_as_lenient::<u32>("db.pool.min").unwrap(),
+        2
+    );
+
+    // A conflict within one file is rejected, and nothing from that file is merged.
+    let conflicting = dir.path().join("conflicting.properties");
+    std::fs::write(&conflicting, "server.port=9090\nserver.port.http=80\n").unwrap();
+    let err = config::load_properties_file(&conflicting).unwrap_err();
+    assert!(err.to_string().contains("at line 2"), "{err}");
+    assert_eq!(read_config!("server.port", String).unwrap(), "8080");
+}
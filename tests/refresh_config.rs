@@ -0,0 +1,116 @@
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{Value, json};
+use zirv_config::config::{self, RefreshHandle};
+
+/// Polls until `key` reads `expected`, failing after a few seconds.
+fn wait_for(key: &str, expected: Value) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while config::get_config_by_key(key) != Some(expected.clone()) {
+        assert!(
+            Instant::now() < deadline,
+            "{key} is {:?}, not {expected}",
+            config::get_config_by_key(key)
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn wait_until(handle: &RefreshHandle, done: impl Fn(&config::RefreshStatus) -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !done(&handle.status()) {
+        assert!(Instant::now() < deadline, "{:?}", handle.status());
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn refresh_file(path: &Path) -> RefreshHandle {
+    let path = path.to_path_buf();
+    config::start_refresh(
+        move || config::load_json_file(&path),
+        Duration::from_millis(20),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_refresh_picks_up_file_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("flags.json");
+    std::fs::write(&path, r#"{ "refresh_flags": { "beta": false } }"#).unwrap();
+    config::load_json_file(&path).unwrap();
+
+    let handle = refresh_file(&path);
+    std::fs::write(&path, r#"{ "refresh_flags": { "beta": true } }"#).unwrap();
+    wait_for("refresh_flags.beta", json!(true));
+    std::fs::write(
+        &path,
+        r#"{ "refresh_flags": { "beta": true, "limit": 3 } }"#,
+    )
+    .unwrap();
+    wait_for("refresh_flags.limit", json!(3));
+
+    let status = handle.status();
+    assert!(status.successes >= 2, "{status:?}");
+    assert!(status.last_success.is_some());
+    handle.stop();
+}
+
+#[test]
+fn test_refresh_errors_keep_the_last_good_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("limits.json");
+    std::fs::write(&path, r#"{ "refresh_limits": { "rps": 10 } }"#).unwrap();
+    config::load_json_file(&path).unwrap();
+
+    let handle = refresh_file(&path);
+    std::fs::write(&path, r#"{ "refresh_limits": "#).unwrap();
+    wait_until(&handle, |status| status.failures >= 2);
+    let status = handle.status();
+    assert!(status.last_error.is_some());
+    assert!(
+        status
+            .last_error_message
+            .as_deref()
+            .unwrap()
+            .starts_with("invalid JSON in "),
+        "{status:?}"
+    );
+    assert_eq!(
+        config::get_config_by_key("refresh_limits.rps"),
+        Some(json!(10))
+    );
+
+    // The worker survives the errors and picks the file up once it is fixed.
+    std::fs::write(&path, r#"{ "refresh_limits": { "rps": 20 } }"#).unwrap();
+    wait_for("refresh_limits.rps", json!(20));
+    assert!(handle.status().last_success > status.last_success);
+    handle.stop();
+}
+
+#[test]
+fn test_stopped_refresh_does_not_run_again() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("stopped.json");
+    std::fs::write(&path, r#"{ "refresh_stopped": 1 }"#).unwrap();
+
+    let handle = refresh_file(&path);
+    wait_for("refresh_stopped", json!(1));
+    let started = Instant::now();
+    handle.stop();
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    std::fs::write(&path, r#"{ "refresh_stopped": 2 }"#).unwrap();
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(config::get_config_by_key("refresh_stopped"), Some(json!(1)));
+
+    // Dropping the handle stops the worker as well.
+    let handle = refresh_file(&path);
+    wait_for("refresh_stopped", json!(2));
+    drop(handle);
+    std::fs::write(&path, r#"{ "refresh_stopped": 3 }"#).unwrap();
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(config::get_config_by_key("refresh_stopped"), Some(json!(2)));
+}
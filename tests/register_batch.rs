@@ -0,0 +1,54 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use serde_json::json;
+use zirv_config::{config, register_configs};
+
+const NAMESPACES: [&str; 3] = ["server", "db", "metrics"];
+
+#[test]
+fn test_register_batch_is_never_observed_partially_applied() {
+    let started = AtomicBool::new(false);
+    let done = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        let reader = scope.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                let root = config::get_config();
+                let present: Vec<_> = NAMESPACES
+                    .iter()
+                    .filter_map(|namespace| root.get(namespace))
+                    .collect();
+                assert!(present.is_empty() || present.len() == NAMESPACES.len());
+                if let Some(first) = present.first() {
+                    assert!(present.iter().all(|block| block["n"] == first["n"]));
+                }
+                started.store(true, Ordering::Relaxed);
+            }
+        });
+
+        while !started.load(Ordering::Relaxed) {
+            thread::yield_now();
+        }
+        for n in 0..200 {
+            register_configs!(
+                "server" => json!({ "n": n }),
+                "db" => json!({ "n": n }),
+                "metrics" => json!({ "n": n }),
+            )
+            .unwrap();
+            if n % 2 == 0 {
+                config::transaction(|tx| {
+                    for namespace in NAMESPACES {
+                        tx.remove(namespace);
+                    }
+                })
+                .unwrap();
+            }
+        }
+        done.store(true, Ordering::Relaxed);
+        reader.join().unwrap();
+    });
+
+    assert_eq!(config::get_config_by_key("metrics.n"), Some(json!(199)));
+}
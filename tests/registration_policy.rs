@@ -0,0 +1,60 @@
+use serde_json::json;
+use zirv_config::config::{self, ConfigError, RegistrationPolicy};
+
+#[test]
+fn test_each_registration_policy() {
+    assert_eq!(config::registration_policy(), RegistrationPolicy::Overwrite);
+    config::register_config("metrics", json!({ "port": 9100, "path": "/metrics" }));
+    let previous = config::register_config("metrics", json!({ "port": 9200 }));
+    assert_eq!(previous, Some(json!({ "port": 9100, "path": "/metrics" })));
+    assert_eq!(
+        config::get_config_by_key("metrics"),
+        Some(json!({ "port": 9200 }))
+    );
+
+    config::set_registration_policy(RegistrationPolicy::Warn);
+    let previous = config::register_config("metrics", json!({ "port": 9300 }));
+    assert_eq!(previous, Some(json!({ "port": 9200 })));
+    assert_eq!(
+        config::get_config_by_key("metrics"),
+        Some(json!({ "port": 9300 }))
+    );
+
+    config::set_registration_policy(RegistrationPolicy::Error);
+    let err = config::try_register_config("metrics", json!({ "port": 9400 })).unwrap_err();
+    assert!(
+        matches!(err, ConfigError::AlreadyRegistered { ref namespace } if namespace == "metrics")
+    );
+    assert_eq!(
+        err.to_string(),
+        r#"a configuration is already registered under "metrics""#
+    );
+    assert_eq!(
+        config::get_config_by_key("metrics"),
+        Some(json!({ "port": 9300 }))
+    );
+    // Writing a whole namespace is a registration too.
+    let err = zirv_config::write_config!("metrics", json!({ "port": 9400 })).unwrap_err();
+    assert!(matches!(err, ConfigError::AlreadyRegistered { .. }));
+    // New namespaces are still accepted, but a batch touching an existing one is not.
+    assert_eq!(
+        config::try_register_config("tracing", json!({})).unwrap(),
+        None
+    );
+    let err = config::register_batch(|batch| {
+        batch
+            .register("logging", json!({}))
+            .register("metrics", json!({}));
+    })
+    .unwrap_err();
+    assert!(matches!(err, ConfigError::AlreadyRegistered { .. }));
+    assert_eq!(config::get_config_by_key("logging"), None);
+
+    config::set_registration_policy(RegistrationPolicy::DeepMerge);
+    let previous = config::register_config("metrics", json!({ "path": "/stats" }));
+    assert_eq!(previous, Some(json!({ "port": 9300 })));
+    assert_eq!(
+        config::get_config_by_key("metrics"),
+        Some(json!({ "port": 9300, "path": "/stats" }))
+    );
+}
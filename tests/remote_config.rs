@@ -0,0 +1,376 @@
+#![cfg(feature = "remote")]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+use zirv_config::config::{
+    self, ConfigError, ConfigSource, FetchPolicy, Format, RemoteOptions, RemoteSource, Validators,
+};
+
+/// A server that answers each connection with the next of a list of canned responses,
+/// recording the head of every request it receives. An empty response holds the
+/// connection open without answering, for a second.
+struct MockServer {
+    url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    fn start(responses: Vec<String>) -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut head = String::new();
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                        break;
+                    }
+                    head.push_str(&line);
+                }
+                recorded.lock().unwrap().push(head);
+                if response.is_empty() {
+                    thread::sleep(Duration::from_secs(1));
+                } else {
+                    stream.write_all(response.as_bytes()).unwrap();
+                }
+            }
+        });
+        MockServer { url, requests }
+    }
+
+    fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn response(status: &str, headers: &[(&str, &str)], body: &str) -> String {
+    let mut response = format!("HTTP/1.1 {status}\r\nContent-Length: {}\r\n", body.len());
+    for (name, value) in headers {
+        response.push_str(&format!("{name}: {value}\r\n"));
+    }
+    response.push_str("Connection: close\r\n\r\n");
+    response.push_str(body);
+    response
+}
+
+#[test]
+fn test_load_url_success_with_authentication() {
+    let server = MockServer::start(vec![response(
+        "200 OK",
+        &[("Content-Type", "application/json; charset=utf-8")],
+        r#"{ "remote_ok": { "port": 8080 } }"#,
+    )]);
+    let options = RemoteOptions {
+        bearer_token: Some("s3cr3t".to_string()),
+        headers: vec![("X-Service".to_string(), "billing".to_string())],
+        ..RemoteOptions::default()
+    };
+
+    config::load_url(&format!("{}/config", server.url), &options).unwrap();
+    assert_eq!(
+        config::get_config_by_key("remote_ok.port"),
+        Some(json!(8080))
+    );
+    let request = server.requests().remove(0).to_ascii_lowercase();
+    assert!(request.starts_with("get /config http/1.1"), "{request}");
+    assert!(
+        request.contains("authorization: bearer s3cr3t\r\n"),
+        "{request}"
+    );
+    assert!(request.contains("x-service: billing\r\n"), "{request}");
+}
+
+#[test]
+fn test_load_url_http_status_error() {
+    let server = MockServer::start(vec![response("404 Not Found", &[], "missing")]);
+    let url = format!("{}/missing", server.url);
+
+    let err = config::load_url(&url, &RemoteOptions::default()).unwrap_err();
+    assert!(
+        matches!(&err, ConfigError::HttpStatus { url: u, status: 404 } if *u == url),
+        "{err}"
+    );
+    assert_eq!(
+        err.to_string(),
+        format!("fetching {url} failed with HTTP status 404")
+    );
+}
+
+#[test]
+fn test_load_url_content_type() {
+    let body = r#"{ "remote_typed": true }"#;
+    let server = MockServer::start(vec![
+        response("200 OK", &[("Content-Type", "text/html")], body),
+        response("200 OK", &[("Content-Type", "text/html")], body),
+    ]);
+
+    let err = config::load_url(&server.url, &RemoteOptions::default()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "cannot tell the format of {} from its content type \"text/html\"",
+            server.url
+        )
+    );
+    assert_eq!(config::get_config_by_key("remote_typed"), None);
+
+    // An explicit format overrides the content type.
+    let options = RemoteOptions {
+        format: Some(Format::Json),
+        ..RemoteOptions::default()
+    };
+    config::load_url(&server.url, &options).unwrap();
+    assert_eq!(config::get_config_by_key("remote_typed"), Some(json!(true)));
+}
+
+#[test]
+fn test_load_url_network_error() {
+    // Bind and drop a listener to find a port that nothing listens on.
+    let url = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}/config", listener.local_addr().unwrap())
+    };
+    let err = config::load_url(&url, &RemoteOptions::default()).unwrap_err();
+    assert!(
+        matches!(&err, ConfigError::Network { url: u, .. } if *u == url),
+        "{err}"
+    );
+
+    let server = MockServer::start(vec![response(
+        "200 OK",
+        &[("Content-Type", "application/json")],
+        "{ \"remote_broken\": ",
+    )]);
+    let err = config::load_url(&server.url, &RemoteOptions::default()).unwrap_err();
+    assert!(
+        matches!(&err, ConfigError::Parse { origin, .. } if *origin == server.url),
+        "{err}"
+    );
+}
+
+fn retrying(retries: u32) -> RemoteOptions {
+    RemoteOptions {
+        policy: FetchPolicy {
+            retries,
+            backoff: Duration::from_millis(50),
+            ..FetchPolicy::default()
+        },
+        ..RemoteOptions::default()
+    }
+}
+
+#[test]
+fn test_load_url_retries_with_backoff() {
+    let server = MockServer::start(vec![
+        response("503 Service Unavailable", &[], "starting"),
+        response("502 Bad Gateway", &[], "starting"),
+        response(
+            "200 OK",
+            &[("Content-Type", "application/json")],
+            r#"{ "remote_retried": { "ready": true } }"#,
+        ),
+    ]);
+
+    let started = Instant::now();
+    config::load_url(&server.url, &retrying(3)).unwrap();
+    let elapsed = started.elapsed();
+    assert_eq!(server.requests().len(), 3);
+    assert_eq!(
+        config::get_config_by_key("remote_retried.ready"),
+        Some(json!(true))
+    );
+    // Waits of 50ms and then 100ms.
+    assert!(elapsed >= Duration::from_millis(150), "{elapsed:?}");
+    assert!(elapsed < Duration::from_secs(5), "{elapsed:?}");
+}
+
+#[test]
+fn test_load_url_reports_every_failed_attempt() {
+    let server = MockServer::start(vec![
+        response("503 Service Unavailable", &[], ""),
+        response("500 Internal Server Error", &[], ""),
+        response("503 Service Unavailable", &[], ""),
+    ]);
+
+    let err = config::load_url(&server.url, &retrying(2)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "fetching {url} failed after 3 attempts; \
+             attempt 1: fetching {url} failed with HTTP status 503; \
+             attempt 2: fetching {url} failed with HTTP status 500; \
+             attempt 3: fetching {url} failed with HTTP status 503",
+            url = server.url
+        )
+    );
+
+    // Failures that would recur are not retried.
+    let server = MockServer::start(vec![response("404 Not Found", &[], "")]);
+    let err = config::load_url(&server.url, &retrying(2)).unwrap_err();
+    assert!(
+        matches!(err, ConfigError::HttpStatus { status: 404, .. }),
+        "{err}"
+    );
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+fn test_load_url_times_out_and_fails_open() {
+    let server = MockServer::start(vec![String::new()]);
+    config::register_config("remote_kept", json!({ "source": "local" }));
+    let options = RemoteOptions {
+        policy: FetchPolicy {
+            total_timeout: Duration::from_millis(100),
+            fail_open: true,
+            ..FetchPolicy::default()
+        },
+        ..RemoteOptions::default()
+    };
+
+    let started = Instant::now();
+    config::load_url(&server.url, &options).unwrap();
+    assert!(
+        started.elapsed() < Duration::from_secs(1),
+        "{:?}",
+        started.elapsed()
+    );
+    assert_eq!(
+        config::get_config_by_key("remote_kept"),
+        Some(json!({ "source": "local" }))
+    );
+
+    let server = MockServer::start(vec![String::new()]);
+    let options = RemoteOptions {
+        policy: FetchPolicy {
+            fail_open: false,
+            ..options.policy
+        },
+        ..options
+    };
+    let err = config::load_url(&server.url, &options).unwrap_err();
+    assert!(matches!(err, ConfigError::Network { .. }), "{err}");
+}
+
+#[test]
+fn test_remote_source_conditional_refresh() {
+    let json = ("Content-Type", "application/json");
+    let server = MockServer::start(vec![
+        response(
+            "200 OK",
+            &[
+                json,
+                ("ETag", "\"v1\""),
+                ("Last-Modified", "Tue, 01 Oct 2024 10:00:00 GMT"),
+            ],
+            r#"{ "remote_etag": { "port": 8080 } }"#,
+        ),
+        response("304 Not Modified", &[("ETag", "\"v1\"")], ""),
+        response(
+            "200 OK",
+            &[json, ("ETag", "\"v2\"")],
+            r#"{ "remote_etag": { "port": 9090 } }"#,
+        ),
+    ]);
+    let source = RemoteSource::new(format!("{}/config", server.url), RemoteOptions::default());
+    assert_eq!(source.validators(), Validators::default());
+
+    assert!(source.load().unwrap());
+    assert_eq!(
+        source.validators(),
+        Validators {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: Some("Tue, 01 Oct 2024 10:00:00 GMT".to_string()),
+        }
+    );
+    assert_eq!(
+        config::get_config_by_key("remote_etag.port"),
+        Some(json!(8080))
+    );
+
+    // A local change shows that the unchanged document is not merged over it.
+    config::set_config_by_key("remote_etag.port", 1).unwrap();
+    assert!(!source.load().unwrap());
+    assert_eq!(
+        config::get_config_by_key("remote_etag.port"),
+        Some(json!(1))
+    );
+
+    assert!(source.load().unwrap());
+    assert_eq!(
+        config::get_config_by_key("remote_etag.port"),
+        Some(json!(9090))
+    );
+    assert_eq!(
+        source.validators(),
+        Validators {
+            etag: Some("\"v2\"".to_string()),
+            last_modified: None,
+        }
+    );
+
+    let requests: Vec<String> = server
+        .requests()
+        .iter()
+        .map(|request| request.to_ascii_lowercase())
+        .collect();
+    assert!(!requests[0].contains("if-none-match"), "{}", requests[0]);
+    for request in &requests[1..] {
+        assert!(request.contains("if-none-match: \"v1\"\r\n"), "{request}");
+        assert!(
+            request.contains("if-modified-since: tue, 01 oct 2024 10:00:00 gmt\r\n"),
+            "{request}"
+        );
+    }
+}
+
+#[test]
+fn test_remote_source_as_a_config_source() {
+    let json = ("Content-Type", "application/json");
+    let server = MockServer::start(vec![
+        response(
+            "200 OK",
+            &[json, ("ETag", "\"a\"")],
+            r#"{ "remote_layer": 1 }"#,
+        ),
+        response("304 Not Modified", &[], ""),
+        response("503 Service Unavailable", &[], ""),
+    ]);
+    let source = RemoteSource::new(
+        server.url.clone(),
+        RemoteOptions {
+            policy: FetchPolicy {
+                fail_open: true,
+                ..FetchPolicy::default()
+            },
+            ..RemoteOptions::default()
+        },
+    );
+
+    // Loading as a source returns the document without merging it.
+    assert_eq!(source.name(), server.url);
+    assert_eq!(
+        ConfigSource::load(&source).unwrap(),
+        json!({ "remote_layer": 1 })
+    );
+    assert_eq!(config::get_config_by_key("remote_layer"), None);
+    // Unchanged, and then failing under fail_open, it returns the last document.
+    assert_eq!(
+        ConfigSource::load(&source).unwrap(),
+        json!({ "remote_layer": 1 })
+    );
+    assert_eq!(
+        ConfigSource::load(&source).unwrap(),
+        json!({ "remote_layer": 1 })
+    );
+    assert_eq!(server.requests().len(), 3);
+}
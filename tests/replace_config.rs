@@ -0,0 +1,49 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use serde_json::{Map, Value, json};
+use zirv_config::config;
+
+fn generation(n: u64) -> Map<String, Value> {
+    let mut map = Map::new();
+    map.insert(
+        "db".to_string(),
+        json!({ "host": format!("host-{n}"), "port": n }),
+    );
+    map.insert("cache".to_string(), json!({ "generation": n }));
+    map
+}
+
+#[test]
+fn test_replace_config_is_never_observed_half_applied() {
+    config::replace_config(generation(0));
+    let started = AtomicBool::new(false);
+    let done = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        let reader = scope.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                let root = config::get_config();
+                let n = root["db"]["port"].as_u64().unwrap();
+                assert_eq!(root["db"]["host"], json!(format!("host-{n}")));
+                assert_eq!(root["cache"]["generation"], json!(n));
+                started.store(true, Ordering::Relaxed);
+            }
+        });
+
+        while !started.load(Ordering::Relaxed) {
+            thread::yield_now();
+        }
+        for n in 1..=200 {
+            let previous = config::replace_config(generation(n));
+            assert_eq!(previous, generation(n - 1));
+        }
+        done.store(true, Ordering::Relaxed);
+        reader.join().unwrap();
+    });
+
+    assert_eq!(
+        config::get_config_by_key("cache.generation"),
+        Some(json!(200))
+    );
+}
@@ -0,0 +1,26 @@
+use serde_json::json;
+use serde_json::value::RawValue;
+use zirv_config::config;
+
+#[test]
+fn test_serialize_config_to_writer_matches_get_config() {
+    config::register_config("server", json!({ "host": "::", "ports": [80, 443] }));
+    config::register_config("zeta", json!({ "escaped": "tab\t\"quote\"" }));
+    // Compact, so that writing it verbatim and re-serializing it agree.
+    let raw = RawValue::from_string(r#"{"routes":[{"path":"/"}]}"#.to_string()).unwrap();
+    config::register_raw_config("routing", raw).unwrap();
+
+    let mut out = Vec::new();
+    config::serialize_config_to_writer(&mut out, false).unwrap();
+    assert_eq!(out, serde_json::to_vec(&config::get_config()).unwrap());
+
+    let mut out = Vec::new();
+    config::serialize_config_to_writer(&mut out, true).unwrap();
+    let pretty = String::from_utf8(out).unwrap();
+    let reparsed: serde_json::Value = serde_json::from_str(&pretty).unwrap();
+    assert_eq!(reparsed, config::get_config());
+    assert!(
+        pretty.contains("\n  \"routing\": {\"routes\":[{\"path\":\"/\"}]},\n"),
+        "{pretty}"
+    );
+}
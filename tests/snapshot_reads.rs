@@ -0,0 +1,57 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use serde::Deserialize;
+use serde_json::json;
+use zirv_config::config;
+
+const WRITES: u64 = 500;
+
+#[derive(Deserialize)]
+struct Pair {
+    a: u64,
+    b: u64,
+}
+
+#[test]
+fn test_readers_see_whole_writes_in_order() {
+    config::register_config("pair", json!({ "a": 0, "b": 0 }));
+    let done = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                scope.spawn(|| {
+                    let (mut last_seen, mut last_cached) = (0, 0);
+                    while !done.load(Ordering::Relaxed) {
+                        let root = config::get_config();
+                        let a = root["pair"]["a"].as_u64().unwrap();
+                        assert_eq!(root["pair"]["b"], json!(a), "torn write");
+                        assert!(a >= last_seen, "went back from {last_seen} to {a}");
+                        last_seen = a;
+
+                        let cached = config::get_cached::<Pair>("pair").unwrap();
+                        assert_eq!(cached.a, cached.b, "torn cached write");
+                        assert!(cached.a >= last_cached, "cache went back");
+                        last_cached = cached.a;
+                    }
+                })
+            })
+            .collect();
+
+        for n in 1..=WRITES {
+            config::update_config(|map| {
+                map["pair"]["a"] = json!(n);
+                map["pair"]["b"] = json!(n);
+            });
+        }
+        done.store(true, Ordering::Relaxed);
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    });
+
+    // Whatever the readers cached along the way, the last write is what is served now.
+    let cached = config::get_cached::<Pair>("pair").unwrap();
+    assert_eq!((cached.a, cached.b), (WRITES, WRITES));
+}
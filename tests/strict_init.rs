@@ -0,0 +1,32 @@
+use serde_json::json;
+use zirv_config::config::{self, ConfigError};
+
+#[test]
+fn test_strict_mode_rejects_reads_before_init() {
+    config::enable_strict_init();
+
+    assert!(matches!(
+        config::try_get_config(),
+        Err(ConfigError::NotInitialized)
+    ));
+    assert!(matches!(
+        config::try_get_config_by_key("server.port"),
+        Err(ConfigError::NotInitialized)
+    ));
+    let err = config::get_required("server.port").unwrap_err();
+    assert!(matches!(err, ConfigError::NotInitialized));
+    assert_eq!(
+        err.to_string(),
+        "configuration read before init_config() was called"
+    );
+
+    // The non-`try` readers keep their lenient behavior.
+    assert_eq!(config::get_config(), json!({}));
+    assert_eq!(config::get_config_by_key("server.port"), None);
+
+    config::init_config();
+    assert_eq!(config::try_get_config().unwrap(), json!({}));
+    assert_eq!(config::try_get_config_by_key("server.port").unwrap(), None);
+    config::register_config("server", json!({ "port": 3000 }));
+    assert_eq!(config::get_required("server.port").unwrap(), json!(3000));
+}
@@ -0,0 +1,90 @@
+#![cfg(feature = "toml")]
+
+use serde::Deserialize;
+use serde_json::json;
+use zirv_config::{config, read_config};
+
+const CONFIG_TOML: &str = r#"
+# Settings shared by every environment.
+[server]
+host = "0.0.0.0"
+port = 8080
+workers = 4
+shutdown_grace = 2.5
+
+[server.tls]
+enabled = true
+cert = "/etc/app/cert.pem"
+
+[database]
+url = "postgres://localhost/app"
+pool = { min = 2, max = 16 }
+replicas = ["db-1.internal", "db-2.internal"]
+
+[[jobs]]
+name = "cleanup"
+schedule = "0 3 * * *"
+
+[[jobs]]
+name = "report"
+schedule = "0 6 * * 1"
+
+[release]
+built_at = 2024-03-01T12:30:00+01:00
+cutoff = 2024-03-01
+"#;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Pool {
+    min: u32,
+    max: u32,
+}
+
+#[test]
+fn test_load_toml_file_end_to_end() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, CONFIG_TOML).unwrap();
+    config::load_toml_file(&path).unwrap();
+
+    assert_eq!(read_config!("server.host", String).unwrap(), "0.0.0.0");
+    assert_eq!(read_config!("server.port" as u16).unwrap(), 8080);
+    assert_eq!(read_config!("server.shutdown_grace", f64).unwrap(), 2.5);
+    assert!(read_config!("server.tls.enabled", bool).unwrap());
+    assert_eq!(
+        read_config!("database.pool", Pool).unwrap(),
+        Pool { min: 2, max: 16 }
+    );
+    assert_eq!(
+        read_config!("database.replicas.1", String).unwrap(),
+        "db-2.internal"
+    );
+    assert_eq!(read_config!("jobs.0.name"), Some(json!("cleanup")));
+    assert_eq!(
+        read_config!("release"),
+        Some(json!({ "built_at": "2024-03-01T12:30:00+01:00", "cutoff": "2024-03-01" }))
+    );
+
+    // A second file deep-merges over the first, and `load_toml_str` behaves the same.
+    config::load_toml_str("[server]\nport = 9090\n\n[server.tls]\nenabled = false\n").unwrap();
+    assert_eq!(read_config!("server.port" as u16).unwrap(), 9090);
+    assert!(!read_config!("server.tls.enabled", bool).unwrap());
+    assert_eq!(
+        read_config!("server.tls.cert", String).unwrap(),
+        "/etc/app/cert.pem"
+    );
+
+    // A float is not an integer, even when it has no fractional part.
+    config::load_toml_str("[server]\nworkers = 8.0\n").unwrap();
+    assert!(read_config!("server.workers" as u32).is_err());
+
+    let broken = dir.path().join("broken.toml");
+    std::fs::write(&broken, "[server]\nport = \n").unwrap();
+    let err = config::load_toml_file(&broken).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with(&format!("invalid TOML in {} at line 2", broken.display())),
+        "{err}"
+    );
+    assert_eq!(read_config!("server.port" as u16).unwrap(), 9090);
+}
@@ -0,0 +1,37 @@
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde_json::json;
+use zirv_config::config;
+
+#[derive(Deserialize)]
+struct Limits {
+    max_body: u64,
+}
+
+#[test]
+fn test_get_cached_is_invalidated_by_writes() {
+    config::register_config("limits", json!({ "max_body": 1024 }));
+
+    let first = config::get_cached::<Limits>("limits").unwrap();
+    let second = config::get_cached::<Limits>("limits").unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(first.max_body, 1024);
+
+    config::register_config("limits", json!({ "max_body": 2048 }));
+    let third = config::get_cached::<Limits>("limits").unwrap();
+    assert!(!Arc::ptr_eq(&first, &third));
+    assert_eq!(third.max_body, 2048);
+
+    config::set_config_by_key("limits.max_body", 4096).unwrap();
+    assert_eq!(
+        config::get_cached::<Limits>("limits").unwrap().max_body,
+        4096
+    );
+
+    config::unregister_config("limits");
+    assert!(matches!(
+        config::get_cached::<Limits>("limits"),
+        Err(config::ConfigError::KeyNotFound { .. })
+    ));
+}
@@ -0,0 +1,177 @@
+#![cfg(feature = "watch")]
+
+use std::fs;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+use zirv_config::config::{self, ConfigError, Format, WatchHandle, WatchOptions};
+
+/// Waits up to five seconds for `done` to hold.
+fn eventually(mut done: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if done() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    done()
+}
+
+fn watch(path: &std::path::Path) -> WatchHandle {
+    config::watch_file(path, &WatchOptions::default()).unwrap()
+}
+
+#[test]
+fn test_watch_file_reloads_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.json");
+    fs::write(
+        &path,
+        r#"{ "watch_edit": { "port": 80, "host": "localhost" } }"#,
+    )
+    .unwrap();
+    config::load_file(&path).unwrap();
+    config::set_config_by_key("watch_edit.debug", true).unwrap();
+    let handle = watch(&path);
+
+    let version = config::get_config_version();
+    fs::write(&path, r#"{ "watch_edit": { "port": 8080 } }"#).unwrap();
+    assert!(eventually(|| {
+        config::get_config_by_key("watch_edit.port") == Some(json!(8080))
+    }));
+    // The file is deep-merged over what the store holds.
+    assert_eq!(
+        config::get_config_by_key("watch_edit"),
+        Some(json!({ "port": 8080, "host": "localhost", "debug": true }))
+    );
+    assert!(config::get_config_version() > version);
+    assert!(handle.reloads() >= 1);
+
+    // A file that no longer parses keeps the previous configuration.
+    fs::write(&path, r#"{ "watch_edit": { "port": "#).unwrap();
+    assert!(eventually(|| handle.last_error().is_some()));
+    assert!(
+        handle.last_error().unwrap().contains("app.json"),
+        "{:?}",
+        handle.last_error()
+    );
+    assert_eq!(
+        config::get_config_by_key("watch_edit.port"),
+        Some(json!(8080))
+    );
+
+    fs::write(&path, r#"{ "watch_edit": { "port": 9090 } }"#).unwrap();
+    assert!(eventually(|| {
+        config::get_config_by_key("watch_edit.port") == Some(json!(9090))
+    }));
+    assert!(eventually(|| handle.last_error().is_none()));
+
+    // Once stopped, changes are no longer loaded.
+    handle.stop();
+    fs::write(&path, r#"{ "watch_edit": { "port": 1 } }"#).unwrap();
+    std::thread::sleep(Duration::from_millis(300));
+    assert_eq!(
+        config::get_config_by_key("watch_edit.port"),
+        Some(json!(9090))
+    );
+}
+
+#[test]
+fn test_watch_file_follows_a_renamed_replacement() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.conf");
+    fs::write(&path, r#"{ "watch_rename": { "level": "info" } }"#).unwrap();
+    let handle = config::watch_file(
+        &path,
+        &WatchOptions {
+            format: Some(Format::Json),
+            ..WatchOptions::default()
+        },
+    )
+    .unwrap();
+
+    // Editors write a temporary file and rename it over the original.
+    for level in ["debug", "trace"] {
+        let temporary = dir.path().join(".app.conf.swp");
+        fs::write(
+            &temporary,
+            format!(r#"{{ "watch_rename": {{ "level": "{level}" }} }}"#),
+        )
+        .unwrap();
+        fs::rename(&temporary, &path).unwrap();
+        assert!(eventually(|| {
+            config::get_config_by_key("watch_rename.level") == Some(json!(level))
+        }));
+    }
+    assert_eq!(handle.last_error(), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_watch_file_follows_a_swapped_symlink() {
+    use std::os::unix::fs::symlink;
+
+    // The layout of a mounted Kubernetes ConfigMap.
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join("v1")).unwrap();
+    fs::write(root.join("v1/app.json"), r#"{ "watch_link": 1 }"#).unwrap();
+    symlink("v1", root.join("..data")).unwrap();
+    symlink("..data/app.json", root.join("app.json")).unwrap();
+    config::load_file(root.join("app.json")).unwrap();
+    let _handle = watch(&root.join("app.json"));
+
+    fs::create_dir(root.join("v2")).unwrap();
+    fs::write(root.join("v2/app.json"), r#"{ "watch_link": 2 }"#).unwrap();
+    symlink("v2", root.join("..data_tmp")).unwrap();
+    fs::rename(root.join("..data_tmp"), root.join("..data")).unwrap();
+    assert!(eventually(|| {
+        config::get_config_by_key("watch_link") == Some(json!(2))
+    }));
+}
+
+#[test]
+fn test_watch_file_fails_for_a_missing_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("missing/app.json");
+    let err = config::watch_file(&path, &WatchOptions::default()).unwrap_err();
+    assert!(
+        matches!(&err, ConfigError::Watch { path: p, .. } if *p == path),
+        "{err}"
+    );
+    assert!(
+        err.to_string()
+            .starts_with(&format!("cannot watch {}: ", path.display()))
+    );
+}
+
+#[test]
+fn test_watch_file_coalesces_rapid_writes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.json");
+    fs::write(&path, r#"{ "watch_burst": { "n": 0 } }"#).unwrap();
+    config::load_file(&path).unwrap();
+    let handle = watch(&path);
+    let debounce = WatchOptions::default().debounce;
+
+    for n in 1..=5 {
+        fs::write(&path, format!(r#"{{ "watch_burst": {{ "n": {n} }} }}"#)).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    assert!(eventually(|| {
+        config::get_config_by_key("watch_burst.n") == Some(json!(5))
+    }));
+    std::thread::sleep(debounce * 2);
+    assert_eq!(handle.reloads(), 1);
+    assert!(handle.events() >= 5, "{}", handle.events());
+
+    // Writing the same contents again is seen, but not loaded over runtime writes.
+    config::set_config_by_key("watch_burst.n", 6).unwrap();
+    let events = handle.events();
+    fs::write(&path, r#"{ "watch_burst": { "n": 5 } }"#).unwrap();
+    assert!(eventually(|| handle.events() > events));
+    std::thread::sleep(debounce * 2);
+    assert_eq!(handle.reloads(), 1);
+    assert_eq!(config::get_config_by_key("watch_burst.n"), Some(json!(6)));
+}
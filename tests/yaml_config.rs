@@ -0,0 +1,115 @@
+#![cfg(feature = "yaml")]
+
+use serde_json::json;
+use zirv_config::{config, read_config};
+
+const CONFIG_YAML: &str = r#"
+# Rendered from the deployment chart.
+server:
+  host: 0.0.0.0
+  port: 8080
+  debug: no
+  tls:
+    enabled: true
+    cert: /etc/app/cert.pem
+database:
+  url: "postgres://localhost/app"
+  pool: { min: 2, max: 16 }
+  password: ~
+status_pages:
+  404: /errors/not-found.html
+  500: /errors/internal.html
+"#;
+
+#[test]
+fn test_load_yaml_file_end_to_end() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.yaml");
+    std::fs::write(&path, CONFIG_YAML).unwrap();
+    config::load_yaml_file(&path).unwrap();
+
+    assert_eq!(read_config!("server.port" as u16).unwrap(), 8080);
+    assert_eq!(read_config!("server.debug", String).unwrap(), "no");
+    assert!(read_config!("server.tls.enabled", bool).unwrap());
+    assert_eq!(read_config!("database.pool.max" as u32).unwrap(), 16);
+    assert_eq!(read_config!("database.password"), Some(json!(null)));
+    assert_eq!(
+        read_config!("status_pages.404", String).unwrap(),
+        "/errors/not-found.html"
+    );
+
+    // Later documents deep-merge over earlier ones, as with the other loaders.
+    config::load_yaml_str("server:\n  port: 9090\n  tls:\n    enabled: false\n").unwrap();
+    assert_eq!(read_config!("server.port" as u16).unwrap(), 9090);
+    assert_eq!(read_config!("server.host", String).unwrap(), "0.0.0.0");
+    assert!(!read_config!("server.tls.enabled", bool).unwrap());
+
+    let duplicated = dir.path().join("duplicated.yaml");
+    std::fs::write(&duplicated, "server:\n  port: 1\n  port: 2\n").unwrap();
+    let err = config::load_yaml_file(&duplicated).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "invalid YAML in {} at line 3, column 3: duplicate key \"port\"",
+            duplicated.display()
+        )
+    );
+    assert_eq!(read_config!("server.port" as u16).unwrap(), 9090);
+}
+
+const PROFILES_YAML: &str = "\
+profiled:
+  db_host: localhost
+  pool: { min: 1, max: 4 }
+  debug: true
+---
+profile: staging
+profiled:
+  db_host: db.staging
+---
+profile: [production, production-eu]
+profiled:
+  db_host: db.prod
+  pool: { max: 64 }
+  debug: false
+";
+
+#[test]
+fn test_load_yaml_profiles_end_to_end() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.yaml");
+    std::fs::write(&path, PROFILES_YAML).unwrap();
+
+    config::load_yaml_profiles(&path, "staging").unwrap();
+    assert_eq!(
+        config::get_config_by_key("profiled"),
+        Some(json!({ "db_host": "db.staging", "pool": { "min": 1, "max": 4 }, "debug": true }))
+    );
+    assert_eq!(config::get_config_by_key("profile"), None);
+
+    config::unregister_config("profiled");
+    config::load_yaml_profiles(&path, "production").unwrap();
+    assert_eq!(
+        config::get_config_by_key("profiled"),
+        Some(json!({ "db_host": "db.prod", "pool": { "min": 1, "max": 64 }, "debug": false }))
+    );
+
+    // Only the base document applies to an unlisted profile.
+    config::unregister_config("profiled");
+    config::load_yaml_profiles(&path, "development").unwrap();
+    assert_eq!(
+        read_config!("profiled.db_host", String).unwrap(),
+        "localhost"
+    );
+
+    let malformed = dir.path().join("malformed.yaml");
+    std::fs::write(&malformed, "a: 1\n---\nprofile: [staging, 2]\n").unwrap();
+    let err = config::load_yaml_profiles(&malformed, "staging").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "invalid YAML in {}: document 2: `profile` must be a string or a list of strings",
+            malformed.display()
+        )
+    );
+}
@@ -0,0 +1,250 @@
+//! Derive macro for [zirv-config](https://docs.rs/zirv-config).
+//!
+//! This crate is not meant to be used directly; enable the `derive` feature of
+//! `zirv-config` and use `zirv_config::ZirvConfig` instead.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::{Data, DeriveInput, Expr, Fields, LitStr, Token, parse_macro_input};
+
+/// Ties a configuration struct to a namespace in the global store.
+///
+/// The struct must be annotated with `#[zirv(namespace = "...")]`. The derive generates:
+///
+/// - `NAMESPACE`, a `&'static str` constant holding the namespace.
+/// - `register(self)`, which registers the struct under `NAMESPACE` and returns the block
+///   it replaced, if any.
+/// - `load()`, which deserializes the block registered under `NAMESPACE`.
+///
+/// The struct must also implement `Serialize` and `Deserialize`. The derive implements
+/// [`ConfigSection`](https://docs.rs/zirv-config/latest/zirv_config/config/trait.ConfigSection.html)
+/// as well, with `load` applying the field defaults, so derived structs can be passed to
+/// code that is generic over sections.
+///
+/// Fields may carry `#[zirv(default = expr)]`, where `expr` has the type of the field.
+/// When the stored block has no key under the name the field is deserialized from, which
+/// follows `#[serde(rename)]` and `#[serde(rename_all)]`, `load` serializes `expr` and uses
+/// it instead.
+///
+/// # Examples
+///
+/// ```rust
+/// use zirv_config::ZirvConfig;
+///
+/// #[derive(ZirvConfig, serde::Serialize, serde::Deserialize)]
+/// #[zirv(namespace = "server")]
+/// struct ServerConfig {
+///     host: String,
+///     #[zirv(default = 8080)]
+///     port: u16,
+/// }
+///
+/// zirv_config::config::register_config("server", serde_json::json!({ "host": "0.0.0.0" }));
+///
+/// let server = ServerConfig::load().unwrap();
+/// assert_eq!(ServerConfig::NAMESPACE, "server");
+/// assert_eq!(server.port, 8080);
+/// ```
+#[proc_macro_derive(ZirvConfig, attributes(zirv))]
+pub fn derive_zirv_config(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let namespace = parse_namespace(&input)?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "ZirvConfig can only be derived for structs with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "ZirvConfig can only be derived for structs",
+            ));
+        }
+    };
+
+    let rename_all = parse_serde_name(&input.attrs, "rename_all")?;
+    let mut defaults = Vec::new();
+    for field in fields {
+        if let Some(default) = parse_field_default(&field.attrs)? {
+            let ident = field.ident.as_ref().expect("named fields have identifiers");
+            let name = match parse_serde_name(&field.attrs, "rename")? {
+                Some(name) => name.value(),
+                None => rename_field(&ident.unraw().to_string(), rename_all.as_ref()),
+            };
+            let ty = &field.ty;
+            defaults.push(quote! {
+                (#name, || {
+                    let value: #ty = #default;
+                    ::zirv_config::__private::serde_json::to_value(value)
+                })
+            });
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// The namespace this configuration is registered under.
+            pub const NAMESPACE: &'static str = #namespace;
+
+            /// Registers `self` under [`Self::NAMESPACE`], returning the block it replaced.
+            pub fn register(self) -> ::core::option::Option<::zirv_config::__private::serde_json::Value> {
+                ::zirv_config::config::register_config(Self::NAMESPACE, self)
+            }
+
+            /// Deserializes the block registered under [`Self::NAMESPACE`].
+            pub fn load() -> ::core::result::Result<Self, ::zirv_config::config::ConfigError> {
+                ::zirv_config::__private::load_with_defaults(Self::NAMESPACE, &[#(#defaults),*])
+            }
+        }
+
+        impl #impl_generics ::zirv_config::config::ConfigSection for #ident #ty_generics #where_clause {
+            const NAMESPACE: &'static str = #namespace;
+
+            fn load() -> ::core::result::Result<Self, ::zirv_config::config::ConfigError> {
+                #ident::load()
+            }
+        }
+    })
+}
+
+/// Reads the required `#[zirv(namespace = "...")]` container attribute.
+fn parse_namespace(input: &DeriveInput) -> syn::Result<LitStr> {
+    let mut namespace = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("zirv"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("namespace") {
+                if namespace.is_some() {
+                    return Err(meta.error("duplicate `namespace` attribute"));
+                }
+                let value: LitStr = meta.value()?.parse()?;
+                if value.value().is_empty() {
+                    return Err(syn::Error::new_spanned(
+                        &value,
+                        "namespace must not be empty",
+                    ));
+                }
+                namespace = Some(value);
+                Ok(())
+            } else {
+                Err(meta.error("unknown zirv attribute, expected `namespace`"))
+            }
+        })?;
+    }
+    namespace.ok_or_else(|| {
+        syn::Error::new_spanned(
+            &input.ident,
+            "missing `#[zirv(namespace = \"...\")]` attribute",
+        )
+    })
+}
+
+/// Reads an optional `#[zirv(default = expr)]` field attribute.
+fn parse_field_default(attrs: &[syn::Attribute]) -> syn::Result<Option<Expr>> {
+    let mut default = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("zirv")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("default") {
+                if default.is_some() {
+                    return Err(meta.error("duplicate `default` attribute"));
+                }
+                default = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown zirv field attribute, expected `default`"))
+            }
+        })?;
+    }
+    Ok(default)
+}
+
+/// Reads the name a serde attribute such as `#[serde(rename = "...")]` gives for
+/// deserializing, in either its plain or its `(deserialize = "...")` form.
+fn parse_serde_name(attrs: &[syn::Attribute], key: &str) -> syn::Result<Option<LitStr>> {
+    let mut name = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident(key) {
+                return skip_meta(&meta);
+            }
+            if meta.input.peek(Token![=]) {
+                name = Some(meta.value()?.parse()?);
+                return Ok(());
+            }
+            meta.parse_nested_meta(|inner| {
+                if inner.path.is_ident("deserialize") {
+                    name = Some(inner.value()?.parse()?);
+                    Ok(())
+                } else {
+                    skip_meta(&inner)
+                }
+            })
+        })?;
+    }
+    Ok(name)
+}
+
+/// Consumes the value of a serde attribute this derive does not look at.
+fn skip_meta(meta: &syn::meta::ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        let content;
+        syn::parenthesized!(content in meta.input);
+        content.parse::<TokenStream2>()?;
+    }
+    Ok(())
+}
+
+/// Applies a `#[serde(rename_all = "...")]` rule to a field name, as serde does. Rules
+/// serde does not know are left for serde to report.
+fn rename_field(field: &str, rule: Option<&LitStr>) -> String {
+    let pascal = || {
+        let mut pascal = String::new();
+        let mut capitalize = true;
+        for ch in field.chars() {
+            if ch == '_' {
+                capitalize = true;
+            } else if capitalize {
+                pascal.push(ch.to_ascii_uppercase());
+                capitalize = false;
+            } else {
+                pascal.push(ch);
+            }
+        }
+        pascal
+    };
+    match rule.map(LitStr::value).as_deref() {
+        Some("UPPERCASE" | "SCREAMING_SNAKE_CASE") => field.to_ascii_uppercase(),
+        Some("PascalCase") => pascal(),
+        Some("camelCase") => {
+            let pascal = pascal();
+            let mut chars = pascal.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                None => pascal,
+            }
+        }
+        Some("kebab-case") => field.replace('_', "-"),
+        Some("SCREAMING-KEBAB-CASE") => field.to_ascii_uppercase().replace('_', "-"),
+        _ => field.to_string(),
+    }
+}
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use zirv_config::ZirvConfig;
+use zirv_config::config::{self, ConfigError};
+
+#[derive(ZirvConfig, Serialize, Deserialize, Debug, PartialEq)]
+#[zirv(namespace = "derive_server")]
+struct ServerConfig {
+    host: String,
+    #[zirv(default = 8080)]
+    port: u16,
+    #[zirv(default = vec!["https://example.com".to_string()])]
+    allowed_origins: Vec<String>,
+}
+
+#[derive(ZirvConfig, Serialize, Deserialize, Debug, PartialEq)]
+#[zirv(namespace = "derive_logging")]
+struct LoggingConfig {
+    level: String,
+}
+
+#[test]
+fn test_register_and_load() {
+    assert_eq!(ServerConfig::NAMESPACE, "derive_server");
+    let server = ServerConfig {
+        host: "0.0.0.0".to_string(),
+        port: 3000,
+        allowed_origins: vec![],
+    };
+    assert_eq!(server.register(), None);
+    assert_eq!(
+        ServerConfig::load().unwrap(),
+        ServerConfig {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            allowed_origins: vec![],
+        }
+    );
+
+    let previous = LoggingConfig {
+        level: "info".to_string(),
+    }
+    .register();
+    assert_eq!(previous, None);
+    let previous = LoggingConfig {
+        level: "debug".to_string(),
+    }
+    .register();
+    assert_eq!(previous, Some(json!({ "level": "info" })));
+    assert_eq!(LoggingConfig::load().unwrap().level, "debug");
+}
+
+#[test]
+fn test_load_applies_defaults() {
+    #[derive(ZirvConfig, Serialize, Deserialize, Debug)]
+    #[zirv(namespace = "derive_defaults")]
+    struct Defaults {
+        host: String,
+        #[zirv(default = 8080)]
+        port: u16,
+        #[zirv(default = "info".to_string())]
+        level: String,
+    }
+
+    config::register_config(
+        "derive_defaults",
+        json!({ "host": "localhost", "level": "warn" }),
+    );
+    let loaded = Defaults::load().unwrap();
+    assert_eq!(loaded.host, "localhost");
+    assert_eq!(loaded.port, 8080);
+    assert_eq!(loaded.level, "warn");
+
+    config::register_config("derive_defaults", json!({ "port": 1 }));
+    assert!(matches!(
+        Defaults::load(),
+        Err(ConfigError::TypeMismatch { .. })
+    ));
+    config::unregister_config("derive_defaults");
+    assert!(matches!(
+        Defaults::load(),
+        Err(ConfigError::KeyNotFound { .. })
+    ));
+}
+
+#[test]
+fn test_defaults_follow_serde_renames() {
+    #[derive(ZirvConfig, Serialize, Deserialize, Debug)]
+    #[zirv(namespace = "derive_renamed")]
+    #[serde(rename_all = "camelCase")]
+    struct Renamed {
+        #[zirv(default = 5)]
+        max_conns: u32,
+        #[serde(rename(deserialize = "kind"))]
+        #[zirv(default = "tcp".to_string())]
+        r#type: String,
+        #[zirv(default = true)]
+        r#async: bool,
+    }
+
+    config::register_config("derive_renamed", json!({}));
+    let loaded = Renamed::load().unwrap();
+    assert_eq!(loaded.max_conns, 5);
+    assert_eq!(loaded.r#type, "tcp");
+    assert!(loaded.r#async);
+
+    config::register_config(
+        "derive_renamed",
+        json!({ "maxConns": 8, "kind": "udp", "async": false }),
+    );
+    let loaded = Renamed::load().unwrap();
+    assert_eq!(loaded.max_conns, 8);
+    assert_eq!(loaded.r#type, "udp");
+    assert!(!loaded.r#async);
+}
+
+#[test]
+fn test_derive_implements_config_section() {
+    fn load_section<T: config::ConfigSection>() -> Result<T, ConfigError> {
+        T::load()
+    }
+
+    #[derive(ZirvConfig, Serialize, Deserialize, Debug)]
+    #[zirv(namespace = "derive_section")]
+    struct Section {
+        #[zirv(default = 3)]
+        retries: u32,
+    }
+
+    config::register_config("derive_section", json!({}));
+    assert_eq!(
+        <Section as config::ConfigSection>::NAMESPACE,
+        "derive_section"
+    );
+    assert_eq!(load_section::<Section>().unwrap().retries, 3);
+}
+
+#[test]
+fn test_ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,9 @@
+use zirv_config::ZirvConfig;
+
+#[derive(ZirvConfig)]
+#[zirv(namespace = "")]
+struct ServerConfig {
+    port: u16,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use zirv_config::ZirvConfig;
+
+#[derive(ZirvConfig)]
+struct ServerConfig {
+    port: u16,
+}
+
+fn main() {}
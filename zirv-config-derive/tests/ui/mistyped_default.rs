@@ -0,0 +1,10 @@
+use zirv_config::ZirvConfig;
+
+#[derive(ZirvConfig, serde::Serialize, serde::Deserialize)]
+#[zirv(namespace = "server")]
+struct ServerConfig {
+    #[zirv(default = "5")]
+    port: u16,
+}
+
+fn main() {}
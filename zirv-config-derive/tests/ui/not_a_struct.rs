@@ -0,0 +1,10 @@
+use zirv_config::ZirvConfig;
+
+#[derive(ZirvConfig)]
+#[zirv(namespace = "mode")]
+enum Mode {
+    Fast,
+    Slow,
+}
+
+fn main() {}
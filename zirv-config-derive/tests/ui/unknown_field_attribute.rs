@@ -0,0 +1,10 @@
+use zirv_config::ZirvConfig;
+
+#[derive(ZirvConfig)]
+#[zirv(namespace = "server")]
+struct ServerConfig {
+    #[zirv(fallback = 8080)]
+    port: u16,
+}
+
+fn main() {}